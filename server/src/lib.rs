@@ -0,0 +1,7 @@
+//! The protocols the simulation server speaks, with nothing but the
+//! standard library: enough HTTP to answer requests and open WebSockets, and
+//! enough of WebSockets to talk to the page. The server itself is in
+//! `main.rs`.
+
+pub mod http;
+pub mod websocket;
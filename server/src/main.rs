@@ -0,0 +1,452 @@
+//! Host a universe natively and let pages connect to it as viewers.
+//!
+//! ```text
+//! cargo run --release -p server -- [--port PORT] [--size WIDTHxHEIGHT]
+//!     [--rule RULE] [--speed GENERATIONS_PER_SECOND] [--pattern FILE]
+//! ```
+//!
+//! The server steps the universe itself and sends every page connected over
+//! a WebSocket the part of it that page shows, as the `board` messages of
+//! `wasm_game_of_life::network`, which pages draw instead of stepping their
+//! own. Pages are first sent the top-left corner, up to `SERVED_VIEW` cells
+//! square, and ask for other parts with `view` messages as they pan, so
+//! universes far bigger than a page could hold can be watched. Edits made
+//! on any page are sent back, made to the universe here and so reach every
+//! other page. Pages connect with the page's relay server field, which
+//! points at the default port.
+//!
+//! Over plain HTTP, `GET /stats` answers with the generation, population
+//! and size as JSON, and `GET /board` with the board as RLE, or the part of
+//! it given by `row`, `column`, `width` and `height` in the query.
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::{self, BufReader};
+use std::net::{TcpListener, TcpStream};
+use std::process;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use server::http;
+use server::websocket::{self, Frame};
+use wasm_game_of_life::network::{NetworkMessage, Window, SERVED_VIEW};
+use wasm_game_of_life::patterns::{Pattern, MAX_CELLS};
+use wasm_game_of_life::{Bounds, Position, Universe};
+
+/// How often the universe is stepped and sent to viewers.
+const FRAME: Duration = Duration::from_millis(100);
+
+/// How long a viewer that stops reading can hold up the others.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The most rows and columns a viewer may ask to be sent.
+const MAX_VIEW: u32 = 1024;
+
+struct Options {
+    port: u16,
+    width: u32,
+    height: u32,
+    rule: Option<String>,
+    speed: f64,
+    pattern: Option<String>,
+}
+
+/// What every connection shares. Whatever locks both locks `viewers`
+/// first.
+struct Server {
+    universe: Mutex<Universe>,
+    viewers: Mutex<Vec<Viewer>>,
+}
+
+/// A page connected over a WebSocket.
+struct Viewer {
+    /// The number it was given on connecting.
+    id: u64,
+    /// Written to by both the viewer's own thread and the simulation's, so
+    /// each frame is written whole under the lock.
+    stream: Arc<Mutex<TcpStream>>,
+    /// The part of the universe it is sent.
+    view: Bounds,
+}
+
+impl Server {
+    /// Send every viewer its part of the board, dropping those that can't
+    /// keep up. Viewers looking at the same part share one message. The
+    /// messages are written once `viewers` is unlocked, so that a slow
+    /// viewer holds up no one else's connecting, leaving or panning.
+    fn broadcast(&self) {
+        let mut messages = HashMap::new();
+        let sends: Vec<_> = self
+            .viewers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|viewer| {
+                let message = messages
+                    .entry(viewer.view)
+                    .or_insert_with(|| Arc::new(self.board_message(viewer.view)));
+                (viewer.id, viewer.stream.clone(), message.clone())
+            })
+            .collect();
+        let failed: Vec<_> = sends
+            .into_iter()
+            .filter(|(_, stream, message)| send(stream, message).is_err())
+            .map(|(id, _, _)| id)
+            .collect();
+        if !failed.is_empty() {
+            self.viewers
+                .lock()
+                .unwrap()
+                .retain(|viewer| !failed.contains(&viewer.id));
+        }
+    }
+
+    /// The `board` message for the part of the universe in `view`.
+    fn board_message(&self, view: Bounds) -> String {
+        let universe = self.universe.lock().unwrap();
+        let view = clip(view, &universe);
+        NetworkMessage::Board {
+            generation: universe.generation(),
+            window: Window {
+                row: view.row,
+                column: view.column,
+                rows: universe.height(),
+                columns: universe.width(),
+            },
+            board: region(&universe, view),
+        }
+        .to_json()
+    }
+}
+
+/// Write `text` to a viewer as one frame.
+fn send(stream: &Mutex<TcpStream>, text: &str) -> io::Result<()> {
+    websocket::write_text(&mut *stream.lock().unwrap(), text)
+}
+
+fn main() {
+    let options = parse_args(env::args().skip(1));
+    let mut universe = Universe::new_with_size(options.width, options.height);
+    match &options.pattern {
+        Some(path) => {
+            let text = fs::read_to_string(path)
+                .unwrap_or_else(|err| fail(&format!("couldn't read {}: {}", path, err)));
+            let pattern = Pattern::parse(&text)
+                .unwrap_or_else(|err| fail(&format!("couldn't parse {}: {}", path, err)));
+            if let Some(rule) = pattern.rule() {
+                universe.set_rule(&rule.to_string()).unwrap();
+            }
+            let row = options.height.saturating_sub(pattern.height()) / 2;
+            let column = options.width.saturating_sub(pattern.width()) / 2;
+            universe.insert_pattern(Position::new(row, column), &pattern);
+        }
+        None => universe.fill_stripes(),
+    }
+    if let Some(rule) = &options.rule {
+        universe
+            .set_rule(rule)
+            .unwrap_or_else(|err| fail(&format!("invalid rule {}: {}", rule, err)));
+    }
+
+    let server = Arc::new(Server {
+        universe: Mutex::new(universe),
+        viewers: Mutex::new(Vec::new()),
+    });
+    let listener = TcpListener::bind(("127.0.0.1", options.port))
+        .unwrap_or_else(|err| fail(&format!("couldn't listen on {}: {}", options.port, err)));
+    println!("Hosting a universe at:");
+    println!("ws://localhost:{}", options.port);
+
+    let my_server = server.clone();
+    thread::spawn(move || {
+        for (id, stream) in listener.incoming().flatten().enumerate() {
+            let server = my_server.clone();
+            let id = id as u64 + 1;
+            thread::spawn(move || {
+                if let Err(err) = respond(&server, stream, id) {
+                    eprintln!("Couldn't answer a request: {}", err);
+                }
+            });
+        }
+    });
+
+    simulate(&server, options.speed);
+}
+
+/// Step the universe `speed` generations a second, forever.
+fn simulate(server: &Server, speed: f64) {
+    let start = Instant::now();
+    let mut stepped = 0;
+    loop {
+        thread::sleep(FRAME);
+        let due = (start.elapsed().as_secs_f64() * speed) as u64;
+        if due > stepped {
+            let generations = due - stepped;
+            server
+                .universe
+                .lock()
+                .unwrap()
+                .step(generations.min(u32::MAX as u64) as u32);
+            stepped = due;
+            server.broadcast();
+        }
+    }
+}
+
+fn respond(server: &Server, stream: TcpStream, id: u64) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let request = match http::Request::read(&mut reader)? {
+        Some(request) => request,
+        None => {
+            return http::send(
+                &mut &stream,
+                "400 Bad Request",
+                "text/plain",
+                b"Bad request\n",
+            )
+        }
+    };
+
+    if request.is_upgrade() {
+        let key = request.header("sec-websocket-key").unwrap_or("");
+        websocket::accept(&mut &stream, key)?;
+        return view(server, reader, stream, id);
+    }
+    println!("{} {}", request.method, request.path);
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/stats") => {
+            // Every member is a number, so this can't fail.
+            let stats = serde_json::to_string(&stats(server)).unwrap();
+            http::send(&mut &stream, "200 OK", "application/json", stats.as_bytes())
+        }
+        ("GET", "/board") => match board(server, &request) {
+            Some(rle) => http::send(&mut &stream, "200 OK", "text/plain", rle.as_bytes()),
+            None => http::send(
+                &mut &stream,
+                "400 Bad Request",
+                "text/plain",
+                b"Invalid region\n",
+            ),
+        },
+        _ => http::send(&mut &stream, "404 Not Found", "text/plain", b"Not found\n"),
+    }
+}
+
+/// Keep a viewer up to date until it disconnects, making every edit it
+/// sends.
+fn view(
+    server: &Server,
+    mut reader: BufReader<TcpStream>,
+    stream: TcpStream,
+    id: u64,
+) -> io::Result<()> {
+    println!("Viewer {} connected", id);
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    let stream = Arc::new(Mutex::new(stream));
+    let view = Bounds {
+        row: 0,
+        column: 0,
+        width: SERVED_VIEW,
+        height: SERVED_VIEW,
+    };
+    send(&stream, &server.board_message(view))?;
+    server.viewers.lock().unwrap().push(Viewer {
+        id,
+        stream: stream.clone(),
+        view,
+    });
+
+    let result = loop {
+        match websocket::read_frame(&mut reader) {
+            Ok(Frame::Text(text)) => receive(server, id, &text),
+            Ok(Frame::Ping(payload)) => {
+                if let Err(err) = websocket::write_pong(&mut *stream.lock().unwrap(), &payload) {
+                    break Err(err);
+                }
+            }
+            Ok(Frame::Close) => break websocket::write_close(&mut *stream.lock().unwrap()),
+            Ok(Frame::Other) => {}
+            Err(err) => break Err(err),
+        }
+    };
+    server
+        .viewers
+        .lock()
+        .unwrap()
+        .retain(|viewer| viewer.id != id);
+    println!("Viewer {} disconnected", id);
+    result
+}
+
+/// Take a message from viewer number `id`: an edit to make to the universe
+/// here, which is the one everyone sees, or the part of it the viewer
+/// wants to be sent.
+fn receive(server: &Server, id: u64, text: &str) {
+    match NetworkMessage::from_json(text) {
+        Ok(NetworkMessage::Edit { edit, .. }) => {
+            if let Err(err) = edit.apply(&mut server.universe.lock().unwrap()) {
+                eprintln!("Couldn't make an edit: {}", err);
+                return;
+            }
+            server.broadcast();
+        }
+        Ok(NetworkMessage::View(view)) => {
+            let answer = {
+                let mut viewers = server.viewers.lock().unwrap();
+                viewers
+                    .iter_mut()
+                    .find(|viewer| viewer.id == id)
+                    .map(|viewer| {
+                        viewer.view = clip(view, &server.universe.lock().unwrap());
+                        (viewer.stream.clone(), server.board_message(viewer.view))
+                    })
+            };
+            // Send it straight away rather than leave the page showing the
+            // old part until the next step.
+            if let Some((stream, message)) = answer {
+                if let Err(err) = send(&stream, &message) {
+                    eprintln!("Couldn't answer viewer {}: {}", id, err);
+                }
+            }
+        }
+        Ok(_) => {}
+        Err(err) => eprintln!("Couldn't read a message: {}", err),
+    }
+}
+
+/// What `GET /stats` answers with.
+#[derive(Serialize)]
+struct Stats {
+    generation: u64,
+    population: u32,
+    width: u32,
+    height: u32,
+    viewers: usize,
+}
+
+fn stats(server: &Server) -> Stats {
+    let viewers = server.viewers.lock().unwrap().len();
+    let universe = server.universe.lock().unwrap();
+    Stats {
+        generation: universe.generation(),
+        population: universe.population(),
+        width: universe.width(),
+        height: universe.height(),
+        viewers,
+    }
+}
+
+/// The part of the board the query asks for as RLE, or `None` if it
+/// doesn't fit on the board.
+fn board(server: &Server, request: &http::Request) -> Option<String> {
+    let universe = server.universe.lock().unwrap();
+    let number = |name: &str, default: u32| match request.query(name) {
+        Some(value) => value.parse::<u32>().ok(),
+        None => Some(default),
+    };
+    let (row, column) = (number("row", 0)?, number("column", 0)?);
+    let width = number("width", universe.width().checked_sub(column)?)?;
+    let height = number("height", universe.height().checked_sub(row)?)?;
+    if row.checked_add(height)? > universe.height() || column.checked_add(width)? > universe.width()
+    {
+        return None;
+    }
+
+    Some(region(
+        &universe,
+        Bounds {
+            row,
+            column,
+            width,
+            height,
+        },
+    ))
+}
+
+/// `view` moved and cut down to fit on `universe`, and to at most
+/// `MAX_VIEW` cells a side.
+fn clip(view: Bounds, universe: &Universe) -> Bounds {
+    let (rows, columns) = (universe.height(), universe.width());
+    let height = view.height.clamp(1, MAX_VIEW).min(rows);
+    let width = view.width.clamp(1, MAX_VIEW).min(columns);
+    Bounds {
+        row: view.row.min(rows - height),
+        column: view.column.min(columns - width),
+        width,
+        height,
+    }
+}
+
+/// The cells of `universe` in `view`, which must fit on it, as RLE with
+/// every numbered state.
+fn region(universe: &Universe, view: Bounds) -> String {
+    let mut region = Universe::new_with_size(view.width, view.height);
+    region.set_rule(&universe.rule().to_string()).unwrap();
+    let states: Vec<_> = (0..view.height)
+        .flat_map(|row| (0..view.width).map(move |column| (row, column)))
+        .map(|(row, column)| {
+            let position = Position::new(view.row + row, view.column + column);
+            universe.state(position).unwrap_or(0)
+        })
+        .collect();
+    region.set_cells(&states);
+    region.serialize()
+}
+
+fn parse_args(args: impl Iterator<Item = String>) -> Options {
+    let mut options = Options {
+        port: 9001,
+        width: 64,
+        height: 64,
+        rule: None,
+        speed: 10.0,
+        pattern: None,
+    };
+    let mut args = args;
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .unwrap_or_else(|| fail(&format!("missing value for {}", arg)));
+        match arg.as_str() {
+            "--port" => {
+                options.port = value
+                    .parse()
+                    .unwrap_or_else(|_| fail(&format!("invalid port {}", value)))
+            }
+            "--size" => {
+                let size = value
+                    .split_once('x')
+                    .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)));
+                match size {
+                    Some((width, height)) if width as u64 * height as u64 > MAX_CELLS => fail(
+                        &format!("{} is too big, the most cells is {}", value, MAX_CELLS),
+                    ),
+                    Some((width, height)) if width > 0 && height > 0 => {
+                        options.width = width;
+                        options.height = height;
+                    }
+                    _ => fail(&format!("invalid size {}, expected WIDTHxHEIGHT", value)),
+                }
+            }
+            "--rule" => options.rule = Some(value),
+            "--speed" => {
+                options.speed = value
+                    .parse()
+                    .ok()
+                    .filter(|speed: &f64| *speed >= 0.0)
+                    .unwrap_or_else(|| fail(&format!("invalid speed {}", value)))
+            }
+            "--pattern" => options.pattern = Some(value),
+            _ => fail(&format!("unknown flag {}", arg)),
+        }
+    }
+    options
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
@@ -0,0 +1,244 @@
+//! The hand-written HTTP, WebSocket, SHA-1 and base64 code the server
+//! speaks, checked against published test vectors and frames built by
+//! hand.
+//!
+//! ```text
+//! cargo test -p server --test protocol
+//! ```
+
+use std::io::Cursor;
+
+use server::http::{self, Request};
+use server::websocket::{self, Frame};
+
+/// A frame as a browser sends it, masked with `mask`.
+fn client_frame(first: u8, payload: &[u8], mask: [u8; 4]) -> Vec<u8> {
+    let mut frame = vec![first];
+    match payload.len() {
+        length if length < 126 => frame.push(0x80 | length as u8),
+        length if length <= u16::MAX as usize => {
+            frame.push(0x80 | 126);
+            frame.extend_from_slice(&(length as u16).to_be_bytes());
+        }
+        length => {
+            frame.push(0x80 | 127);
+            frame.extend_from_slice(&(length as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(&mask);
+    frame.extend(
+        payload
+            .iter()
+            .enumerate()
+            .map(|(i, byte)| byte ^ mask[i % 4]),
+    );
+    frame
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[test]
+fn sha1_matches_the_published_digests() {
+    assert_eq!(
+        hex(&websocket::sha1(b"")),
+        "da39a3ee5e6b4b0d3255bfef95601890afd80709"
+    );
+    assert_eq!(
+        hex(&websocket::sha1(b"abc")),
+        "a9993e364706816aba3e25717850c26c9cd0d89d"
+    );
+    // Long enough to be padded into a second block.
+    assert_eq!(
+        hex(&websocket::sha1(
+            b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+        )),
+        "84983e441c3bd26ebaae4aa1f95129e5e54670f1"
+    );
+}
+
+#[test]
+fn base64_pads_every_length() {
+    // The vectors of RFC 4648.
+    for (data, text) in [
+        ("", ""),
+        ("f", "Zg=="),
+        ("fo", "Zm8="),
+        ("foo", "Zm9v"),
+        ("foob", "Zm9vYg=="),
+        ("fooba", "Zm9vYmE="),
+        ("foobar", "Zm9vYmFy"),
+    ] {
+        assert_eq!(websocket::base64(data.as_bytes()), text, "{:?}", data);
+    }
+}
+
+#[test]
+fn handshakes_answer_the_example_of_rfc_6455() {
+    assert_eq!(
+        websocket::accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+        "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+    );
+
+    let mut response = Vec::new();
+    websocket::accept(&mut response, "dGhlIHNhbXBsZSBub25jZQ==").unwrap();
+    let response = String::from_utf8(response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 101 Switching Protocols\r\n"));
+    assert!(response.contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n"));
+    assert!(response.ends_with("\r\n\r\n"));
+}
+
+#[test]
+fn masked_frames_are_read() {
+    let mask = [0x37, 0xfa, 0x21, 0x3d];
+    // The masked "Hello" of RFC 6455 section 5.7.
+    let hello = [
+        0x81, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58,
+    ];
+    assert_eq!(client_frame(0x81, b"Hello", mask), hello);
+
+    let mut stream = Vec::new();
+    stream.extend(hello);
+    stream.extend(client_frame(0x89, b"ping", mask));
+    stream.extend(client_frame(0x8A, b"pong", mask));
+    stream.extend(client_frame(0x88, &[], mask));
+    let mut reader = Cursor::new(stream);
+    assert_eq!(
+        websocket::read_frame(&mut reader).unwrap(),
+        Frame::Text("Hello".to_string())
+    );
+    assert_eq!(
+        websocket::read_frame(&mut reader).unwrap(),
+        Frame::Ping(b"ping".to_vec())
+    );
+    assert_eq!(websocket::read_frame(&mut reader).unwrap(), Frame::Other);
+    assert_eq!(websocket::read_frame(&mut reader).unwrap(), Frame::Close);
+    assert!(websocket::read_frame(&mut reader).is_err());
+}
+
+#[test]
+fn long_frames_are_read() {
+    let mask = [1, 2, 3, 4];
+    for length in [126, 70_000] {
+        let text = "x".repeat(length);
+        let frame = client_frame(0x81, text.as_bytes(), mask);
+        assert_eq!(
+            websocket::read_frame(&mut Cursor::new(frame)).unwrap(),
+            Frame::Text(text),
+            "{} bytes",
+            length
+        );
+    }
+}
+
+#[test]
+fn fragments_and_bad_text_are_ignored() {
+    let mask = [1, 2, 3, 4];
+    let fragment = client_frame(0x01, b"Hel", mask);
+    assert_eq!(
+        websocket::read_frame(&mut Cursor::new(fragment)).unwrap(),
+        Frame::Other
+    );
+    let invalid = client_frame(0x81, &[0xff, 0xfe], mask);
+    assert_eq!(
+        websocket::read_frame(&mut Cursor::new(invalid)).unwrap(),
+        Frame::Other
+    );
+}
+
+#[test]
+fn frames_too_long_are_refused() {
+    // Only the header is there: the length alone must be refused.
+    let mut frame = vec![0x81, 0x80 | 127];
+    frame.extend_from_slice(&u64::MAX.to_be_bytes());
+    let err = websocket::read_frame(&mut Cursor::new(frame)).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn frames_are_written_unmasked() {
+    let mut written = Vec::new();
+    websocket::write_text(&mut written, "Hello").unwrap();
+    assert_eq!(written, [0x81, 0x05, b'H', b'e', b'l', b'l', b'o']);
+
+    let mut written = Vec::new();
+    websocket::write_pong(&mut written, b"ping").unwrap();
+    assert_eq!(written, [0x8A, 0x04, b'p', b'i', b'n', b'g']);
+
+    let mut written = Vec::new();
+    websocket::write_close(&mut written).unwrap();
+    assert_eq!(written, [0x88, 0x00]);
+
+    let mut written = Vec::new();
+    websocket::write_text(&mut written, &"x".repeat(300)).unwrap();
+    assert_eq!(written[..4], [0x81, 126, 0x01, 0x2C]);
+    assert_eq!(written.len(), 4 + 300);
+
+    let mut written = Vec::new();
+    websocket::write_text(&mut written, &"x".repeat(70_000)).unwrap();
+    assert_eq!(written[..2], [0x81, 127]);
+    assert_eq!(written[2..10], 70_000u64.to_be_bytes());
+    assert_eq!(written.len(), 10 + 70_000);
+
+    // What is written reads back, once masked as a browser would.
+    let mut masked = written.clone();
+    masked[1] |= 0x80;
+    masked.splice(10..10, [0; 4]);
+    assert_eq!(
+        websocket::read_frame(&mut Cursor::new(masked)).unwrap(),
+        Frame::Text("x".repeat(70_000))
+    );
+}
+
+#[test]
+fn requests_are_read() {
+    let text = "GET /board?row=2&column=3&width HTTP/1.1\r\n\
+                Host: localhost:8080\r\n\
+                Upgrade: WebSocket\r\n\
+                Sec-WebSocket-Key:  dGhlIHNhbXBsZSBub25jZQ== \r\n\
+                \r\n\
+                left for the body";
+    let mut reader = Cursor::new(text.as_bytes());
+    let request = Request::read(&mut reader).unwrap().unwrap();
+    assert_eq!(request.method, "GET");
+    assert_eq!(request.path, "/board");
+    assert_eq!(request.query("row"), Some("2"));
+    assert_eq!(request.query("column"), Some("3"));
+    assert_eq!(request.query("width"), None);
+    assert_eq!(request.header("host"), Some("localhost:8080"));
+    assert_eq!(
+        request.header("sec-websocket-key"),
+        Some("dGhlIHNhbXBsZSBub25jZQ==")
+    );
+    assert!(request.is_upgrade());
+    assert_eq!(reader.position() as usize, text.find("left").unwrap());
+
+    let plain = Request::read(&mut Cursor::new("GET / HTTP/1.1\n\n"))
+        .unwrap()
+        .unwrap();
+    assert_eq!(plain.path, "/");
+    assert_eq!(plain.query("row"), None);
+    assert!(!plain.is_upgrade());
+}
+
+#[test]
+fn requests_that_arent_http_are_none() {
+    assert!(Request::read(&mut Cursor::new("")).unwrap().is_none());
+    assert!(Request::read(&mut Cursor::new("garbage\r\n\r\n"))
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn responses_are_written_whole() {
+    let mut written = Vec::new();
+    http::send(&mut written, "200 OK", "text/plain", b"hello").unwrap();
+    let written = String::from_utf8(written).unwrap();
+    let (head, body) = written.split_once("\r\n\r\n").unwrap();
+    assert!(head.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(head.contains("Content-Type: text/plain\r\n"));
+    assert!(head.contains("Content-Length: 5\r\n"));
+    assert!(head.contains("Access-Control-Allow-Origin: *\r\n"));
+    assert_eq!(body, "hello");
+}
@@ -0,0 +1,62 @@
+//! How many generations per second `Universe::tick` manages on boards of
+//! different sizes.
+//!
+//! ```text
+//! cargo bench -p wasm-game-of-life
+//! ```
+//!
+//! With `--features parallel` the boards tick across threads.
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+
+use wasm_game_of_life::patterns::Pattern;
+use wasm_game_of_life::{Position, Universe};
+
+const SIZES: [u32; 3] = [64, 256, 1024];
+
+/// Builds a board of the given side length.
+type Board = fn(u32) -> Universe;
+
+/// Half the cells alive, from a fixed seed so every run ticks the same board.
+fn random_board(size: u32) -> Universe {
+    let mut universe = Universe::new_with_size(size, size);
+    universe.randomize_with_seed(1, 0.5);
+    universe
+}
+
+/// As many Gosper glider guns as fit, each with room around it for its
+/// stream of gliders.
+fn glider_gun_board(size: u32) -> Universe {
+    let gun = Pattern::from_library("Gosper glider gun").unwrap();
+    let mut universe = Universe::new_with_size(size, size);
+    for row in (0..=size - gun.height()).step_by(24) {
+        for column in (0..=size - gun.width()).step_by(48) {
+            universe.insert_pattern(Position::new(row, column), &gun);
+        }
+    }
+    universe
+}
+
+fn tick(c: &mut Criterion) {
+    let boards: [(&str, Board); 2] = [("random", random_board), ("glider-gun", glider_gun_board)];
+    for (name, board) in boards.iter() {
+        let mut group = c.benchmark_group(format!("tick/{}", name));
+        // Report ticks per second rather than seconds per tick.
+        group.throughput(Throughput::Elements(1));
+        for &size in SIZES.iter() {
+            let mut universe = board(size);
+            universe.set_parallel(true);
+            group.bench_with_input(
+                BenchmarkId::from_parameter(size),
+                &universe,
+                |b, universe| {
+                    b.iter_batched_ref(|| universe.clone(), Universe::tick, BatchSize::LargeInput)
+                },
+            );
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, tick);
+criterion_main!(benches);
@@ -0,0 +1,58 @@
+//! Run a universe natively, without a browser, and print the result.
+//!
+//! ```text
+//! cargo run -p wasm-game-of-life --example headless -- [GENERATIONS] [PATTERN]
+//! ```
+//!
+//! `PATTERN` is an RLE, Life 1.06 or plaintext file placed in the middle of
+//! a 64x64 universe. Without one the universe starts with the same stripes
+//! as the website.
+
+use std::env;
+use std::fs;
+use std::process;
+
+use wasm_game_of_life::patterns::Pattern;
+use wasm_game_of_life::{Position, Universe};
+
+const SIZE: u32 = 64;
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let generations = match args.next().map(|arg| arg.parse::<u32>()) {
+        None => 100,
+        Some(Ok(generations)) => generations,
+        Some(Err(err)) => fail(&format!("invalid number of generations: {}", err)),
+    };
+
+    let mut universe = match args.next() {
+        None => Universe::new(),
+        Some(path) => {
+            let text = fs::read_to_string(&path)
+                .unwrap_or_else(|err| fail(&format!("couldn't read {}: {}", path, err)));
+            let pattern = Pattern::parse(&text)
+                .unwrap_or_else(|err| fail(&format!("couldn't parse {}: {}", path, err)));
+            let mut universe = Universe::new_with_size(SIZE, SIZE);
+            if let Some(rule) = pattern.rule() {
+                universe.set_rule(&rule.to_string()).unwrap();
+            }
+            let row = SIZE.saturating_sub(pattern.height()) / 2;
+            let column = SIZE.saturating_sub(pattern.width()) / 2;
+            universe.insert_pattern(Position::new(row, column), &pattern);
+            universe
+        }
+    };
+
+    universe.step(generations);
+    print!("{}", universe);
+    println!(
+        "generation {}, population {}",
+        universe.generation(),
+        universe.population()
+    );
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
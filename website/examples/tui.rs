@@ -0,0 +1,180 @@
+//! Play a universe in the terminal, without a browser.
+//!
+//! ```text
+//! cargo run -p wasm-game-of-life --example tui -- [PATTERN]
+//! ```
+//!
+//! The universe fills the terminal, two rows of cells to each line of text,
+//! with `PATTERN`, an RLE, Life 1.06 or plaintext file, in the middle.
+//! Without one it starts with the same stripes as the website.
+//!
+//! Space plays and pauses, `n` steps one generation, `+` and `-` change the
+//! speed, `r` randomizes the board, `c` clears it and `q` or Escape quits.
+//!
+//! There are no terminals in wasm, so this builds to nothing there.
+
+#![cfg_attr(target_arch = "wasm32", no_main)]
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::process;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::Print;
+use crossterm::terminal::{self, ClearType};
+use crossterm::{cursor, execute, queue};
+
+use wasm_game_of_life::patterns::Pattern;
+use wasm_game_of_life::{Cell, Position, Universe};
+
+/// The board size when the terminal's size can't be read.
+const DEFAULT_SIZE: (u32, u32) = (64, 48);
+
+/// The time between generations while playing, at first and at the ends of
+/// the range `+` and `-` move through.
+const DEFAULT_DELAY: Duration = Duration::from_millis(100);
+const MIN_DELAY: Duration = Duration::from_millis(10);
+const MAX_DELAY: Duration = Duration::from_millis(1600);
+
+/// The density `r` randomizes the board to.
+const DENSITY: f64 = 0.3;
+
+fn main() {
+    let pattern = env::args().nth(1).map(|path| {
+        let text = fs::read_to_string(&path)
+            .unwrap_or_else(|err| fail(&format!("couldn't read {}: {}", path, err)));
+        Pattern::parse(&text)
+            .unwrap_or_else(|err| fail(&format!("couldn't parse {}: {}", path, err)))
+    });
+
+    // The last line of the terminal is left for the status line.
+    let (width, height) = match terminal::size() {
+        Ok((columns, lines)) if columns > 0 && lines > 1 => {
+            (columns as u32, (lines as u32 - 1) * 2)
+        }
+        _ => DEFAULT_SIZE,
+    };
+    let mut universe = Universe::new_with_size(width, height);
+    match &pattern {
+        Some(pattern) => {
+            if let Some(rule) = pattern.rule() {
+                universe.set_rule(&rule.to_string()).unwrap();
+            }
+            let row = height.saturating_sub(pattern.height()) / 2;
+            let column = width.saturating_sub(pattern.width()) / 2;
+            universe.insert_pattern(Position::new(row, column), pattern);
+        }
+        None => universe.fill_stripes(),
+    }
+
+    let result = Screen::enter().and_then(|_screen| run(&mut universe));
+    if let Err(err) = result {
+        fail(&format!("couldn't draw to the terminal: {}", err));
+    }
+}
+
+/// The terminal in raw mode on its alternate screen, put back as it was when
+/// dropped, however the game ends.
+struct Screen;
+
+impl Screen {
+    fn enter() -> io::Result<Screen> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(Screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn run(universe: &mut Universe) -> io::Result<()> {
+    let mut playing = false;
+    let mut delay = DEFAULT_DELAY;
+    let mut next_step = Instant::now();
+    loop {
+        draw(universe, playing, delay)?;
+
+        let timeout = if playing {
+            next_step.saturating_duration_since(Instant::now())
+        } else {
+            // Only keys change anything while paused.
+            Duration::from_secs(60)
+        };
+        if event::poll(timeout)? {
+            match event::read()? {
+                Event::Key(key) if key.kind != KeyEventKind::Release => match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        return Ok(())
+                    }
+                    KeyCode::Char(' ') => {
+                        playing = !playing;
+                        next_step = Instant::now();
+                    }
+                    KeyCode::Char('n') => universe.tick(),
+                    KeyCode::Char('+') | KeyCode::Char('=') => delay = (delay / 2).max(MIN_DELAY),
+                    KeyCode::Char('-') => delay = (delay * 2).min(MAX_DELAY),
+                    KeyCode::Char('r') => universe.randomize(DENSITY),
+                    KeyCode::Char('c') => universe.clear(),
+                    _ => {}
+                },
+                Event::Resize(..) => queue!(io::stdout(), terminal::Clear(ClearType::All))?,
+                _ => {}
+            }
+        }
+
+        if playing && Instant::now() >= next_step {
+            universe.tick();
+            next_step = Instant::now() + delay;
+        }
+    }
+}
+
+/// Draw the board with half blocks, the top cell of each pair in the upper
+/// half, and the status line beneath it.
+fn draw(universe: &Universe, playing: bool, delay: Duration) -> io::Result<()> {
+    let alive =
+        |row: u32, column: u32| universe.get(Position::new(row, column)) == Some(Cell::Alive);
+    let lines = universe.height().div_ceil(2);
+    let mut stdout = io::stdout();
+    for line in 0..lines {
+        let (top, bottom) = (line * 2, line * 2 + 1);
+        let text: String = (0..universe.width())
+            .map(|column| match (alive(top, column), alive(bottom, column)) {
+                (true, true) => '█',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                (false, false) => ' ',
+            })
+            .collect();
+        queue!(stdout, cursor::MoveTo(0, line as u16), Print(text))?;
+    }
+    let status = format!(
+        "generation {}, population {}, {} ms a generation, {} | \
+         space play/pause, n step, +/- speed, r random, c clear, q quit",
+        universe.generation(),
+        universe.population(),
+        delay.as_millis(),
+        if playing { "playing" } else { "paused" },
+    );
+    queue!(
+        stdout,
+        cursor::MoveTo(0, lines as u16),
+        terminal::Clear(ClearType::CurrentLine),
+        Print(status)
+    )?;
+    stdout.flush()
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
@@ -0,0 +1,25 @@
+// Running the simulation from Node, with the module `cargo xtask build-node`
+// makes in `out-node/`:
+//
+//     node out-node/example.js
+
+const { Pattern, Position, Universe, run_experiment } = require('./wasm_game_of_life.js');
+
+// Read a glider from RLE, run it for a while and write the board back out.
+const glider = Pattern.from_rle('x = 3, y = 3\nbo$2bo$3o!');
+const universe = Universe.new_with_size(16, 16);
+universe.insert_pattern(new Position(0, 0), glider);
+universe.step(8);
+console.log(`After 8 generations, ${universe.population()} cells alive:`);
+console.log(universe.to_rle());
+
+// Sweep two rules over random boards and print how they settled.
+const results = JSON.parse(run_experiment(JSON.stringify({
+  width: 32,
+  height: 32,
+  rules: ['B3/S23', 'B36/S23'],
+  densities: [0.3, 0.5],
+  seeds: 20,
+  maxGenerations: 2000,
+})));
+console.table(results);
@@ -1,3 +1,10 @@
-import init from './wasm_game_of_life.js';
+import init, * as wasm from './wasm_game_of_life.js';
 
-init()
+// Builds with the `wasm-threads` feature can split ticks across a pool of
+// workers, if the page is cross-origin isolated.
+init().then(async () => {
+  if (wasm.initThreadPool && self.crossOriginIsolated) {
+    await wasm.initThreadPool(navigator.hardwareConcurrency);
+    wasm.thread_pool_ready();
+  }
+});
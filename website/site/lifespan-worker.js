@@ -0,0 +1,10 @@
+import init, { lifespan_worker_start } from './wasm_game_of_life.js';
+
+// Hold on to messages that arrive while the module is still loading.
+const early = [];
+self.onmessage = event => early.push(event);
+
+init().then(() => {
+  lifespan_worker_start();
+  early.forEach(event => self.onmessage(event));
+});
@@ -0,0 +1,10 @@
+import init, { worker_start } from './wasm_game_of_life.js';
+
+// Hold on to messages that arrive while the module is still loading.
+const early = [];
+self.onmessage = event => early.push(event);
+
+init().then(() => {
+  worker_start();
+  early.forEach(event => self.onmessage(event));
+});
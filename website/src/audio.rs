@@ -0,0 +1,230 @@
+//! Turning the simulation into sound. Every generation plays a handful of
+//! short notes picked from a musical scale, either for how many cells were
+//! born and died or for how full each band of columns is.
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use crate::{Cell, Position, Universe};
+
+/// The lowest note played, in hertz: the A below middle C.
+pub const BASE_FREQUENCY: f32 = 220.0;
+
+/// How many notes a generation can play in each register.
+pub const SOUND_BANDS: u32 = 8;
+
+/// How long each note rings, in seconds.
+pub const NOTE_LENGTH: f64 = 0.15;
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scale {
+    Major,
+    Minor,
+    Pentatonic,
+    Chromatic,
+}
+
+impl Scale {
+    /// Parse the name used by the page's scale dropdown.
+    pub fn from_name(name: &str) -> Option<Scale> {
+        match name {
+            "major" => Some(Scale::Major),
+            "minor" => Some(Scale::Minor),
+            "pentatonic" => Some(Scale::Pentatonic),
+            "chromatic" => Some(Scale::Chromatic),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Scale::Major => "major",
+            Scale::Minor => "minor",
+            Scale::Pentatonic => "pentatonic",
+            Scale::Chromatic => "chromatic",
+        }
+    }
+
+    /// The semitones above the first note of each note in an octave.
+    fn steps(self) -> &'static [u32] {
+        match self {
+            Scale::Major => &[0, 2, 4, 5, 7, 9, 11],
+            Scale::Minor => &[0, 2, 3, 5, 7, 8, 10],
+            Scale::Pentatonic => &[0, 2, 4, 7, 9],
+            Scale::Chromatic => &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+        }
+    }
+
+    /// The frequency of the note `degree` steps up the scale from
+    /// `BASE_FREQUENCY`, in hertz.
+    pub fn frequency(self, degree: u32) -> f32 {
+        let steps = self.steps();
+        let octave = degree / steps.len() as u32;
+        let semitones = octave * 12 + steps[degree as usize % steps.len()];
+        BASE_FREQUENCY * 2f32.powf(semitones as f32 / 12.0)
+    }
+}
+
+/// What the notes of a generation stand for.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sonification {
+    /// A low note for deaths and a high one for births, higher the more
+    /// cells changed.
+    Changes,
+    /// One note for each band of columns, louder the more of it is alive.
+    Columns,
+}
+
+impl Sonification {
+    /// Parse the name used by the page's sound dropdown.
+    pub fn from_name(name: &str) -> Option<Sonification> {
+        match name {
+            "changes" => Some(Sonification::Changes),
+            "columns" => Some(Sonification::Columns),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Sonification::Changes => "changes",
+            Sonification::Columns => "columns",
+        }
+    }
+
+    /// The notes to play for the universe's latest generation, as scale
+    /// degrees and loudnesses from 0 to 1.
+    pub fn notes(self, universe: &Universe) -> Vec<(u32, f32)> {
+        match self {
+            Sonification::Changes => {
+                // One step up for every doubling of the count.
+                let degree = |count: u32| (u32::BITS - count.leading_zeros()).min(SOUND_BANDS) - 1;
+                let mut notes = Vec::new();
+                if universe.deaths() > 0 {
+                    notes.push((degree(universe.deaths()), 1.0));
+                }
+                if universe.births() > 0 {
+                    notes.push((SOUND_BANDS + degree(universe.births()), 1.0));
+                }
+                notes
+            }
+            Sonification::Columns => {
+                let (width, height) = (universe.width(), universe.height());
+                let mut bands = vec![0u32; SOUND_BANDS as usize];
+                for column in 0..width {
+                    let band = (column * SOUND_BANDS / width.max(1)) as usize;
+                    bands[band] += (0..height)
+                        .filter(|&row| {
+                            universe.get(Position::new(row, column)) == Some(Cell::Alive)
+                        })
+                        .count() as u32;
+                }
+                let loudest = bands.iter().copied().max().unwrap_or(0);
+                if loudest == 0 {
+                    return Vec::new();
+                }
+                bands
+                    .iter()
+                    .enumerate()
+                    .filter(|&(_, &population)| population > 0)
+                    .map(|(band, &population)| (band as u32, population as f32 / loudest as f32))
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Plays the notes of each new generation through the Web Audio API.
+///
+/// Browsers only let pages make sound after the user has interacted with
+/// them, so this should be created from an event handler.
+#[cfg(all(target_arch = "wasm32", feature = "web"))]
+pub struct Sonifier {
+    context: web_sys::AudioContext,
+    /// Every note goes through this, to set the overall volume.
+    volume: web_sys::GainNode,
+    scale: Scale,
+    sonification: Sonification,
+    muted: bool,
+    /// The generation last played, so that redraws don't repeat it.
+    generation: Option<u64>,
+}
+
+#[cfg(all(target_arch = "wasm32", feature = "web"))]
+impl Sonifier {
+    pub fn new() -> Result<Sonifier, JsValue> {
+        let context = web_sys::AudioContext::new()?;
+        let volume = context.create_gain()?;
+        volume.connect_with_audio_node(&context.destination())?;
+        Ok(Sonifier {
+            context,
+            volume,
+            scale: Scale::Pentatonic,
+            sonification: Sonification::Changes,
+            muted: false,
+            generation: None,
+        })
+    }
+
+    /// Set the overall volume, from 0 to 1.
+    pub fn set_volume(&self, volume: f32) {
+        self.volume.gain().set_value(volume.clamp(0.0, 1.0));
+    }
+
+    pub fn set_scale(&mut self, scale: Scale) {
+        self.scale = scale;
+    }
+
+    pub fn set_sonification(&mut self, sonification: Sonification) {
+        self.sonification = sonification;
+    }
+
+    /// Stop or restart all sound.
+    pub fn set_muted(&mut self, muted: bool) -> Result<(), JsValue> {
+        self.muted = muted;
+        if muted {
+            let _ = self.context.suspend()?;
+        } else {
+            let _ = self.context.resume()?;
+        }
+        Ok(())
+    }
+
+    /// Play the notes of the universe's generation, unless it was already
+    /// played.
+    pub fn play(&mut self, universe: &Universe) -> Result<(), JsValue> {
+        let generation = universe.generation();
+        if self.generation.replace(generation) == Some(generation) || self.muted {
+            return Ok(());
+        }
+        let notes = self.sonification.notes(universe);
+        let now = self.context.current_time();
+        for &(degree, loudness) in &notes {
+            let oscillator = self.context.create_oscillator()?;
+            oscillator.set_type(web_sys::OscillatorType::Triangle);
+            oscillator
+                .frequency()
+                .set_value(self.scale.frequency(degree));
+
+            // Fade in quickly and die away, sharing the volume between the
+            // notes so that chords aren't louder than single notes.
+            let envelope = self.context.create_gain()?;
+            let peak = loudness / notes.len() as f32;
+            envelope.gain().set_value_at_time(0.0, now)?;
+            envelope
+                .gain()
+                .linear_ramp_to_value_at_time(peak.max(0.001), now + 0.01)?;
+            envelope
+                .gain()
+                .exponential_ramp_to_value_at_time(0.001, now + NOTE_LENGTH)?;
+
+            oscillator.connect_with_audio_node(&envelope)?;
+            envelope.connect_with_audio_node(&self.volume)?;
+            oscillator.start_with_when(now)?;
+            oscillator.stop_with_when(now + NOTE_LENGTH)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,215 @@
+//! The shapes painted under the pointer, the lines and rectangles drawn by
+//! dragging from one cell to another, and the symmetry that repeats them.
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+pub const MIN_BRUSH_RADIUS: u32 = 1;
+pub const MAX_BRUSH_RADIUS: u32 = 10;
+
+/// The chance that a spray brush paints each cell under it.
+pub const SPRAY_DENSITY: f64 = 0.2;
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BrushShape {
+    Square,
+    Circle,
+    /// A circle that only paints some of the cells under it, at random.
+    Spray,
+}
+
+impl BrushShape {
+    /// Parse the name used by the page's brush dropdown.
+    pub fn from_name(name: &str) -> Option<BrushShape> {
+        match name {
+            "square" => Some(BrushShape::Square),
+            "circle" => Some(BrushShape::Circle),
+            "spray" => Some(BrushShape::Spray),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BrushShape::Square => "square",
+            BrushShape::Circle => "circle",
+            BrushShape::Spray => "spray",
+        }
+    }
+}
+
+/// A shape and a radius. A brush of radius 1 covers a single cell, and each
+/// step up reaches one more cell out from the middle.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Brush {
+    shape: BrushShape,
+    radius: u32,
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Brush {
+    /// The radius is clamped to the range brushes allow.
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen(constructor))]
+    pub fn new(shape: BrushShape, radius: u32) -> Brush {
+        Brush {
+            shape,
+            radius: radius.clamp(MIN_BRUSH_RADIUS, MAX_BRUSH_RADIUS),
+        }
+    }
+
+    pub fn shape(&self) -> BrushShape {
+        self.shape
+    }
+
+    pub fn radius(&self) -> u32 {
+        self.radius
+    }
+}
+
+impl Brush {
+    /// The (`row`, `column`) offsets from the middle of every cell the brush
+    /// covers. Spray brushes cover a circle but only paint part of it.
+    pub fn offsets(&self) -> impl Iterator<Item = (i32, i32)> {
+        let reach = self.radius as i32 - 1;
+        // Cells whose middles are within half a cell of the circle count as
+        // inside it.
+        let limit = (reach as f64 + 0.5).powi(2);
+        let square = self.shape == BrushShape::Square;
+        (-reach..=reach)
+            .flat_map(move |row| (-reach..=reach).map(move |column| (row, column)))
+            .filter(move |&(row, column)| square || ((row * row + column * column) as f64) < limit)
+    }
+}
+
+impl Default for Brush {
+    fn default() -> Brush {
+        Brush::new(BrushShape::Square, MIN_BRUSH_RADIUS)
+    }
+}
+
+/// Which mirror images of every painted cell are painted too.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Symmetry {
+    None,
+    /// The left and right halves of the universe mirror each other.
+    Horizontal,
+    /// The top and bottom halves mirror each other.
+    Vertical,
+    /// Both of the above.
+    FourFold,
+    /// Both of the above, and the halves either side of each diagonal
+    /// through the middle.
+    EightFold,
+}
+
+impl Symmetry {
+    /// Parse the name used by the page's symmetry dropdown.
+    pub fn from_name(name: &str) -> Option<Symmetry> {
+        match name {
+            "none" => Some(Symmetry::None),
+            "horizontal" => Some(Symmetry::Horizontal),
+            "vertical" => Some(Symmetry::Vertical),
+            "four-fold" => Some(Symmetry::FourFold),
+            "eight-fold" => Some(Symmetry::EightFold),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Symmetry::None => "none",
+            Symmetry::Horizontal => "horizontal",
+            Symmetry::Vertical => "vertical",
+            Symmetry::FourFold => "four-fold",
+            Symmetry::EightFold => "eight-fold",
+        }
+    }
+
+    /// The (`row`, `column`) of a cell of a `width` by `height` universe and
+    /// of each of its distinct mirror images, starting with the cell itself.
+    /// Diagonal images that would fall off a universe that isn't square are
+    /// left out.
+    pub fn images(self, row: u32, column: u32, width: u32, height: u32) -> Vec<(u32, u32)> {
+        // Twice the distance from the middle, so that the middle of an even
+        // size, between two cells, is a whole number.
+        let y = 2 * row as i64 - (height as i64 - 1);
+        let x = 2 * column as i64 - (width as i64 - 1);
+        let (left_right, top_bottom, diagonal) = match self {
+            Symmetry::None => (false, false, false),
+            Symmetry::Horizontal => (true, false, false),
+            Symmetry::Vertical => (false, true, false),
+            Symmetry::FourFold => (true, true, false),
+            Symmetry::EightFold => (true, true, true),
+        };
+        let mut offsets = vec![(y, x)];
+        if left_right {
+            offsets.push((y, -x));
+        }
+        if top_bottom {
+            let flipped: Vec<(i64, i64)> = offsets.iter().map(|&(y, x)| (-y, x)).collect();
+            offsets.extend(flipped);
+        }
+        if diagonal {
+            let transposed: Vec<(i64, i64)> = offsets.iter().map(|&(y, x)| (x, y)).collect();
+            offsets.extend(transposed);
+        }
+
+        let mut images = Vec::with_capacity(offsets.len());
+        for (y, x) in offsets {
+            let (y, x) = (y + height as i64 - 1, x + width as i64 - 1);
+            if y % 2 != 0 || x % 2 != 0 {
+                continue;
+            }
+            let (row, column) = (y / 2, x / 2);
+            if (0..height as i64).contains(&row) && (0..width as i64).contains(&column) {
+                let image = (row as u32, column as u32);
+                if !images.contains(&image) {
+                    images.push(image);
+                }
+            }
+        }
+        images
+    }
+}
+
+/// The (`row`, `column`) of every cell on the straight line from `from` to
+/// `to`, both included, by Bresenham's algorithm.
+pub fn line(from: (u32, u32), to: (u32, u32)) -> Vec<(u32, u32)> {
+    let (mut row, mut column) = (from.0 as i64, from.1 as i64);
+    let (to_row, to_column) = (to.0 as i64, to.1 as i64);
+    let (d_row, d_column) = (-(to_row - row).abs(), (to_column - column).abs());
+    let (step_row, step_column) = ((to_row - row).signum(), (to_column - column).signum());
+    let mut error = d_column + d_row;
+    let mut cells = Vec::new();
+    loop {
+        cells.push((row as u32, column as u32));
+        if (row, column) == (to_row, to_column) {
+            return cells;
+        }
+        let doubled = 2 * error;
+        if doubled >= d_row {
+            error += d_row;
+            column += step_column;
+        }
+        if doubled <= d_column {
+            error += d_column;
+            row += step_row;
+        }
+    }
+}
+
+/// The (`row`, `column`) of every cell in the rectangle with opposite
+/// corners `from` and `to`, or only of those on its edges unless `filled`.
+pub fn rectangle(from: (u32, u32), to: (u32, u32), filled: bool) -> Vec<(u32, u32)> {
+    let (top, bottom) = (from.0.min(to.0), from.0.max(to.0));
+    let (left, right) = (from.1.min(to.1), from.1.max(to.1));
+    (top..=bottom)
+        .flat_map(|row| (left..=right).map(move |column| (row, column)))
+        .filter(|&(row, column)| {
+            filled || row == top || row == bottom || column == left || column == right
+        })
+        .collect()
+}
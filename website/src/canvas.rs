@@ -0,0 +1,3000 @@
+//! Drawing a universe onto a `<canvas>` and editing it with the mouse.
+
+mod grid;
+mod image;
+mod minimap;
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::{Rc, Weak};
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::audio::{Scale, Sonification, Sonifier};
+use crate::brush::{self, Brush, BrushShape, Symmetry};
+use crate::census::{self, Census};
+use crate::chunks::ChunkedPlane;
+use crate::elementary::{self, Elementary};
+use crate::engine::{
+    Position, SimulationEngine, Snapshot, Stabilization, DEFAULT_CHECKPOINT_INTERVAL,
+    DEFAULT_REWIND_STEPS,
+};
+use crate::export::gif::GifRecorder;
+use crate::export::png::{encode_apng, encode_png};
+use crate::hashlife::HashLife;
+use crate::history::History;
+use crate::lenia::Lenia;
+use crate::network::{self, Connection, ConnectionStatus, NetworkMessage, Window};
+use crate::patterns::{Flip, ParseError, Pattern};
+use crate::peer::{PeerRole, PeerSession, Signal};
+use crate::replay::{Edit, Replay, ReplayError, ReplayLog};
+use crate::rule::{Neighborhood, Rule, RuleError};
+use crate::sand::{Material, Sand};
+use crate::scenario::{Scenario, View};
+use crate::scripting::NextGeneration;
+use crate::stats::Sparkline;
+use crate::storage;
+use crate::table::{RuleTable, TableBoard, TableError};
+use crate::theme::Theme;
+use crate::turmite::{self, Turmite, TurmiteError, TurmiteSpec};
+use crate::viewport::Viewport;
+use crate::worker::WorkerEngine;
+use crate::{Cell, Topology, Universe};
+use grid::GridRenderer;
+use image::{rgba, ImageRenderer};
+use minimap::Minimap;
+
+/// The size of a cell in pixels until the page changes it.
+const DEFAULT_CELL_SIZE: u32 = 10;
+
+/// Marks a cell in `Canvas::drawn` that must be painted on the next draw.
+const NOT_DRAWN: u16 = u16::MAX;
+
+/// How many shades `RenderMode::Age` uses, and the age at which a cell
+/// reaches the last one.
+const AGE_SHADES: u16 = 16;
+const OLD_AGE: u16 = 64;
+
+/// The color keys of `RenderMode::Age` shades start after every possible
+/// cell state.
+const AGE_KEYS: u16 = 256;
+
+/// How many generations a dead cell's trail takes to fade out.
+const TRAIL_FRAMES: u8 = 8;
+
+/// The color keys of `RenderMode::Trails` fading cells start after the age
+/// shades.
+const TRAIL_KEYS: u16 = 512;
+
+/// How many shades Lenia's levels are drawn in.
+const LENIA_SHADES: u16 = 64;
+
+/// The color keys of Lenia's shades start after the trails.
+const LENIA_KEYS: u16 = 1024;
+
+/// The color keys of rule table states start after Lenia's shades.
+const TABLE_KEYS: u16 = 2048;
+
+/// The color keys of turmite tape colors start after the rule table states,
+/// followed by the key of the turmite's head.
+const TURMITE_KEYS: u16 = 2560;
+const TURMITE_HEAD_KEY: u16 = TURMITE_KEYS + turmite::MAX_COLORS as u16;
+
+/// The color keys of falling-sand materials start after the turmite's head.
+const SAND_KEYS: u16 = 3072;
+
+/// The materials in the order of their color keys.
+const MATERIALS: [Material; 4] = [
+    Material::Empty,
+    Material::Sand,
+    Material::Water,
+    Material::Wall,
+];
+
+/// How many steps the activity overlay looks back over.
+const ACTIVITY_WINDOW: u32 = 32;
+
+/// How many strengths of tint the activity overlay uses.
+const HEAT_LEVELS: u32 = 8;
+
+/// The shortest time between updates of the accessible summary while the
+/// universe runs, in milliseconds, so that screen readers can keep up.
+const SUMMARY_INTERVAL: f64 = 2000.0;
+
+/// How far the view moves towards what it is tracking on each draw, as a
+/// fraction of the distance left.
+const TRACKING_EASE: f64 = 0.2;
+
+/// The longest jump `step_pow2` makes, as a power of two, when the board
+/// isn't run by HashLife and so goes one generation at a time.
+pub(crate) const MAX_STEPPED_JUMP: u8 = 12;
+
+/// How `draw` colors the cells.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RenderMode {
+    /// Color each cell by its state.
+    States,
+    /// Shade live cells by how long they have been alive, along the theme's
+    /// age gradient.
+    Age,
+    /// Let cells that just died fade out over several generations, leaving
+    /// trails behind moving patterns.
+    Trails,
+}
+
+impl RenderMode {
+    /// Parse the name used by the page's render mode dropdown.
+    pub fn from_name(name: &str) -> Option<RenderMode> {
+        match name {
+            "states" => Some(RenderMode::States),
+            "age" => Some(RenderMode::Age),
+            "trails" => Some(RenderMode::Trails),
+            _ => None,
+        }
+    }
+}
+
+/// How `draw` gets the cells onto the canvas.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Renderer {
+    /// Fill a rectangle for every cell that changed since the last draw.
+    Rects,
+    /// Write every visible cell into an image and scale it up in one go.
+    Image,
+}
+
+impl Renderer {
+    /// Parse the name used by the page's renderer dropdown.
+    pub fn from_name(name: &str) -> Option<Renderer> {
+        match name {
+            "rects" => Some(Renderer::Rects),
+            "image" => Some(Renderer::Image),
+            _ => None,
+        }
+    }
+
+    /// The name used by the page's renderer dropdown.
+    pub fn name(self) -> &'static str {
+        match self {
+            Renderer::Rects => "rects",
+            Renderer::Image => "image",
+        }
+    }
+}
+
+/// What dragging across the canvas does.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tool {
+    /// Paint cells alive or dead.
+    Paint,
+    /// Pick a rectangle of cells to cut, copy or delete.
+    Select,
+    /// Drag a straight line of live cells.
+    Line,
+    /// Drag the outline of a rectangle of live cells.
+    Rectangle,
+    /// Drag a rectangle of live cells.
+    FilledRectangle,
+}
+
+impl Tool {
+    /// Parse the name used by the page's tool dropdown.
+    pub fn from_name(name: &str) -> Option<Tool> {
+        match name {
+            "paint" => Some(Tool::Paint),
+            "select" => Some(Tool::Select),
+            "line" => Some(Tool::Line),
+            "rectangle" => Some(Tool::Rectangle),
+            "filled-rectangle" => Some(Tool::FilledRectangle),
+            _ => None,
+        }
+    }
+
+    /// Whether dragging with the tool draws a shape between two cells.
+    pub fn draws_shapes(self) -> bool {
+        matches!(self, Tool::Line | Tool::Rectangle | Tool::FilledRectangle)
+    }
+}
+
+/// A pattern waiting to be placed, previewed under the cursor.
+struct Placement {
+    pattern: Pattern,
+    /// Where the pattern's top-left corner would go, once the cursor has been
+    /// over the universe.
+    at: Option<(u32, u32)>,
+}
+
+/// A rectangle of cells picked with the select tool, between two opposite
+/// corners.
+#[derive(Clone, Copy, Debug)]
+struct Selection {
+    /// The (`row`, `column`) where the drag started.
+    anchor: (u32, u32),
+    /// The (`row`, `column`) where the drag is now or ended.
+    corner: (u32, u32),
+    dragging: bool,
+}
+
+impl Selection {
+    fn rows(&self) -> std::ops::Range<u32> {
+        self.anchor.0.min(self.corner.0)..self.anchor.0.max(self.corner.0) + 1
+    }
+
+    fn columns(&self) -> std::ops::Range<u32> {
+        self.anchor.1.min(self.corner.1)..self.anchor.1.max(self.corner.1) + 1
+    }
+}
+
+/// What the view stays on as the universe runs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Tracking {
+    /// The middle of all the live cells.
+    Centroid,
+    /// The object last seen with its middle at this (`row`, `column`),
+    /// found again on every draw as the one nearest there.
+    Object(f64, f64),
+}
+
+/// A line or rectangle being dragged out, previewed until the drag ends.
+#[derive(Clone, Copy, Debug)]
+struct Drawing {
+    tool: Tool,
+    /// The (`row`, `column`) where the drag started.
+    anchor: (u32, u32),
+    /// The (`row`, `column`) where the drag is now.
+    corner: (u32, u32),
+}
+
+impl Drawing {
+    /// The cells the shape covers.
+    fn cells(&self) -> Vec<(u32, u32)> {
+        match self.tool {
+            Tool::Line => brush::line(self.anchor, self.corner),
+            tool => brush::rectangle(self.anchor, self.corner, tool == Tool::FilledRectangle),
+        }
+    }
+}
+
+/// The engine `Canvas` uses to advance its universe.
+enum Engine {
+    /// Tick the universe directly.
+    Naive,
+    /// Run the universe through HashLife and copy back the visible region.
+    /// Rebuilt from the universe on the first step after an edit.
+    HashLife(Option<HashLife>),
+    /// Step a copy of the universe in a Web Worker, which sends back the
+    /// cells asynchronously.
+    Worker(WorkerEngine),
+    /// Run the universe on an unbounded plane of chunks, which is drawn
+    /// straight from its chunks and can be panned around beyond the edges,
+    /// so patterns that leave the universe stay in sight. The universe keeps
+    /// the part of the plane it covers, which is where edits go. Built from
+    /// the universe on the first step.
+    Chunked(Option<ChunkedPlane>),
+    /// Run Lenia, whose continuous levels are drawn as shades, and copy back
+    /// which cells are mostly alive. Built from the universe on the first
+    /// step.
+    Lenia(Option<Lenia>),
+    /// Run the loaded rule table, whose states are drawn in their own
+    /// colors, and copy back which cells are in any state but 0. Built from
+    /// the universe on the first step.
+    Table(Option<TableBoard>),
+    /// Run an elementary automaton, one generation to a row, and copy back
+    /// the whole board. Built from the universe on the first step.
+    Elementary(Option<Elementary>),
+    /// Walk a turmite over the board, drawing its tape colors and its head,
+    /// and copy back which cells are any color but the first. Built from
+    /// the universe on the first step.
+    Turmite(Option<Turmite>),
+    /// Let sand, water and walls fall, drawn in their own colors, and copy
+    /// back which cells hold anything. Built from the universe on the first
+    /// step.
+    Sand(Option<Sand>),
+}
+
+#[wasm_bindgen]
+pub struct Canvas {
+    pub(crate) canvas: web_sys::HtmlCanvasElement,
+    context: web_sys::CanvasRenderingContext2d,
+    pub(crate) universe: Universe,
+    engine: Engine,
+    /// The rule table the table engine runs, once one is loaded.
+    rule_table: Option<RuleTable>,
+    /// The number of the rule the elementary engine runs.
+    elementary_rule: u8,
+    /// The turmite the turmite engine runs.
+    turmite: TurmiteSpec,
+    /// What painting fills cells with under the falling-sand engine.
+    material: Material,
+
+    pub(crate) viewport: Viewport,
+    /// The viewport the canvas was last painted with, or `None` when it
+    /// must be repainted from scratch.
+    drawn_viewport: Option<Viewport>,
+    /// The color key of every cell as it was last painted, in row-major
+    /// order: its state, `AGE_KEYS` plus its shade when shading by age,
+    /// `TRAIL_KEYS` plus its trail when showing trails, `LENIA_KEYS` plus
+    /// its shade under Lenia, `TABLE_KEYS` plus its state under a rule
+    /// table, `TURMITE_KEYS` plus its color under a turmite, or `SAND_KEYS`
+    /// plus its material under the falling-sand engine.
+    drawn: Vec<u16>,
+    /// For `RenderMode::Trails`, how many more generations each cell's trail
+    /// lasts: `TRAIL_FRAMES` while it is alive, counting down once it dies.
+    trails: Vec<u8>,
+    /// The generation `trails` was last counted down for.
+    trail_generation: Option<u64>,
+    history: History<Snapshot>,
+    placement: Option<Placement>,
+    tool: Tool,
+    /// What painting covers.
+    brush: Brush,
+    /// Which mirror images of painted cells are painted too.
+    symmetry: Symmetry,
+    selection: Option<Selection>,
+    drawing: Option<Drawing>,
+    /// The population graph, if the page has somewhere to draw it.
+    sparkline: Option<Sparkline>,
+    /// The overview of the whole universe, if the page has somewhere to draw
+    /// it.
+    minimap: Option<Minimap>,
+    pub(crate) theme: Theme,
+    render_mode: RenderMode,
+    renderer: Renderer,
+    /// Created the first time `Renderer::Image` draws.
+    image: Option<ImageRenderer>,
+    /// Created the first time the grid is drawn.
+    grid: Option<GridRenderer>,
+    /// Whether to tint cells by how often they changed recently.
+    show_activity: bool,
+    /// The GIF being recorded, and the generation of its last frame.
+    recording: Option<(GifRecorder, u64)>,
+    /// Created the first time sound is turned on.
+    sound: Option<Sonifier>,
+    /// The (`row`, `column`) of the keyboard cursor, in accessibility mode.
+    cursor: Option<(u32, u32)>,
+    /// When the accessible summary was last written, or `None` if it should
+    /// be written on the next draw.
+    summarized: Option<f64>,
+    /// Every edit since logging was started, to save and play back.
+    replay_log: Option<ReplayLog>,
+    /// The log being played back in place of simulating, until it ends or
+    /// the board is edited.
+    replaying: Option<Replay>,
+    /// The relay server edits are shared through, while connected.
+    network: Option<Connection>,
+    /// Where the board lies in the universe of a simulation server, when
+    /// the relay is one. The server steps its universe and sends back the
+    /// part the page shows, so that this page only draws it.
+    served: Option<Window>,
+    /// How far the view has been panned past the edges of the served
+    /// board, in rows and columns, until it adds up to whole cells to move
+    /// the window by.
+    served_drift: (f64, f64),
+    /// Peer-to-peer sessions, each with a guest if this page is hosting, or
+    /// with the host if it joined one.
+    peers: Vec<PeerSession>,
+    /// The (`row`, `column`) of the cells to tint as differing from another
+    /// board.
+    difference: Vec<(u32, u32)>,
+    /// A checkpoint's board to tint the differences from on every draw.
+    diff_from: Option<Universe>,
+    /// The id of the element the generation and population are shown in,
+    /// if any.
+    stats_id: Option<&'static str>,
+    /// Generations between censuses of the objects on the board, or 0 for
+    /// none.
+    census_interval: u32,
+    /// The latest census, shown in `#census`.
+    census: Option<Census>,
+    /// Whether this canvas is shown beside the main one, which keeps the
+    /// timeline to itself.
+    secondary: bool,
+    /// Whether to keep the live cells in the middle of the view as they
+    /// move, zooming out when they outgrow it.
+    follow: bool,
+    /// What the view glides after on every draw, if anything. Takes over
+    /// from `follow`.
+    tracking: Option<Tracking>,
+}
+
+#[wasm_bindgen]
+impl Canvas {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        mut universe: Universe,
+        canvas: web_sys::HtmlCanvasElement,
+    ) -> Result<Canvas, JsError> {
+        // Canvases only give out one kind of context, so asking for a 2d one
+        // finds nothing when something else already drew on it with WebGL.
+        let context = match canvas.get_context("2d") {
+            Ok(Some(context)) => context.unchecked_into::<web_sys::CanvasRenderingContext2d>(),
+            Ok(None) => {
+                return Err(JsError::new(
+                    "the canvas already has a context other than a 2d one",
+                ))
+            }
+            Err(err) => {
+                return Err(JsError::new(&format!(
+                    "couldn't get a 2d context for the canvas: {:?}",
+                    err
+                )))
+            }
+        };
+
+        let sparkline = canvas
+            .owner_document()
+            .and_then(|document| Sparkline::from_document(&document));
+        let minimap = canvas
+            .owner_document()
+            .and_then(|document| Minimap::from_document(&document));
+        universe.set_checkpoint_interval(DEFAULT_CHECKPOINT_INTERVAL);
+
+        let mut canvas = Canvas {
+            canvas,
+            context,
+            viewport: Viewport::new(DEFAULT_CELL_SIZE, universe.width(), universe.height()),
+            drawn_viewport: None,
+            drawn: Vec::new(),
+            trails: Vec::new(),
+            trail_generation: None,
+            history: History::default(),
+            placement: None,
+            tool: Tool::Paint,
+            brush: Brush::default(),
+            symmetry: Symmetry::None,
+            selection: None,
+            drawing: None,
+            sparkline,
+            minimap,
+            theme: Theme::default(),
+            render_mode: RenderMode::States,
+            renderer: Renderer::Rects,
+            image: None,
+            grid: None,
+            show_activity: false,
+            recording: None,
+            sound: None,
+            cursor: None,
+            summarized: None,
+            replay_log: None,
+            network: None,
+            served: None,
+            served_drift: (0.0, 0.0),
+            peers: Vec::new(),
+            replaying: None,
+            difference: Vec::new(),
+            diff_from: None,
+            census_interval: 0,
+            census: None,
+            stats_id: Some("stats"),
+            secondary: false,
+            follow: false,
+            tracking: None,
+            universe,
+            engine: Engine::Naive,
+            rule_table: None,
+            elementary_rule: elementary::DEFAULT_RULE,
+            turmite: TurmiteSpec::default(),
+            material: Material::Sand,
+        };
+        canvas.fit_to_universe();
+        Ok(canvas)
+    }
+
+    /// Resize the universe, clearing every cell, and grow or shrink the
+    /// canvas element to match. Sizes of more than `MAX_CELLS` cells are
+    /// cut down to fit.
+    pub fn set_size(&mut self, width: u32, height: u32) {
+        self.checkpoint();
+        self.universe.set_width(width);
+        self.universe.set_height(height);
+        let (width, height) = (self.universe.width(), self.universe.height());
+        self.log_edit(Edit::Resize { width, height });
+        self.invalidate_engine();
+        self.fit_to_universe();
+        // Bring the keyboard cursor back inside if the universe shrank.
+        self.move_cursor(0, 0);
+        self.draw();
+    }
+
+    pub fn width(&self) -> u32 {
+        self.universe.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.universe.height()
+    }
+
+    pub fn cells(&self) -> Vec<u8> {
+        self.universe.cells()
+    }
+
+    pub fn clear(&mut self) {
+        self.checkpoint();
+        self.universe.clear();
+        self.log_edit(Edit::Clear);
+        self.invalidate_engine();
+    }
+
+    pub fn fill_stripes(&mut self) {
+        self.checkpoint();
+        self.universe.fill_stripes();
+        self.log_board();
+        self.invalidate_engine();
+    }
+
+    pub fn randomize(&mut self, density: f64) {
+        self.checkpoint();
+        self.universe.randomize(density);
+        self.log_board();
+        self.invalidate_engine();
+    }
+
+    pub fn randomize_with_seed(&mut self, seed: u64, density: f64) {
+        self.checkpoint();
+        self.universe.randomize_with_seed(seed, density);
+        self.log_board();
+        self.invalidate_engine();
+    }
+
+    pub fn translate(&mut self, rows: i32, columns: i32) {
+        self.checkpoint();
+        self.universe.translate(rows, columns);
+        self.log_board();
+        self.invalidate_engine();
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.universe.generation()
+    }
+
+    pub fn population(&self) -> u32 {
+        self.universe.population()
+    }
+
+    pub fn births(&self) -> u32 {
+        self.universe.births()
+    }
+
+    pub fn deaths(&self) -> u32 {
+        self.universe.deaths()
+    }
+
+    /// See `Universe::detect_period`.
+    pub fn detect_period(&self, max_lookback: u32) -> u32 {
+        self.universe.detect_period(max_lookback)
+    }
+
+    pub fn cell_size(&self) -> u32 {
+        self.viewport.cell_size()
+    }
+
+    /// Zoom to `cell_size` pixels per cell, keeping the cell under the canvas
+    /// pixel (`x`, `y`) in place.
+    pub fn zoom_at(&mut self, cell_size: u32, x: f64, y: f64) {
+        self.viewport.zoom_at(cell_size, x, y);
+        self.fit_to_universe();
+    }
+
+    /// Zoom to `cell_size` pixels per cell around the middle of the canvas.
+    pub fn zoom(&mut self, cell_size: u32) {
+        self.viewport.zoom(cell_size);
+        self.fit_to_universe();
+    }
+
+    /// Zoom in as far as still shows every live cell, and center on them.
+    pub fn fit_to_pattern(&mut self) {
+        if let Some((rows, columns)) = self.universe.bounding_box() {
+            self.viewport.frame(rows, columns);
+            self.fit_to_universe();
+        }
+    }
+
+    /// Keep the live cells in the middle of the view on every draw, zooming
+    /// out whenever they outgrow it, or stop.
+    pub fn set_follow(&mut self, follow: bool) {
+        self.follow = follow;
+        self.draw();
+    }
+
+    /// Center the view on the cell under the pixel (`x`, `y`) of the
+    /// minimap.
+    pub fn jump_to_minimap(&mut self, x: f64, y: f64) {
+        let cell = match &self.minimap {
+            Some(minimap) => minimap.cell_at(&self.universe, x, y),
+            None => None,
+        };
+        if let Some((row, column)) = cell {
+            self.viewport.center_on(row..row + 1, column..column + 1);
+            self.draw();
+        }
+    }
+
+    /// Glide after the middle of all the live cells on every draw.
+    pub fn track_centroid(&mut self) {
+        self.tracking = Some(Tracking::Centroid);
+        self.draw();
+    }
+
+    /// Glide after the object in the selection on every draw, and drop the
+    /// selection. Returns false, tracking nothing, if there are no live cells
+    /// in the selection.
+    pub fn track_selection(&mut self) -> bool {
+        let selection = match self.selection {
+            Some(selection) => selection,
+            None => return false,
+        };
+        let cells: Vec<_> = selection
+            .rows()
+            .flat_map(|row| selection.columns().map(move |col| (row, col)))
+            .filter(|&(row, col)| self.universe.get(Position::new(row, col)) == Some(Cell::Alive))
+            .collect();
+        if cells.is_empty() {
+            return false;
+        }
+        let (row, column) = centroid(&cells);
+        self.tracking = Some(Tracking::Object(row, column));
+        self.selection = None;
+        self.draw();
+        true
+    }
+
+    pub fn stop_tracking(&mut self) {
+        self.tracking = None;
+    }
+
+    pub fn is_tracking(&self) -> bool {
+        self.tracking.is_some()
+    }
+
+    /// Switch to the colors of `theme` and repaint.
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.theme = theme.clone();
+        if let Some(sparkline) = &mut self.sparkline {
+            sparkline.set_color(&theme.text);
+        }
+        self.drawn_viewport = None;
+        self.draw();
+    }
+
+    pub fn render_mode(&self) -> RenderMode {
+        self.render_mode
+    }
+
+    /// Change how cells are colored and repaint.
+    pub fn set_render_mode(&mut self, mode: RenderMode) {
+        self.render_mode = mode;
+        self.trails.clear();
+        self.trail_generation = None;
+        self.drawn_viewport = None;
+        self.draw();
+    }
+
+    /// Split ticks across threads, if they are available. Returns whether
+    /// they are.
+    pub fn set_parallel(&mut self, parallel: bool) -> bool {
+        self.universe.set_parallel(parallel)
+    }
+
+    /// Flip each cell with probability `noise` on every tick. See
+    /// `Universe::set_noise`.
+    pub fn set_noise(&mut self, noise: f64) {
+        let edit = self.reseed_noise(noise);
+        self.log_edit(edit);
+        self.invalidate_engine();
+    }
+
+    /// Set the noise, drawing it from now on from a new seed, and return
+    /// the edit that does the same, so that replays and peers flip the same
+    /// cells.
+    fn reseed_noise(&mut self, probability: f64) -> Edit {
+        let seed = self.universe.rng.next_u64();
+        let edit = Edit::Noise { probability, seed };
+        // Noise edits can't fail.
+        let _ = edit.apply(&mut self.universe);
+        edit
+    }
+
+    /// Choose how cells get onto the canvas.
+    pub fn set_renderer(&mut self, renderer: Renderer) {
+        self.renderer = renderer;
+        self.drawn_viewport = None;
+        self.draw();
+    }
+
+    /// Time `frames` full repaints with each renderer, and describe how long
+    /// a frame took on average. Rects are usually faster when few cells
+    /// change, since they only paint those.
+    pub fn benchmark_renderers(&mut self, frames: u32) -> String {
+        let renderer = self.renderer;
+        let mut results = Vec::new();
+        for &candidate in [Renderer::Rects, Renderer::Image].iter() {
+            self.renderer = candidate;
+            let start = js_sys::Date::now();
+            for _ in 0..frames {
+                self.drawn_viewport = None;
+                self.draw();
+            }
+            let per_frame = (js_sys::Date::now() - start) / frames.max(1) as f64;
+            results.push(format!("{}: {:.2} ms", candidate.name(), per_frame));
+        }
+        self.set_renderer(renderer);
+        results.join(", ")
+    }
+
+    /// Start recording every generation drawn from now on into a GIF, each
+    /// shown for `delay` milliseconds, up to `max_frames` frames. Cells are
+    /// as many pixels across as they are on the canvas.
+    pub fn start_recording(&mut self, delay: u32, max_frames: u32) -> Result<(), JsValue> {
+        let mut recorder = GifRecorder::new(
+            self.universe.width(),
+            self.universe.height(),
+            self.viewport.cell_size(),
+            delay,
+            max_frames,
+            &self.theme.dead,
+            &self.theme.alive,
+        )?;
+        recorder.capture(&self.universe)?;
+        self.recording = Some((recorder, self.universe.generation()));
+        Ok(())
+    }
+
+    /// The whole universe as a PNG file, with cells `scale` pixels across
+    /// whatever the zoom.
+    pub fn export_png(&self, scale: u32) -> Result<Vec<u8>, JsValue> {
+        Ok(encode_png(
+            &self.universe,
+            scale,
+            &self.theme.dead,
+            &self.theme.alive,
+        )?)
+    }
+
+    /// A looping animated PNG of the next `generations` generations, cropped
+    /// to where they happen, without advancing the universe. Each frame is
+    /// shown for `delay` milliseconds.
+    pub fn export_apng(
+        &self,
+        generations: u32,
+        scale: u32,
+        delay: u32,
+    ) -> Result<Vec<u8>, JsValue> {
+        Ok(encode_apng(
+            &self.universe,
+            generations,
+            scale,
+            delay,
+            &self.theme.dead,
+            &self.theme.alive,
+        )?)
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// How many frames the recording has so far.
+    pub fn recorded_frames(&self) -> u32 {
+        self.recording
+            .as_ref()
+            .map_or(0, |(recorder, _)| recorder.frames())
+    }
+
+    /// Stop recording and return the GIF file.
+    pub fn stop_recording(&mut self) -> Result<Vec<u8>, JsValue> {
+        match self.recording.take() {
+            Some((recorder, _)) => Ok(recorder.finish()?),
+            None => Err(JsValue::from_str("not recording")),
+        }
+    }
+
+    /// Play each new generation as notes of `scale` at `volume`, from 0 to 1,
+    /// or stop if `on` is false. Browsers only allow sound to start from a
+    /// user's input, so the first call that turns it on must come from an
+    /// event handler.
+    pub fn set_sound(
+        &mut self,
+        on: bool,
+        volume: f32,
+        scale: Scale,
+        sonification: Sonification,
+    ) -> Result<(), JsValue> {
+        if self.sound.is_none() {
+            if !on {
+                return Ok(());
+            }
+            self.sound = Some(Sonifier::new()?);
+        }
+        let sound = self.sound.as_mut().unwrap();
+        sound.set_volume(volume);
+        sound.set_scale(scale);
+        sound.set_sonification(sonification);
+        sound.set_muted(!on)
+    }
+
+    /// Show or hide the activity overlay. Activity is only counted while it
+    /// is shown.
+    pub fn set_show_activity(&mut self, show: bool) {
+        self.show_activity = show;
+        if show {
+            self.universe.track_activity(ACTIVITY_WINDOW);
+        } else {
+            self.universe.stop_tracking_activity();
+        }
+        self.drawn_viewport = None;
+        self.draw();
+    }
+
+    /// Start or stop remembering the changes of the last
+    /// `DEFAULT_REWIND_STEPS` steps, or fewer on busy boards, so that the
+    /// timeline can wind back through them.
+    pub fn set_rewind(&mut self, on: bool) {
+        if on {
+            self.universe.track_rewind(DEFAULT_REWIND_STEPS);
+        } else {
+            self.universe.stop_tracking_rewind();
+        }
+        self.draw();
+    }
+
+    /// Put the board back how it was `steps` steps before the newest one
+    /// without simulating again. See `Universe::rewind_to`.
+    pub fn rewind_to(&mut self, steps: u32) {
+        self.universe.rewind_to(steps);
+        self.log_board();
+        self.invalidate_engine();
+        self.draw();
+    }
+
+    /// Draw every cell as a `cell_size` pixel square, resizing the canvas
+    /// element to show as much of the universe as fits and repainting it.
+    /// The size is clamped to the range the viewport allows.
+    pub fn set_cell_size(&mut self, cell_size: u32) {
+        self.zoom(cell_size);
+        self.draw();
+    }
+
+    /// Show or hide the lines between cells. They are left out anyway while
+    /// cells are smaller than `MIN_GRID_CELL_SIZE`.
+    pub fn set_show_grid(&mut self, show: bool) {
+        self.viewport.set_show_grid(show);
+        self.fit_to_universe();
+        self.draw();
+    }
+
+    /// Scroll the view by the given number of pixels. Pages shown part of
+    /// a simulation server's universe ask for another part once they pan
+    /// past its edges.
+    pub fn pan(&mut self, dx: f64, dy: f64) {
+        let before = self.viewport.center();
+        self.viewport.pan(dx, dy);
+        let (window, network) = match (self.served, &self.network) {
+            (Some(window), Some(network)) => (window, network),
+            _ => return,
+        };
+        let (after, pitch) = (self.viewport.center(), self.viewport.pitch());
+        let drift = &mut self.served_drift;
+        drift.0 += dy / pitch - (after.0 - before.0);
+        drift.1 += dx / pitch - (after.1 - before.1);
+        let (rows, columns) = (drift.0.trunc(), drift.1.trunc());
+        if rows == 0.0 && columns == 0.0 {
+            return;
+        }
+        *drift = (drift.0 - rows, drift.1 - columns);
+        let (width, height) = (self.universe.width(), self.universe.height());
+        let view = window.panned(rows as i64, columns as i64, width, height);
+        network.send(&NetworkMessage::View(view));
+    }
+
+    /// Choose what happens at the edges of the universe.
+    ///
+    /// The HashLife and chunked engines always simulate an unbounded plane
+    /// and ignore this setting.
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.universe.set_topology(topology);
+        self.log_edit(Edit::Topology { topology });
+        self.invalidate_engine();
+    }
+
+    pub fn set_rule(&mut self, rule: &str) -> Result<(), RuleError> {
+        self.change_rule(Rule::parse(rule)?);
+        Ok(())
+    }
+
+    /// Keep the rule's birth and survival counts but count a different
+    /// neighborhood.
+    pub fn set_neighborhood(&mut self, neighborhood: Neighborhood) {
+        self.change_rule(self.universe.rule().with_neighborhood(neighborhood));
+    }
+
+    fn change_rule(&mut self, rule: Rule) {
+        self.checkpoint();
+        self.universe.apply_rule(rule);
+        self.log_edit(Edit::Rule { rule });
+        self.invalidate_engine();
+        // The colors of dying cells depend on the number of states.
+        self.drawn_viewport = None;
+    }
+
+    /// Select the engine used to advance the universe: `"naive"`,
+    /// `"hashlife"`, `"chunked"`, `"lenia"`, which ignores the rule,
+    /// `"elementary"` for a one-dimensional automaton, `"turmite"`,
+    /// `"sand"` for falling sand, or `"table"` once a rule table is loaded. The worker engine needs a
+    /// shared handle on the canvas and is started by the page instead.
+    pub fn set_engine(&mut self, name: &str) {
+        self.engine = match name {
+            "hashlife" => Engine::HashLife(None),
+            "chunked" => Engine::Chunked(None),
+            "lenia" => Engine::Lenia(None),
+            "table" if self.rule_table.is_some() => Engine::Table(None),
+            "elementary" => Engine::Elementary(None),
+            "turmite" => Engine::Turmite(None),
+            "sand" => Engine::Sand(None),
+            _ => Engine::Naive,
+        };
+        if let Engine::Chunked(plane) = &mut self.engine {
+            *plane = chunked_plane(&self.universe);
+        }
+        // Lenia, rule tables, turmites and sand draw their own colors.
+        self.drawn_viewport = None;
+        self.draw();
+    }
+
+    pub fn tick(&mut self) {
+        self.advance(1);
+    }
+
+    pub fn step(&mut self, generations: u32) {
+        self.advance(generations as u64);
+    }
+
+    /// Advance the universe by `2^exponent` generations at once, returning
+    /// whether it did.
+    ///
+    /// This is only fast with the HashLife engine under a rule it runs.
+    /// Everything else steps one generation at a time, so jumps longer than
+    /// `2^MAX_STEPPED_JUMP` generations are refused rather than left to
+    /// freeze the page.
+    pub fn step_pow2(&mut self, exponent: u8) -> bool {
+        let hashlife =
+            matches!(self.engine, Engine::HashLife(_)) && runs_on_a_plane(&self.universe);
+        let longest = if hashlife { 63 } else { MAX_STEPPED_JUMP };
+        if exponent > longest {
+            return false;
+        }
+        self.advance(1 << exponent);
+        true
+    }
+
+    /// Step until the board settles into a still life or oscillator, or
+    /// `max_generations` have been run. See `Universe::run_until_stable`.
+    ///
+    /// Boards stepped by a worker or the simulation server only change once
+    /// their answer arrives, so those are sent all `max_generations` at once
+    /// and only settle on a later call.
+    pub fn run_until_stable(&mut self, max_generations: u32) -> Stabilization {
+        if self.served.is_some() || matches!(self.engine, Engine::Worker(_)) {
+            let period = self.universe.settled_period();
+            if period == 0 {
+                self.advance(max_generations as u64);
+            }
+            return Stabilization {
+                generations: if period == 0 { max_generations } else { 0 },
+                period,
+            };
+        }
+        let mut generations = 0;
+        loop {
+            let period = self.universe.settled_period();
+            if period > 0 || generations == max_generations {
+                return Stabilization {
+                    generations,
+                    period,
+                };
+            }
+            self.advance(1);
+            generations += 1;
+        }
+    }
+
+    /// Step one generation and hand back what the rule did, for a hook to
+    /// change before `finish_generation`. See `scripting`.
+    pub fn propose_generation(&mut self) -> NextGeneration {
+        let before = self.universe.cells();
+        let live_neighbors = self.universe.live_neighbor_counts();
+        self.advance(1);
+        NextGeneration::new(before, live_neighbors, &self.universe)
+    }
+
+    /// Apply what the hook changed in `next`, logged as edits so that
+    /// replays and peers see the same generation.
+    pub fn finish_generation(&mut self, next: &NextGeneration) {
+        let overrides = next.take_overrides();
+        if overrides.is_empty() {
+            return;
+        }
+        self.universe.override_cells(&overrides);
+        let width = self.universe.width();
+        let mut cells: BTreeMap<u8, Vec<(u32, u32)>> = BTreeMap::new();
+        for (idx, _, state) in overrides {
+            let position = (idx as u32 / width, idx as u32 % width);
+            cells.entry(state).or_default().push(position);
+        }
+        for (state, cells) in cells {
+            self.log_edit(Edit::Cells {
+                cell: Cell::from_state(state),
+                cells,
+            });
+        }
+        self.invalidate_engine();
+    }
+
+    /// The cell at `position`, or `None` off the board.
+    pub fn cell(&self, position: Position) -> Option<Cell> {
+        self.universe.get(position)
+    }
+
+    /// Make the cell at `position` `cell`, returning what it was, or `None`
+    /// off the board.
+    pub fn set_cell(&mut self, position: Position, cell: Cell) -> Option<Cell> {
+        let was = self.universe.set_cell(position, cell)?;
+        self.log_edit(Edit::Cells {
+            cell,
+            cells: vec![(position.row, position.column)],
+        });
+        self.invalidate_engine();
+        Some(was)
+    }
+
+    /// Paint `cell` under the brush centred on (`row`, `column`) and its
+    /// mirror images. Like `set_cell`, this leaves checkpoints to the
+    /// caller. Returns whether any cell changed.
+    pub fn paint(&mut self, row: u32, column: u32, cell: Cell) -> bool {
+        let mut cells = Vec::new();
+        for (row, column) in self.mirror_images(row, column) {
+            cells.extend(self.universe.paint_cells(row, column, &self.brush, cell));
+        }
+        if cells.is_empty() {
+            return false;
+        }
+        self.log_edit(Edit::Cells { cell, cells });
+        self.invalidate_engine();
+        true
+    }
+
+    /// Toggle the cell at `position`, returning what it became, or `None`
+    /// off the board.
+    pub fn toggle_cell(&mut self, position: Position) -> Option<Cell> {
+        if !self.universe.contains(position) {
+            return None;
+        }
+        self.checkpoint();
+        let cell = self.universe.toggle_cell(position);
+        let Position { row, column } = position;
+        self.log_edit(Edit::Toggle { row, column });
+        self.invalidate_engine();
+        cell
+    }
+
+    /// Copy `pattern` onto the board with its top-left corner at `position`.
+    /// Returns whether the corner was on the board.
+    pub fn insert_pattern(&mut self, position: Position, pattern: &Pattern) -> bool {
+        if !self.universe.contains(position) {
+            return false;
+        }
+        self.checkpoint();
+        self.universe.insert_pattern(position, pattern);
+        let Position { row, column } = position;
+        self.log_edit(Edit::Stamp {
+            row,
+            column,
+            pattern: pattern.clone(),
+        });
+        self.invalidate_engine();
+        true
+    }
+
+    pub fn to_rle(&self) -> String {
+        self.universe.to_rle()
+    }
+
+    /// Load a macrocell (`.mc`) file straight into the HashLife engine, with
+    /// the top-left corner of its quadtree at the top-left of the universe.
+    /// Only the part that fits in the universe is ever expanded into cells.
+    pub fn load_macrocell(&mut self, text: &str) -> Result<(), ParseError> {
+        let mut hashlife = match HashLife::from_macrocell(text) {
+            Ok(hashlife) => hashlife,
+            Err(err) => {
+                trace_event!(warn, error = ?err, "could not import macrocell");
+                return Err(err);
+            }
+        };
+        trace_event!(info, rule = %hashlife.rule(), "imported macrocell");
+        hashlife.move_to_corner();
+        hashlife.set_size(self.universe.width(), self.universe.height());
+        self.checkpoint();
+        if hashlife.rule() != self.universe.rule() {
+            self.universe.apply_rule(hashlife.rule());
+            self.drawn_viewport = None;
+        }
+        self.universe.clear();
+        self.universe.copy_from(&hashlife);
+        self.log_board();
+        self.engine = Engine::HashLife(Some(hashlife));
+        Ok(())
+    }
+
+    /// Run the rule table in the contents of a `.rule` file on the table
+    /// engine, starting from the cells of the universe.
+    pub fn load_rule_table(&mut self, text: &str) -> Result<(), TableError> {
+        self.rule_table = Some(RuleTable::parse(text)?);
+        self.set_engine("table");
+        Ok(())
+    }
+
+    /// Run the elementary automaton numbered `rule` on the elementary
+    /// engine.
+    pub fn set_elementary_rule(&mut self, rule: u8) {
+        self.elementary_rule = rule;
+        if let Engine::Elementary(Some(elementary)) = &mut self.engine {
+            elementary.set_rule(rule);
+        }
+    }
+
+    /// Clear the board and run the elementary automaton numbered `rule` from
+    /// a single live cell in the middle of the top row.
+    pub fn start_elementary(&mut self, rule: u8) {
+        self.checkpoint();
+        self.universe.clear();
+        if self.universe.height() > 0 {
+            let middle = self.universe.width() / 2;
+            self.universe
+                .set_cell(Position::new(0, middle), Cell::Alive);
+        }
+        self.log_board();
+        self.elementary_rule = rule;
+        self.set_engine("elementary");
+    }
+
+    /// Fill the cells painted from now on with `material` under the
+    /// falling-sand engine. Cells that are already full keep what they
+    /// hold until they are erased.
+    pub fn set_material(&mut self, material: Material) {
+        self.material = material;
+        if let Engine::Sand(Some(sand)) = &mut self.engine {
+            sand.set_material(material);
+        }
+    }
+
+    /// Clear the board and walk the turmite `spec`, in Golly's notation or
+    /// as a string of turns such as `RL`, from the middle of it.
+    pub fn start_turmite(&mut self, spec: &str) -> Result<(), TurmiteError> {
+        self.turmite = TurmiteSpec::parse(spec)?;
+        self.checkpoint();
+        self.universe.clear();
+        self.log_board();
+        self.set_engine("turmite");
+        Ok(())
+    }
+
+    /// Write the plane as a macrocell file. With the HashLife engine this
+    /// includes cells that have left the universe. Only Life-like rules
+    /// without `B0` can be written, since a macrocell's plane has no edges.
+    pub fn to_macrocell(&self) -> Result<String, RuleError> {
+        match &self.engine {
+            Engine::HashLife(Some(hashlife)) => Ok(hashlife.to_macrocell()),
+            _ => {
+                let mut hashlife = HashLife::new(self.universe.width(), self.universe.height());
+                hashlife.set_rule(self.universe.rule())?;
+                hashlife.copy_from(&self.universe);
+                Ok(hashlife.to_macrocell())
+            }
+        }
+    }
+
+    /// Replace the universe with `universe`, keeping the current topology.
+    pub fn set_universe(&mut self, mut universe: Universe) {
+        self.checkpoint();
+        universe.topology = self.universe.topology;
+        universe.rng = self.universe.rng.clone();
+        universe.set_parallel(self.universe.parallel());
+        if self.show_activity {
+            universe.track_activity(ACTIVITY_WINDOW);
+        }
+        self.universe = universe;
+        self.log_board();
+        self.invalidate_engine();
+        self.fit_to_universe();
+    }
+
+    /// The board along with everything about how it is simulated and
+    /// shown, to save as JSON.
+    pub fn scenario(&self) -> Scenario {
+        Scenario::new(
+            self.universe.clone(),
+            Some(View::of(&self.viewport)),
+            Some(self.theme.clone()),
+        )
+    }
+
+    /// Set up `scenario`, keeping the current view and theme if it leaves
+    /// them out.
+    pub fn load_scenario(&mut self, scenario: &Scenario) {
+        let universe = scenario.universe();
+        let topology = universe.topology();
+        self.set_universe(universe);
+        self.set_topology(topology);
+        if let Some(view) = scenario.view() {
+            self.viewport.set_show_grid(view.show_grid);
+            self.zoom(view.cell_size);
+            let (row, column) = view.center;
+            self.viewport.glide_to(row, column, 1.0);
+        }
+        match scenario.theme() {
+            Some(theme) => self.set_theme(theme),
+            None => self.draw(),
+        }
+    }
+
+    /// Save the universe in `localStorage` under `name`.
+    pub fn save_to_storage(&self, name: &str) -> Result<(), JsValue> {
+        storage::save_to_storage(&self.universe, name)
+    }
+
+    /// Load the universe saved under `name`. Returns whether there was one.
+    pub fn load_from_storage(&mut self, name: &str) -> Result<bool, JsValue> {
+        match storage::load_from_storage(name)? {
+            Some(universe) => {
+                self.set_universe(universe);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Remember the board so that `undo` can return to it. Edits through
+    /// `Canvas` do this themselves, except `set_cell`, so that a stroke of
+    /// several cells can be undone at once.
+    pub fn checkpoint(&mut self) {
+        self.history.push(self.universe.snapshot());
+    }
+
+    /// Wind back the last step, if the rewind timeline remembers it, or
+    /// else go back to the board before the last edit. Returns whether
+    /// there was anything to undo.
+    pub fn undo(&mut self) -> bool {
+        // Edits clear the timeline, so every step on it came after the last.
+        let rewound = self.universe.rewound();
+        if rewound < self.universe.rewind_steps() {
+            self.rewind_to(rewound + 1);
+            return true;
+        }
+        match self.history.undo(self.universe.snapshot()) {
+            Some(snapshot) => {
+                self.restore(snapshot);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Reapply the last undone step or edit. Returns whether there was
+    /// anything to redo.
+    pub fn redo(&mut self) -> bool {
+        let rewound = self.universe.rewound();
+        if rewound > 0 {
+            self.rewind_to(rewound - 1);
+            return true;
+        }
+        match self.history.redo(self.universe.snapshot()) {
+            Some(snapshot) => {
+                self.restore(snapshot);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Keep a compressed copy of the board to go back to with
+    /// `restore_checkpoint`, returning its id.
+    pub fn save_checkpoint(&mut self) -> u32 {
+        self.universe.checkpoint()
+    }
+
+    /// Go back to the board kept as checkpoint `id`, which can be undone.
+    /// Returns whether there was such a checkpoint.
+    pub fn restore_checkpoint(&mut self, id: u32) -> bool {
+        let (snapshot, shape) = (self.universe.snapshot(), self.shape());
+        if !self.universe.restore(id) {
+            return false;
+        }
+        self.history.push(snapshot);
+        self.log_board();
+        self.restored(shape);
+        true
+    }
+
+    /// Count the known objects on the board every `generations` generations,
+    /// starting now, or stop if 0. See `Census`.
+    pub fn set_census_interval(&mut self, generations: u32) {
+        self.census_interval = generations;
+        self.census = None;
+        self.draw();
+    }
+
+    /// The latest census, if one has been taken.
+    pub fn census(&self) -> Option<Census> {
+        self.census.clone()
+    }
+
+    /// Tint the cells that differ from the board kept as checkpoint `id`
+    /// until called with `None`, to see how far a small change spreads.
+    /// Returns whether there was such a checkpoint.
+    pub fn diff_from_checkpoint(&mut self, id: Option<u32>) -> bool {
+        self.diff_from = id.and_then(|id| self.universe.checkpoint_board(id));
+        if self.diff_from.is_none() {
+            self.set_difference(Vec::new());
+        }
+        self.draw();
+        self.diff_from.is_some() || id.is_none()
+    }
+
+    /// Take an automatic checkpoint every `generations` generations, or none
+    /// if 0.
+    pub fn set_checkpoint_interval(&mut self, generations: u32) {
+        self.universe.set_checkpoint_interval(generations);
+    }
+
+    /// Go back to `generation` from the nearest automatic checkpoint, which
+    /// can be undone. See `Universe::seek`.
+    pub fn seek(&mut self, generation: u64) -> bool {
+        let (snapshot, shape) = (self.universe.snapshot(), self.shape());
+        if !self.universe.seek(generation) {
+            return false;
+        }
+        self.history.push(snapshot);
+        self.log_board();
+        self.restored(shape);
+        true
+    }
+
+    /// Stop sharing the universe through the relay server.
+    pub fn disconnect(&mut self) {
+        self.network = None;
+        self.served = None;
+    }
+
+    /// Whether the universe is shared through a relay server, even if the
+    /// connection has since closed.
+    pub fn is_connected(&self) -> bool {
+        self.network.is_some()
+    }
+
+    /// Finish connecting the hosted session waiting for `answer`, a guest's
+    /// answer as pasted from their page. Returns whether `answer` was an
+    /// answer and a session was waiting for one.
+    pub fn accept_peer(&mut self, answer: &str) -> bool {
+        let answer = match Signal::parse(answer) {
+            Some(answer) if !answer.offer => answer,
+            _ => return false,
+        };
+        match self.peers.iter().rev().find(|peer| peer.is_waiting()) {
+            Some(peer) => {
+                peer.accept(&answer);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Close every peer-to-peer session.
+    pub fn end_peer_sessions(&mut self) {
+        self.peers.clear();
+    }
+
+    /// Start logging every edit, with the generation it was made in, from
+    /// the board as it is now.
+    pub fn start_replay_log(&mut self) {
+        let mut log = ReplayLog::new(&self.universe);
+        // The log can only say what noise is drawn from once it is reseeded.
+        if self.universe.noise() > 0.0 {
+            log.record(self.reseed_noise(self.universe.noise()));
+        }
+        self.replay_log = Some(log);
+    }
+
+    /// Stop logging edits, returning the log. `ReplayLog::to_json` writes
+    /// it for `play_replay`.
+    pub fn stop_replay_log(&mut self) -> Option<ReplayLog> {
+        self.replay_log.take()
+    }
+
+    pub fn is_logging_edits(&self) -> bool {
+        self.replay_log.is_some()
+    }
+
+    /// Start playing back a log written by `stop_replay_log` from an empty
+    /// board, a generation per step, until it ends or the board is edited.
+    /// This stops logging edits.
+    pub fn play_replay(&mut self, json: &str) -> Result<(), ReplayError> {
+        let (replay, universe) = Replay::start(ReplayLog::from_json(json)?)?;
+        self.replay_log = None;
+        let topology = universe.topology();
+        self.set_universe(universe);
+        self.universe.set_topology(topology);
+        self.replaying = Some(replay);
+        self.draw();
+        Ok(())
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.replaying.is_some()
+    }
+
+    /// Start previewing `pattern` under the cursor, ready for `place`.
+    pub fn start_placing(&mut self, pattern: Pattern) {
+        trace_event!(
+            info,
+            width = pattern.width(),
+            height = pattern.height(),
+            "placing pattern"
+        );
+        self.placement = Some(Placement { pattern, at: None });
+    }
+
+    pub fn cancel_placing(&mut self) {
+        self.placement = None;
+    }
+
+    pub fn is_placing(&self) -> bool {
+        self.placement.is_some()
+    }
+
+    /// Turn the pattern being placed a right angle clockwise.
+    pub fn rotate_placement(&mut self) {
+        if let Some(placement) = &mut self.placement {
+            placement.pattern = placement.pattern.rotate(1);
+        }
+    }
+
+    /// Mirror the pattern being placed across `axis`.
+    pub fn flip_placement(&mut self, axis: Flip) {
+        if let Some(placement) = &mut self.placement {
+            placement.pattern = placement.pattern.flip(axis);
+        }
+    }
+
+    /// Center the pattern being placed on the cell under a point on the
+    /// canvas element, in pixels.
+    pub fn move_placement(&mut self, x: f64, y: f64) {
+        let position = self.cell_at(x, y);
+        let (width, height) = (self.width(), self.height());
+        if let (Some(placement), Some((row, col))) = (&mut self.placement, position) {
+            let row = (row + height - placement.pattern.height() / 2 % height) % height;
+            let col = (col + width - placement.pattern.width() / 2 % width) % width;
+            placement.at = Some((row, col));
+        }
+    }
+
+    /// Stamp the pattern being placed where it is previewed, and stop
+    /// placing. Returns whether anything was placed.
+    pub fn place(&mut self) -> bool {
+        match self.placement.take() {
+            Some(Placement {
+                pattern,
+                at: Some((row, col)),
+            }) => {
+                self.checkpoint();
+                self.universe.stamp(&pattern, Position::new(row, col), 0);
+                self.log_edit(Edit::Stamp {
+                    row,
+                    column: col,
+                    pattern,
+                });
+                self.invalidate_engine();
+                true
+            }
+            placement => {
+                self.placement = placement;
+                false
+            }
+        }
+    }
+
+    pub fn tool(&self) -> Tool {
+        self.tool
+    }
+
+    /// Choose what dragging across the canvas does. Switching tools drops
+    /// the selection and any shape being dragged out.
+    pub fn set_tool(&mut self, tool: Tool) {
+        self.tool = tool;
+        self.selection = None;
+        self.drawing = None;
+    }
+
+    pub fn brush(&self) -> Brush {
+        self.brush
+    }
+
+    /// Paint with a brush of `shape` and `radius` cells.
+    pub fn set_brush(&mut self, shape: BrushShape, radius: u32) {
+        self.brush = Brush::new(shape, radius);
+    }
+
+    pub fn symmetry(&self) -> Symmetry {
+        self.symmetry
+    }
+
+    /// Mirror everything painted or drawn from now on.
+    pub fn set_symmetry(&mut self, symmetry: Symmetry) {
+        self.symmetry = symmetry;
+    }
+
+    /// Turn accessibility mode on or off. While it is on, a keyboard cursor
+    /// starting in the middle of the universe can be moved and toggle cells,
+    /// and a summary of the universe is kept in `#a11y-status`.
+    pub fn set_accessible(&mut self, on: bool) {
+        self.cursor = if on {
+            Some((self.universe.height() / 2, self.universe.width() / 2))
+        } else {
+            None
+        };
+        self.summarized = None;
+        self.drawn_viewport = None;
+        self.draw();
+    }
+
+    pub fn is_accessible(&self) -> bool {
+        self.cursor.is_some()
+    }
+
+    /// Move the keyboard cursor by the given number of rows and columns,
+    /// stopping at the edges, and scroll to keep it in view.
+    pub fn move_cursor(&mut self, rows: i32, columns: i32) {
+        let (row, col) = match self.cursor {
+            Some(cursor) => cursor,
+            None => return,
+        };
+        let (width, height) = (self.universe.width(), self.universe.height());
+        let row = (row as i64 + rows as i64).clamp(0, height as i64 - 1) as u32;
+        let col = (col as i64 + columns as i64).clamp(0, width as i64 - 1) as u32;
+        self.cursor = Some((row, col));
+
+        let (x, y) = self.viewport.cell_origin(row, col);
+        let cell_size = self.viewport.cell_size() as f64;
+        let (right, bottom) = (self.viewport.width() as f64, self.viewport.height() as f64);
+        let dx = x.min(0.0) + (x + cell_size - right).max(0.0);
+        let dy = y.min(0.0) + (y + cell_size - bottom).max(0.0);
+        self.viewport.pan(dx, dy);
+        self.summarized = None;
+    }
+
+    /// Toggle the cell under the keyboard cursor.
+    pub fn toggle_cursor_cell(&mut self) {
+        if let Some((row, column)) = self.cursor {
+            self.toggle_cell(Position::new(row, column));
+            self.summarized = None;
+        }
+    }
+
+    /// The generation, the population and the last cell to change, plus the
+    /// keyboard cursor's cell in accessibility mode, as a sentence.
+    pub fn accessible_summary(&self) -> String {
+        let mut summary = tr!(
+            Summary,
+            self.universe.generation(),
+            self.universe.population()
+        );
+        if let Some((row, col)) = self.universe.last_change() {
+            summary += &tr!(SummaryLastChange, row, col);
+        }
+        if let Some((row, col)) = self.cursor {
+            let state = match self.universe.get(Position::new(row, col)) {
+                Some(Cell::Alive) => tr!(CellAlive),
+                Some(Cell::Dying) => tr!(CellDying),
+                _ => tr!(CellDead),
+            };
+            summary += &tr!(SummaryCursor, row, col, state);
+        }
+        summary
+    }
+
+    pub fn has_selection(&self) -> bool {
+        self.selection.is_some()
+    }
+
+    pub fn deselect(&mut self) {
+        self.selection = None;
+    }
+
+    /// The selected cells as RLE text, or `None` without a selection.
+    pub fn copy_selection(&self) -> Option<String> {
+        let selection = self.selection?;
+        let cells = selection
+            .rows()
+            .flat_map(|row| selection.columns().map(move |col| (row, col)))
+            .filter_map(|(row, col)| self.universe.get(Position::new(row, col)))
+            .collect();
+        let pattern = Pattern::from_cells(
+            selection.columns().len() as u32,
+            selection.rows().len() as u32,
+            cells,
+        );
+        Some(pattern.with_rule(self.universe.rule()).to_rle())
+    }
+
+    /// Kill every selected cell. Returns whether there was a selection.
+    pub fn delete_selection(&mut self) -> bool {
+        let selection = match self.selection {
+            Some(selection) => selection,
+            None => return false,
+        };
+        self.checkpoint();
+        let mut cells = Vec::new();
+        for row in selection.rows() {
+            for col in selection.columns() {
+                self.universe.set_cell(Position::new(row, col), Cell::Dead);
+                cells.push((row, col));
+            }
+        }
+        self.log_edit(Edit::Cells {
+            cell: Cell::Dead,
+            cells,
+        });
+        self.invalidate_engine();
+        true
+    }
+
+    /// Copy the selected cells as RLE text and then kill them.
+    pub fn cut_selection(&mut self) -> Option<String> {
+        let rle = self.copy_selection()?;
+        self.delete_selection();
+        Some(rle)
+    }
+
+    /// Start placing the pattern in `rle`, as copied by `copy_selection`.
+    /// Life 1.06 and plaintext patterns are accepted too.
+    pub fn paste(&mut self, rle: &str) -> Result<(), ParseError> {
+        let pattern = Pattern::parse(rle)?;
+        self.selection = None;
+        self.start_placing(pattern);
+        Ok(())
+    }
+
+    /// Place `pattern` in the middle of the universe.
+    pub fn insert_pattern_centered(&mut self, pattern: &Pattern) {
+        let row = self.universe.height().saturating_sub(pattern.height()) / 2;
+        let column = self.universe.width().saturating_sub(pattern.width()) / 2;
+        self.insert_pattern(Position::new(row, column), pattern);
+    }
+
+    /// Paint the cells that changed since the last call, or everything if
+    /// the canvas was resized, zoomed or panned in the meantime.
+    pub fn draw(&mut self) {
+        trace_span!("render", renderer = self.renderer.name());
+        if self.render_mode == RenderMode::Trails {
+            self.update_trails();
+        }
+        if let Some(board) = &self.diff_from {
+            self.set_difference(self.universe.diff(board));
+        }
+        if self.tracking.is_some() {
+            self.track();
+        } else if self.follow {
+            self.follow_pattern();
+        }
+        // Only a plane has anything to show beyond the universe's edges.
+        self.viewport
+            .set_bounded(!matches!(self.engine, Engine::Chunked(Some(_))));
+        match self.renderer {
+            // The plane reaches beyond the universe, so it is drawn from its
+            // own chunks whichever renderer is chosen.
+            _ if matches!(self.engine, Engine::Chunked(Some(_))) => {
+                self.draw_plane();
+                self.drawn_viewport = None;
+            }
+            Renderer::Rects => {
+                let cells = (self.universe.width() * self.universe.height()) as usize;
+                if self.drawn_viewport != Some(self.viewport) || self.drawn.len() != cells {
+                    // Cells are only ever painted inside the lines, so the
+                    // grid lasts until the next full repaint.
+                    self.context.clear_rect(
+                        0.0,
+                        0.0,
+                        self.viewport.width() as f64,
+                        self.viewport.height() as f64,
+                    );
+                    self.draw_grid();
+                    self.drawn = vec![NOT_DRAWN; cells];
+                    self.drawn_viewport = Some(self.viewport);
+                }
+                self.draw_cells();
+            }
+            Renderer::Image => {
+                // The image covers the whole canvas, so the grid goes back
+                // on top of it every time.
+                self.draw_image();
+                self.draw_grid();
+                // Nothing is known to be painted if the rects take over.
+                self.drawn_viewport = None;
+            }
+        }
+        self.draw_activity();
+        self.draw_selection();
+        self.draw_drawing();
+        self.draw_placement();
+        self.draw_cursor();
+        self.draw_difference();
+        self.draw_stats();
+        self.draw_census();
+        self.draw_minimap();
+        self.draw_timeline();
+        self.draw_summary();
+        self.record_frame();
+        if let Some(sound) = &mut self.sound {
+            if let Err(err) = sound.play(&self.universe) {
+                log!("{}", tr!(CouldNotPlayGeneration, format!("{:?}", err)));
+            }
+        }
+    }
+}
+
+impl Canvas {
+    /// Show this canvas beside the main one, with its stats in `#stats_id`
+    /// and without the population graph or timeline.
+    pub(crate) fn make_secondary(&mut self, stats_id: &'static str) {
+        self.sparkline = None;
+        self.minimap = None;
+        self.stats_id = Some(stats_id);
+        self.secondary = true;
+    }
+
+    /// Show this canvas on a page of its own, as a widget, leaving every
+    /// element of the game's page alone.
+    pub(crate) fn make_embedded(&mut self) {
+        self.sparkline = None;
+        self.minimap = None;
+        self.stats_id = None;
+        self.secondary = true;
+    }
+
+    /// Tint the cells at each (`row`, `column`) in `cells`, and stop tinting
+    /// those tinted before. See `Universe::diff`.
+    pub(crate) fn set_difference(&mut self, cells: Vec<(u32, u32)>) {
+        let width = self.universe.width();
+        for &(row, col) in self.difference.iter().chain(&cells) {
+            if let Some(drawn) = self.drawn.get_mut((row * width + col) as usize) {
+                *drawn = NOT_DRAWN;
+            }
+        }
+        self.difference = cells;
+    }
+
+    /// The (`row`, `column`) of the cell under a point on the canvas element,
+    /// in pixels.
+    pub(crate) fn cell_at(&self, x: f64, y: f64) -> Option<(u32, u32)> {
+        self.viewport.cell_at(x, y)
+    }
+
+    fn advance(&mut self, generations: u64) {
+        // The simulation server steps the universe instead.
+        if self.served.is_some() {
+            return;
+        }
+        trace_span!("tick", generations);
+        // Steps are wound back through the rewind timeline rather than kept
+        // in the history, so their boards don't pile up as the universe runs,
+        // but undone edits can't be redone after one.
+        self.history.forget_redo();
+        if self.replaying.is_some() {
+            self.play(generations);
+            return;
+        }
+        if let Some(log) = &mut self.replay_log {
+            log.advance(generations);
+        }
+        let before = self.universe.generation();
+        let universe = &mut self.universe;
+        let rule_table = &self.rule_table;
+        let elementary_rule = &self.elementary_rule;
+        let turmite = &self.turmite;
+        let material = self.material;
+        match &mut self.engine {
+            Engine::HashLife(_) | Engine::Chunked(_) if !runs_on_a_plane(universe) => {
+                SimulationEngine::step(universe, generations)
+            }
+            Engine::Naive => SimulationEngine::step(universe, generations),
+            Engine::HashLife(hashlife) => {
+                let hashlife = hashlife.get_or_insert_with(|| {
+                    let mut hashlife = HashLife::new(universe.width(), universe.height());
+                    hashlife
+                        .set_rule(universe.rule())
+                        .expect("other rules are stepped above");
+                    hashlife.copy_from(universe);
+                    hashlife
+                });
+                hashlife.step(generations);
+                universe.skip_ahead(generations, |universe| universe.copy_from(hashlife));
+            }
+            Engine::Worker(worker) => worker.step(universe, generations),
+            Engine::Chunked(plane) => {
+                let plane = plane.get_or_insert_with(|| {
+                    chunked_plane(universe).expect("other rules are stepped above")
+                });
+                plane.step(generations);
+                universe.skip_ahead(generations, |universe| universe.copy_from(plane));
+            }
+            Engine::Lenia(lenia) => {
+                let lenia = lenia.get_or_insert_with(|| {
+                    let mut lenia = Lenia::new(universe.width(), universe.height());
+                    lenia.set_topology(universe.topology());
+                    lenia.copy_from(universe);
+                    lenia
+                });
+                lenia.step(generations);
+                universe.skip_ahead(generations, |universe| universe.copy_from(lenia));
+            }
+            Engine::Elementary(elementary) => {
+                let elementary = elementary.get_or_insert_with(|| {
+                    let mut elementary = Elementary::new(universe.width(), universe.height());
+                    elementary.set_rule(*elementary_rule);
+                    elementary.set_topology(universe.topology());
+                    elementary.copy_from(universe);
+                    elementary
+                });
+                elementary.step(generations);
+                universe.skip_ahead(generations, |universe| universe.copy_from(elementary));
+            }
+            Engine::Turmite(board) => {
+                let board = board.get_or_insert_with(|| {
+                    let mut board =
+                        Turmite::new(universe.width(), universe.height(), turmite.clone());
+                    board.set_topology(universe.topology());
+                    board.copy_from(universe);
+                    board
+                });
+                board.step(generations);
+                universe.skip_ahead(generations, |universe| universe.copy_from(board));
+            }
+            Engine::Sand(sand) => {
+                let sand = sand.get_or_insert_with(|| {
+                    let mut sand = Sand::new(universe.width(), universe.height());
+                    sand.set_material(material);
+                    sand.copy_from(universe);
+                    sand
+                });
+                sand.step(generations);
+                universe.skip_ahead(generations, |universe| universe.copy_from(sand));
+            }
+            Engine::Table(board) => {
+                let board = board.get_or_insert_with(|| {
+                    let table = rule_table.clone().expect("the table engine needs a table");
+                    let mut board = TableBoard::new(universe.width(), universe.height(), table);
+                    board.set_topology(universe.topology());
+                    board.copy_from(universe);
+                    board
+                });
+                board.step(generations);
+                universe.skip_ahead(generations, |universe| universe.copy_from(board));
+            }
+        }
+        let stepped = self.universe.generation() - before;
+        if stepped > 0 {
+            let tick = NetworkMessage::Tick {
+                generations: stepped,
+            };
+            for peer in self
+                .peers
+                .iter()
+                .filter(|peer| peer.role() == PeerRole::Host)
+            {
+                peer.send(&tick);
+            }
+        }
+        if self.universe.generation() / network::SYNC_INTERVAL != before / network::SYNC_INTERVAL {
+            self.send_sync();
+        }
+    }
+
+    /// Take the cells sent back by the worker engine. Returns whether they
+    /// were applied.
+    fn receive_from_worker(&mut self, cells: &[u8]) -> bool {
+        let generations = match &mut self.engine {
+            Engine::Worker(worker) => worker.receive(&self.universe),
+            _ => None,
+        };
+        if let Some(generations) = generations {
+            self.universe
+                .skip_ahead(generations, |universe| universe.set_cells(cells));
+        }
+        generations.is_some()
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        let shape = self.shape();
+        self.universe.restore_snapshot(snapshot);
+        self.log_board();
+        self.restored(shape);
+    }
+
+    /// Step the log being played back instead of simulating.
+    fn play(&mut self, generations: u64) {
+        let shape = self.shape();
+        if let Some(replay) = &mut self.replaying {
+            for _ in 0..generations {
+                match replay.step(&mut self.universe) {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        self.replaying = None;
+                        break;
+                    }
+                    Err(err) => {
+                        log!("{}", tr!(CannotReplay, err));
+                        self.replaying = None;
+                        break;
+                    }
+                }
+            }
+        }
+        self.restored(shape);
+    }
+
+    /// Send `message` to everyone else sharing the universe, through the
+    /// relay server and to every peer.
+    fn share(&self, message: &NetworkMessage) {
+        if let Some(network) = &self.network {
+            network.send(message);
+        }
+        for peer in &self.peers {
+            peer.send(message);
+        }
+    }
+
+    /// Send the whole board to everyone else sharing it. Guests of
+    /// peer-to-peer sessions leave this to the host.
+    fn send_sync(&self) {
+        let guests = self
+            .peers
+            .iter()
+            .filter(|peer| peer.role() == PeerRole::Host);
+        if self.network.is_none() && guests.clone().next().is_none() {
+            return;
+        }
+        let sync = NetworkMessage::Sync {
+            generation: self.universe.generation(),
+            board: self.universe.serialize(),
+        };
+        if let Some(network) = &self.network {
+            network.send(&sync);
+        }
+        for guest in guests {
+            guest.send(&sync);
+        }
+    }
+
+    /// Take a message from peer number `index`. Hosts pass guests' messages
+    /// on to the other guests, and guests take the host's board as it is
+    /// rather than merging it.
+    fn receive_peer(&mut self, index: usize, text: &str) -> Result<(), ReplayError> {
+        match self.peers[index].role() {
+            PeerRole::Host => {
+                for (i, peer) in self.peers.iter().enumerate() {
+                    if i != index && peer.role() == PeerRole::Host {
+                        peer.send_text(text);
+                    }
+                }
+            }
+            PeerRole::Guest => {
+                if let NetworkMessage::Sync { generation, board } = NetworkMessage::from_json(text)?
+                {
+                    let shape = self.shape();
+                    let board = Universe::deserialize(&board)?;
+                    network::adopt(&mut self.universe, generation, &board);
+                    self.restored(shape);
+                    return Ok(());
+                }
+            }
+        }
+        self.receive_network(text)
+    }
+
+    /// Make an edit or merge a board sent by someone else sharing the
+    /// universe. Edits are logged but not sent back out.
+    fn receive_network(&mut self, text: &str) -> Result<(), ReplayError> {
+        let shape = self.shape();
+        match NetworkMessage::from_json(text)? {
+            NetworkMessage::Edit { edit, .. } => {
+                self.checkpoint();
+                edit.apply(&mut self.universe)?;
+                self.replaying = None;
+                if let Some(log) = &mut self.replay_log {
+                    log.record(edit);
+                }
+            }
+            NetworkMessage::Sync { generation, board } => {
+                let board = Universe::deserialize(&board)?;
+                if !network::merge(&mut self.universe, generation, &board) {
+                    return Ok(());
+                }
+            }
+            NetworkMessage::Tick { generations } => {
+                self.advance(generations);
+                return Ok(());
+            }
+            NetworkMessage::Board {
+                generation,
+                window,
+                board,
+            } => {
+                let board = Universe::deserialize(&board)?;
+                network::adopt(&mut self.universe, generation, &board);
+                self.served = Some(window);
+            }
+            // Only simulation servers are sent views.
+            NetworkMessage::View(_) => return Ok(()),
+        }
+        self.restored(shape);
+        Ok(())
+    }
+
+    /// Log `edit` if edits are being logged. Editing by hand also ends any
+    /// playback.
+    fn log_edit(&mut self, edit: Edit) {
+        self.replaying = None;
+        if self.network.is_some() || !self.peers.is_empty() {
+            let (width, height) = (self.universe.width(), self.universe.height());
+            let shared = match self.served {
+                Some(window) => window.edit_for_universe(edit.clone(), width, height),
+                None => Some(edit.clone()),
+            };
+            if let Some(shared) = shared {
+                self.share(&NetworkMessage::Edit {
+                    generation: self.universe.generation(),
+                    edit: shared,
+                });
+            }
+        }
+        if let Some(log) = &mut self.replay_log {
+            log.record(edit);
+        }
+    }
+
+    /// Log the whole board, for edits that can't be logged more simply.
+    fn log_board(&mut self) {
+        if self.replay_log.is_some() || self.network.is_some() || !self.peers.is_empty() {
+            self.log_edit(Edit::Board {
+                board: self.universe.serialize(),
+            });
+        } else {
+            self.replaying = None;
+        }
+    }
+
+    /// The width, height and number of states of the universe, to tell
+    /// whether `restored` has to redo the layout or colors.
+    fn shape(&self) -> (u32, u32, u8) {
+        let (width, height) = (self.universe.width(), self.universe.height());
+        (width, height, self.universe.rule().states())
+    }
+
+    /// Catch up with a board put back over one of the given `shape`.
+    fn restored(&mut self, (width, height, states): (u32, u32, u8)) {
+        self.invalidate_engine();
+        if (width, height) != (self.universe.width(), self.universe.height()) {
+            self.fit_to_universe();
+        }
+        if states != self.universe.rule().states() {
+            self.drawn_viewport = None;
+        }
+    }
+
+    /// Called after the universe is edited so the engine picks up the change.
+    fn invalidate_engine(&mut self) {
+        match &mut self.engine {
+            Engine::HashLife(hashlife) => *hashlife = None,
+            Engine::Worker(worker) => worker.invalidate(),
+            // The chunked plane keeps everything outside the visible region,
+            // unless the new rule is one it can't run.
+            Engine::Chunked(kept) => {
+                if let Some(plane) = kept {
+                    if plane.set_rule(self.universe.rule()).is_ok() {
+                        plane.set_size(self.universe.width(), self.universe.height());
+                        plane.copy_from(&self.universe);
+                    } else {
+                        *kept = None;
+                    }
+                }
+            }
+            // Lenia keeps the levels of every cell the edit left alone.
+            Engine::Lenia(Some(lenia))
+                if lenia.width() == self.universe.width()
+                    && lenia.height() == self.universe.height() =>
+            {
+                lenia.set_topology(self.universe.topology());
+                lenia.copy_from(&self.universe);
+            }
+            Engine::Lenia(lenia) => *lenia = None,
+            // So does the table board with the states.
+            Engine::Table(Some(board))
+                if board.width() == self.universe.width()
+                    && board.height() == self.universe.height() =>
+            {
+                board.set_topology(self.universe.topology());
+                board.copy_from(&self.universe);
+            }
+            Engine::Table(board) => *board = None,
+            // The elementary engine carries on from the lowest live cell.
+            Engine::Elementary(elementary) => *elementary = None,
+            // The turmite keeps its head and the colors the edit left alone.
+            Engine::Turmite(Some(board))
+                if board.width() == self.universe.width()
+                    && board.height() == self.universe.height() =>
+            {
+                board.set_topology(self.universe.topology());
+                board.copy_from(&self.universe);
+            }
+            Engine::Turmite(board) => *board = None,
+            // Painted cells are filled with the current material.
+            Engine::Sand(Some(sand))
+                if sand.width() == self.universe.width()
+                    && sand.height() == self.universe.height() =>
+            {
+                sand.copy_from(&self.universe);
+            }
+            Engine::Sand(sand) => *sand = None,
+            Engine::Naive => {}
+        }
+    }
+
+    fn fit_to_universe(&mut self) {
+        self.viewport
+            .set_universe_size(self.universe.width(), self.universe.height());
+        self.canvas.set_width(self.viewport.width());
+        self.canvas.set_height(self.viewport.height());
+        // Resizing the element blanks it.
+        self.drawn_viewport = None;
+    }
+
+    /// Center the view on the live cells, zooming out first if they don't
+    /// fit.
+    fn follow_pattern(&mut self) {
+        let (rows, columns) = match self.universe.bounding_box() {
+            Some(bounds) => bounds,
+            None => return,
+        };
+        if self.viewport.shows(&rows, &columns) {
+            self.viewport.center_on(rows, columns);
+        } else {
+            self.viewport.frame(rows, columns);
+            self.fit_to_universe();
+        }
+    }
+
+    fn draw_minimap(&mut self) {
+        if let Some(minimap) = &mut self.minimap {
+            minimap.draw(&self.universe, &self.viewport, &self.theme);
+        }
+    }
+
+    /// Glide the view towards what is being tracked. On a torus, the board
+    /// is first moved to bring it back to the middle if it has wandered far
+    /// from there, so spaceships can be watched for as long as they fly.
+    fn track(&mut self) {
+        let target = match self.tracking {
+            Some(Tracking::Centroid) => self.universe.centroid(),
+            Some(Tracking::Object(row, column)) => nearest_object(&self.universe, row, column),
+            None => None,
+        };
+        let (mut row, mut column) = match target {
+            Some(target) => target,
+            None => return,
+        };
+        if self.universe.topology() == Topology::Torus {
+            let rows = recentering(row, self.universe.height());
+            let columns = recentering(column, self.universe.width());
+            if rows != 0 || columns != 0 {
+                self.translate(rows, columns);
+                // Keep the picture where it was, to glide on from there.
+                let pitch = self.viewport.pitch();
+                self.viewport
+                    .pan(columns as f64 * pitch, rows as f64 * pitch);
+                row += rows as f64;
+                column += columns as f64;
+            }
+        }
+        if let Some(Tracking::Object(..)) = self.tracking {
+            self.tracking = Some(Tracking::Object(row, column));
+        }
+        self.viewport.glide_to(row, column, TRACKING_EASE);
+    }
+
+    /// Draw the lines between the visible cells, if there are any.
+    fn draw_grid(&mut self) {
+        let mut grid = self
+            .grid
+            .take()
+            .unwrap_or_else(|| GridRenderer::new(&self.canvas.owner_document().unwrap()));
+        grid.draw(&self.context, &self.viewport, &self.theme);
+        self.grid = Some(grid);
+    }
+
+    pub(crate) fn begin_selection(&mut self, x: f64, y: f64) {
+        self.selection = self.cell_at(x, y).map(|position| Selection {
+            anchor: position,
+            corner: position,
+            dragging: true,
+        });
+    }
+
+    /// Move the selection's corner to the cell under (`x`, `y`) if it is
+    /// being dragged. Returns whether it was.
+    pub(crate) fn drag_selection(&mut self, x: f64, y: f64) -> bool {
+        let position = self.cell_at(x, y);
+        match (&mut self.selection, position) {
+            (Some(selection), Some(position)) if selection.dragging => {
+                selection.corner = position;
+                true
+            }
+            (Some(selection), None) => selection.dragging,
+            _ => false,
+        }
+    }
+
+    pub(crate) fn end_selection(&mut self) {
+        if let Some(selection) = &mut self.selection {
+            selection.dragging = false;
+        }
+    }
+
+    /// Start dragging out a shape with the current tool from the cell under
+    /// (`x`, `y`).
+    pub(crate) fn begin_drawing(&mut self, x: f64, y: f64) {
+        let tool = self.tool;
+        self.drawing = self.cell_at(x, y).map(|position| Drawing {
+            tool,
+            anchor: position,
+            corner: position,
+        });
+    }
+
+    /// Move the end of the shape to the cell under (`x`, `y`) if one is being
+    /// dragged out. Returns whether one is.
+    pub(crate) fn drag_drawing(&mut self, x: f64, y: f64) -> bool {
+        let position = self.cell_at(x, y);
+        match (&mut self.drawing, position) {
+            (Some(drawing), Some(position)) => {
+                drawing.corner = position;
+                true
+            }
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+
+    /// Bring the cells of the shape being dragged out to life. Returns
+    /// whether there was one.
+    pub(crate) fn end_drawing(&mut self) -> bool {
+        let drawing = match self.drawing.take() {
+            Some(drawing) => drawing,
+            None => return false,
+        };
+        self.checkpoint();
+        let mut cells = Vec::new();
+        for (row, col) in drawing.cells() {
+            for (row, col) in self.mirror_images(row, col) {
+                self.universe.set_cell(Position::new(row, col), Cell::Alive);
+                cells.push((row, col));
+            }
+        }
+        self.log_edit(Edit::Cells {
+            cell: Cell::Alive,
+            cells,
+        });
+        self.invalidate_engine();
+        true
+    }
+
+    /// A cell and its mirror images under the current symmetry.
+    fn mirror_images(&self, row: u32, col: u32) -> Vec<(u32, u32)> {
+        let (width, height) = (self.universe.width(), self.universe.height());
+        self.symmetry.images(row, col, width, height)
+    }
+
+    /// Preview the shape being dragged out like a pattern being placed.
+    fn draw_drawing(&mut self) {
+        let drawing = match self.drawing {
+            Some(drawing) => drawing,
+            None => return,
+        };
+        let width = self.universe.width();
+        let rows = self.viewport.visible_rows();
+        let columns = self.viewport.visible_columns();
+        let cell_size = self.viewport.cell_size() as f64;
+        self.context.set_fill_style_str(&self.theme.ghost);
+        let cells: Vec<(u32, u32)> = drawing
+            .cells()
+            .into_iter()
+            .flat_map(|(row, col)| self.mirror_images(row, col))
+            .collect();
+        for (row, col) in cells {
+            if rows.contains(&row) && columns.contains(&col) {
+                let (x, y) = self.viewport.cell_origin(row, col);
+                self.context.fill_rect(x, y, cell_size, cell_size);
+                self.drawn[(row * width + col) as usize] = NOT_DRAWN;
+            }
+        }
+    }
+
+    /// Shade the selected cells, marking them as not drawn like
+    /// `draw_placement` does.
+    fn draw_selection(&mut self) {
+        let selection = match self.selection {
+            Some(selection) => selection,
+            None => return,
+        };
+        let width = self.universe.width();
+        let rows = self.viewport.visible_rows();
+        let columns = self.viewport.visible_columns();
+        let cell_size = self.viewport.cell_size() as f64;
+        self.context.set_fill_style_str(&self.theme.selection);
+        for row in selection.rows().filter(|row| rows.contains(row)) {
+            for col in selection.columns().filter(|col| columns.contains(col)) {
+                let (x, y) = self.viewport.cell_origin(row, col);
+                self.context.fill_rect(x, y, cell_size, cell_size);
+                self.drawn[(row * width + col) as usize] = NOT_DRAWN;
+            }
+        }
+    }
+
+    /// Draw the pattern being placed over the cells. The cells under it are
+    /// marked as not drawn, so the next `draw` paints them again.
+    fn draw_placement(&mut self) {
+        let (pattern, (row, col)) = match &self.placement {
+            Some(Placement {
+                pattern,
+                at: Some(at),
+            }) => (pattern, *at),
+            _ => return,
+        };
+        let (width, height) = (self.universe.width(), self.universe.height());
+        let rows = self.viewport.visible_rows();
+        let columns = self.viewport.visible_columns();
+        let cell_size = self.viewport.cell_size() as f64;
+        self.context.set_fill_style_str(&self.theme.ghost);
+        for pattern_row in 0..pattern.height() {
+            for pattern_col in 0..pattern.width() {
+                if pattern.cell_at(pattern_row, pattern_col) == Cell::Dead {
+                    continue;
+                }
+                let row = (row + pattern_row) % height;
+                let col = (col + pattern_col) % width;
+                if !rows.contains(&row) || !columns.contains(&col) {
+                    continue;
+                }
+                let (x, y) = self.viewport.cell_origin(row, col);
+                self.context.fill_rect(x, y, cell_size, cell_size);
+                self.drawn[(row * width + col) as usize] = NOT_DRAWN;
+            }
+        }
+    }
+
+    /// Outline the cell under the keyboard cursor, inside its grid lines.
+    fn draw_difference(&mut self) {
+        let width = self.universe.width();
+        let (rows, columns) = (
+            self.viewport.visible_rows(),
+            self.viewport.visible_columns(),
+        );
+        let cell_size = self.viewport.cell_size() as f64;
+        self.context.set_fill_style_str(&self.theme.difference);
+        for &(row, col) in &self.difference {
+            if !rows.contains(&row) || !columns.contains(&col) {
+                continue;
+            }
+            let (x, y) = self.viewport.cell_origin(row, col);
+            self.context.fill_rect(x, y, cell_size, cell_size);
+            if let Some(drawn) = self.drawn.get_mut((row * width + col) as usize) {
+                *drawn = NOT_DRAWN;
+            }
+        }
+    }
+
+    fn draw_cursor(&mut self) {
+        let (row, col) = match self.cursor {
+            Some(cursor) => cursor,
+            None => return,
+        };
+        let (width, height) = (self.universe.width(), self.universe.height());
+        if row >= height || col >= width {
+            return;
+        }
+        if !self.viewport.visible_rows().contains(&row)
+            || !self.viewport.visible_columns().contains(&col)
+        {
+            return;
+        }
+        let (x, y) = self.viewport.cell_origin(row, col);
+        let cell_size = self.viewport.cell_size() as f64;
+        let line_width = (cell_size / 5.0).clamp(1.0, 3.0);
+        self.context.set_stroke_style_str(&self.theme.focus);
+        self.context.set_line_width(line_width);
+        let inset = line_width / 2.0;
+        self.context.stroke_rect(
+            x + inset,
+            y + inset,
+            cell_size - line_width,
+            cell_size - line_width,
+        );
+        if let Some(drawn) = self.drawn.get_mut((row * width + col) as usize) {
+            *drawn = NOT_DRAWN;
+        }
+    }
+
+    /// Write the accessible summary into `#a11y-status`, if the page has it,
+    /// at most once every `SUMMARY_INTERVAL` unless something asked for it
+    /// sooner.
+    fn draw_summary(&mut self) {
+        if self.cursor.is_none() {
+            return;
+        }
+        let now = js_sys::Date::now();
+        if matches!(self.summarized, Some(at) if now - at < SUMMARY_INTERVAL) {
+            return;
+        }
+        let status = self
+            .canvas
+            .owner_document()
+            .and_then(|document| document.get_element_by_id("a11y-status"));
+        if let Some(status) = status {
+            let summary = self.accessible_summary();
+            // Screen readers announce every change, so leave it alone if
+            // nothing happened.
+            if status.text_content().as_deref() != Some(summary.as_str()) {
+                status.set_text_content(Some(&summary));
+            }
+        }
+        self.summarized = Some(now);
+    }
+
+    /// Add the universe to the recording if it has moved on since the last
+    /// frame.
+    fn record_frame(&mut self) {
+        let generation = self.universe.generation();
+        if let Some((recorder, recorded)) = &mut self.recording {
+            if *recorded != generation {
+                *recorded = generation;
+                if let Err(err) = recorder.capture(&self.universe) {
+                    log!("{}", tr!(CouldNotRecordFrame, err));
+                }
+            }
+        }
+    }
+
+    /// Show the generation and population in `#stats`, or wherever
+    /// `make_secondary` said, and the population graph, if the page has
+    /// them.
+    fn draw_stats(&mut self) {
+        if let Some(sparkline) = &mut self.sparkline {
+            sparkline.record(self.universe.generation(), self.universe.population());
+        }
+
+        let stats = self
+            .canvas
+            .owner_document()
+            .zip(self.stats_id)
+            .and_then(|(document, id)| document.get_element_by_id(id));
+        if let Some(stats) = stats {
+            stats.set_text_content(Some(&tr!(
+                Stats,
+                self.universe.generation(),
+                self.universe.population(),
+                self.universe.births(),
+                self.universe.deaths(),
+            )));
+        }
+    }
+
+    /// Take a census if another `census_interval` generations have passed,
+    /// or the board went back in time, and show it in `#census`.
+    fn draw_census(&mut self) {
+        let interval = self.census_interval as u64;
+        if interval == 0 {
+            return;
+        }
+        let generation = self.universe.generation();
+        let due = match &self.census {
+            Some(census) => {
+                generation < census.generation()
+                    || generation / interval > census.generation() / interval
+            }
+            None => true,
+        };
+        if !due {
+            return;
+        }
+        let census = Census::take(&self.universe);
+        let mut found = census.to_string();
+        if census.other() > 0 {
+            if !found.is_empty() {
+                found.push_str(", ");
+            }
+            found.push_str(&tr!(CensusOther, census.other()));
+        }
+        if found.is_empty() {
+            found = tr!(CensusNothing);
+        }
+        let element = self
+            .canvas
+            .owner_document()
+            .and_then(|document| document.get_element_by_id("census"));
+        if let Some(element) = element {
+            element.set_text_content(Some(&tr!(Census, generation, found)));
+        }
+        self.census = Some(census);
+    }
+
+    /// Keep the `#timeline` slider, if the page has it, spanning the steps
+    /// that can be wound back through, with the current board at its thumb.
+    fn draw_timeline(&self) {
+        if self.secondary {
+            return;
+        }
+        let timeline = self
+            .canvas
+            .owner_document()
+            .and_then(|document| document.get_element_by_id("timeline"))
+            .and_then(|element| element.dyn_into::<web_sys::HtmlInputElement>().ok());
+        if let Some(timeline) = timeline {
+            let steps = self.universe.rewind_steps();
+            timeline.set_max(&steps.to_string());
+            timeline.set_value(&(steps - self.universe.rewound()).to_string());
+        }
+    }
+
+    /// Paint every visible cell whose state differs from what was last
+    /// painted.
+    fn draw_cells(&mut self) {
+        // Collect the cells to paint by color first, so the fill style only
+        // changes once per color rather than once per cell.
+        let mut changed: BTreeMap<u16, Vec<(u32, u32)>> = BTreeMap::new();
+        for row in self.viewport.visible_rows() {
+            for col in self.viewport.visible_columns() {
+                let idx = (row * self.universe.width() + col) as usize;
+                let key = self.color_key(row, col);
+                if self.drawn[idx] != key {
+                    self.drawn[idx] = key;
+                    changed.entry(key).or_default().push((row, col));
+                }
+            }
+        }
+
+        let cell_size = self.viewport.cell_size() as f64;
+        for (key, cells) in changed {
+            self.context.set_fill_style_str(&self.color(key));
+            for (row, col) in cells {
+                let (x, y) = self.viewport.cell_origin(row, col);
+                self.context.fill_rect(x, y, cell_size, cell_size);
+            }
+        }
+    }
+
+    /// Paint the chunked plane, reading only the chunks that overlap the
+    /// view, with the grid over the part the universe covers.
+    fn draw_plane(&mut self) {
+        let plane = match &self.engine {
+            Engine::Chunked(Some(plane)) => plane,
+            _ => return,
+        };
+        let (rows, columns) = self.viewport.visible_area();
+        let cells = plane.live_cells_in(rows, columns);
+        let (width, height) = (self.viewport.width(), self.viewport.height());
+        self.context.set_fill_style_str(&self.theme.dead);
+        self.context
+            .fill_rect(0.0, 0.0, width as f64, height as f64);
+        self.draw_grid();
+
+        let cell_size = self.viewport.cell_size() as f64;
+        self.context.set_fill_style_str(&self.theme.alive);
+        for (row, col) in cells {
+            let (x, y) = self.viewport.plane_origin(row, col);
+            self.context.fill_rect(x, y, cell_size, cell_size);
+        }
+    }
+
+    fn draw_image(&mut self) {
+        let mut image = self
+            .image
+            .take()
+            .unwrap_or_else(|| ImageRenderer::new(&self.canvas.owner_document().unwrap()));
+        let mut colors: BTreeMap<u16, [u8; 4]> = BTreeMap::new();
+        image.draw(&self.context, &self.viewport, |row, col| {
+            let key = self.color_key(row, col);
+            *colors.entry(key).or_insert_with(|| rgba(&self.color(key)))
+        });
+        self.image = Some(image);
+    }
+
+    /// Restart the trails of live cells, and fade those of dead cells by one
+    /// step if the universe has moved on since the last draw.
+    fn update_trails(&mut self) {
+        let (width, height) = (self.universe.width(), self.universe.height());
+        let cells = (width * height) as usize;
+        if self.trails.len() != cells {
+            self.trails = vec![0; cells];
+        }
+        let generation = self.universe.generation();
+        let moved = self.trail_generation != Some(generation);
+        self.trail_generation = Some(generation);
+        for row in 0..height {
+            for col in 0..width {
+                let trail = &mut self.trails[(row * width + col) as usize];
+                if self.universe.get(Position::new(row, col)) == Some(Cell::Alive) {
+                    *trail = TRAIL_FRAMES;
+                } else if moved {
+                    *trail = trail.saturating_sub(1);
+                }
+            }
+        }
+    }
+
+    /// Tint the cells that changed recently, marking them as not drawn so
+    /// the next `draw` paints them again.
+    fn draw_activity(&mut self) {
+        if !self.show_activity {
+            return;
+        }
+        let width = self.universe.width();
+        let mut levels: Vec<Vec<(u32, u32)>> = vec![Vec::new(); HEAT_LEVELS as usize];
+        for row in self.viewport.visible_rows() {
+            for col in self.viewport.visible_columns() {
+                let count = self.universe.activity(Position::new(row, col)).unwrap_or(0) as u32;
+                if count > 0 {
+                    let level = (count * HEAT_LEVELS / ACTIVITY_WINDOW).min(HEAT_LEVELS - 1);
+                    levels[level as usize].push((row, col));
+                    self.drawn[(row * width + col) as usize] = NOT_DRAWN;
+                }
+            }
+        }
+
+        let cell_size = self.viewport.cell_size() as f64;
+        self.context.set_fill_style_str(&self.theme.heat);
+        for (level, cells) in levels.iter().enumerate() {
+            let alpha = 0.2 + 0.6 * level as f64 / (HEAT_LEVELS - 1) as f64;
+            self.context.set_global_alpha(alpha);
+            for &(row, col) in cells {
+                let (x, y) = self.viewport.cell_origin(row, col);
+                self.context.fill_rect(x, y, cell_size, cell_size);
+            }
+        }
+        self.context.set_global_alpha(1.0);
+    }
+
+    /// What decides a cell's color under the current render mode.
+    fn color_key(&self, row: u32, col: u32) -> u16 {
+        if let Engine::Lenia(Some(lenia)) = &self.engine {
+            let shade = (lenia.level(row, col) * (LENIA_SHADES - 1) as f32).round() as u16;
+            return if shade == 0 { 0 } else { LENIA_KEYS + shade };
+        }
+        if let Engine::Table(Some(board)) = &self.engine {
+            let state = board.state(row, col) as u16;
+            return if state == 0 { 0 } else { TABLE_KEYS + state };
+        }
+        if let Engine::Sand(Some(sand)) = &self.engine {
+            return match sand.material(row, col) {
+                Material::Empty => 0,
+                material => SAND_KEYS + material as u16,
+            };
+        }
+        if let Engine::Turmite(Some(board)) = &self.engine {
+            if board.head() == Some((row, col)) {
+                return TURMITE_HEAD_KEY;
+            }
+            let color = board.color(row, col) as u16;
+            return if color == 0 { 0 } else { TURMITE_KEYS + color };
+        }
+        let position = Position::new(row, col);
+        let state = self.universe.state(position).unwrap_or(0);
+        match self.render_mode {
+            RenderMode::Age if self.universe.rule().is_alive(state) => {
+                let age = self.universe.age(position).unwrap_or(0).clamp(1, OLD_AGE);
+                AGE_KEYS + (age - 1) * (AGE_SHADES - 1) / (OLD_AGE - 1)
+            }
+            RenderMode::Trails if state == 0 => {
+                let trail = self.trails[(row * self.universe.width() + col) as usize];
+                if trail > 0 {
+                    TRAIL_KEYS + trail as u16
+                } else {
+                    0
+                }
+            }
+            _ => state as u16,
+        }
+    }
+
+    /// The color for a key returned by `color_key`.
+    fn color(&self, key: u16) -> String {
+        let rule = self.universe.rule();
+        let states = rule.states() as u16;
+        match key {
+            0 => self.theme.dead.clone(),
+            1 => self.theme.alive.clone(),
+            // Live cells of the other colors under multi-color rules.
+            color if color <= rule.colors() as u16 => {
+                let colors = &self.theme.colors;
+                colors[(color as usize - 2) % colors.len()].clone()
+            }
+            SAND_KEYS.. => MATERIALS[(key - SAND_KEYS) as usize].color().to_string(),
+            // The turmite's head stands out in the focus color, and its tape
+            // takes the colors of multi-color rules.
+            TURMITE_HEAD_KEY => self.theme.focus.clone(),
+            TURMITE_KEYS.. => match key - TURMITE_KEYS {
+                1 => self.theme.alive.clone(),
+                color => {
+                    let colors = &self.theme.colors;
+                    colors[(color as usize - 2) % colors.len()].clone()
+                }
+            },
+            // Rule table states take the colors the table gives them, or the
+            // colors of multi-color rules.
+            key if key >= TABLE_KEYS => {
+                let state = (key - TABLE_KEYS) as u8;
+                let given = self
+                    .rule_table
+                    .as_ref()
+                    .and_then(|table| table.color(state));
+                match given {
+                    Some(color) => color.to_string(),
+                    None if state == 1 => self.theme.alive.clone(),
+                    None => {
+                        let colors = &self.theme.colors;
+                        colors[(state as usize - 2) % colors.len()].clone()
+                    }
+                }
+            }
+            // Lenia's levels go from the dead color to the alive one.
+            key if key >= LENIA_KEYS => {
+                let level = (key - LENIA_KEYS) as f64 / (LENIA_SHADES - 1) as f64;
+                mix_colors(&self.theme.dead, &self.theme.alive, level)
+            }
+            // Trails blend the alive color over the dead one, less and less
+            // opaquely as they fade.
+            key if key >= TRAIL_KEYS => {
+                let opacity = (key - TRAIL_KEYS) as f64 / (TRAIL_FRAMES + 1) as f64;
+                mix_colors(&self.theme.alive, &self.theme.dead, 1.0 - opacity)
+            }
+            // Older cells move along the age gradient.
+            key if key >= AGE_KEYS => {
+                let shade = (key - AGE_KEYS) as f64 / (AGE_SHADES - 1) as f64;
+                mix_colors(&self.theme.young, &self.theme.old, shade)
+            }
+            // Dying cells fade from the alive color towards the dead color.
+            state => {
+                let fade = (state - 1) as f64 / (states - 1) as f64;
+                mix_colors(&self.theme.alive, &self.theme.dead, fade)
+            }
+        }
+    }
+}
+
+/// Share `canvas` with everyone else connected to the relay server at
+/// `url`, calling `on_status` whenever the connection opens or closes. The
+/// whole board is sent as soon as it opens, so that others catch up.
+pub(crate) fn connect(
+    canvas: &Rc<RefCell<Canvas>>,
+    url: &str,
+    on_status: impl Fn(ConnectionStatus) + 'static,
+) -> Result<(), JsValue> {
+    let weak_canvas: Weak<RefCell<Canvas>> = Rc::downgrade(canvas);
+    let my_weak_canvas = weak_canvas.clone();
+    let connection = Connection::open(
+        url,
+        move |status| {
+            if let Some(canvas) = my_weak_canvas.upgrade() {
+                if status == ConnectionStatus::Connected {
+                    canvas.borrow().send_sync();
+                }
+            }
+            on_status(status);
+        },
+        move |text| {
+            if let Some(canvas) = weak_canvas.upgrade() {
+                let received = canvas.borrow_mut().receive_network(&text);
+                match received {
+                    Ok(()) => canvas.borrow_mut().draw(),
+                    Err(err) => log!("{}", tr!(CouldNotMerge, err)),
+                }
+            }
+        },
+    )?;
+    canvas.borrow_mut().network = Some(connection);
+    Ok(())
+}
+
+/// Host a new peer-to-peer session on `canvas`, calling `on_signal` with
+/// the offer to pass to the guest and `on_status` whenever the session
+/// opens or closes. The whole board is sent as soon as it opens.
+pub(crate) fn host_peer(
+    canvas: &Rc<RefCell<Canvas>>,
+    on_signal: impl FnOnce(String) + 'static,
+    on_status: impl Fn(ConnectionStatus) + 'static,
+) -> Result<(), JsValue> {
+    let index = canvas.borrow().peers.len();
+    let (on_status, on_message) = peer_callbacks(canvas, index, on_status);
+    let session = PeerSession::host(
+        move |offer| on_signal(offer.to_text()),
+        on_status,
+        on_message,
+    )?;
+    canvas.borrow_mut().peers.push(session);
+    Ok(())
+}
+
+/// Join the peer-to-peer session offered in `offer`, as pasted from the
+/// host's page, calling `on_signal` with the answer to pass back and
+/// `on_status` whenever the session opens or closes.
+pub(crate) fn join_peer(
+    canvas: &Rc<RefCell<Canvas>>,
+    offer: &str,
+    on_signal: impl FnOnce(String) + 'static,
+    on_status: impl Fn(ConnectionStatus) + 'static,
+) -> Result<(), JsValue> {
+    let offer = match Signal::parse(offer) {
+        Some(offer) if offer.offer => offer,
+        _ => return Err(JsValue::from_str(&tr!(InvalidPeerSignal))),
+    };
+    let index = canvas.borrow().peers.len();
+    let (on_status, on_message) = peer_callbacks(canvas, index, on_status);
+    let session = PeerSession::join(
+        &offer,
+        move |answer| on_signal(answer.to_text()),
+        on_status,
+        on_message,
+    )?;
+    canvas.borrow_mut().peers.push(session);
+    Ok(())
+}
+
+/// The status and message callbacks of peer number `index` of `canvas`.
+/// Hosts send the board to guests as soon as they connect.
+fn peer_callbacks(
+    canvas: &Rc<RefCell<Canvas>>,
+    index: usize,
+    on_status: impl Fn(ConnectionStatus) + 'static,
+) -> (impl Fn(ConnectionStatus), impl FnMut(String)) {
+    let weak_canvas: Weak<RefCell<Canvas>> = Rc::downgrade(canvas);
+    let my_weak_canvas = weak_canvas.clone();
+    let on_status = move |status| {
+        if let Some(canvas) = my_weak_canvas.upgrade() {
+            let canvas = canvas.borrow();
+            if status == ConnectionStatus::Connected
+                && canvas.peers.get(index).map(PeerSession::role) == Some(PeerRole::Host)
+            {
+                canvas.peers[index].send(&NetworkMessage::Sync {
+                    generation: canvas.universe.generation(),
+                    board: canvas.universe.serialize(),
+                });
+            }
+        }
+        on_status(status);
+    };
+    let on_message = move |text: String| {
+        if let Some(canvas) = weak_canvas.upgrade() {
+            let received = canvas.borrow_mut().receive_peer(index, &text);
+            match received {
+                Ok(()) => canvas.borrow_mut().draw(),
+                Err(err) => log!("{}", tr!(CouldNotMerge, err)),
+            }
+        }
+    };
+    (on_status, on_message)
+}
+
+/// Switch `canvas` to stepping in a Web Worker, redrawing whenever the
+/// worker sends back cells.
+pub(crate) fn start_worker(canvas: &Rc<RefCell<Canvas>>) -> Result<(), JsValue> {
+    let weak_canvas: Weak<RefCell<Canvas>> = Rc::downgrade(canvas);
+    let worker = WorkerEngine::new(move |cells| {
+        if let Some(canvas) = weak_canvas.upgrade() {
+            if canvas.borrow_mut().receive_from_worker(&cells) {
+                canvas.borrow_mut().draw();
+            }
+        }
+    })?;
+    let mut canvas = canvas.borrow_mut();
+    canvas.engine = Engine::Worker(worker);
+    canvas.drawn_viewport = None;
+    canvas.draw();
+    Ok(())
+}
+
+/// Whether HashLife and the chunked plane can run `universe`. They only
+/// understand Life-like rules without noise, and take the space around the
+/// board to be empty, which `B0` fills.
+fn runs_on_a_plane(universe: &Universe) -> bool {
+    let rule = universe.rule();
+    rule.is_life_like() && !rule.births_on_zero() && universe.noise() == 0.0
+}
+
+/// A chunked plane holding `universe`, if it can run it.
+fn chunked_plane(universe: &Universe) -> Option<ChunkedPlane> {
+    if !runs_on_a_plane(universe) {
+        return None;
+    }
+    let mut plane = ChunkedPlane::new(universe.width(), universe.height());
+    plane.set_rule(universe.rule()).ok()?;
+    plane.copy_from(universe);
+    Some(plane)
+}
+
+/// The mean (`row`, `column`) of `cells`, which mustn't be empty.
+fn centroid(cells: &[(u32, u32)]) -> (f64, f64) {
+    let (rows, columns) = cells
+        .iter()
+        .fold((0.0, 0.0), |(rows, columns), &(row, col)| {
+            (rows + row as f64, columns + col as f64)
+        });
+    (rows / cells.len() as f64, columns / cells.len() as f64)
+}
+
+/// The middle of the object on `universe` whose middle is nearest
+/// (`row`, `column`), as the census splits the board into objects.
+fn nearest_object(universe: &Universe, row: f64, column: f64) -> Option<(f64, f64)> {
+    let distance = |&(r, c): &(f64, f64)| (r - row).powi(2) + (c - column).powi(2);
+    census::identify(universe)
+        .iter()
+        .map(|(_, cells)| centroid(cells))
+        .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+}
+
+/// How far to shift a torus of `size` rows or columns to bring `position`
+/// back to the middle, once it is more than a quarter of the way off it.
+fn recentering(position: f64, size: u32) -> i32 {
+    let middle = size as f64 / 2.0;
+    if (position - middle).abs() > size as f64 / 4.0 {
+        (middle - position).round() as i32
+    } else {
+        0
+    }
+}
+
+/// Blend two `#RRGGBB` colors, going from `from` at 0 to `to` at 1.
+fn mix_colors(from: &str, to: &str, amount: f64) -> String {
+    let channels = |color: &str| {
+        let rgb = u32::from_str_radix(color.trim_start_matches('#'), 16).unwrap_or(0);
+        [(rgb >> 16) & 0xFF, (rgb >> 8) & 0xFF, rgb & 0xFF]
+    };
+    let (from, to) = (channels(from), channels(to));
+    let mut mixed = String::from("#");
+    for (from, to) in from.iter().zip(to.iter()) {
+        let channel = *from as f64 + (*to as f64 - *from as f64) * amount;
+        mixed.push_str(&format!("{:02X}", channel.round() as u8));
+    }
+    mixed
+}
+
+/// A canvas that isn't part of the page, and its 2D context.
+fn offscreen_canvas(
+    document: &web_sys::Document,
+) -> (
+    web_sys::HtmlCanvasElement,
+    web_sys::CanvasRenderingContext2d,
+) {
+    let canvas = document
+        .create_element("canvas")
+        .unwrap()
+        .dyn_into::<web_sys::HtmlCanvasElement>()
+        .unwrap();
+    let context = canvas
+        .get_context("2d")
+        .unwrap()
+        .unwrap()
+        .dyn_into::<web_sys::CanvasRenderingContext2d>()
+        .unwrap();
+    (canvas, context)
+}
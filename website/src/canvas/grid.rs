@@ -0,0 +1,84 @@
+//! The lines between cells, drawn once onto an offscreen layer and copied
+//! onto the page's canvas whenever it needs them.
+
+use crate::theme::Theme;
+use crate::viewport::Viewport;
+
+/// Every this many cells a major grid line is drawn instead of a minor one.
+const MAJOR_GRID_SPACING: u32 = 10;
+
+pub(super) struct GridRenderer {
+    /// The grid lines on a transparent canvas the size of the page's one.
+    canvas: web_sys::HtmlCanvasElement,
+    context: web_sys::CanvasRenderingContext2d,
+    /// The viewport and the minor and major colors the layer was last drawn
+    /// for.
+    drawn: Option<(Viewport, String, String)>,
+}
+
+impl GridRenderer {
+    pub(super) fn new(document: &web_sys::Document) -> GridRenderer {
+        let (canvas, context) = super::offscreen_canvas(document);
+        GridRenderer {
+            canvas,
+            context,
+            drawn: None,
+        }
+    }
+
+    /// Copy the grid lines around the cells visible through `viewport` onto
+    /// `target`, redrawing the layer first if the view or the colors
+    /// changed. Draws nothing if the viewport has no grid lines.
+    pub(super) fn draw(
+        &mut self,
+        target: &web_sys::CanvasRenderingContext2d,
+        viewport: &Viewport,
+        theme: &Theme,
+    ) {
+        if !viewport.grid_lines() {
+            return;
+        }
+        let key = (*viewport, theme.grid.clone(), theme.major_grid.clone());
+        if self.drawn.as_ref() != Some(&key) {
+            self.draw_layer(viewport, theme);
+            self.drawn = Some(key);
+        }
+        target
+            .draw_image_with_html_canvas_element(&self.canvas, 0.0, 0.0)
+            .unwrap();
+    }
+
+    /// Draw every visible line as a one pixel wide rectangle, the minor lines
+    /// first so that the major ones win where they cross.
+    fn draw_layer(&self, viewport: &Viewport, theme: &Theme) {
+        // Resizing blanks the canvas, so this always starts from nothing.
+        self.canvas.set_width(viewport.width());
+        self.canvas.set_height(viewport.height());
+
+        let (rows, columns) = (viewport.visible_rows(), viewport.visible_columns());
+        if rows.is_empty() || columns.is_empty() {
+            return;
+        }
+        // The line before a cell is the pixel before its origin.
+        let (left, top) = viewport.cell_origin(rows.start, columns.start);
+        let (right, bottom) = viewport.cell_origin(rows.end, columns.end);
+        let (left, top, right, bottom) = (left - 1.0, top - 1.0, right - 1.0, bottom - 1.0);
+        let pitch = viewport.pitch();
+
+        for (major, color) in [(false, &theme.grid), (true, &theme.major_grid)].iter() {
+            self.context.set_fill_style_str(color);
+            for column in columns.start..=columns.end {
+                if (column % MAJOR_GRID_SPACING == 0) == *major {
+                    let x = left + (column - columns.start) as f64 * pitch;
+                    self.context.fill_rect(x, top, 1.0, bottom - top + 1.0);
+                }
+            }
+            for row in rows.start..=rows.end {
+                if (row % MAJOR_GRID_SPACING == 0) == *major {
+                    let y = top + (row - rows.start) as f64 * pitch;
+                    self.context.fill_rect(left, y, right - left + 1.0, 1.0);
+                }
+            }
+        }
+    }
+}
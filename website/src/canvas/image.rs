@@ -0,0 +1,82 @@
+//! Painting every visible cell in one go: one pixel per cell is written into
+//! an RGBA buffer, put onto a small offscreen canvas and scaled up onto the
+//! page's canvas, leaving the grid to be drawn over the top.
+
+use wasm_bindgen::Clamped;
+
+use crate::viewport::Viewport;
+
+pub(super) struct ImageRenderer {
+    canvas: web_sys::HtmlCanvasElement,
+    context: web_sys::CanvasRenderingContext2d,
+    /// The color of every visible cell, reused from frame to frame.
+    pixels: Vec<u8>,
+}
+
+impl ImageRenderer {
+    pub(super) fn new(document: &web_sys::Document) -> ImageRenderer {
+        let (canvas, context) = super::offscreen_canvas(document);
+        ImageRenderer {
+            canvas,
+            context,
+            pixels: Vec::new(),
+        }
+    }
+
+    /// Paint the cells visible through `viewport` onto `target`, coloring
+    /// each with `color`.
+    pub(super) fn draw(
+        &mut self,
+        target: &web_sys::CanvasRenderingContext2d,
+        viewport: &Viewport,
+        mut color: impl FnMut(u32, u32) -> [u8; 4],
+    ) {
+        let (rows, columns) = (viewport.visible_rows(), viewport.visible_columns());
+        let (width, height) = (columns.len() as u32, rows.len() as u32);
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        self.pixels.clear();
+        for row in rows.clone() {
+            for column in columns.clone() {
+                self.pixels.extend_from_slice(&color(row, column));
+            }
+        }
+        if self.canvas.width() != width || self.canvas.height() != height {
+            self.canvas.set_width(width);
+            self.canvas.set_height(height);
+        }
+        let image = web_sys::ImageData::new_with_u8_clamped_array_and_sh(
+            Clamped(&self.pixels),
+            width,
+            height,
+        )
+        .unwrap();
+        self.context.put_image_data(&image, 0.0, 0.0).unwrap();
+
+        // Each pixel becomes a whole cell plus the grid line before it, if
+        // there is one, for the grid to paint over.
+        let line = viewport.grid_lines() as u32 as f64;
+        let (x, y) = viewport.cell_origin(rows.start, columns.start);
+        let (x, y) = (x - line, y - line);
+        let pitch = viewport.pitch();
+        let (full_width, full_height) = (width as f64 * pitch, height as f64 * pitch);
+        target.set_image_smoothing_enabled(false);
+        target
+            .draw_image_with_html_canvas_element_and_dw_and_dh(
+                &self.canvas,
+                x,
+                y,
+                full_width,
+                full_height,
+            )
+            .unwrap();
+    }
+}
+
+/// The red, green, blue and alpha bytes of an opaque `#RRGGBB` color.
+pub(super) fn rgba(color: &str) -> [u8; 4] {
+    let rgb = u32::from_str_radix(color.trim_start_matches('#'), 16).unwrap_or(0);
+    [(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, 0xFF]
+}
@@ -0,0 +1,153 @@
+//! A scaled-down picture of the whole universe on the page's `#minimap`
+//! canvas, with the part the main canvas shows outlined. It is hidden while
+//! the main canvas shows everything anyway.
+
+use wasm_bindgen::{Clamped, JsCast};
+
+use super::image::rgba;
+use crate::theme::Theme;
+use crate::viewport::Viewport;
+use crate::{Cell, Position, Universe};
+
+/// The most room the minimap takes up along either side, in pixels.
+const MINIMAP_SIZE: u32 = 160;
+
+/// What the board layer was last drawn from: the universe's size,
+/// generation, population and last change, and the live and dead colors.
+type Drawn = (u32, u32, u64, u32, Option<(u32, u32)>, String, String);
+
+pub(super) struct Minimap {
+    canvas: web_sys::HtmlCanvasElement,
+    context: web_sys::CanvasRenderingContext2d,
+    /// One pixel per cell, scaled down onto the minimap on every draw.
+    board: web_sys::HtmlCanvasElement,
+    board_context: web_sys::CanvasRenderingContext2d,
+    pixels: Vec<u8>,
+    drawn: Option<Drawn>,
+}
+
+impl Minimap {
+    /// The minimap for `#minimap`, or `None` if the page doesn't have one.
+    pub(super) fn from_document(document: &web_sys::Document) -> Option<Minimap> {
+        let canvas = document
+            .get_element_by_id("minimap")?
+            .dyn_into::<web_sys::HtmlCanvasElement>()
+            .ok()?;
+        let context = canvas
+            .get_context("2d")
+            .ok()??
+            .dyn_into::<web_sys::CanvasRenderingContext2d>()
+            .ok()?;
+        let (board, board_context) = super::offscreen_canvas(document);
+        Some(Minimap {
+            canvas,
+            context,
+            board,
+            board_context,
+            pixels: Vec::new(),
+            drawn: None,
+        })
+    }
+
+    /// Show `universe` with the part visible through `viewport` outlined,
+    /// redrawing the board layer first if the board changed since last
+    /// time, or hide the minimap if the viewport shows all of it.
+    pub(super) fn draw(&mut self, universe: &Universe, viewport: &Viewport, theme: &Theme) {
+        let (width, height) = (universe.width(), universe.height());
+        let (rows, columns) = (viewport.visible_rows(), viewport.visible_columns());
+        let everything = rows.len() as u32 == height && columns.len() as u32 == width;
+        self.canvas
+            .set_hidden(everything || width == 0 || height == 0);
+        if self.canvas.hidden() {
+            return;
+        }
+
+        let key = (
+            width,
+            height,
+            universe.generation(),
+            universe.population(),
+            universe.last_change(),
+            theme.alive.clone(),
+            theme.dead.clone(),
+        );
+        if self.drawn.as_ref() != Some(&key) {
+            self.draw_board(universe, theme);
+            self.drawn = Some(key);
+        }
+
+        let scale = self.scale(universe);
+        let (minimap_width, minimap_height) = (
+            (width as f64 * scale).round() as u32,
+            (height as f64 * scale).round() as u32,
+        );
+        if self.canvas.width() != minimap_width || self.canvas.height() != minimap_height {
+            self.canvas.set_width(minimap_width);
+            self.canvas.set_height(minimap_height);
+        }
+        self.context.set_image_smoothing_enabled(false);
+        self.context
+            .draw_image_with_html_canvas_element_and_dw_and_dh(
+                &self.board,
+                0.0,
+                0.0,
+                minimap_width as f64,
+                minimap_height as f64,
+            )
+            .unwrap();
+        self.context.set_stroke_style_str(&theme.focus);
+        self.context.stroke_rect(
+            columns.start as f64 * scale + 0.5,
+            rows.start as f64 * scale + 0.5,
+            (columns.len() as f64 * scale - 1.0).max(1.0),
+            (rows.len() as f64 * scale - 1.0).max(1.0),
+        );
+    }
+
+    /// The (`row`, `column`) of the cell of `universe` under the minimap
+    /// pixel (`x`, `y`), if any.
+    pub(super) fn cell_at(&self, universe: &Universe, x: f64, y: f64) -> Option<(u32, u32)> {
+        let scale = self.scale(universe);
+        let (row, column) = ((y / scale).floor(), (x / scale).floor());
+        if row < 0.0
+            || column < 0.0
+            || row >= universe.height() as f64
+            || column >= universe.width() as f64
+        {
+            return None;
+        }
+        Some((row as u32, column as u32))
+    }
+
+    /// Minimap pixels per cell, fitting the universe into `MINIMAP_SIZE`.
+    fn scale(&self, universe: &Universe) -> f64 {
+        let longest = universe.width().max(universe.height()).max(1);
+        MINIMAP_SIZE as f64 / longest as f64
+    }
+
+    fn draw_board(&mut self, universe: &Universe, theme: &Theme) {
+        let (width, height) = (universe.width(), universe.height());
+        let (alive, dead) = (rgba(&theme.alive), rgba(&theme.dead));
+        self.pixels.clear();
+        for row in 0..height {
+            for column in 0..width {
+                let color = match universe.get(Position::new(row, column)) {
+                    Some(Cell::Alive) => alive,
+                    _ => dead,
+                };
+                self.pixels.extend_from_slice(&color);
+            }
+        }
+        if self.board.width() != width || self.board.height() != height {
+            self.board.set_width(width);
+            self.board.set_height(height);
+        }
+        let image = web_sys::ImageData::new_with_u8_clamped_array_and_sh(
+            Clamped(&self.pixels),
+            width,
+            height,
+        )
+        .unwrap();
+        self.board_context.put_image_data(&image, 0.0, 0.0).unwrap();
+    }
+}
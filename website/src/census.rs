@@ -0,0 +1,223 @@
+//! Counting the small, well-known objects on a board, the way soup searches
+//! take a census of what a random start settles into.
+//!
+//! Live cells within `REACH` of each other, in any direction, are taken to
+//! be one object, which keeps together oscillators like the toad that come
+//! apart in some phases. Each object is turned into a canonical form, the
+//! same whichever way it is rotated or reflected and wherever it is, and
+//! looked up among the canonical forms of every phase of the objects in
+//! `KNOWN_OBJECTS`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use crate::engine::{Cell, Position, Universe};
+use crate::patterns::Pattern;
+
+/// The objects the census recognizes, by name, as RLE of one phase, with
+/// their periods under Conway's Life.
+const KNOWN_OBJECTS: &[(&str, &str, u32)] = &[
+    ("block", "x = 2, y = 2\n2o$2o!", 1),
+    ("beehive", "x = 4, y = 3\nb2o$o2bo$b2o!", 1),
+    ("loaf", "x = 4, y = 4\nb2o$o2bo$bobo$2bo!", 1),
+    ("boat", "x = 3, y = 3\n2o$obo$bo!", 1),
+    ("tub", "x = 3, y = 3\nbo$obo$bo!", 1),
+    ("pond", "x = 4, y = 4\nb2o$o2bo$o2bo$b2o!", 1),
+    ("ship", "x = 3, y = 3\n2o$obo$b2o!", 1),
+    ("blinker", "x = 3, y = 1\n3o!", 2),
+    ("toad", "x = 4, y = 2\nb3o$3o!", 2),
+    ("beacon", "x = 4, y = 4\n2o$2o$2b2o$2b2o!", 2),
+    ("glider", "x = 3, y = 3\nbo$2bo$3o!", 4),
+    ("lwss", "x = 5, y = 4\nbo2bo$o4b$o3bo$4o!", 4),
+];
+
+/// How far apart, in rows or columns, two live cells can be and still be
+/// part of the same object.
+const REACH: u32 = 2;
+
+/// Objects with more live cells than this aren't looked up, since nothing
+/// in `KNOWN_OBJECTS` is that big.
+const MAX_OBJECT_CELLS: usize = 16;
+
+/// The live cells of an object, where they are on the board.
+type Object = Vec<(u32, u32)>;
+
+/// The live cells of an object, moved so its top-left corner is at the
+/// origin and sorted.
+type Shape = Vec<(u32, u32)>;
+
+thread_local! {
+    /// The name of the known object each canonical shape is a phase of.
+    static KNOWN_SHAPES: HashMap<Shape, &'static str> = known_shapes();
+}
+
+/// How many of each known object were on the board at one generation.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Census {
+    generation: u64,
+    counts: BTreeMap<&'static str, u32>,
+    /// Objects that aren't any of the known ones.
+    other: u32,
+}
+
+impl Census {
+    /// Count the objects on `universe`. Objects are cut off at the edges,
+    /// even on a torus, so ones that wrap around count as unknown.
+    pub fn take(universe: &Universe) -> Census {
+        let mut census = Census {
+            generation: universe.generation(),
+            ..Census::default()
+        };
+        for (name, _) in identify(universe) {
+            match name {
+                Some(name) => *census.counts.entry(name).or_default() += 1,
+                None => census.other += 1,
+            }
+        }
+        census
+    }
+
+    /// Each known object that was found and how many of it, the most common
+    /// first.
+    pub fn counts(&self) -> Vec<(&'static str, u32)> {
+        let mut counts: Vec<_> = self.counts.iter().map(|(&name, &n)| (name, n)).collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        counts
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Census {
+    /// The generation the census was taken at.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// How many of the known object called `name` were found.
+    pub fn count(&self, name: &str) -> u32 {
+        self.counts.get(name).copied().unwrap_or(0)
+    }
+
+    /// How many objects weren't recognized.
+    pub fn other(&self) -> u32 {
+        self.other
+    }
+}
+
+/// Lists the counts as `name count`, the most common first.
+impl fmt::Display for Census {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, (name, count)) in self.counts().into_iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{} {}", name, count)?;
+        }
+        Ok(())
+    }
+}
+
+/// The name of each object on the board, if it is a known one, and its live
+/// cells.
+pub(crate) fn identify(universe: &Universe) -> Vec<(Option<&'static str>, Object)> {
+    KNOWN_SHAPES.with(|known| {
+        objects(universe)
+            .into_iter()
+            .map(|object| {
+                let name = if object.len() <= MAX_OBJECT_CELLS {
+                    known.get(&canonical(&object)).copied()
+                } else {
+                    None
+                };
+                (name, object)
+            })
+            .collect()
+    })
+}
+
+/// The live cells of each object on the board.
+fn objects(universe: &Universe) -> Vec<Object> {
+    let (width, height) = (universe.width(), universe.height());
+    let mut seen = vec![false; (width * height) as usize];
+    let mut objects = Vec::new();
+    for row in 0..height {
+        for col in 0..width {
+            let idx = (row * width + col) as usize;
+            if seen[idx] || universe.get(Position::new(row, col)) != Some(Cell::Alive) {
+                continue;
+            }
+            seen[idx] = true;
+            let mut object = Vec::new();
+            let mut pending = vec![(row, col)];
+            while let Some((row, col)) = pending.pop() {
+                object.push((row, col));
+                for y in row.saturating_sub(REACH)..(row + REACH + 1).min(height) {
+                    for x in col.saturating_sub(REACH)..(col + REACH + 1).min(width) {
+                        let idx = (y * width + x) as usize;
+                        if !seen[idx] && universe.get(Position::new(y, x)) == Some(Cell::Alive) {
+                            seen[idx] = true;
+                            pending.push((y, x));
+                        }
+                    }
+                }
+            }
+            objects.push(object);
+        }
+    }
+    objects
+}
+
+/// The smallest of the eight rotations and reflections of `cells`, moved
+/// to the origin.
+fn canonical(cells: &[(u32, u32)]) -> Shape {
+    (0..8)
+        .map(|transform| {
+            let turned = cells.iter().map(|&(row, col)| {
+                let (row, col) = (row as i64, col as i64);
+                let (row, col) = if transform & 4 != 0 {
+                    (col, row)
+                } else {
+                    (row, col)
+                };
+                let row = if transform & 1 != 0 { -row } else { row };
+                let col = if transform & 2 != 0 { -col } else { col };
+                (row, col)
+            });
+            let turned: Vec<_> = turned.collect();
+            let top = turned.iter().map(|&(row, _)| row).min().unwrap_or(0);
+            let left = turned.iter().map(|&(_, col)| col).min().unwrap_or(0);
+            let mut shape: Shape = turned
+                .into_iter()
+                .map(|(row, col)| ((row - top) as u32, (col - left) as u32))
+                .collect();
+            shape.sort_unstable();
+            shape
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+/// Run every known object through each of its phases.
+fn known_shapes() -> HashMap<Shape, &'static str> {
+    // Room for a spaceship to travel a full period without reaching the
+    // edges.
+    const MARGIN: u32 = 4;
+    let mut shapes = HashMap::new();
+    for &(name, rle, period) in KNOWN_OBJECTS {
+        let pattern = Pattern::from_rle(rle).expect("known objects should parse");
+        let mut universe =
+            Universe::new_with_size(pattern.width() + 2 * MARGIN, pattern.height() + 2 * MARGIN);
+        universe.insert_pattern(Position::new(MARGIN, MARGIN), &pattern);
+        for _ in 0..period {
+            for object in objects(&universe) {
+                shapes.insert(canonical(&object), name);
+            }
+            universe.tick();
+        }
+    }
+    shapes
+}
@@ -0,0 +1,256 @@
+//! An unbounded plane stored sparsely, as 64x64 chunks that only exist
+//! while they have live cells in them.
+//!
+//! Like HashLife, the plane has no edges: `width` and `height` only describe
+//! the region that is visible through the `SimulationEngine` interface, and
+//! patterns that leave it, such as gliders, keep going forever. Unlike
+//! HashLife it steps one generation at a time, so it costs the same for
+//! chaotic patterns as for regular ones, in proportion to how much of the
+//! plane is alive.
+//!
+//! The page draws the plane straight from its chunks with `live_cells_in`,
+//! so the view can follow patterns well beyond the visible region.
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use crate::engine::SimulationEngine;
+use crate::rule::{Rule, RuleError};
+use crate::{Cell, Position};
+
+/// The side length of a chunk, in cells.
+pub const CHUNK_SIZE: i64 = 64;
+
+/// One bit per cell: bit `column` of `rows[row]` is set when that cell is
+/// alive.
+type Chunk = [u64; CHUNK_SIZE as usize];
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+pub struct ChunkedPlane {
+    width: u32,
+    height: u32,
+    rule: Rule,
+    /// The chunks with live cells in them, by chunk row and chunk column.
+    chunks: HashMap<(i64, i64), Chunk>,
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl ChunkedPlane {
+    /// Creates an empty plane with a visible region of the given size.
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen(constructor))]
+    pub fn new(width: u32, height: u32) -> ChunkedPlane {
+        ChunkedPlane {
+            width,
+            height,
+            rule: Rule::conway(),
+            chunks: HashMap::new(),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Change the size of the visible region, leaving the plane as it is.
+    pub fn set_size(&mut self, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+    }
+
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    /// Change the rule, which must be Life-like, since chunks only count
+    /// the eight neighbors of live cells. Empty chunks are never stepped,
+    /// so rules with `B0` are refused too.
+    pub fn set_rule(&mut self, rule: Rule) -> Result<(), RuleError> {
+        if !rule.is_life_like() {
+            return Err(RuleError::NotLifeLike(rule.to_string()));
+        }
+        if rule.births_on_zero() {
+            return Err(RuleError::BirthOnZero(rule.to_string()));
+        }
+        self.rule = rule;
+        Ok(())
+    }
+
+    /// The number of live cells on the whole plane, visible or not.
+    pub fn population(&self) -> u64 {
+        self.chunks
+            .values()
+            .flat_map(|chunk| chunk.iter())
+            .map(|row| row.count_ones() as u64)
+            .sum()
+    }
+
+    /// The number of chunks that have live cells in them.
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Whether the cell at any position on the plane is alive.
+    pub fn get(&self, row: i64, column: i64) -> bool {
+        let (key, y, x) = locate(row, column);
+        self.chunks
+            .get(&key)
+            .is_some_and(|chunk| chunk[y] & 1 << x != 0)
+    }
+
+    pub fn set(&mut self, row: i64, column: i64, alive: bool) {
+        let (key, y, x) = locate(row, column);
+        if alive {
+            self.chunks.entry(key).or_insert([0; CHUNK_SIZE as usize])[y] |= 1 << x;
+        } else if let Some(chunk) = self.chunks.get_mut(&key) {
+            chunk[y] &= !(1 << x);
+            if chunk.iter().all(|&row| row == 0) {
+                self.chunks.remove(&key);
+            }
+        }
+    }
+}
+
+impl ChunkedPlane {
+    /// The (`row`, `column`) of every live cell in `rows` and `columns`,
+    /// looking only at the chunks that overlap them.
+    pub fn live_cells_in(&self, rows: Range<i64>, columns: Range<i64>) -> Vec<(i64, i64)> {
+        let mut cells = Vec::new();
+        if rows.is_empty() || columns.is_empty() {
+            return cells;
+        }
+        let chunks = |range: &Range<i64>| {
+            range.start.div_euclid(CHUNK_SIZE)..=(range.end - 1).div_euclid(CHUNK_SIZE)
+        };
+        for chunk_row in chunks(&rows) {
+            for chunk_column in chunks(&columns) {
+                let chunk = match self.chunks.get(&(chunk_row, chunk_column)) {
+                    Some(chunk) => chunk,
+                    None => continue,
+                };
+                for (y, &bits) in chunk.iter().enumerate() {
+                    let row = chunk_row * CHUNK_SIZE + y as i64;
+                    if !rows.contains(&row) {
+                        continue;
+                    }
+                    let mut bits = bits;
+                    while bits != 0 {
+                        let column = chunk_column * CHUNK_SIZE + bits.trailing_zeros() as i64;
+                        bits &= bits - 1;
+                        if columns.contains(&column) {
+                            cells.push((row, column));
+                        }
+                    }
+                }
+            }
+        }
+        cells
+    }
+
+    /// A row of a chunk, or 0 if the chunk is empty.
+    fn row(&self, key: (i64, i64), y: usize) -> u64 {
+        self.chunks.get(&key).map_or(0, |chunk| chunk[y])
+    }
+
+    /// Row `y` of the chunk at `key`, which may be one row above or below the
+    /// chunk, with the neighboring cell on either side: bit 0 is the last
+    /// cell of the chunk to the west, bits 1 to 64 the chunk's own cells and
+    /// bit 65 the first cell of the chunk to the east.
+    fn padded_row(&self, (chunk_row, chunk_column): (i64, i64), y: i64) -> u128 {
+        let (chunk_row, y) = match y {
+            -1 => (chunk_row - 1, CHUNK_SIZE as usize - 1),
+            CHUNK_SIZE => (chunk_row + 1, 0),
+            y => (chunk_row, y as usize),
+        };
+        let west = self.row((chunk_row, chunk_column - 1), y) >> (CHUNK_SIZE - 1);
+        let middle = self.row((chunk_row, chunk_column), y);
+        let east = self.row((chunk_row, chunk_column + 1), y) & 1;
+        west as u128 | (middle as u128) << 1 | (east as u128) << (CHUNK_SIZE + 1)
+    }
+
+    /// The chunk at `key` one generation later.
+    fn next_chunk(&self, key: (i64, i64)) -> Chunk {
+        let rows: Vec<u128> = (-1..=CHUNK_SIZE).map(|y| self.padded_row(key, y)).collect();
+        let mut next = [0; CHUNK_SIZE as usize];
+        for (y, lines) in rows.windows(3).enumerate() {
+            // Nothing is born next to empty rows.
+            if lines.iter().all(|&line| line == 0) {
+                continue;
+            }
+            for x in 0..CHUNK_SIZE as usize {
+                let alive = (lines[1] >> (x + 1) & 1) as u8;
+                let block: u32 = lines
+                    .iter()
+                    .map(|line| (line >> x & 0b111).count_ones())
+                    .sum();
+                let live_neighbors = (block - alive as u32) as u16;
+                if self.rule.next_state(alive, live_neighbors) == 1 {
+                    next[y] |= 1 << x;
+                }
+            }
+        }
+        next
+    }
+}
+
+/// The chunk holding a cell, and the cell's row and column within it.
+fn locate(row: i64, column: i64) -> ((i64, i64), usize, usize) {
+    (
+        (row.div_euclid(CHUNK_SIZE), column.div_euclid(CHUNK_SIZE)),
+        row.rem_euclid(CHUNK_SIZE) as usize,
+        column.rem_euclid(CHUNK_SIZE) as usize,
+    )
+}
+
+impl SimulationEngine for ChunkedPlane {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn cell(&self, position: Position) -> Option<Cell> {
+        if !self.contains(position) {
+            return None;
+        }
+        Some(Cell::from(
+            self.get(position.row as i64, position.column as i64),
+        ))
+    }
+
+    fn set_cell(&mut self, position: Position, cell: Cell) -> Option<Cell> {
+        let was = self.cell(position)?;
+        self.set(
+            position.row as i64,
+            position.column as i64,
+            cell == Cell::Alive,
+        );
+        Some(was)
+    }
+
+    /// Only chunks with live cells and their neighbors are stepped, and
+    /// chunks that die out are dropped.
+    fn tick(&mut self) {
+        let candidates: HashSet<(i64, i64)> = self
+            .chunks
+            .keys()
+            .flat_map(|&(chunk_row, chunk_column)| {
+                (-1..=1)
+                    .flat_map(move |dy| (-1..=1).map(move |dx| (chunk_row + dy, chunk_column + dx)))
+            })
+            .collect();
+        self.chunks = candidates
+            .into_iter()
+            .map(|key| (key, self.next_chunk(key)))
+            .filter(|(_, chunk)| chunk.iter().any(|&row| row != 0))
+            .collect();
+    }
+}
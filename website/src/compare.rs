@@ -0,0 +1,78 @@
+//! A second canvas beside the main one, running a copy of its board under
+//! another rule, or with a few cells changed, in lockstep with it.
+
+use crate::canvas::Canvas;
+use crate::engine::Position;
+use crate::rule::RuleError;
+
+/// Where the compared board's generation and population are shown.
+const STATS_ID: &str = "compare-stats";
+
+pub struct Comparison {
+    canvas: Canvas,
+    /// Whether to tint the cells where the two boards differ.
+    show_difference: bool,
+}
+
+impl Comparison {
+    /// Run `canvas`, made with a copy of the board of `main`, under `rule`.
+    pub fn new(main: &Canvas, mut canvas: Canvas, rule: &str) -> Result<Comparison, RuleError> {
+        canvas.make_secondary(STATS_ID);
+        canvas.set_theme(&main.theme);
+        canvas.set_cell_size(main.viewport.cell_size());
+        canvas.set_rule(rule)?;
+        Ok(Comparison {
+            canvas,
+            show_difference: false,
+        })
+    }
+
+    /// Start again from a copy of the board of `main`, keeping the rule.
+    pub fn reset(&mut self, main: &Canvas) {
+        let mut universe = main.universe.clone();
+        universe.apply_rule(self.canvas.universe.rule());
+        self.canvas.set_universe(universe);
+    }
+
+    pub fn set_rule(&mut self, rule: &str) -> Result<(), RuleError> {
+        self.canvas.set_rule(rule)
+    }
+
+    pub fn step(&mut self, generations: u32) {
+        self.canvas.step(generations);
+    }
+
+    /// Advance `2^exponent` generations at once, as `Canvas::step_pow2`.
+    pub fn step_pow2(&mut self, exponent: u8) -> bool {
+        self.canvas.step_pow2(exponent)
+    }
+
+    /// Use the engine called `name`, as `Canvas::set_engine`. The worker
+    /// engine belongs to the main canvas, so the naive engine stands in.
+    pub fn set_engine(&mut self, name: &str) {
+        self.canvas.set_engine(name);
+    }
+
+    /// Toggle the cell under (`x`, `y`) on the compared canvas, in pixels.
+    pub fn toggle_cell_at(&mut self, x: f64, y: f64) {
+        if let Some((row, column)) = self.canvas.cell_at(x, y) {
+            self.canvas.toggle_cell(Position::new(row, column));
+        }
+    }
+
+    pub fn set_show_difference(&mut self, show: bool) {
+        self.show_difference = show;
+    }
+
+    /// Draw the compared board, tinting the cells that differ from the board
+    /// of `main` if asked to.
+    pub fn draw(&mut self, main: &Canvas) {
+        let difference = if self.show_difference {
+            self.canvas.universe.diff(&main.universe)
+        } else {
+            Vec::new()
+        };
+        self.canvas.set_difference(difference);
+        self.canvas.draw();
+    }
+}
@@ -0,0 +1,121 @@
+//! Elementary cellular automata, the one-dimensional rules Wolfram numbered
+//! from 0 to 255, drawn with each row of the board one generation after the
+//! row above it.
+//!
+//! A cell's next state depends on itself and the cells either side of it.
+//! Read as a three-bit number with the left cell highest, those three cells
+//! pick a bit of the rule number, which is the cell's state in the row
+//! below. Once the board is full it scrolls up a row every generation, so
+//! the newest generation is always along the bottom.
+
+use crate::engine::{SimulationEngine, Topology};
+use crate::{Cell, Position};
+
+/// Rule 30, which makes a chaotic triangle out of a single cell.
+pub const DEFAULT_RULE: u8 = 30;
+
+#[derive(Clone, Debug)]
+pub struct Elementary {
+    width: u32,
+    height: u32,
+    /// Whether the ends of a row wrap around to each other.
+    topology: Topology,
+    rule: u8,
+    /// Every cell in row-major order, the oldest generation at the top.
+    cells: Vec<bool>,
+    /// How many rows have been filled. The last of them is the current
+    /// generation.
+    filled: u32,
+}
+
+impl Elementary {
+    /// An empty board running `DEFAULT_RULE`.
+    pub fn new(width: u32, height: u32) -> Elementary {
+        Elementary {
+            width,
+            height,
+            topology: Topology::Torus,
+            rule: DEFAULT_RULE,
+            cells: vec![false; (width * height) as usize],
+            filled: 0,
+        }
+    }
+
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+    }
+
+    pub fn rule(&self) -> u8 {
+        self.rule
+    }
+
+    pub fn set_rule(&mut self, rule: u8) {
+        self.rule = rule;
+    }
+
+    /// The row holding the current generation, or `None` before any row
+    /// has a live cell.
+    pub fn current_row(&self) -> Option<u32> {
+        self.filled.checked_sub(1)
+    }
+
+    fn get_index(&self, row: u32, column: u32) -> usize {
+        (row * self.width + column) as usize
+    }
+}
+
+impl SimulationEngine for Elementary {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn cell(&self, position: Position) -> Option<Cell> {
+        if !self.contains(position) {
+            return None;
+        }
+        Some(Cell::from(
+            self.cells[self.get_index(position.row, position.column)],
+        ))
+    }
+
+    /// Live cells below the current generation make their row the current
+    /// one, so a board copied in carries on from its lowest live cell.
+    fn set_cell(&mut self, position: Position, cell: Cell) -> Option<Cell> {
+        let was = self.cell(position)?;
+        let idx = self.get_index(position.row, position.column);
+        self.cells[idx] = cell == Cell::Alive;
+        if cell == Cell::Alive {
+            self.filled = self.filled.max(position.row + 1);
+        }
+        Some(was)
+    }
+
+    fn tick(&mut self) {
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+        let current =
+            |column: i64| match (self.current_row(), self.topology.wrap(column, self.width)) {
+                (Some(row), Some(column)) => self.cells[self.get_index(row, column)] as u8,
+                _ => 0,
+            };
+        let next: Vec<bool> = (0..self.width as i64)
+            .map(|column| {
+                let pattern = current(column - 1) << 2 | current(column) << 1 | current(column + 1);
+                self.rule >> pattern & 1 == 1
+            })
+            .collect();
+        if self.filled == self.height {
+            self.cells.drain(..self.width as usize);
+            self.cells.extend(next);
+        } else {
+            let start = self.get_index(self.filled, 0);
+            self.cells[start..start + self.width as usize].copy_from_slice(&next);
+            self.filled += 1;
+        }
+    }
+}
@@ -0,0 +1,63 @@
+//! The simulation itself, with no dependence on the browser, and the
+//! interface shared by every way of running it.
+
+mod activity;
+mod checkpoints;
+mod rewind;
+mod sums;
+mod universe;
+
+pub use activity::Activity;
+pub use checkpoints::{
+    Checkpoint, Checkpoints, DEFAULT_CHECKPOINT_INTERVAL, MAX_AUTOMATIC_CHECKPOINTS,
+};
+pub use rewind::{Change, Delta, Rewind, DEFAULT_REWIND_CHANGES, DEFAULT_REWIND_STEPS};
+pub use universe::{Bounds, Cell, Position, Snapshot, Stabilization, Topology, Universe};
+
+/// A Game of Life simulation that can be inspected, edited and advanced.
+pub trait SimulationEngine {
+    /// Width of the visible region, in cells.
+    fn width(&self) -> u32;
+
+    /// Height of the visible region, in cells.
+    fn height(&self) -> u32;
+
+    /// Whether `position` is within `width` and `height`.
+    fn contains(&self, position: Position) -> bool {
+        position.row < self.height() && position.column < self.width()
+    }
+
+    /// The cell at `position`, or `None` outside `width` and `height`.
+    fn cell(&self, position: Position) -> Option<Cell>;
+
+    /// Make the cell at `position` `cell`. Returns what it was, or `None`
+    /// outside `width` and `height`, where nothing is changed.
+    fn set_cell(&mut self, position: Position, cell: Cell) -> Option<Cell>;
+
+    /// Advance the simulation by a single generation.
+    fn tick(&mut self);
+
+    /// Advance the simulation by `generations` generations.
+    ///
+    /// Engines that can skip ahead faster than one tick at a time should
+    /// override this.
+    fn step(&mut self, generations: u64) {
+        for _ in 0..generations {
+            self.tick();
+        }
+    }
+
+    /// Copy the visible region of `other` into this engine.
+    fn copy_from(&mut self, other: &dyn SimulationEngine) {
+        let height = self.height().min(other.height());
+        let width = self.width().min(other.width());
+        for row in 0..height {
+            for column in 0..width {
+                let position = Position::new(row, column);
+                if let Some(cell) = other.cell(position) {
+                    self.set_cell(position, cell);
+                }
+            }
+        }
+    }
+}
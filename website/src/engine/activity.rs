@@ -0,0 +1,56 @@
+//! How often cells have changed state recently.
+
+use std::collections::VecDeque;
+
+use fixedbitset::FixedBitSet;
+
+/// Counts, for every cell, how many of the last `window` recorded steps
+/// changed whether it was alive.
+#[derive(Clone, Debug)]
+pub struct Activity {
+    window: usize,
+    /// The cells each recorded step changed, oldest first.
+    steps: VecDeque<FixedBitSet>,
+    counts: Vec<u16>,
+}
+
+impl Activity {
+    pub fn new(window: u32, cells: usize) -> Activity {
+        Activity {
+            window: window.clamp(1, u16::MAX as u32) as usize,
+            steps: VecDeque::new(),
+            counts: vec![0; cells],
+        }
+    }
+
+    pub fn window(&self) -> u32 {
+        self.window as u32
+    }
+
+    /// Record a step from the live cells `before` to those `after`.
+    pub fn record(&mut self, before: &FixedBitSet, after: &FixedBitSet) {
+        let mut changed = before.clone();
+        changed.symmetric_difference_with(after);
+        for idx in changed.ones() {
+            self.counts[idx] += 1;
+        }
+        self.steps.push_back(changed);
+        if self.steps.len() > self.window {
+            let forgotten = self.steps.pop_front().unwrap();
+            for idx in forgotten.ones() {
+                self.counts[idx] -= 1;
+            }
+        }
+    }
+
+    /// Forget every step, for a board of `cells` cells.
+    pub fn reset(&mut self, cells: usize) {
+        self.steps.clear();
+        self.counts = vec![0; cells];
+    }
+
+    /// How many of the recorded steps changed cell `idx`.
+    pub fn count(&self, idx: usize) -> u16 {
+        self.counts[idx]
+    }
+}
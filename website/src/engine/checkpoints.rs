@@ -0,0 +1,148 @@
+//! Compressed copies of whole boards, taken by hand or every so many
+//! generations, to go back to long after the steps in between are
+//! forgotten.
+
+use std::collections::BTreeMap;
+
+use crate::random::XorShift;
+use crate::rule::Rule;
+
+/// How many generations apart the page takes automatic checkpoints.
+pub const DEFAULT_CHECKPOINT_INTERVAL: u32 = 100;
+
+/// The most automatic checkpoints kept at once. The oldest are dropped to
+/// make room for new ones; checkpoints taken by hand are always kept.
+pub const MAX_AUTOMATIC_CHECKPOINTS: usize = 100;
+
+/// A board with its cells' numbered states deflated.
+#[derive(Clone, Debug)]
+pub struct Checkpoint {
+    pub width: u32,
+    pub height: u32,
+    pub rule: Rule,
+    pub generation: u64,
+    /// Whether it was taken every `interval` generations rather than by hand.
+    pub automatic: bool,
+    /// The random number generator noise was drawn from, so that stepping
+    /// on from the checkpoint flips the same cells again.
+    pub rng: XorShift,
+    states: Vec<u8>,
+}
+
+impl Checkpoint {
+    /// Compress the numbered `states` of a board's cells, in row-major order.
+    pub fn new(
+        width: u32,
+        height: u32,
+        rule: Rule,
+        generation: u64,
+        automatic: bool,
+        rng: XorShift,
+        states: &[u8],
+    ) -> Checkpoint {
+        Checkpoint {
+            width,
+            height,
+            rule,
+            generation,
+            automatic,
+            rng,
+            states: miniz_oxide::deflate::compress_to_vec(states, 6),
+        }
+    }
+
+    /// The numbered states of the cells, in row-major order.
+    pub fn states(&self) -> Vec<u8> {
+        // Only ever inflating what `new` deflated, so this can't fail.
+        miniz_oxide::inflate::decompress_to_vec(&self.states).expect("checkpoints should inflate")
+    }
+}
+
+/// Every checkpoint kept, by id, and when to take the next automatic one.
+#[derive(Clone, Debug)]
+pub struct Checkpoints {
+    /// Generations between automatic checkpoints, or 0 for none.
+    interval: u64,
+    checkpoints: BTreeMap<u32, Checkpoint>,
+    next_id: u32,
+}
+
+impl Checkpoints {
+    pub fn new(interval: u32) -> Checkpoints {
+        Checkpoints {
+            interval: interval as u64,
+            checkpoints: BTreeMap::new(),
+            next_id: 0,
+        }
+    }
+
+    pub fn interval(&self) -> u32 {
+        self.interval as u32
+    }
+
+    pub fn set_interval(&mut self, interval: u32) {
+        self.interval = interval as u64;
+    }
+
+    /// Whether the board should be checkpointed on reaching `generation`
+    /// from `previous`, because it passed a multiple of the interval.
+    pub fn due(&self, previous: u64, generation: u64) -> bool {
+        self.interval > 0 && generation / self.interval > previous / self.interval
+    }
+
+    /// Keep `checkpoint`, returning its id.
+    pub fn add(&mut self, checkpoint: Checkpoint) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.checkpoints.insert(id, checkpoint);
+        let automatic = self.automatic().count();
+        if automatic > MAX_AUTOMATIC_CHECKPOINTS {
+            let oldest: Vec<u32> = self
+                .automatic()
+                .map(|(id, _)| id)
+                .take(automatic - MAX_AUTOMATIC_CHECKPOINTS)
+                .collect();
+            for id in oldest {
+                self.checkpoints.remove(&id);
+            }
+        }
+        id
+    }
+
+    pub fn get(&self, id: u32) -> Option<&Checkpoint> {
+        self.checkpoints.get(&id)
+    }
+
+    /// The ids of every checkpoint, oldest first.
+    pub fn ids(&self) -> impl Iterator<Item = u32> + '_ {
+        self.checkpoints.keys().copied()
+    }
+
+    /// The latest automatic checkpoint at or before `generation`.
+    pub fn nearest(&self, generation: u64) -> Option<u32> {
+        self.automatic()
+            .filter(|(_, checkpoint)| checkpoint.generation <= generation)
+            .max_by_key(|(_, checkpoint)| checkpoint.generation)
+            .map(|(id, _)| id)
+    }
+
+    /// Drop the automatic checkpoints, once the board no longer follows on
+    /// from them.
+    pub fn forget_automatic(&mut self) {
+        self.checkpoints
+            .retain(|_, checkpoint| !checkpoint.automatic);
+    }
+
+    /// Forget the automatic checkpoints taken at `generation`.
+    pub fn forget_automatic_at(&mut self, generation: u64) {
+        self.checkpoints
+            .retain(|_, checkpoint| !checkpoint.automatic || checkpoint.generation != generation);
+    }
+
+    fn automatic(&self) -> impl Iterator<Item = (u32, &Checkpoint)> + '_ {
+        self.checkpoints
+            .iter()
+            .filter(|(_, checkpoint)| checkpoint.automatic)
+            .map(|(&id, checkpoint)| (id, checkpoint))
+    }
+}
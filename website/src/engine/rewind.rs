@@ -0,0 +1,158 @@
+//! The changes made by recent steps, kept so that the board can be wound
+//! back and forth through them without simulating again.
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::random::XorShift;
+
+/// How many steps the page keeps for rewinding.
+pub const DEFAULT_REWIND_STEPS: u32 = 500;
+
+/// How many changed cells are kept for rewinding, across every step, so
+/// that a busy board doesn't keep hundreds of megabytes of them. Each takes
+/// 8 bytes.
+pub const DEFAULT_REWIND_CHANGES: usize = 1 << 21;
+
+/// A cell a step changed: its index in row-major order and its numbered
+/// states before and after the step.
+pub type Change = (u32, u8, u8);
+
+/// Everything one step changed.
+#[derive(Clone, Debug)]
+pub struct Delta {
+    /// How many generations the step advanced, 1 unless an engine skipped
+    /// ahead.
+    pub generations: u64,
+    pub changes: Vec<Change>,
+    /// The random number generator noise is drawn from, before and after
+    /// the step.
+    pub rng: (XorShift, XorShift),
+}
+
+/// The deltas of up to `capacity` recent steps, holding up to
+/// `max_changes` changes between them, some of which may have been undone by
+/// winding back.
+#[derive(Clone, Debug)]
+pub struct Rewind {
+    capacity: usize,
+    max_changes: usize,
+    /// Oldest first.
+    deltas: VecDeque<Delta>,
+    /// The changes in `deltas`, all told.
+    changes: usize,
+    /// How many of the newest deltas are undone.
+    undone: usize,
+}
+
+impl Rewind {
+    pub fn new(capacity: u32, max_changes: usize) -> Rewind {
+        Rewind {
+            capacity: capacity.max(1) as usize,
+            max_changes,
+            deltas: VecDeque::new(),
+            changes: 0,
+            undone: 0,
+        }
+    }
+
+    /// Record a step taken from the current board. Steps that were undone
+    /// can't be redone after this, since the board has moved on another way.
+    /// The oldest steps are forgotten to make room, and a step with more
+    /// changes than there is room for at all isn't kept.
+    pub fn record(&mut self, delta: Delta) {
+        for undone in self.deltas.drain(self.deltas.len() - self.undone..) {
+            self.changes -= undone.changes.len();
+        }
+        self.undone = 0;
+        self.changes += delta.changes.len();
+        self.deltas.push_back(delta);
+        while self.deltas.len() > self.capacity || self.changes > self.max_changes {
+            match self.deltas.pop_front() {
+                Some(oldest) => self.changes -= oldest.changes.len(),
+                None => break,
+            }
+        }
+    }
+
+    /// How many steps are remembered, undone or not.
+    pub fn len(&self) -> u32 {
+        self.deltas.len() as u32
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.deltas.is_empty()
+    }
+
+    /// How many changed cells are kept, across every step.
+    pub fn changes(&self) -> usize {
+        self.changes
+    }
+
+    /// How many of the newest steps are undone.
+    pub fn undone(&self) -> u32 {
+        self.undone as u32
+    }
+
+    /// The newest step that isn't undone, which the board should now undo.
+    pub fn back(&mut self) -> Option<&Delta> {
+        if self.undone == self.deltas.len() {
+            return None;
+        }
+        self.undone += 1;
+        self.deltas.get(self.deltas.len() - self.undone)
+    }
+
+    /// The oldest undone step, which the board should now redo.
+    pub fn forward(&mut self) -> Option<&Delta> {
+        if self.undone == 0 {
+            return None;
+        }
+        self.undone -= 1;
+        self.deltas.get(self.deltas.len() - self.undone - 1)
+    }
+
+    /// The step that led to the current board, if it is remembered.
+    pub fn latest(&self) -> Option<&Delta> {
+        let done = self.deltas.len() - self.undone;
+        done.checked_sub(1).and_then(|idx| self.deltas.get(idx))
+    }
+
+    /// Make the step that led to the current board end with `changes` as
+    /// well, each a cell with its state before the step and the state it
+    /// ends in, as when a hook overrides what the rule did. Nothing is
+    /// amended while steps are undone.
+    pub fn amend_latest(&mut self, changes: &[Change]) {
+        if self.undone > 0 {
+            return;
+        }
+        let mut delta = match self.deltas.pop_back() {
+            Some(delta) => delta,
+            None => return,
+        };
+        self.changes -= delta.changes.len();
+        let mut positions: HashMap<u32, usize> = delta
+            .changes
+            .iter()
+            .enumerate()
+            .map(|(position, &(idx, _, _))| (idx, position))
+            .collect();
+        for &(idx, before, after) in changes {
+            match positions.get(&idx) {
+                Some(&position) => delta.changes[position].2 = after,
+                None => {
+                    positions.insert(idx, delta.changes.len());
+                    delta.changes.push((idx, before, after));
+                }
+            }
+        }
+        delta.changes.retain(|&(_, before, after)| before != after);
+        self.record(delta);
+    }
+
+    /// Forget every step.
+    pub fn clear(&mut self) {
+        self.deltas.clear();
+        self.changes = 0;
+        self.undone = 0;
+    }
+}
@@ -0,0 +1,86 @@
+//! Neighbor counts for large neighborhoods from prefix sums, so that a cell
+//! costs about the same however far its neighborhood reaches.
+
+use fixedbitset::FixedBitSet;
+
+use super::Topology;
+use crate::rule::Neighborhood;
+
+/// The number of live cells within `range` of every cell, in row-major
+/// order, counting the cell itself.
+///
+/// The board is padded by `range` on every side according to `topology`, and
+/// each padded row turned into running totals. Square neighborhoods then sum
+/// those totals down the columns into a summed-area table and read each
+/// count from its four corners, while diamond-shaped von Neumann
+/// neighborhoods add up one slice of a row per row they cover.
+pub(crate) fn neighbor_counts(
+    cells: &FixedBitSet,
+    width: u32,
+    height: u32,
+    topology: Topology,
+    neighborhood: Neighborhood,
+    range: u8,
+) -> Vec<u16> {
+    if width == 0 || height == 0 {
+        return Vec::new();
+    }
+    let range = range as usize;
+    let padded_width = width as usize + 2 * range;
+    let padded_height = height as usize + 2 * range;
+    // Each line has a leading zero, so that line[x] is the total of the
+    // cells before column x.
+    let stride = padded_width + 1;
+
+    let mut totals = vec![0u32; (padded_height + 1) * stride];
+    for y in 0..padded_height {
+        let row = topology.wrap(y as i64 - range as i64, height);
+        let line = (y + 1) * stride;
+        for x in 0..padded_width {
+            let column = topology.wrap(x as i64 - range as i64, width);
+            let alive = match (row, column) {
+                (Some(row), Some(column)) => cells[row as usize * width as usize + column as usize],
+                _ => false,
+            };
+            totals[line + x + 1] = totals[line + x] + alive as u32;
+        }
+    }
+
+    let mut counts = Vec::with_capacity(width as usize * height as usize);
+    match neighborhood {
+        Neighborhood::VonNeumann => {
+            for y in 0..height as usize {
+                for x in 0..width as usize {
+                    let mut count = 0;
+                    for dy in 0..=2 * range {
+                        let reach = range - dy.abs_diff(range);
+                        let line = (y + dy + 1) * stride;
+                        let center = x + range;
+                        count += totals[line + center + reach + 1] - totals[line + center - reach];
+                    }
+                    counts.push(count as u16);
+                }
+            }
+        }
+        _ => {
+            // Turn the row totals into a summed-area table: the total of
+            // every cell above and to the left.
+            for y in 1..=padded_height {
+                for x in 0..stride {
+                    totals[y * stride + x] += totals[(y - 1) * stride + x];
+                }
+            }
+            let side = 2 * range + 1;
+            for y in 0..height as usize {
+                for x in 0..width as usize {
+                    let (top, bottom) = (y * stride, (y + side) * stride);
+                    let count = totals[bottom + x + side] + totals[top + x]
+                        - totals[top + x + side]
+                        - totals[bottom + x];
+                    counts.push(count as u16);
+                }
+            }
+        }
+    }
+    counts
+}
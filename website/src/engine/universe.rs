@@ -0,0 +1,1686 @@
+//! A bounded board of cells, advanced one generation at a time.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+
+use fixedbitset::FixedBitSet;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use super::{
+    sums, Activity, Change, Checkpoint, Checkpoints, Delta, Rewind, SimulationEngine,
+    DEFAULT_REWIND_CHANGES,
+};
+use crate::brush::{Brush, BrushShape, SPRAY_DENSITY};
+use crate::parallel;
+use crate::patterns::{NumberedStates, ParseError, Pattern, MAX_CELLS};
+use crate::random::XorShift;
+use crate::rule::{Rule, RuleError, CONFIGURATION_OFFSETS};
+use crate::scripting::NextGeneration;
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cell {
+    Dead = 0,
+    Alive = 1,
+    /// Decaying under a Generations rule. Dying cells don't count as
+    /// neighbors and can't be born into.
+    Dying = 2,
+}
+
+impl Cell {
+    /// Collapse a numbered Generations state into a `Cell`.
+    pub fn from_state(state: u8) -> Cell {
+        match state {
+            0 => Cell::Dead,
+            1 => Cell::Alive,
+            _ => Cell::Dying,
+        }
+    }
+}
+
+/// Cells are written to JSON as their states.
+impl Serialize for Cell {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(*self as u8)
+    }
+}
+
+impl<'de> Deserialize<'de> for Cell {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Cell, D::Error> {
+        u8::deserialize(deserializer).map(Cell::from_state)
+    }
+}
+
+impl From<bool> for Cell {
+    fn from(alive: bool) -> Cell {
+        if alive {
+            Cell::Alive
+        } else {
+            Cell::Dead
+        }
+    }
+}
+
+/// Where a cell is on the board, counted from the top left.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Position {
+    pub row: u32,
+    pub column: u32,
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Position {
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen(constructor))]
+    pub fn new(row: u32, column: u32) -> Position {
+        Position { row, column }
+    }
+}
+
+/// A rectangle of the board: the cell at its top left and its size.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Bounds {
+    pub row: u32,
+    pub column: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// How `Universe::run_until_stable` ended.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Stabilization {
+    /// How many generations were run.
+    pub generations: u32,
+    /// The period of the cycle the board settled into, as returned by
+    /// `detect_period`, or 0 if it hadn't settled by the cap.
+    pub period: u32,
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Stabilization {
+    /// Whether the board settled into a still life or oscillator.
+    pub fn is_stable(&self) -> bool {
+        self.period > 0
+    }
+}
+
+/// How many past boards `Universe` remembers for `detect_period`.
+const CYCLE_HISTORY: usize = 1024;
+
+/// How many rows of the board a tick works on at a time, each band on its
+/// own thread when ticks are parallel.
+const BAND_ROWS: usize = 16;
+
+/// What lies beyond the edges of the universe. Written to JSON by `name`.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Topology {
+    /// Opposite edges are joined, so gliders wrap around.
+    Torus,
+    /// Everything outside the universe is permanently dead.
+    Dead,
+    /// The edges act as mirrors, reflecting the cells next to them.
+    Mirror,
+}
+
+impl Topology {
+    /// Parse the name used by the page's topology dropdown.
+    pub fn from_name(name: &str) -> Option<Topology> {
+        match name {
+            "torus" => Some(Topology::Torus),
+            "dead" => Some(Topology::Dead),
+            "mirror" => Some(Topology::Mirror),
+            _ => None,
+        }
+    }
+
+    /// The name used by the page's topology dropdown.
+    pub fn name(self) -> &'static str {
+        match self {
+            Topology::Torus => "torus",
+            Topology::Dead => "dead",
+            Topology::Mirror => "mirror",
+        }
+    }
+
+    /// Map a possibly out of range coordinate onto the universe.
+    pub(crate) fn wrap(self, coordinate: i64, size: u32) -> Option<u32> {
+        let size = size as i64;
+        if (0..size).contains(&coordinate) {
+            return Some(coordinate as u32);
+        }
+        match self {
+            Topology::Torus => Some(coordinate.rem_euclid(size) as u32),
+            Topology::Dead => None,
+            Topology::Mirror if coordinate < 0 => Some((-coordinate - 1).min(size - 1) as u32),
+            Topology::Mirror => Some((2 * size - coordinate - 1).max(0) as u32),
+        }
+    }
+}
+
+/// `size` cut down so that a board `size` by `other` cells has no more than
+/// `MAX_CELLS` of them.
+fn fit(size: u32, other: u32) -> u32 {
+    size.min((MAX_CELLS / other.max(1) as u64).min(u32::MAX as u64) as u32)
+}
+
+/// The number of cells on a `width` by `height` board that `fit` has cut
+/// down to size.
+fn cell_count(width: u32, height: u32) -> usize {
+    (width as usize)
+        .checked_mul(height as usize)
+        .expect("board bigger than MAX_CELLS")
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone)]
+pub struct Universe {
+    width: u32,
+    height: u32,
+    pub(crate) topology: Topology,
+    rule: Rule,
+    /// One bit per cell in row-major order, set when the cell is alive.
+    cells: FixedBitSet,
+    /// The state of every cell that isn't alive: 0 when dead, otherwise its
+    /// dying state. Empty unless the rule has more than two states.
+    dying: Vec<u8>,
+    /// How many generations each cell has been alive, or 0 if it isn't.
+    ages: Vec<u16>,
+    /// Recent changes to each cell, while someone is watching them.
+    activity: Option<Activity>,
+    /// The changes made by recent steps, while someone wants to wind back
+    /// through them. Cleared by edits, like `history`.
+    rewind: Option<Rewind>,
+    /// Whole boards to go back to. Edits drop the automatic ones, which
+    /// `seek` simulates forward from.
+    checkpoints: Checkpoints,
+    pub(crate) rng: XorShift,
+    /// Generations since the board was last cleared or resized.
+    generation: u64,
+    /// Number of live cells, kept up to date by every edit.
+    population: u32,
+    /// Cells that came to life during the last step.
+    births: u32,
+    /// Live cells that stopped being alive during the last step.
+    deaths: u32,
+    /// The index of the last cell to change state, in row-major order
+    /// within a step, since the board was last cleared or resized.
+    last_change: Option<usize>,
+    /// The generation and hash of recent boards, oldest first. Cleared by
+    /// edits, which break the chain from one generation to the next.
+    history: VecDeque<(u64, u64)>,
+    /// Whether ticks are split across threads.
+    parallel: bool,
+    /// The rows and columns of the smallest rectangle containing every cell
+    /// that isn't dead, kept up to date by every step and edit.
+    bounds: Option<(Range<u32>, Range<u32>)>,
+    /// Set when an edit kills a cell on the edge of `bounds`, which may
+    /// leave it bigger than it needs to be until the next step.
+    bounds_loose: bool,
+    /// The chance of each cell flipping between alive and not on every
+    /// tick, whatever the rule says, drawn from `rng`.
+    noise: f64,
+}
+
+impl Universe {
+    fn get_index(&self, row: u32, column: u32) -> usize {
+        row as usize * self.width as usize + column as usize
+    }
+
+    /// Where `position` is in the board's storage, or `None` off the board.
+    fn checked_index(&self, position: Position) -> Option<usize> {
+        if !self.contains(position) {
+            return None;
+        }
+        Some(self.get_index(position.row, position.column))
+    }
+
+    /// The cell at `row` and `column`, which must be on the board; `get`
+    /// checks.
+    fn cell_at(&self, row: u32, column: u32) -> Cell {
+        let idx = self.get_index(row, column);
+        if self.cells[idx] {
+            Cell::Alive
+        } else {
+            Cell::from_state(self.state_at(idx))
+        }
+    }
+
+    /// The numbered state of the cell at `idx`. Live cells of any color but
+    /// the first keep their color alongside the dying states.
+    fn state_at(&self, idx: usize) -> u8 {
+        if self.cells[idx] {
+            match self.dying.get(idx) {
+                Some(&color) if color >= 2 => color,
+                _ => 1,
+            }
+        } else {
+            self.dying.get(idx).copied().unwrap_or(0)
+        }
+    }
+
+    fn set_state_at(&mut self, idx: usize, state: u8) {
+        self.history.clear();
+        if let Some(rewind) = &mut self.rewind {
+            rewind.clear();
+        }
+        self.checkpoints.forget_automatic();
+        let before = self.state_at(idx);
+        if before != state {
+            self.last_change = Some(idx);
+            self.track_bounds(idx, before, state);
+        }
+        let alive = self.rule.is_alive(state);
+        match (self.cells[idx], alive) {
+            (false, true) => self.population += 1,
+            (true, false) => self.population -= 1,
+            _ => {}
+        }
+        // Ages are taken out while `skip_ahead` updates the cells, and
+        // worked out afterwards.
+        if let Some(age) = self.ages.get_mut(idx) {
+            if !alive {
+                *age = 0;
+            } else if !self.cells[idx] {
+                *age = 1;
+            }
+        }
+        self.cells.set(idx, alive);
+        if let Some(dying) = self.dying.get_mut(idx) {
+            *dying = if state >= 2 { state } else { 0 };
+        }
+    }
+
+    /// Make room for dying states if the rule needs them.
+    fn reset_dying(&mut self) {
+        self.dying = if self.rule.states() > 2 {
+            vec![0; self.cells.len()]
+        } else {
+            Vec::new()
+        };
+    }
+
+    /// Start counting generations and statistics again for a new board.
+    fn reset_stats(&mut self) {
+        self.ages = vec![0; self.cells.len()];
+        for idx in self.cells.ones() {
+            self.ages[idx] = 1;
+        }
+        if let Some(activity) = &mut self.activity {
+            activity.reset(self.cells.len());
+        }
+        self.generation = 0;
+        self.population = self.cells.count_ones(..) as u32;
+        self.births = 0;
+        self.deaths = 0;
+        self.last_change = None;
+        self.history.clear();
+        if let Some(rewind) = &mut self.rewind {
+            rewind.clear();
+        }
+        self.checkpoints.forget_automatic();
+        self.record_board();
+        self.bounds = self.scan_bounds();
+        self.bounds_loose = false;
+    }
+
+    /// Grow the bounding box to take in the cell at `idx` if it came alive
+    /// or started dying, or note that it may now be too big if the cell was
+    /// on its edge and died.
+    fn track_bounds(&mut self, idx: usize, before: u8, after: u8) {
+        let (row, col) = (idx as u32 / self.width, idx as u32 % self.width);
+        match (&mut self.bounds, before == 0, after == 0) {
+            (Some((rows, columns)), true, false) => {
+                *rows = rows.start.min(row)..rows.end.max(row + 1);
+                *columns = columns.start.min(col)..columns.end.max(col + 1);
+            }
+            (None, true, false) => self.bounds = Some((row..row + 1, col..col + 1)),
+            (Some((rows, columns)), false, true)
+                if row == rows.start
+                    || row + 1 == rows.end
+                    || col == columns.start
+                    || col + 1 == columns.end =>
+            {
+                self.bounds_loose = true;
+            }
+            _ => {}
+        }
+    }
+
+    /// Find the bounding box by looking at every cell that isn't dead.
+    fn scan_bounds(&self) -> Option<(Range<u32>, Range<u32>)> {
+        let mut rows = self.height..0;
+        let mut columns = self.width..0;
+        let dying = self
+            .dying
+            .iter()
+            .enumerate()
+            .filter(|&(_, &state)| state != 0)
+            .map(|(idx, _)| idx);
+        for idx in self.cells.ones().chain(dying) {
+            let (row, col) = (idx as u32 / self.width, idx as u32 % self.width);
+            rows = rows.start.min(row)..rows.end.max(row + 1);
+            columns = columns.start.min(col)..columns.end.max(col + 1);
+        }
+        if rows.is_empty() {
+            None
+        } else {
+            Some((rows, columns))
+        }
+    }
+
+    fn board_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.cells.as_slice().hash(&mut hasher);
+        self.dying.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Remember the current board for `detect_period`.
+    fn record_board(&mut self) {
+        if self.history.len() == CYCLE_HISTORY {
+            self.history.pop_front();
+        }
+        self.history.push_back((self.generation, self.board_hash()));
+    }
+
+    /// Replace the cells with the board `generations` generations later, as
+    /// computed by `update`, keeping the statistics up to date.
+    ///
+    /// Only the start and end of the jump are known, so cells alive at both
+    /// are assumed to have lived throughout it, and cells alive only at the
+    /// end to have just been born.
+    pub fn skip_ahead(&mut self, generations: u64, update: impl FnOnce(&mut Universe)) {
+        let before = self.cells.clone();
+        let mut ages = std::mem::take(&mut self.ages);
+        let history = std::mem::take(&mut self.history);
+        let mut rewind = self.rewind.take();
+        let checkpoints = std::mem::replace(&mut self.checkpoints, Checkpoints::new(0));
+        let before_states = rewind.as_ref().map(|_| self.cells());
+        let rng = self.rng.clone();
+        update(self);
+        self.history = history;
+        self.checkpoints = checkpoints;
+        if let (Some(rewind), Some(before_states)) = (&mut rewind, before_states) {
+            let changes = before_states
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, &state)| {
+                    let after = self.state_at(idx);
+                    (after != state).then_some((idx as u32, state, after))
+                })
+                .collect();
+            rewind.record(Delta {
+                generations,
+                changes,
+                rng: (rng, self.rng.clone()),
+            });
+        }
+        self.rewind = rewind;
+        let elapsed = generations.min(u16::MAX as u64) as u16;
+        for (idx, age) in ages.iter_mut().enumerate() {
+            *age = match (before[idx], self.cells[idx]) {
+                (true, true) => age.saturating_add(elapsed),
+                (false, true) => 1,
+                (_, false) => 0,
+            };
+        }
+        self.ages = ages;
+        if let Some(activity) = &mut self.activity {
+            activity.record(&before, &self.cells);
+        }
+        self.births = self.cells.difference(&before).count() as u32;
+        self.deaths = before.difference(&self.cells).count() as u32;
+        self.generation += generations;
+        self.bounds = self.scan_bounds();
+        self.bounds_loose = false;
+        self.record_board();
+        self.checkpoint_if_due(self.generation - generations);
+    }
+
+    /// Switch rules, keeping the live cells and dropping any dying ones.
+    pub(crate) fn apply_rule(&mut self, rule: Rule) {
+        self.rule = rule;
+        self.reset_dying();
+        // Any dying cells are gone, and the bounds may have been around them.
+        self.bounds_loose = true;
+        self.history.clear();
+        if let Some(rewind) = &mut self.rewind {
+            rewind.clear();
+        }
+        self.checkpoints.forget_automatic();
+    }
+
+    /// Count generations from `generation` on, as a board saved part way
+    /// through a run is loaded.
+    pub(crate) fn set_generation(&mut self, generation: u64) {
+        self.generation = generation;
+        self.history.clear();
+        self.checkpoints.forget_automatic();
+    }
+
+    /// Take an automatic checkpoint if the board just passed a multiple of
+    /// the interval on its way from generation `previous`, unless there
+    /// already is one for this generation.
+    fn checkpoint_if_due(&mut self, previous: u64) {
+        if !self.checkpoints.due(previous, self.generation) {
+            return;
+        }
+        let taken = self
+            .checkpoints
+            .nearest(self.generation)
+            .and_then(|id| self.checkpoints.get(id))
+            .is_some_and(|checkpoint| checkpoint.generation == self.generation);
+        if !taken {
+            self.add_checkpoint(true);
+        }
+    }
+
+    fn add_checkpoint(&mut self, automatic: bool) -> u32 {
+        let checkpoint = Checkpoint::new(
+            self.width,
+            self.height,
+            self.rule,
+            self.generation,
+            automatic,
+            self.rng.clone(),
+            &self.cells(),
+        );
+        self.checkpoints.add(checkpoint)
+    }
+
+    /// Take on the size, rule, cells, generation and random number
+    /// generator of `checkpoint`.
+    fn load_checkpoint(&mut self, checkpoint: &Checkpoint) {
+        self.width = checkpoint.width;
+        self.height = checkpoint.height;
+        self.rule = checkpoint.rule;
+        self.rng = checkpoint.rng.clone();
+        self.load_states(&checkpoint.states());
+        self.generation = checkpoint.generation;
+    }
+
+    /// Replace every cell with the numbered `states`, in row-major order,
+    /// of a board the size of this one. States the rule doesn't have are
+    /// taken to be its last.
+    fn load_states(&mut self, states: &[u8]) {
+        let last = self.rule.states() - 1;
+        self.cells = FixedBitSet::with_capacity(states.len());
+        self.reset_dying();
+        for (idx, &state) in states.iter().enumerate() {
+            let state = state.min(last);
+            self.cells.set(idx, self.rule.is_alive(state));
+            if let Some(dying) = self.dying.get_mut(idx) {
+                *dying = if state >= 2 { state } else { 0 };
+            }
+        }
+        self.reset_stats();
+    }
+
+    /// Undo the changes of `delta`, or redo them if `forward`, keeping the
+    /// statistics up to date. Cells that come back to life are taken to be
+    /// newborn.
+    fn apply_delta(&mut self, delta: &Delta, forward: bool) {
+        self.rng = if forward { &delta.rng.1 } else { &delta.rng.0 }.clone();
+        let elapsed = delta.generations.min(u16::MAX as u64) as u16;
+        for age in self.ages.iter_mut().filter(|age| **age > 0) {
+            *age = if forward {
+                age.saturating_add(elapsed)
+            } else {
+                age.saturating_sub(elapsed).max(1)
+            };
+        }
+        for &(idx, before, after) in &delta.changes {
+            let idx = idx as usize;
+            let state = if forward { after } else { before };
+            self.track_bounds(idx, self.state_at(idx), state);
+            let alive = self.rule.is_alive(state);
+            match (self.cells[idx], alive) {
+                (false, true) => self.population += 1,
+                (true, false) => self.population -= 1,
+                _ => {}
+            }
+            self.ages[idx] = alive as u16;
+            self.cells.set(idx, alive);
+            if let Some(dying) = self.dying.get_mut(idx) {
+                *dying = if state >= 2 { state } else { 0 };
+            }
+            self.last_change = Some(idx);
+        }
+        if forward {
+            self.generation += delta.generations;
+        } else {
+            self.generation -= delta.generations;
+        }
+    }
+
+    /// The state of every cell in the next generation, given the neighbor
+    /// counts of rules that reach beyond the nearest cells. The board is
+    /// worked through in bands of rows, in parallel if ticks are parallel.
+    fn next_states(&self, counts: Option<&[u16]>) -> Vec<u8> {
+        let width = self.width as usize;
+        let mut next = vec![0; width * self.height as usize];
+        if next.is_empty() {
+            return next;
+        }
+        let band = |(band, states): (usize, &mut [u8])| {
+            let first = band * BAND_ROWS * width;
+            for (i, state) in states.iter_mut().enumerate() {
+                let idx = first + i;
+                let (row, column) = ((idx / width) as u32, (idx % width) as u32);
+                let live_neighbors = match counts {
+                    Some(counts) => counts[idx],
+                    None if self.rule.is_isotropic() => self.neighbor_configuration(row, column),
+                    None => self.live_neighbor_count(row, column),
+                };
+                let before = self.state_at(idx);
+                *state = self.rule.next_state(before, live_neighbors);
+                if before == 0 && *state == 1 && self.rule.colors() > 1 {
+                    *state = self.rule.birth_color(self.neighbor_states(row, column));
+                }
+            }
+        };
+
+        #[cfg(feature = "parallel")]
+        if self.parallel {
+            use rayon::prelude::*;
+            next.par_chunks_mut(BAND_ROWS * width)
+                .enumerate()
+                .for_each(band);
+            return next;
+        }
+        next.chunks_mut(BAND_ROWS * width)
+            .enumerate()
+            .for_each(band);
+        next
+    }
+
+    /// Flip each of `states` between alive and dead with probability
+    /// `noise`, jumping straight from one flipped cell to the next so that a
+    /// little noise costs little.
+    fn add_noise(&mut self, states: &mut [u8]) {
+        if self.noise <= 0.0 {
+            return;
+        }
+        let mut idx = 0usize;
+        loop {
+            // The number of cells before the next flip is geometrically
+            // distributed.
+            let gap = (1.0 - self.rng.next_f64()).ln() / (1.0 - self.noise).ln();
+            idx = idx.saturating_add(gap as usize);
+            if idx >= states.len() {
+                break;
+            }
+            states[idx] = if self.rule.is_alive(states[idx]) {
+                0
+            } else {
+                1
+            };
+            idx += 1;
+        }
+    }
+
+    /// The states of the cells around (`row`, `column`), leaving out any
+    /// beyond dead edges.
+    fn neighbor_states(&self, row: u32, column: u32) -> impl Iterator<Item = u8> + '_ {
+        self.rule
+            .neighborhood()
+            .offsets()
+            .filter_map(move |(delta_row, delta_col)| {
+                let neighbor_row = self.topology.wrap(row as i64 + delta_row, self.height)?;
+                let neighbor_col = self.topology.wrap(column as i64 + delta_col, self.width)?;
+                Some(self.state_at(self.get_index(neighbor_row, neighbor_col)))
+            })
+    }
+
+    /// Which of the Moore neighbors of (`row`, `column`) are alive, as the
+    /// bits of a neighbor configuration for isotropic rules.
+    fn neighbor_configuration(&self, row: u32, column: u32) -> u16 {
+        let mut configuration = 0;
+        for (bit, &(delta_row, delta_col)) in CONFIGURATION_OFFSETS.iter().enumerate() {
+            let neighbor_row = self.topology.wrap(row as i64 + delta_row, self.height);
+            let neighbor_col = self.topology.wrap(column as i64 + delta_col, self.width);
+            if let (Some(neighbor_row), Some(neighbor_col)) = (neighbor_row, neighbor_col) {
+                let idx = self.get_index(neighbor_row, neighbor_col);
+                configuration |= (self.cells[idx] as u16) << bit;
+            }
+        }
+        configuration
+    }
+
+    /// How many live neighbors every cell has, in row-major order, counted
+    /// as `tick` counts them: as far as the rule's range reaches, and the
+    /// cell itself too if the rule says so.
+    pub(crate) fn live_neighbor_counts(&self) -> Vec<u16> {
+        self.range_counts().unwrap_or_else(|| {
+            let width = self.width as usize;
+            (0..self.cells.len())
+                .map(|idx| self.live_neighbor_count((idx / width) as u32, (idx % width) as u32))
+                .collect()
+        })
+    }
+
+    /// The neighbor counts of every cell under rules that reach beyond the
+    /// nearest cells, which are counted all at once, or `None` under rules
+    /// that don't.
+    fn range_counts(&self) -> Option<Vec<u16>> {
+        if self.rule.range() <= 1 {
+            return None;
+        }
+        let mut counts = sums::neighbor_counts(
+            &self.cells,
+            self.width,
+            self.height,
+            self.topology,
+            self.rule.neighborhood(),
+            self.rule.range(),
+        );
+        if !self.rule.counts_middle() {
+            for idx in self.cells.ones() {
+                counts[idx] -= 1;
+            }
+        }
+        Some(counts)
+    }
+
+    fn live_neighbor_count(&self, row: u32, column: u32) -> u16 {
+        let mut count =
+            self.rule.counts_middle() as u16 * self.cells[self.get_index(row, column)] as u16;
+        for (delta_row, delta_col) in self.rule.neighborhood().offsets() {
+            let neighbor_row = self.topology.wrap(row as i64 + delta_row, self.height);
+            let neighbor_col = self.topology.wrap(column as i64 + delta_col, self.width);
+            let (neighbor_row, neighbor_col) = match (neighbor_row, neighbor_col) {
+                (Some(neighbor_row), Some(neighbor_col)) => (neighbor_row, neighbor_col),
+                _ => continue,
+            };
+            let idx = self.get_index(neighbor_row, neighbor_col);
+            count += self.cells[idx] as u16;
+        }
+        count
+    }
+}
+
+/// Everything `Universe::restore_snapshot` needs to put a board back.
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) rule: Rule,
+    cells: FixedBitSet,
+    dying: Vec<u8>,
+    generation: u64,
+}
+
+impl Universe {
+    /// A copy of the board to `restore_snapshot` later, for undo.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            width: self.width,
+            height: self.height,
+            rule: self.rule,
+            cells: self.cells.clone(),
+            dying: self.dying.clone(),
+            generation: self.generation,
+        }
+    }
+
+    /// Put back a board saved by `snapshot`, keeping the topology.
+    pub fn restore_snapshot(&mut self, snapshot: Snapshot) {
+        self.width = snapshot.width;
+        self.height = snapshot.height;
+        self.rule = snapshot.rule;
+        self.cells = snapshot.cells;
+        self.dying = snapshot.dying;
+        self.reset_stats();
+        self.generation = snapshot.generation;
+    }
+
+    /// The (`row`, `column`) of the cell that changed most recently, by an
+    /// edit or a step. Of the cells a step changes, the last in row-major
+    /// order counts.
+    pub fn last_change(&self) -> Option<(u32, u32)> {
+        self.last_change
+            .map(|idx| (idx as u32 / self.width, idx as u32 % self.width))
+    }
+
+    /// The rows and columns of the smallest rectangle containing every cell
+    /// that isn't dead, or `None` if they all are.
+    pub fn bounding_box(&self) -> Option<(Range<u32>, Range<u32>)> {
+        if self.bounds_loose {
+            self.scan_bounds()
+        } else {
+            self.bounds.clone()
+        }
+    }
+
+    /// The mean (`row`, `column`) of the live cells, or `None` if there
+    /// aren't any.
+    pub fn centroid(&self) -> Option<(f64, f64)> {
+        if self.population == 0 {
+            return None;
+        }
+        let width = self.width as usize;
+        let (rows, columns) = self.cells.ones().fold((0.0, 0.0), |(rows, columns), idx| {
+            (rows + (idx / width) as f64, columns + (idx % width) as f64)
+        });
+        let population = self.population as f64;
+        Some((rows / population, columns / population))
+    }
+
+    /// The (`row`, `column`) of every cell alive here but not in `other`,
+    /// or the other way round, in row-major order. Boards of different sizes
+    /// are compared where they overlap.
+    pub fn diff(&self, other: &Universe) -> Vec<(u32, u32)> {
+        let rows = 0..self.height.min(other.height);
+        let columns = 0..self.width.min(other.width);
+        rows.flat_map(|row| columns.clone().map(move |col| (row, col)))
+            .filter(|&(row, col)| {
+                (self.cell_at(row, col) == Cell::Alive) != (other.cell_at(row, col) == Cell::Alive)
+            })
+            .collect()
+    }
+
+    /// Like `paint`, but returns the (`row`, `column`) of every cell that
+    /// changed.
+    pub fn paint_cells(
+        &mut self,
+        row: u32,
+        column: u32,
+        brush: &Brush,
+        cell: Cell,
+    ) -> Vec<(u32, u32)> {
+        let mut changed = Vec::new();
+        for (dy, dx) in brush.offsets() {
+            if brush.shape() == BrushShape::Spray && self.rng.next_f64() >= SPRAY_DENSITY {
+                continue;
+            }
+            let (y, x) = (row as i64 + dy as i64, column as i64 + dx as i64);
+            let position = match self.topology {
+                Topology::Torus => self
+                    .topology
+                    .wrap(y, self.height)
+                    .zip(self.topology.wrap(x, self.width)),
+                _ if (0..self.height as i64).contains(&y)
+                    && (0..self.width as i64).contains(&x) =>
+                {
+                    Some((y as u32, x as u32))
+                }
+                _ => None,
+            };
+            if let Some((y, x)) = position {
+                if self.cell_at(y, x) != cell {
+                    let idx = self.get_index(y, x);
+                    self.set_state_at(idx, cell as u8);
+                    changed.push((y, x));
+                }
+            }
+        }
+        changed
+    }
+
+    /// Tick, then let `hook` veto or override what the rule did to any cell
+    /// before the generation is final. See `scripting`.
+    pub fn tick_with(&mut self, hook: impl FnOnce(&mut NextGeneration)) {
+        let (before, live_neighbors) = (self.cells(), self.live_neighbor_counts());
+        self.tick();
+        let mut next = NextGeneration::new(before, live_neighbors, self);
+        hook(&mut next);
+        self.override_cells(&next.take_overrides());
+    }
+
+    /// Give cells of the generation just ticked the states a hook chose,
+    /// from `NextGeneration::take_overrides`, as part of that generation:
+    /// the step kept for rewinding and the board kept for `detect_period`
+    /// take them in. The rule alone can't make the generation again, so it
+    /// is checkpointed for `seek` to start from rather than simulate across.
+    pub(crate) fn override_cells(&mut self, overrides: &[(usize, u8, u8)]) {
+        if overrides.is_empty() {
+            return;
+        }
+        // Set aside what edits forget, as `skip_ahead` does.
+        let mut history = std::mem::take(&mut self.history);
+        let mut rewind = self.rewind.take();
+        let mut checkpoints = std::mem::replace(&mut self.checkpoints, Checkpoints::new(0));
+        let (mut births, mut deaths) = (self.births as i64, self.deaths as i64);
+        for &(idx, before, state) in overrides {
+            let was_alive = self.rule.is_alive(before);
+            let (proposed, alive) = (self.cells[idx], self.rule.is_alive(state));
+            births += (!was_alive && alive) as i64 - (!was_alive && proposed) as i64;
+            deaths += (was_alive && !alive) as i64 - (was_alive && !proposed) as i64;
+            self.set_state_at(idx, state);
+        }
+        self.births = births as u32;
+        self.deaths = deaths as u32;
+        if history
+            .back()
+            .is_some_and(|&(generation, _)| generation == self.generation)
+        {
+            history.pop_back();
+        }
+        self.history = history;
+        self.record_board();
+        if let Some(rewind) = &mut rewind {
+            let changes: Vec<Change> = overrides
+                .iter()
+                .map(|&(idx, before, state)| (idx as u32, before, state))
+                .collect();
+            rewind.amend_latest(&changes);
+        }
+        self.rewind = rewind;
+        checkpoints.forget_automatic_at(self.generation);
+        self.checkpoints = checkpoints;
+        if self.checkpoints.interval() > 0 {
+            self.add_checkpoint(true);
+        }
+    }
+
+    /// Every cell of the board as a pattern.
+    fn board(&self) -> Pattern {
+        let cells = (0..self.height)
+            .flat_map(|row| (0..self.width).map(move |col| (row, col)))
+            .map(|(row, col)| self.cell_at(row, col))
+            .collect();
+        Pattern::from_cells(self.width, self.height, cells)
+    }
+}
+
+impl Default for Universe {
+    fn default() -> Universe {
+        Universe::new()
+    }
+}
+
+/// Draws the board as text, one line per row.
+impl fmt::Display for Universe {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in 0..self.height {
+            for column in 0..self.width {
+                let symbol = match self.cell_at(row, column) {
+                    Cell::Alive => '◼',
+                    Cell::Dying => '▫',
+                    Cell::Dead => '◻',
+                };
+                write!(f, "{}", symbol)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Universe {
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns a copy of the cells as a `Uint8Array`, one byte per cell in
+    /// row-major order holding the cell's numbered state.
+    pub fn cells(&self) -> Vec<u8> {
+        (0..self.cells.len())
+            .map(|idx| self.state_at(idx))
+            .collect()
+    }
+
+    /// Set every cell from states in the format returned by `cells`.
+    pub fn set_cells(&mut self, cells: &[u8]) {
+        let max_state = self.rule.states() - 1;
+        for (idx, &state) in cells.iter().enumerate().take(self.cells.len()) {
+            self.set_state_at(idx, state.min(max_state));
+        }
+    }
+
+    /// How many generations the cell at `position` has been alive, counting
+    /// the one it was born in, or 0 if it is not alive. Saturates at
+    /// `u16::MAX`, and is `None` off the board.
+    pub fn age(&self, position: Position) -> Option<u16> {
+        let idx = self.checked_index(position)?;
+        Some(self.ages[idx])
+    }
+
+    /// Start counting how many of the last `window` steps changed each
+    /// cell, or restart if already counting.
+    pub fn track_activity(&mut self, window: u32) {
+        self.activity = Some(Activity::new(window, self.cells.len()));
+    }
+
+    pub fn stop_tracking_activity(&mut self) {
+        self.activity = None;
+    }
+
+    /// Start remembering what each of the next `steps` steps changes, so
+    /// that `rewind_to` can go back through them, or start again if already
+    /// remembering. Older steps are forgotten sooner on busy boards, so as
+    /// to keep no more than `DEFAULT_REWIND_CHANGES` changes.
+    pub fn track_rewind(&mut self, steps: u32) {
+        self.rewind = Some(Rewind::new(steps, DEFAULT_REWIND_CHANGES));
+    }
+
+    pub fn stop_tracking_rewind(&mut self) {
+        self.rewind = None;
+    }
+
+    /// Keep a compressed copy of the board to `restore` later, returning its
+    /// id. Unlike automatic checkpoints, these are kept until the universe
+    /// is dropped.
+    pub fn checkpoint(&mut self) -> u32 {
+        self.add_checkpoint(false)
+    }
+
+    /// Put back the board kept as checkpoint `id`, keeping the topology.
+    /// Returns whether there was such a checkpoint.
+    pub fn restore(&mut self, id: u32) -> bool {
+        let checkpoint = match self.checkpoints.get(id) {
+            Some(checkpoint) => checkpoint.clone(),
+            None => return false,
+        };
+        let checkpoints = std::mem::replace(&mut self.checkpoints, Checkpoints::new(0));
+        self.load_checkpoint(&checkpoint);
+        self.checkpoints = checkpoints;
+        // A checkpoint taken by hand may be from before an edit, and the
+        // automatic ones may not follow on from it.
+        if !checkpoint.automatic {
+            self.checkpoints.forget_automatic();
+        }
+        true
+    }
+
+    /// A universe holding the board kept as checkpoint `id`, to compare with
+    /// this one.
+    pub fn checkpoint_board(&self, id: u32) -> Option<Universe> {
+        let checkpoint = self.checkpoints.get(id)?;
+        let mut board = Universe::new_with_size(checkpoint.width, checkpoint.height);
+        board.topology = self.topology;
+        board.load_checkpoint(checkpoint);
+        Some(board)
+    }
+
+    /// Take an automatic checkpoint every `generations` generations, or none
+    /// if 0.
+    pub fn set_checkpoint_interval(&mut self, generations: u32) {
+        self.checkpoints.set_interval(generations);
+    }
+
+    pub fn checkpoint_interval(&self) -> u32 {
+        self.checkpoints.interval()
+    }
+
+    /// The ids of every checkpoint kept, oldest first.
+    pub fn checkpoint_ids(&self) -> Vec<u32> {
+        self.checkpoints.ids().collect()
+    }
+
+    /// The generation checkpoint `id` was taken at, if it is kept.
+    pub fn checkpoint_generation(&self, id: u32) -> Option<u64> {
+        self.checkpoints
+            .get(id)
+            .map(|checkpoint| checkpoint.generation)
+    }
+
+    /// Go back to `generation` by restoring the latest automatic checkpoint
+    /// at or before it and simulating forward from there. Returns whether
+    /// that was possible: the generation can't be later than the current
+    /// one, nor earlier than every automatic checkpoint.
+    pub fn seek(&mut self, generation: u64) -> bool {
+        if generation > self.generation {
+            return false;
+        }
+        if generation == self.generation {
+            return true;
+        }
+        match self.checkpoints.nearest(generation) {
+            Some(id) => self.restore(id),
+            None => return false,
+        };
+        while self.generation < generation {
+            self.tick();
+        }
+        true
+    }
+
+    /// How many steps can be wound back through.
+    pub fn rewind_steps(&self) -> u32 {
+        self.rewind.as_ref().map_or(0, Rewind::len)
+    }
+
+    /// How many steps the board has been wound back from the newest.
+    pub fn rewound(&self) -> u32 {
+        self.rewind.as_ref().map_or(0, Rewind::undone)
+    }
+
+    /// Put the board back how it was `steps` steps before the newest one, as
+    /// far back as is remembered, by undoing or redoing the recorded changes
+    /// rather than simulating. Stepping from a wound back board forgets the
+    /// steps after it.
+    pub fn rewind_to(&mut self, steps: u32) {
+        let mut rewind = match self.rewind.take() {
+            Some(rewind) => rewind,
+            None => return,
+        };
+        let steps = steps.min(rewind.len());
+        while rewind.undone() < steps {
+            let delta = rewind.back().unwrap();
+            self.apply_delta(delta, false);
+        }
+        while rewind.undone() > steps {
+            let delta = rewind.forward().unwrap();
+            self.apply_delta(delta, true);
+        }
+        let changes = rewind.latest().map_or(&[][..], |delta| &delta.changes[..]);
+        let rule = self.rule;
+        self.births = changes
+            .iter()
+            .filter(|&&(_, before, after)| !rule.is_alive(before) && rule.is_alive(after))
+            .count() as u32;
+        self.deaths = changes
+            .iter()
+            .filter(|&&(_, before, after)| rule.is_alive(before) && !rule.is_alive(after))
+            .count() as u32;
+        self.rewind = Some(rewind);
+        self.history.clear();
+        self.record_board();
+    }
+
+    /// How many of the steps `track_activity` has seen in its window
+    /// changed whether the cell at `position` is alive, or 0 when not
+    /// tracking. `None` off the board.
+    pub fn activity(&self, position: Position) -> Option<u16> {
+        let idx = self.checked_index(position)?;
+        Some(
+            self.activity
+                .as_ref()
+                .map_or(0, |activity| activity.count(idx)),
+        )
+    }
+
+    /// The numbered state of the cell at `position`: 0 when dead, 1 when
+    /// alive and 2 or more while dying under a Generations rule. `None` off
+    /// the board.
+    pub fn state(&self, position: Position) -> Option<u8> {
+        let idx = self.checked_index(position)?;
+        Some(self.state_at(idx))
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+        self.checkpoints.forget_automatic();
+    }
+
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    /// Switch to the rule described by a rulestring such as `B36/S23`.
+    pub fn set_rule(&mut self, rule: &str) -> Result<(), RuleError> {
+        self.apply_rule(Rule::parse(rule)?);
+        Ok(())
+    }
+
+    /// Make the cell at `position` `cell`. Returns what it was, or `None`
+    /// off the board, which is left alone.
+    pub fn set_cell(&mut self, position: Position, cell: Cell) -> Option<Cell> {
+        let was = self.get(position)?;
+        let idx = self.get_index(position.row, position.column);
+        self.set_state_at(idx, cell as u8);
+        Some(was)
+    }
+
+    /// Where every live cell is, in row-major order.
+    pub fn live_cells(&self) -> Vec<Position> {
+        let width = self.width as usize;
+        self.cells
+            .ones()
+            .map(|idx| Position::new((idx / width) as u32, (idx % width) as u32))
+            .collect()
+    }
+
+    /// The smallest rectangle holding every cell that isn't dead, or none if
+    /// they all are.
+    pub fn bounds(&self) -> Option<Bounds> {
+        let (rows, columns) = self.bounding_box()?;
+        Some(Bounds {
+            row: rows.start,
+            column: columns.start,
+            width: columns.end - columns.start,
+            height: rows.end - rows.start,
+        })
+    }
+
+    pub fn contains(&self, position: Position) -> bool {
+        position.row < self.height && position.column < self.width
+    }
+
+    /// The cell at `position`, or `None` off the board.
+    pub fn get(&self, position: Position) -> Option<Cell> {
+        if !self.contains(position) {
+            return None;
+        }
+        Some(self.cell_at(position.row, position.column))
+    }
+
+    /// Bring the cell at `position` to life, or kill it if it is alive.
+    /// Returns what it became, or `None` off the board, which is left alone.
+    pub fn toggle_cell(&mut self, position: Position) -> Option<Cell> {
+        let idx = self.checked_index(position)?;
+        let state = if self.cells[idx] { 0 } else { 1 };
+        self.set_state_at(idx, state);
+        Some(Cell::from_state(state))
+    }
+
+    pub fn tick(&mut self) {
+        let counts = self.range_counts();
+        let mut next_states = self.next_states(counts.as_deref());
+        let rng = self.rng.clone();
+        self.add_noise(&mut next_states);
+        let mut next = self.cells.clone();
+        let mut next_dying = self.dying.clone();
+        let mut births = 0;
+        let mut deaths = 0;
+        let mut changes = self.rewind.as_ref().map(|_| Vec::new());
+        let width = self.width as usize;
+        let (mut rows, mut columns) = (self.height..0, self.width..0);
+
+        for (idx, &next_state) in next_states.iter().enumerate() {
+            if next_state != 0 {
+                let (row, col) = ((idx / width) as u32, (idx % width) as u32);
+                rows = rows.start.min(row)..rows.end.max(row + 1);
+                columns = columns.start.min(col)..columns.end.max(col + 1);
+            }
+            let state = self.state_at(idx);
+            if state != next_state {
+                self.last_change = Some(idx);
+                if let Some(changes) = &mut changes {
+                    changes.push((idx as u32, state, next_state));
+                }
+            }
+            let (alive, next_alive) = (self.rule.is_alive(state), self.rule.is_alive(next_state));
+            match (alive, next_alive) {
+                (false, true) => births += 1,
+                (true, false) => deaths += 1,
+                _ => {}
+            }
+            next.set(idx, next_alive);
+            self.ages[idx] = match (alive, next_alive) {
+                (true, true) => self.ages[idx].saturating_add(1),
+                (false, true) => 1,
+                (_, false) => 0,
+            };
+            if let Some(dying) = next_dying.get_mut(idx) {
+                *dying = if next_state >= 2 { next_state } else { 0 };
+            }
+        }
+
+        if let Some(activity) = &mut self.activity {
+            activity.record(&self.cells, &next);
+        }
+        self.cells = next;
+        self.dying = next_dying;
+        self.bounds = (!rows.is_empty()).then_some((rows, columns));
+        self.bounds_loose = false;
+        self.population = self.population + births - deaths;
+        self.births = births;
+        self.deaths = deaths;
+        self.generation += 1;
+        if let (Some(rewind), Some(changes)) = (&mut self.rewind, changes) {
+            rewind.record(Delta {
+                generations: 1,
+                changes,
+                rng: (rng, self.rng.clone()),
+            });
+        }
+        self.record_board();
+        self.checkpoint_if_due(self.generation - 1);
+    }
+
+    /// Split ticks across threads, if they are available. Returns whether
+    /// ticks are now parallel.
+    pub fn set_parallel(&mut self, parallel: bool) -> bool {
+        self.parallel = parallel && parallel::threads_available();
+        self.parallel
+    }
+
+    pub fn parallel(&self) -> bool {
+        self.parallel
+    }
+
+    /// Check whether the board repeats an earlier one from at most
+    /// `max_lookback` generations ago.
+    ///
+    /// Returns the period of the cycle: 1 for a still life, N for a period N
+    /// oscillator, or 0 if no repeat was found. Only boards since the last
+    /// edit count.
+    pub fn detect_period(&self, max_lookback: u32) -> u32 {
+        let hash = self.board_hash();
+        for &(generation, earlier) in self.history.iter().rev() {
+            let period = self.generation - generation;
+            if period > max_lookback as u64 {
+                break;
+            }
+            if period > 0 && earlier == hash {
+                return period as u32;
+            }
+        }
+        0
+    }
+
+    /// Tick until the board repeats one of the last boards `detect_period`
+    /// remembers, or `max_generations` have been run.
+    ///
+    /// A board that is already repeating isn't ticked at all, so this can be
+    /// called again and again to run a long settle in parts.
+    pub fn run_until_stable(&mut self, max_generations: u32) -> Stabilization {
+        let mut generations = 0;
+        loop {
+            let period = self.settled_period();
+            if period > 0 || generations == max_generations {
+                return Stabilization {
+                    generations,
+                    period,
+                };
+            }
+            self.tick();
+            generations += 1;
+        }
+    }
+
+    /// The period `detect_period` finds looking back as far as the board
+    /// remembers, counting the board as it is now even straight after an
+    /// edit, so that a repeat of it is seen as soon as it comes round.
+    pub(crate) fn settled_period(&mut self) -> u32 {
+        if self.history.is_empty() {
+            self.record_board();
+        }
+        self.detect_period(CYCLE_HISTORY as u32)
+    }
+
+    /// Generations since the board was last cleared, randomized or resized.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// The number of live cells.
+    pub fn population(&self) -> u32 {
+        self.population
+    }
+
+    /// The number of cells that came to life during the last step.
+    pub fn births(&self) -> u32 {
+        self.births
+    }
+
+    /// The number of live cells that stopped being alive during the last
+    /// step.
+    pub fn deaths(&self) -> u32 {
+        self.deaths
+    }
+
+    /// Advance the universe by `generations` ticks.
+    pub fn step(&mut self, generations: u32) {
+        for _ in 0..generations {
+            self.tick();
+        }
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen(constructor))]
+    pub fn new() -> Universe {
+        let mut universe = Universe::new_with_size(64, 64);
+        universe.fill_stripes();
+        universe
+    }
+
+    /// Creates a universe of the given size with every cell dead. Sizes of
+    /// more than `MAX_CELLS` cells are cut down to fit, the height first.
+    pub fn new_with_size(width: u32, height: u32) -> Universe {
+        let width = fit(width, 1);
+        let height = fit(height, width);
+        Universe {
+            width,
+            height,
+            topology: Topology::Torus,
+            rule: Rule::conway(),
+            cells: FixedBitSet::with_capacity(cell_count(width, height)),
+            dying: Vec::new(),
+            ages: vec![0; cell_count(width, height)],
+            activity: None,
+            rewind: None,
+            checkpoints: Checkpoints::new(0),
+            rng: XorShift::new(0),
+            generation: 0,
+            population: 0,
+            births: 0,
+            last_change: None,
+            deaths: 0,
+            history: VecDeque::new(),
+            parallel: false,
+            bounds: None,
+            bounds_loose: false,
+            noise: 0.0,
+        }
+    }
+
+    /// Kill every cell.
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.reset_dying();
+        self.reset_stats();
+    }
+
+    /// Fill the universe with the striped pattern it starts with.
+    pub fn fill_stripes(&mut self) {
+        self.clear();
+        for i in 0..self.cells.len() {
+            self.cells.set(i, i % 2 == 0 || i % 7 == 0);
+        }
+        self.reset_stats();
+    }
+
+    /// Bring each cell to life with probability `density`, and kill the rest.
+    /// Under multi-color rules each live cell gets a random color.
+    pub fn randomize(&mut self, density: f64) {
+        self.clear();
+        let colors = self.rule.colors();
+        for i in 0..self.cells.len() {
+            let alive = self.rng.next_f64() < density;
+            self.cells.set(i, alive);
+            if alive && colors > 1 {
+                let color = 1 + (self.rng.next_f64() * colors as f64) as u8;
+                self.dying[i] = if color >= 2 { color } else { 0 };
+            }
+        }
+        self.reset_stats();
+    }
+
+    /// Randomize the universe so that the same `seed` and `density` always
+    /// produce the same board.
+    pub fn randomize_with_seed(&mut self, seed: u64, density: f64) {
+        self.seed_rng(seed);
+        self.randomize(density);
+    }
+
+    /// Restart the random number generator used by `randomize` and the
+    /// noise. The automatic checkpoints are forgotten, since stepping on
+    /// from them would no longer draw the same noise.
+    pub fn seed_rng(&mut self, seed: u64) {
+        self.rng = XorShift::new(seed);
+        self.checkpoints.forget_automatic();
+    }
+
+    /// Flip each cell between alive and dead with probability `noise` on
+    /// every tick, on top of the rule, or stop if 0. Runs from the same seed
+    /// are the same, noise and all. Clamped to between 0 and 1. The
+    /// automatic checkpoints are forgotten, since `seek` steps on from them
+    /// with the noise as it is now.
+    pub fn set_noise(&mut self, noise: f64) {
+        self.noise = if noise.is_nan() {
+            0.0
+        } else {
+            noise.clamp(0.0, 1.0)
+        };
+        self.history.clear();
+        self.checkpoints.forget_automatic();
+    }
+
+    pub fn noise(&self) -> f64 {
+        self.noise
+    }
+
+    /// Copy `pattern` into the universe with its top-left corner at
+    /// `position`, wrapping around the edges. Returns whether the corner
+    /// was on the board; nothing is copied if it wasn't.
+    pub fn insert_pattern(&mut self, position: Position, pattern: &Pattern) -> bool {
+        if !self.contains(position) {
+            return false;
+        }
+        let (height, width) = (self.height as u64, self.width as u64);
+        for pattern_row in 0..pattern.height() {
+            for pattern_col in 0..pattern.width() {
+                let idx = self.get_index(
+                    ((position.row as u64 + pattern_row as u64) % height) as u32,
+                    ((position.column as u64 + pattern_col as u64) % width) as u32,
+                );
+                let cell = pattern.cell_at(pattern_row, pattern_col);
+                self.set_state_at(idx, cell as u8);
+            }
+        }
+        true
+    }
+
+    /// Copy `pattern`, turned clockwise by `rotation` right angles, into the
+    /// universe with its top-left corner at `position`. Returns whether the
+    /// corner was on the board.
+    pub fn stamp(&mut self, pattern: &Pattern, position: Position, rotation: u8) -> bool {
+        self.insert_pattern(position, &pattern.rotate(rotation))
+    }
+
+    /// Paint `cell` under `brush` centred on (`row`, `column`). The brush
+    /// wraps around the edges of a torus and is cut off by the others.
+    /// Returns whether any cell changed.
+    pub fn paint(&mut self, row: u32, column: u32, brush: &Brush, cell: Cell) -> bool {
+        !self.paint_cells(row, column, brush, cell).is_empty()
+    }
+
+    /// Shift every cell down by `rows` and right by `columns`, wrapping
+    /// around the edges.
+    pub fn translate(&mut self, rows: i32, columns: i32) {
+        let mut cells = FixedBitSet::with_capacity(self.cells.len());
+        let mut dying = vec![0; self.dying.len()];
+        let mut ages = vec![0; self.ages.len()];
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let idx = self.get_index(row, col);
+                let to_row = (row as i64 + rows as i64).rem_euclid(self.height as i64);
+                let to_col = (col as i64 + columns as i64).rem_euclid(self.width as i64);
+                let to_idx = self.get_index(to_row as u32, to_col as u32);
+                cells.set(to_idx, self.cells[idx]);
+                ages[to_idx] = self.ages[idx];
+                if let Some(&state) = self.dying.get(idx) {
+                    dying[to_idx] = state;
+                }
+            }
+        }
+        self.cells = cells;
+        self.dying = dying;
+        self.ages = ages;
+        if let Some(activity) = &mut self.activity {
+            activity.reset(self.cells.len());
+        }
+        self.history.clear();
+        self.bounds = self.scan_bounds();
+        self.bounds_loose = false;
+    }
+
+    /// Copy the smallest rectangle containing every live cell into a
+    /// `Pattern`.
+    pub fn to_pattern(&self) -> Pattern {
+        let (rows, columns) = match self.bounding_box() {
+            Some(bounds) => bounds,
+            None => return Pattern::from_cells(0, 0, Vec::new()).with_rule(self.rule),
+        };
+
+        let mut cells = Vec::new();
+        for row in rows.clone() {
+            for col in columns.clone() {
+                cells.push(self.cell_at(row, col));
+            }
+        }
+        Pattern::from_cells(columns.len() as u32, rows.len() as u32, cells).with_rule(self.rule)
+    }
+
+    /// Serialize the live part of the universe as RLE text, in multi-state
+    /// RLE if any cell is dying or has a color after the first.
+    pub fn to_rle(&self) -> String {
+        let (rows, columns) = self.bounding_box().unwrap_or((0..0, 0..0));
+        let width = self.width as usize;
+        let states = rows
+            .clone()
+            .flat_map(|row| {
+                columns
+                    .clone()
+                    .map(move |col| row as usize * width + col as usize)
+            })
+            .map(|idx| self.state_at(idx))
+            .collect();
+        NumberedStates {
+            width: columns.len() as u32,
+            height: rows.len() as u32,
+            rule: Some(self.rule),
+            states,
+        }
+        .to_rle()
+    }
+
+    /// Serialize the whole board, including its size and rule, as RLE text
+    /// that `deserialize` turns back into a universe. Every numbered state
+    /// is kept, written as multi-state RLE.
+    pub fn serialize(&self) -> String {
+        NumberedStates {
+            width: self.width,
+            height: self.height,
+            rule: Some(self.rule),
+            states: self.cells(),
+        }
+        .to_rle()
+    }
+
+    /// Rebuild a universe from the output of `serialize`.
+    pub fn deserialize(text: &str) -> Result<Universe, ParseError> {
+        let board = NumberedStates::from_rle(text)?;
+        let mut universe = Universe::new_with_size(board.width, board.height);
+        if let Some(rule) = board.rule {
+            universe.apply_rule(rule);
+        }
+        universe.load_states(&board.states);
+        Ok(universe)
+    }
+
+    /// Read a board drawn in the plaintext `.cells` style, one line per row
+    /// with `.` for dead cells and `O` for live ones:
+    ///
+    /// ```text
+    /// .O.
+    /// ..O
+    /// OOO
+    /// ```
+    ///
+    /// The board is as wide as the longest line, with shorter lines padded
+    /// with dead cells, and lines starting with `!` are comments.
+    pub fn from_ascii(text: &str) -> Result<Universe, ParseError> {
+        let pattern = Pattern::from_plaintext(text)?;
+        let mut universe = Universe::new_with_size(pattern.width(), pattern.height());
+        universe.insert_pattern(Position::new(0, 0), &pattern);
+        universe.reset_stats();
+        Ok(universe)
+    }
+
+    /// Draw the whole board in the style `from_ascii` reads, dead edges
+    /// included, so the text turns back into the same board.
+    pub fn to_ascii(&self) -> String {
+        self.board().to_plaintext()
+    }
+
+    /// Set the width of the universe, or as much of it as fits in
+    /// `MAX_CELLS` cells at the current height.
+    ///
+    /// Resets all cells to the dead state.
+    pub fn set_width(&mut self, width: u32) {
+        self.width = fit(width, self.height);
+        self.cells = FixedBitSet::with_capacity(cell_count(self.width, self.height));
+        self.reset_dying();
+        self.reset_stats();
+    }
+
+    /// Set the height of the universe, or as much of it as fits in
+    /// `MAX_CELLS` cells at the current width.
+    ///
+    /// Resets all cells to the dead state.
+    pub fn set_height(&mut self, height: u32) {
+        self.height = fit(height, self.width);
+        self.cells = FixedBitSet::with_capacity(cell_count(self.width, self.height));
+        self.reset_dying();
+        self.reset_stats();
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+impl Universe {
+    /// A `Uint8Array` looking straight at the packed cell bits in wasm
+    /// memory, for renderers that want to read the board without copying it.
+    ///
+    /// Cell `idx` is alive when bit `idx % 8` of byte `idx / 8` is set, and
+    /// the view is `ceil(width * height / 8)` bytes long. Dying cells of
+    /// Generations rules read as dead.
+    ///
+    /// The view is only valid until the next call into the module: ticks
+    /// and resizes replace the buffer it looks at, and any allocation can
+    /// grow wasm memory, which leaves the view empty. Read it right away, or
+    /// take a new view every frame.
+    pub fn cells_view(&self) -> js_sys::Uint8Array {
+        let blocks = self.cells.as_slice();
+        let len = self.cells.len().div_ceil(8);
+        // wasm is little-endian, so the blocks' bytes are the cells in order.
+        unsafe {
+            let bytes = std::slice::from_raw_parts(blocks.as_ptr() as *const u8, len);
+            js_sys::Uint8Array::view(bytes)
+        }
+    }
+
+    /// Step `generations` ahead, calling `hook` with each `NextGeneration`
+    /// to veto or override what the rule did before it is final. See
+    /// `scripting`. The hook can't use the universe while it runs.
+    ///
+    /// Stops at the first generation the hook throws in, keeping whatever
+    /// it had changed, and throws the same error.
+    pub fn step_with(
+        &mut self,
+        generations: u32,
+        #[wasm_bindgen(unchecked_param_type = "(next: NextGeneration) => void")]
+        hook: &js_sys::Function,
+    ) -> Result<(), JsValue> {
+        for _ in 0..generations {
+            let mut result = Ok(JsValue::UNDEFINED);
+            self.tick_with(|next| result = hook.call1(&JsValue::NULL, &next.clone().into()));
+            result?;
+        }
+        Ok(())
+    }
+}
+
+impl SimulationEngine for Universe {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn cell(&self, position: Position) -> Option<Cell> {
+        self.get(position)
+    }
+
+    fn set_cell(&mut self, position: Position, cell: Cell) -> Option<Cell> {
+        Universe::set_cell(self, position, cell)
+    }
+
+    fn tick(&mut self) {
+        Universe::tick(self);
+    }
+}
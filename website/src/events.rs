@@ -0,0 +1,257 @@
+//! What happened to a board between one look at it and the next, for JS to
+//! subscribe to instead of polling the cells:
+//!
+//! ```js
+//! const game = init({ canvas: "#life", autoplay: true });
+//! game.on_tick((tick) => chart.push(tick.population));
+//! game.on_cell_changed((changes) => changes.forEach(playNote));
+//! const id = game.on_stabilized((event) => console.log(event.period));
+//! game.off(id);
+//! ```
+//!
+//! Events are sent once a frame, step, stroke of paint or loaded scenario,
+//! however many generations it covered, after the board has been drawn.
+//! Finding which cells changed compares the whole board, and finding
+//! whether it has settled hashes it, so each is only done while something
+//! is subscribed to it.
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use crate::{Cell, Universe};
+
+/// The board moved on by one or more generations.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TickEvent {
+    pub generation: u64,
+    /// How many generations it moved on since the last event.
+    pub generations: u64,
+    pub population: u32,
+    /// Cells born and cells that died during the last generation.
+    pub births: u32,
+    pub deaths: u32,
+}
+
+/// A cell that isn't in the state it was in at the last event.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CellChange {
+    pub row: u32,
+    pub column: u32,
+    /// The state it is in now.
+    pub cell: Cell,
+}
+
+/// The board settled into a still life, of period 1, or an oscillator.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StabilizedEvent {
+    pub generation: u64,
+    pub period: u32,
+}
+
+/// Everything that happened since the last check.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Changes {
+    pub tick: Option<TickEvent>,
+    /// Only filled in while cells are watched, in row order.
+    pub cells: Vec<CellChange>,
+    /// Sent once as the board settles, and again only after it has changed
+    /// and settled anew.
+    pub stabilized: Option<StabilizedEvent>,
+}
+
+/// Remembers enough of a board to tell what changed when it is next
+/// checked.
+#[derive(Clone, Debug)]
+pub struct Watcher {
+    generation: u64,
+    /// The board's states at the last check, while cells are watched.
+    cells: Option<Vec<u8>>,
+    settled: bool,
+}
+
+impl Watcher {
+    pub fn new(universe: &Universe) -> Watcher {
+        Watcher {
+            generation: universe.generation(),
+            cells: None,
+            settled: false,
+        }
+    }
+
+    /// Start or stop reporting which cells changed. Changes are reported
+    /// from the board as it is now.
+    pub fn watch_cells(&mut self, universe: &Universe, watch: bool) {
+        self.cells = watch.then(|| universe.cells());
+    }
+
+    pub fn is_watching_cells(&self) -> bool {
+        self.cells.is_some()
+    }
+
+    /// What has changed on `universe` since the last check, looking for it
+    /// having settled only if `stabilization` is set.
+    pub fn check(&mut self, universe: &mut Universe, stabilization: bool) -> Changes {
+        let mut changes = Changes::default();
+        let generation = universe.generation();
+        // Generations only go back when the board is replaced, which isn't
+        // a tick.
+        if generation > self.generation {
+            changes.tick = Some(TickEvent {
+                generation,
+                generations: generation - self.generation,
+                population: universe.population(),
+                births: universe.births(),
+                deaths: universe.deaths(),
+            });
+        }
+        self.generation = generation;
+        if stabilization {
+            // This also remembers a board that was just edited, so that
+            // the cycle it starts is seen as soon as it comes round.
+            let period = universe.run_until_stable(0).period;
+            if period > 0 && !self.settled {
+                changes.stabilized = Some(StabilizedEvent { generation, period });
+            }
+            self.settled = period > 0;
+        }
+
+        if let Some(before) = &mut self.cells {
+            let after = universe.cells();
+            // A board of another size is a new board, not a change.
+            if after.len() == before.len() {
+                let width = universe.width();
+                changes.cells = before
+                    .iter()
+                    .zip(&after)
+                    .enumerate()
+                    .filter(|(_, (before, after))| before != after)
+                    .map(|(idx, (_, &state))| CellChange {
+                        row: idx as u32 / width,
+                        column: idx as u32 % width,
+                        cell: Cell::from_state(state),
+                    })
+                    .collect();
+            }
+            *before = after;
+        }
+        changes
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", feature = "web"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Event {
+    Tick,
+    CellChanged,
+    Stabilized,
+}
+
+/// The callbacks subscribed to a game, by the id `subscribe` gave them.
+#[cfg(all(target_arch = "wasm32", feature = "web"))]
+pub(crate) struct Subscriptions {
+    watcher: Watcher,
+    callbacks: Vec<(u32, Event, js_sys::Function)>,
+    next_id: u32,
+    /// Called with each `NextGeneration`; see `scripting`.
+    rule_hook: Option<js_sys::Function>,
+}
+
+/// Callbacks to call with their events, once nothing is borrowed, so
+/// that they can use the game themselves.
+#[cfg(all(target_arch = "wasm32", feature = "web"))]
+#[must_use]
+pub(crate) struct Pending(Vec<(js_sys::Function, JsValue)>);
+
+#[cfg(all(target_arch = "wasm32", feature = "web"))]
+impl Subscriptions {
+    pub(crate) fn new(universe: &Universe) -> Subscriptions {
+        Subscriptions {
+            watcher: Watcher::new(universe),
+            callbacks: Vec::new(),
+            next_id: 1,
+            rule_hook: None,
+        }
+    }
+
+    pub(crate) fn rule_hook(&self) -> Option<js_sys::Function> {
+        self.rule_hook.clone()
+    }
+
+    pub(crate) fn set_rule_hook(&mut self, hook: Option<js_sys::Function>) {
+        self.rule_hook = hook;
+    }
+
+    pub(crate) fn subscribe(
+        &mut self,
+        universe: &Universe,
+        event: Event,
+        callback: js_sys::Function,
+    ) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.callbacks.push((id, event, callback));
+        if event == Event::CellChanged && !self.watcher.is_watching_cells() {
+            self.watcher.watch_cells(universe, true);
+        }
+        id
+    }
+
+    /// Returns whether there was a callback with that id.
+    pub(crate) fn unsubscribe(&mut self, universe: &Universe, id: u32) -> bool {
+        let count = self.callbacks.len();
+        self.callbacks.retain(|&(other, _, _)| other != id);
+        if !self.wants(Event::CellChanged) {
+            self.watcher.watch_cells(universe, false);
+        }
+        self.callbacks.len() < count
+    }
+
+    fn wants(&self, event: Event) -> bool {
+        self.callbacks.iter().any(|&(_, other, _)| other == event)
+    }
+
+    /// The events for whatever happened to `universe` since the last
+    /// check.
+    pub(crate) fn check(&mut self, universe: &mut Universe) -> Pending {
+        let stabilization = self.wants(Event::Stabilized);
+        let Changes {
+            tick,
+            cells,
+            stabilized,
+        } = self.watcher.check(universe, stabilization);
+        let mut pending = Vec::new();
+        for (_, event, callback) in &self.callbacks {
+            let value = match event {
+                Event::Tick => tick.map(JsValue::from),
+                Event::CellChanged if !cells.is_empty() => Some(
+                    cells
+                        .iter()
+                        .map(|&change| JsValue::from(change))
+                        .collect::<js_sys::Array>()
+                        .into(),
+                ),
+                Event::CellChanged => None,
+                Event::Stabilized => stabilized.map(JsValue::from),
+            };
+            if let Some(value) = value {
+                pending.push((callback.clone(), value));
+            }
+        }
+        Pending(pending)
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", feature = "web"))]
+impl Pending {
+    /// Call every callback, carrying on past any that throw.
+    pub(crate) fn send(self) {
+        for (callback, value) in self.0 {
+            if let Err(err) = callback.call1(&JsValue::NULL, &value) {
+                log!("{}", tr!(EventCallbackFailed, format!("{:?}", err)));
+            }
+        }
+    }
+}
@@ -0,0 +1,205 @@
+//! Running many random boards without drawing them, for parameter sweeps
+//! scripted from the browser console or Node.
+//!
+//! An experiment is described by one JSON document:
+//!
+//! ```json
+//! {
+//!   "width": 64,
+//!   "height": 64,
+//!   "topology": "torus",
+//!   "rules": ["B3/S23", "B36/S23"],
+//!   "densities": [0.3, 0.5],
+//!   "seeds": 100,
+//!   "firstSeed": 0,
+//!   "maxGenerations": 10000
+//! }
+//! ```
+//!
+//! Every rule is tried at every density, each with the boards randomized
+//! from seeds `firstSeed` to `firstSeed + seeds - 1`, so the same document
+//! always gives the same results. Each board runs until it settles or has
+//! run `maxGenerations`. Every member is optional, defaulting to the values
+//! above except for `rules`, which defaults to Conway's Life, `densities`,
+//! which defaults to 0.5, and `seeds`, which defaults to 10.
+//!
+//! The results are a JSON array with one object for each rule and density:
+//!
+//! ```json
+//! [{ "rule": "B3/S23", "density": 0.5, "runs": 10, "settled": 9,
+//!    "meanLifespan": 612.4, "meanFinalPopulation": 87.3 }]
+//! ```
+//!
+//! `meanLifespan` only counts the boards that settled, and is `null` if
+//! none did.
+
+use serde::{Deserialize, Serialize};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use crate::engine::{Topology, Universe};
+use crate::lifespan::Measurement;
+use crate::replay::ReplayError;
+use crate::rule::Rule;
+
+const DEFAULT_SIZE: u32 = 64;
+const DEFAULT_DENSITY: f64 = 0.5;
+const DEFAULT_SEEDS: u32 = 10;
+const DEFAULT_MAX_GENERATIONS: u32 = 10_000;
+
+/// The boards to run, as read from the document above.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Experiment {
+    pub width: u32,
+    pub height: u32,
+    pub topology: Topology,
+    pub rules: Vec<Rule>,
+    pub densities: Vec<f64>,
+    pub seeds: u32,
+    pub first_seed: u64,
+    pub max_generations: u32,
+}
+
+impl Default for Experiment {
+    fn default() -> Experiment {
+        Experiment {
+            width: DEFAULT_SIZE,
+            height: DEFAULT_SIZE,
+            topology: Topology::Torus,
+            rules: vec![Rule::default()],
+            densities: vec![DEFAULT_DENSITY],
+            seeds: DEFAULT_SEEDS,
+            first_seed: 0,
+            max_generations: DEFAULT_MAX_GENERATIONS,
+        }
+    }
+}
+
+/// How the boards of one rule and density went.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trial {
+    pub rule: Rule,
+    pub density: f64,
+    pub runs: u32,
+    /// How many of the boards settled within the experiment's generations.
+    pub settled: u32,
+    /// The lifespans of the boards that settled, added up.
+    total_lifespan: u64,
+    /// The populations every board ended with, added up.
+    total_population: u64,
+}
+
+impl Trial {
+    pub fn mean_lifespan(&self) -> Option<f64> {
+        if self.settled == 0 {
+            return None;
+        }
+        Some(self.total_lifespan as f64 / self.settled as f64)
+    }
+
+    pub fn mean_final_population(&self) -> f64 {
+        if self.runs == 0 {
+            return 0.0;
+        }
+        self.total_population as f64 / self.runs as f64
+    }
+}
+
+/// A trial as it is written in the results above.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TrialDocument {
+    rule: Rule,
+    density: f64,
+    runs: u32,
+    settled: u32,
+    mean_lifespan: Option<f64>,
+    mean_final_population: f64,
+}
+
+impl From<&Trial> for TrialDocument {
+    fn from(trial: &Trial) -> TrialDocument {
+        TrialDocument {
+            rule: trial.rule,
+            density: trial.density,
+            runs: trial.runs,
+            settled: trial.settled,
+            mean_lifespan: trial.mean_lifespan(),
+            mean_final_population: trial.mean_final_population(),
+        }
+    }
+}
+
+impl Experiment {
+    /// Read an experiment in the schema above.
+    pub fn from_json(text: &str) -> Result<Experiment, ReplayError> {
+        let experiment: Experiment = serde_json::from_str(text)?;
+        if experiment.width == 0 {
+            return Err(ReplayError::Missing("width"));
+        }
+        if experiment.height == 0 {
+            return Err(ReplayError::Missing("height"));
+        }
+        if !experiment
+            .densities
+            .iter()
+            .all(|density| (0.0..=1.0).contains(density))
+        {
+            return Err(ReplayError::Missing("densities"));
+        }
+        Ok(experiment)
+    }
+
+    /// Run every board, returning a trial for each rule and density in the
+    /// order they were given, the densities of the first rule first.
+    pub fn run(&self) -> Vec<Trial> {
+        let mut trials = Vec::new();
+        for &rule in &self.rules {
+            for &density in &self.densities {
+                trials.push(self.trial(rule, density));
+            }
+        }
+        trials
+    }
+
+    fn trial(&self, rule: Rule, density: f64) -> Trial {
+        let mut trial = Trial {
+            rule,
+            density,
+            runs: 0,
+            settled: 0,
+            total_lifespan: 0,
+            total_population: 0,
+        };
+        for seed in self.first_seed..self.first_seed + self.seeds as u64 {
+            let mut universe = Universe::new_with_size(self.width, self.height);
+            universe.set_topology(self.topology);
+            universe.apply_rule(rule);
+            universe.randomize_with_seed(seed, density);
+            let lifespan =
+                Measurement::new(universe, self.max_generations).run(self.max_generations);
+            trial.runs += 1;
+            trial.total_population += lifespan.population as u64;
+            if lifespan.is_settled() {
+                trial.settled += 1;
+                trial.total_lifespan += lifespan.lifespan() as u64;
+            }
+        }
+        trial
+    }
+}
+
+/// Run the experiment described by `config`, in the schema above, and
+/// return its results as JSON.
+///
+/// Every board runs before this returns, so large sweeps are best run from
+/// a worker or Node rather than on a page that needs to stay responsive.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+pub fn run_experiment(config: &str) -> Result<String, ReplayError> {
+    let trials = Experiment::from_json(config)?.run();
+    let trials: Vec<_> = trials.iter().map(TrialDocument::from).collect();
+    // Densities are between 0 and 1 and means are never NaN, so this can't
+    // fail.
+    Ok(serde_json::to_string(&trials).unwrap())
+}
@@ -0,0 +1,120 @@
+//! Recording generations into an animated GIF.
+//!
+//! Frames are taken from the universe's cells rather than from the canvas,
+//! so recordings don't depend on the zoom, the theme's overlays or what is
+//! scrolled into view.
+
+use std::borrow::Cow;
+
+use ::gif::{DisposalMethod, Encoder, Frame, Repeat};
+
+use super::{cell_pixels, palette, ExportError};
+use crate::Universe;
+
+/// The frame delay until the page changes it, in milliseconds.
+pub const DEFAULT_FRAME_DELAY: u32 = 100;
+
+/// How many frames a recording holds until the page changes it.
+pub const DEFAULT_MAX_FRAMES: u32 = 500;
+
+/// Collects frames of a universe into a looping GIF, painting each cell as a
+/// `scale` pixel square in one of two colors.
+pub struct GifRecorder {
+    encoder: Encoder<Vec<u8>>,
+    width: u32,
+    height: u32,
+    scale: u32,
+    /// How long each frame is shown, in hundredths of a second.
+    delay: u16,
+    max_frames: u32,
+    frames: u32,
+    /// The palette index of every pixel of a frame, reused between frames.
+    pixels: Vec<u8>,
+}
+
+impl GifRecorder {
+    /// Start a recording of a `width` by `height` universe that shows each
+    /// frame for `delay` milliseconds and stops taking frames after
+    /// `max_frames`. Dead and alive cells are painted in the `#RRGGBB`
+    /// colors `dead` and `alive`.
+    pub fn new(
+        width: u32,
+        height: u32,
+        scale: u32,
+        delay: u32,
+        max_frames: u32,
+        dead: &str,
+        alive: &str,
+    ) -> Result<GifRecorder, ExportError> {
+        let scale = scale.max(1);
+        let (pixel_width, pixel_height) = (width * scale, height * scale);
+        if pixel_width > u16::MAX as u32 || pixel_height > u16::MAX as u32 {
+            return Err(ExportError::TooLarge(pixel_width, pixel_height));
+        }
+        let mut encoder = Encoder::new(
+            Vec::new(),
+            pixel_width as u16,
+            pixel_height as u16,
+            &palette(dead, alive),
+        )
+        .map_err(|error| ExportError::Encoding(error.to_string()))?;
+        encoder
+            .set_repeat(Repeat::Infinite)
+            .map_err(|error| ExportError::Encoding(error.to_string()))?;
+        Ok(GifRecorder {
+            encoder,
+            width,
+            height,
+            scale,
+            delay: (delay / 10).min(u16::MAX as u32) as u16,
+            max_frames,
+            frames: 0,
+            pixels: Vec::with_capacity((pixel_width * pixel_height) as usize),
+        })
+    }
+
+    /// How many frames have been recorded.
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.frames >= self.max_frames
+    }
+
+    /// Add the universe as it is now as the next frame. Returns whether it
+    /// was added, which it isn't once the recording is full or if the
+    /// universe has been resized since the recording started.
+    pub fn capture(&mut self, universe: &Universe) -> Result<bool, ExportError> {
+        if self.is_full() || universe.width() != self.width || universe.height() != self.height {
+            return Ok(false);
+        }
+        cell_pixels(
+            universe,
+            0..self.height,
+            0..self.width,
+            self.scale,
+            &mut self.pixels,
+        );
+        let frame = Frame {
+            delay: self.delay,
+            dispose: DisposalMethod::Keep,
+            width: (self.width * self.scale) as u16,
+            height: (self.height * self.scale) as u16,
+            buffer: Cow::Borrowed(&self.pixels),
+            ..Frame::default()
+        };
+        self.encoder
+            .write_frame(&frame)
+            .map_err(|error| ExportError::Encoding(error.to_string()))?;
+        self.frames += 1;
+        Ok(true)
+    }
+
+    /// The finished GIF file.
+    pub fn finish(self) -> Result<Vec<u8>, ExportError> {
+        self.encoder
+            .into_inner()
+            .map_err(|error| ExportError::Encoding(error.to_string()))
+    }
+}
@@ -0,0 +1,81 @@
+//! Saving runs of the universe in formats other programs can show.
+
+pub mod gif;
+pub mod png;
+
+use std::fmt;
+use std::ops::Range;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use crate::{Cell, Position, Universe};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExportError {
+    /// The picture would be wider or taller than the format allows, in
+    /// pixels.
+    TooLarge(u32, u32),
+    /// The universe has no cells to draw.
+    Empty,
+    /// The encoder failed.
+    Encoding(String),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExportError::TooLarge(width, height) => {
+                write!(f, "a {}x{} picture is too large", width, height)
+            }
+            ExportError::Empty => write!(f, "the universe is empty"),
+            ExportError::Encoding(error) => write!(f, "could not encode: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+#[cfg(target_arch = "wasm32")]
+impl From<ExportError> for JsValue {
+    fn from(error: ExportError) -> JsValue {
+        JsValue::from_str(&error.to_string())
+    }
+}
+
+/// A palette of two `#RRGGBB` colors, for dead and then live cells.
+fn palette(dead: &str, alive: &str) -> Vec<u8> {
+    let mut palette = rgb(dead).to_vec();
+    palette.extend_from_slice(&rgb(alive));
+    palette
+}
+
+/// The red, green and blue bytes of a `#RRGGBB` color.
+fn rgb(color: &str) -> [u8; 3] {
+    let rgb = u32::from_str_radix(color.trim_start_matches('#'), 16).unwrap_or(0);
+    [(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8]
+}
+
+/// Replace `pixels` with the `rows` and `columns` of the universe drawn
+/// with every cell as a `scale` pixel square, one byte per pixel in rows
+/// from the top: 0 where the cell is dead and 1 where it is alive.
+fn cell_pixels(
+    universe: &Universe,
+    rows: Range<u32>,
+    columns: Range<u32>,
+    scale: u32,
+    pixels: &mut Vec<u8>,
+) {
+    pixels.clear();
+    for row in rows {
+        let start = pixels.len();
+        for column in columns.clone() {
+            let index = (universe.get(Position::new(row, column)) == Some(Cell::Alive)) as u8;
+            pixels.extend(std::iter::repeat_n(index, scale as usize));
+        }
+        let end = pixels.len();
+        for _ in 1..scale {
+            pixels.extend_from_within(start..end);
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Pictures of the universe as PNG files, drawn at any scale rather than at
+//! the canvas's zoom: still ones of the whole universe, and animated ones of
+//! a run that only show where it happens.
+
+use std::ops::Range;
+
+use super::{cell_pixels, palette, ExportError};
+use crate::Universe;
+
+pub const MIN_PNG_SCALE: u32 = 1;
+pub const MAX_PNG_SCALE: u32 = 20;
+
+/// The most generations an animated PNG can show.
+pub const MAX_ANIMATION_GENERATIONS: u32 = 1000;
+
+/// The whole universe as a PNG with every cell a `scale` pixel square, in
+/// the `#RRGGBB` colors `dead` and `alive`. The scale is clamped to the
+/// range PNG exports allow.
+pub fn encode_png(
+    universe: &Universe,
+    scale: u32,
+    dead: &str,
+    alive: &str,
+) -> Result<Vec<u8>, ExportError> {
+    let scale = scale.clamp(MIN_PNG_SCALE, MAX_PNG_SCALE);
+    let (width, height) = (universe.width() * scale, universe.height() * scale);
+    if width == 0 || height == 0 {
+        return Err(ExportError::Empty);
+    }
+    let mut pixels = Vec::with_capacity((width * height) as usize);
+    cell_pixels(
+        universe,
+        0..universe.height(),
+        0..universe.width(),
+        scale,
+        &mut pixels,
+    );
+
+    let mut png = Vec::new();
+    let mut encoder = ::png::Encoder::new(&mut png, width, height);
+    encoder.set_color(::png::ColorType::Indexed);
+    encoder.set_depth(::png::BitDepth::Eight);
+    encoder.set_palette(palette(dead, alive));
+    let mut writer = encoder
+        .write_header()
+        .map_err(|error| ExportError::Encoding(error.to_string()))?;
+    writer
+        .write_image_data(&pixels)
+        .and_then(|()| writer.finish())
+        .map_err(|error| ExportError::Encoding(error.to_string()))?;
+    Ok(png)
+}
+
+/// A looping animated PNG of the universe as it is now and the next
+/// `generations` generations, each shown for `delay` milliseconds. The
+/// universe itself isn't advanced. The picture is cropped to the smallest
+/// rectangle that holds every live cell of every frame, with cells as in
+/// `encode_png`.
+pub fn encode_apng(
+    universe: &Universe,
+    generations: u32,
+    scale: u32,
+    delay: u32,
+    dead: &str,
+    alive: &str,
+) -> Result<Vec<u8>, ExportError> {
+    let scale = scale.clamp(MIN_PNG_SCALE, MAX_PNG_SCALE);
+    let generations = generations.min(MAX_ANIMATION_GENERATIONS);
+
+    // Run once to find where the run happens, and again to draw it, rather
+    // than keep every generation.
+    let mut run = universe.clone();
+    let mut bounds: Option<(Range<u32>, Range<u32>)> = None;
+    for generation in 0..=generations {
+        if generation > 0 {
+            run.tick();
+        }
+        if let Some((rows, columns)) = run.bounding_box() {
+            bounds = Some(match bounds {
+                Some((all_rows, all_columns)) => (
+                    all_rows.start.min(rows.start)..all_rows.end.max(rows.end),
+                    all_columns.start.min(columns.start)..all_columns.end.max(columns.end),
+                ),
+                None => (rows, columns),
+            });
+        }
+    }
+    let (rows, columns) = bounds.ok_or(ExportError::Empty)?;
+    let (width, height) = (columns.len() as u32 * scale, rows.len() as u32 * scale);
+
+    let encoding = |error: ::png::EncodingError| ExportError::Encoding(error.to_string());
+    let mut png = Vec::new();
+    let mut encoder = ::png::Encoder::new(&mut png, width, height);
+    encoder.set_color(::png::ColorType::Indexed);
+    encoder.set_depth(::png::BitDepth::Eight);
+    encoder.set_palette(palette(dead, alive));
+    encoder.set_animated(generations + 1, 0).map_err(encoding)?;
+    encoder
+        .set_frame_delay(delay.min(u16::MAX as u32) as u16, 1000)
+        .map_err(encoding)?;
+    let mut writer = encoder.write_header().map_err(encoding)?;
+
+    let mut run = universe.clone();
+    let mut pixels = Vec::with_capacity((width * height) as usize);
+    for generation in 0..=generations {
+        if generation > 0 {
+            run.tick();
+        }
+        cell_pixels(&run, rows.clone(), columns.clone(), scale, &mut pixels);
+        writer.write_image_data(&pixels).map_err(encoding)?;
+    }
+    writer.finish().map_err(encoding)?;
+    Ok(png)
+}
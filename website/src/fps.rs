@@ -0,0 +1,145 @@
+//! How fast the animation loop runs: frames and generations a second, and
+//! how long each frame spends stepping the board and drawing it, so that
+//! renderers and engines can be compared.
+//!
+//! Times are in milliseconds and passed in by the caller, which in the
+//! browser reads them from `performance.now()` with `now`.
+
+use std::collections::VecDeque;
+
+use crate::i18n::{self, Message};
+
+/// How many frames the rolling statistics cover.
+pub const WINDOW: usize = 60;
+
+/// The longest gap between frames that counts as a frame. Longer ones are
+/// the loop being paused, or the page hidden, rather than a slow frame.
+pub const MAX_INTERVAL: f64 = 1000.0;
+
+/// The last `WINDOW` samples of something.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Rolling {
+    samples: VecDeque<f64>,
+}
+
+impl Rolling {
+    pub fn push(&mut self, sample: f64) {
+        if self.samples.len() == WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub fn min(&self) -> Option<f64> {
+        self.samples.iter().copied().reduce(f64::min)
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        Some(self.sum() / self.samples.len() as f64)
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        self.samples.iter().copied().reduce(f64::max)
+    }
+
+    fn sum(&self) -> f64 {
+        self.samples.iter().sum()
+    }
+
+    /// The mean with the range either side, as `1.5 ms (1.0-2.5)`.
+    fn describe(&self) -> String {
+        match (self.min(), self.mean(), self.max()) {
+            (Some(min), Some(mean), Some(max)) => {
+                format!("{:.1} ms ({:.1}-{:.1})", mean, min, max)
+            }
+            _ => "-".to_string(),
+        }
+    }
+}
+
+/// Timings of the animation loop's recent frames.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FrameStats {
+    /// When the last frame started.
+    last_frame: Option<f64>,
+    /// The time between the starts of consecutive frames.
+    intervals: Rolling,
+    /// The generations each of those frames ran.
+    generations: VecDeque<u64>,
+    simulation: Rolling,
+    render: Rolling,
+}
+
+impl FrameStats {
+    /// Note that a frame started at `now`, running `generations`.
+    pub fn frame(&mut self, now: f64, generations: u64) {
+        if let Some(last) = self.last_frame.filter(|&last| now - last <= MAX_INTERVAL) {
+            self.intervals.push(now - last);
+            if self.generations.len() == WINDOW {
+                self.generations.pop_front();
+            }
+            self.generations.push_back(generations);
+        }
+        self.last_frame = Some(now);
+    }
+
+    /// Note that stepping the board took `time`.
+    pub fn simulated(&mut self, time: f64) {
+        self.simulation.push(time);
+    }
+
+    /// Note that drawing the board took `time`.
+    pub fn rendered(&mut self, time: f64) {
+        self.render.push(time);
+    }
+
+    pub fn frames_per_second(&self) -> Option<f64> {
+        self.intervals
+            .mean()
+            .filter(|&interval| interval > 0.0)
+            .map(|interval| 1000.0 / interval)
+    }
+
+    pub fn generations_per_second(&self) -> Option<f64> {
+        let elapsed = self.intervals.sum();
+        if elapsed <= 0.0 {
+            return None;
+        }
+        Some(self.generations.iter().sum::<u64>() as f64 * 1000.0 / elapsed)
+    }
+
+    pub fn simulation(&self) -> &Rolling {
+        &self.simulation
+    }
+
+    pub fn render(&self) -> &Rolling {
+        &self.render
+    }
+
+    /// Frames and generations a second, then stepping and drawing times.
+    pub fn describe(&self) -> String {
+        let frames = format!("{:.0}", self.frames_per_second().unwrap_or(0.0));
+        let generations = format!("{:.0}", self.generations_per_second().unwrap_or(0.0));
+        i18n::locale().format(
+            Message::FrameStats,
+            &[
+                &frames,
+                &generations,
+                &self.simulation.describe(),
+                &self.render.describe(),
+            ],
+        )
+    }
+}
+
+/// The time in milliseconds by `performance.now()`, or 0 where there is no
+/// window to ask.
+#[cfg(all(target_arch = "wasm32", feature = "web"))]
+pub fn now() -> f64 {
+    web_sys::window()
+        .and_then(|window| window.performance())
+        .map_or(0.0, |performance| performance.now())
+}
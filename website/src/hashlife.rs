@@ -0,0 +1,447 @@
+//! Bill Gosper's HashLife algorithm.
+//!
+//! The plane is stored as a quadtree in which identical subtrees are shared,
+//! and the future of every subtree is memoized. Repetitive patterns can then
+//! be advanced by huge powers of two in roughly the time it takes to advance
+//! them by a single generation.
+//!
+//! Unlike `Universe`, HashLife simulates an unbounded plane: `width` and
+//! `height` only describe the region that is visible through the
+//! `SimulationEngine` interface, and cells that leave it keep evolving.
+//!
+//! Since the quadtree is already compressed, HashLife can read and write
+//! Golly's macrocell format without ever expanding a pattern to a grid.
+
+mod macrocell;
+
+pub use macrocell::HEADER as MACROCELL_HEADER;
+
+use std::collections::HashMap;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use crate::engine::SimulationEngine;
+use crate::patterns::ParseError;
+use crate::rule::{Rule, RuleError};
+use crate::{Cell, Position};
+
+type NodeId = u32;
+
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+
+/// Once the node arena grows past this size the unreachable nodes and the
+/// memoized results are thrown away.
+const MAX_NODES: usize = 1 << 21;
+
+/// The smallest level the root is allowed to shrink to.
+const MIN_ROOT_LEVEL: u8 = 3;
+
+#[derive(Clone, Copy, Debug)]
+struct Node {
+    level: u8,
+    /// The `nw`, `ne`, `sw` and `se` quadrants, unused for leaves.
+    children: [NodeId; 4],
+    population: u64,
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+pub struct HashLife {
+    width: u32,
+    height: u32,
+    generation: u64,
+    rule: Rule,
+
+    nodes: Vec<Node>,
+    lookup: HashMap<[NodeId; 4], NodeId>,
+    /// `(node, j)` maps to the centre of `node` advanced `2^j` generations.
+    results: HashMap<(NodeId, u8), NodeId>,
+    empty: Vec<NodeId>,
+    /// Covers the square `[-2^(level-1), 2^(level-1))` in both axes, with
+    /// cell (`row`, `column`) stored at `x = column`, `y = row`.
+    root: NodeId,
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl HashLife {
+    /// Creates an empty plane with a visible region of the given size.
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen(constructor))]
+    pub fn new(width: u32, height: u32) -> HashLife {
+        let leaf = |population| Node {
+            level: 0,
+            children: [DEAD; 4],
+            population,
+        };
+        let mut hashlife = HashLife {
+            width,
+            height,
+            generation: 0,
+            rule: Rule::conway(),
+            nodes: vec![leaf(0), leaf(1)],
+            lookup: HashMap::new(),
+            results: HashMap::new(),
+            empty: vec![DEAD],
+            root: DEAD,
+        };
+        hashlife.root = hashlife.empty(MIN_ROOT_LEVEL);
+        hashlife
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Change the size of the visible region, leaving the plane as it is.
+    pub fn set_size(&mut self, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+    }
+
+    /// The number of generations simulated so far.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// The number of live cells on the whole plane, visible or not.
+    pub fn population(&self) -> u64 {
+        self.nodes[self.root as usize].population
+    }
+
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    /// Change the rule, forgetting every result computed with the old one.
+    ///
+    /// Only Life-like rules are run, since results are worked out from
+    /// the eight neighbors of two-state cells. Rules with `B0` are refused
+    /// too, since they would fill the unbounded plane, which is empty
+    /// everywhere outside the tree.
+    pub fn set_rule(&mut self, rule: Rule) -> Result<(), RuleError> {
+        if !rule.is_life_like() {
+            return Err(RuleError::NotLifeLike(rule.to_string()));
+        }
+        if rule.births_on_zero() {
+            return Err(RuleError::BirthOnZero(rule.to_string()));
+        }
+        self.rule = rule;
+        self.results.clear();
+        Ok(())
+    }
+
+    /// Advance the plane by `2^exponent` generations in a single step.
+    pub fn step_pow2(&mut self, exponent: u8) {
+        while self.level(self.root) < exponent + 2 {
+            self.root = self.expand(self.root);
+        }
+        // Pad the pattern so nothing can escape the part of the tree that
+        // the successor keeps.
+        let padded = self.expand(self.root);
+        let padded = self.expand(padded);
+        self.root = self.successor(padded, exponent);
+        self.generation += 1 << exponent;
+
+        self.shrink();
+        if self.nodes.len() > MAX_NODES {
+            self.collect_garbage();
+        }
+    }
+
+    /// Read the contents of a macrocell (`.mc`) file. Like in Golly, the
+    /// pattern's quadtree is centred on the origin, and the visible region
+    /// is empty until `set_size` is called.
+    pub fn from_macrocell(text: &str) -> Result<HashLife, ParseError> {
+        macrocell::parse(text)
+    }
+
+    /// Write the whole plane, visible or not, as a macrocell file.
+    pub fn to_macrocell(&self) -> String {
+        macrocell::write(self)
+    }
+
+    /// Shift the plane so that the top-left corner of the quadtree is at the
+    /// origin, bringing a pattern read with `from_macrocell` into view.
+    pub fn move_to_corner(&mut self) {
+        let empty = self.empty(self.level(self.root));
+        self.root = self.join(empty, empty, empty, self.root);
+    }
+}
+
+impl HashLife {
+    fn level(&self, node: NodeId) -> u8 {
+        self.nodes[node as usize].level
+    }
+
+    fn children(&self, node: NodeId) -> [NodeId; 4] {
+        self.nodes[node as usize].children
+    }
+
+    fn node_population(&self, node: NodeId) -> u64 {
+        self.nodes[node as usize].population
+    }
+
+    /// Find or create the node with the given quadrants.
+    fn join(&mut self, nw: NodeId, ne: NodeId, sw: NodeId, se: NodeId) -> NodeId {
+        let children = [nw, ne, sw, se];
+        if let Some(&node) = self.lookup.get(&children) {
+            return node;
+        }
+        let node = Node {
+            level: self.level(nw) + 1,
+            children,
+            population: children
+                .iter()
+                .map(|&child| self.node_population(child))
+                .sum(),
+        };
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(node);
+        self.lookup.insert(children, id);
+        id
+    }
+
+    fn empty(&mut self, level: u8) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let smaller = *self.empty.last().unwrap();
+            let node = self.join(smaller, smaller, smaller, smaller);
+            self.empty.push(node);
+        }
+        self.empty[level as usize]
+    }
+
+    /// Surround `node` with empty space, doubling its size.
+    fn expand(&mut self, node: NodeId) -> NodeId {
+        let [nw, ne, sw, se] = self.children(node);
+        let e = self.empty(self.level(node) - 1);
+        let nw = self.join(e, e, e, nw);
+        let ne = self.join(e, e, ne, e);
+        let sw = self.join(e, sw, e, e);
+        let se = self.join(se, e, e, e);
+        self.join(nw, ne, sw, se)
+    }
+
+    /// The middle half of `node`.
+    fn centre(&mut self, node: NodeId) -> NodeId {
+        let [nw, ne, sw, se] = self.children(node);
+        let nw = self.children(nw)[3];
+        let ne = self.children(ne)[2];
+        let sw = self.children(sw)[1];
+        let se = self.children(se)[0];
+        self.join(nw, ne, sw, se)
+    }
+
+    /// Drop empty borders so the root doesn't keep growing.
+    fn shrink(&mut self) {
+        while self.level(self.root) > MIN_ROOT_LEVEL {
+            let centre = self.centre(self.root);
+            if self.node_population(centre) != self.node_population(self.root) {
+                break;
+            }
+            self.root = centre;
+        }
+    }
+
+    /// The centre of `node`, advanced `2^j` generations, where `j` is at most
+    /// `level - 2`.
+    fn successor(&mut self, node: NodeId, j: u8) -> NodeId {
+        let level = self.level(node);
+        debug_assert!(level >= 2 && j <= level - 2);
+        if self.node_population(node) == 0 {
+            return self.empty(level - 1);
+        }
+        if let Some(&result) = self.results.get(&(node, j)) {
+            return result;
+        }
+
+        let result = if level == 2 {
+            self.base_case(node)
+        } else {
+            let [nw, ne, sw, se] = self.children(node);
+            let [_, nw_ne, nw_sw, nw_se] = self.children(nw);
+            let [ne_nw, _, ne_sw, ne_se] = self.children(ne);
+            let [sw_nw, sw_ne, _, sw_se] = self.children(sw);
+            let [se_nw, se_ne, se_sw, _] = self.children(se);
+
+            // Nine overlapping sub-squares, each half the size of `node`.
+            let n01 = self.join(nw_ne, ne_nw, nw_se, ne_sw);
+            let n10 = self.join(nw_sw, nw_se, sw_nw, sw_ne);
+            let n11 = self.join(nw_se, ne_sw, sw_ne, se_nw);
+            let n12 = self.join(ne_sw, ne_se, se_nw, se_ne);
+            let n21 = self.join(sw_ne, se_nw, sw_se, se_sw);
+            let squares = [nw, n01, ne, n10, n11, n12, sw, n21, se];
+
+            // Advancing at full speed takes two half-steps, otherwise the
+            // first pass just lines the squares up without advancing them.
+            let full_speed = j == level - 2;
+            let mut r = [DEAD; 9];
+            for (r, &square) in r.iter_mut().zip(squares.iter()) {
+                *r = if full_speed {
+                    self.successor(square, level - 3)
+                } else {
+                    self.centre(square)
+                };
+            }
+
+            let second_j = if full_speed { level - 3 } else { j };
+            let nw = self.join(r[0], r[1], r[3], r[4]);
+            let ne = self.join(r[1], r[2], r[4], r[5]);
+            let sw = self.join(r[3], r[4], r[6], r[7]);
+            let se = self.join(r[4], r[5], r[7], r[8]);
+            let nw = self.successor(nw, second_j);
+            let ne = self.successor(ne, second_j);
+            let sw = self.successor(sw, second_j);
+            let se = self.successor(se, second_j);
+            self.join(nw, ne, sw, se)
+        };
+
+        self.results.insert((node, j), result);
+        result
+    }
+
+    /// Run a single generation of a 4x4 node by brute force.
+    fn base_case(&mut self, node: NodeId) -> NodeId {
+        let mut grid = [[false; 4]; 4];
+        for (quadrant, &child) in self.children(node).iter().enumerate() {
+            for (i, &leaf) in self.children(child).iter().enumerate() {
+                let y = (quadrant / 2) * 2 + i / 2;
+                let x = (quadrant % 2) * 2 + i % 2;
+                grid[y][x] = leaf == ALIVE;
+            }
+        }
+
+        let mut next = [DEAD; 4];
+        for (i, next) in next.iter_mut().enumerate() {
+            let y = 1 + i / 2;
+            let x = 1 + i % 2;
+            let live_neighbors = grid[y - 1..=y + 1]
+                .iter()
+                .flat_map(|line| &line[x - 1..=x + 1])
+                .filter(|&&alive| alive)
+                .count()
+                - grid[y][x] as usize;
+            let cell = Cell::from(grid[y][x]);
+            let alive = self.rule.next(cell, live_neighbors as u16) == Cell::Alive;
+            *next = if alive { ALIVE } else { DEAD };
+        }
+        self.join(next[0], next[1], next[2], next[3])
+    }
+
+    /// Half the side length of the root.
+    fn half_size(&self) -> i64 {
+        1 << (self.level(self.root) - 1)
+    }
+
+    fn get(&self, x: i64, y: i64) -> NodeId {
+        let half = self.half_size();
+        if x < -half || x >= half || y < -half || y >= half {
+            return DEAD;
+        }
+        let (mut x, mut y) = (x + half, y + half);
+        let mut node = self.root;
+        while self.level(node) > 0 {
+            let size = 1 << (self.level(node) - 1);
+            let quadrant = ((y >= size) as usize) * 2 + (x >= size) as usize;
+            node = self.children(node)[quadrant];
+            x %= size;
+            y %= size;
+        }
+        node
+    }
+
+    fn set(&mut self, x: i64, y: i64, leaf: NodeId) {
+        loop {
+            let half = self.half_size();
+            if x >= -half && x < half && y >= -half && y < half {
+                break;
+            }
+            self.root = self.expand(self.root);
+        }
+        let half = self.half_size();
+        self.root = self.set_in(self.root, x + half, y + half, leaf);
+    }
+
+    fn set_in(&mut self, node: NodeId, x: i64, y: i64, leaf: NodeId) -> NodeId {
+        if self.level(node) == 0 {
+            return leaf;
+        }
+        let size = 1 << (self.level(node) - 1);
+        let quadrant = ((y >= size) as usize) * 2 + (x >= size) as usize;
+        let mut children = self.children(node);
+        children[quadrant] = self.set_in(children[quadrant], x % size, y % size, leaf);
+        self.join(children[0], children[1], children[2], children[3])
+    }
+
+    /// Rebuild the arena with only the nodes reachable from the root.
+    fn collect_garbage(&mut self) {
+        let mut fresh = HashLife::new(self.width, self.height);
+        fresh.generation = self.generation;
+        fresh.rule = self.rule;
+        let mut copied = HashMap::new();
+        fresh.root = fresh.copy_node(self, self.root, &mut copied);
+        *self = fresh;
+    }
+
+    fn copy_node(
+        &mut self,
+        other: &HashLife,
+        node: NodeId,
+        copied: &mut HashMap<NodeId, NodeId>,
+    ) -> NodeId {
+        if other.level(node) == 0 {
+            return node;
+        }
+        if let Some(&id) = copied.get(&node) {
+            return id;
+        }
+        let [nw, ne, sw, se] = other.children(node);
+        let nw = self.copy_node(other, nw, copied);
+        let ne = self.copy_node(other, ne, copied);
+        let sw = self.copy_node(other, sw, copied);
+        let se = self.copy_node(other, se, copied);
+        let id = self.join(nw, ne, sw, se);
+        copied.insert(node, id);
+        id
+    }
+}
+
+impl SimulationEngine for HashLife {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn cell(&self, position: Position) -> Option<Cell> {
+        if !self.contains(position) {
+            return None;
+        }
+        let leaf = self.get(position.column as i64, position.row as i64);
+        Some(Cell::from(leaf == ALIVE))
+    }
+
+    fn set_cell(&mut self, position: Position, cell: Cell) -> Option<Cell> {
+        let was = self.cell(position)?;
+        let leaf = if cell == Cell::Alive { ALIVE } else { DEAD };
+        self.set(position.column as i64, position.row as i64, leaf);
+        Some(was)
+    }
+
+    fn tick(&mut self) {
+        self.step_pow2(0);
+    }
+
+    fn step(&mut self, generations: u64) {
+        for exponent in 0..64 {
+            if generations & (1 << exponent) != 0 {
+                self.step_pow2(exponent);
+            }
+        }
+    }
+}
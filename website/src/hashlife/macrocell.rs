@@ -0,0 +1,191 @@
+//! Golly's macrocell format, which stores the HashLife quadtree itself so
+//! that enormous patterns stay small. See
+//! <https://conwaylife.com/wiki/Macrocell>.
+//!
+//! After an `[M2]` header and `#` comment lines (with `#R` naming the rule),
+//! every line describes one node. Lines of `.`, `*` and `$` are 8x8 leaves
+//! written like plaintext rows, with `$` ending each row. Lines `k a b c d`
+//! are nodes of level `k` whose `nw`, `ne`, `sw` and `se` quadrants are the
+//! nodes on lines `a` to `d`, counting nodes from 1, or empty for 0. The
+//! last node is the root, centred on the origin.
+
+use std::collections::HashMap;
+
+use super::{HashLife, NodeId, ALIVE, DEAD};
+use crate::patterns::ParseError;
+use crate::rule::Rule;
+
+pub const HEADER: &str = "[M2]";
+
+/// The level of the 8x8 leaves.
+const LEAF_LEVEL: u8 = 3;
+const LEAF_SIZE: usize = 1 << LEAF_LEVEL;
+
+type Leaf = [[bool; LEAF_SIZE]; LEAF_SIZE];
+
+pub fn parse(text: &str) -> Result<HashLife, ParseError> {
+    let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+    match lines.next() {
+        Some(line) if line.starts_with(HEADER) => {}
+        Some(line) => return Err(ParseError::InvalidHeader(line.to_string())),
+        None => return Err(ParseError::MissingHeader),
+    }
+
+    let mut hashlife = HashLife::new(0, 0);
+    // Node lines in order, so index `i` is line `i + 1`.
+    let mut nodes: Vec<NodeId> = Vec::new();
+    for line in lines {
+        if let Some(rule) = line.strip_prefix("#R") {
+            let rule = rule.trim();
+            let invalid = || ParseError::InvalidRule(rule.to_string());
+            let parsed = Rule::parse(rule).map_err(|_| invalid())?;
+            hashlife.set_rule(parsed).map_err(|_| invalid())?;
+            continue;
+        }
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let invalid = || ParseError::InvalidNode(line.to_string());
+        let node = if line.starts_with(['.', '*', '$']) {
+            let mut leaf = [[false; LEAF_SIZE]; LEAF_SIZE];
+            for (y, row) in line.split('$').enumerate().take(LEAF_SIZE) {
+                for (x, c) in row.chars().enumerate() {
+                    match c {
+                        '.' => {}
+                        '*' if x < LEAF_SIZE => leaf[y][x] = true,
+                        _ => return Err(invalid()),
+                    }
+                }
+            }
+            hashlife.join_leaf(&leaf, 0, 0, LEAF_LEVEL)
+        } else {
+            let numbers: Vec<usize> = line
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<_, _>>()
+                .map_err(|_| invalid())?;
+            let (level, children) = match numbers[..] {
+                [level, nw, ne, sw, se] if level > LEAF_LEVEL as usize && level < 64 => {
+                    (level as u8, [nw, ne, sw, se])
+                }
+                _ => return Err(invalid()),
+            };
+            let mut quadrants = [DEAD; 4];
+            for (quadrant, &child) in quadrants.iter_mut().zip(children.iter()) {
+                *quadrant = match child {
+                    0 => hashlife.empty(level - 1),
+                    child => match nodes.get(child - 1) {
+                        Some(&node) if hashlife.level(node) == level - 1 => node,
+                        _ => return Err(invalid()),
+                    },
+                };
+            }
+            let [nw, ne, sw, se] = quadrants;
+            hashlife.join(nw, ne, sw, se)
+        };
+        nodes.push(node);
+    }
+
+    if let Some(&root) = nodes.last() {
+        hashlife.root = root;
+    }
+    Ok(hashlife)
+}
+
+pub fn write(hashlife: &HashLife) -> String {
+    let mut text = format!("{}\n#R {}\n", HEADER, hashlife.rule);
+    // The root never shrinks below a leaf.
+    write_node(
+        hashlife,
+        hashlife.root,
+        &mut HashMap::new(),
+        &mut 0,
+        &mut text,
+    );
+    text
+}
+
+/// Write `node` after its children, returning its line number or 0 if it is
+/// empty.
+fn write_node(
+    hashlife: &HashLife,
+    node: NodeId,
+    numbers: &mut HashMap<NodeId, usize>,
+    count: &mut usize,
+    text: &mut String,
+) -> usize {
+    if hashlife.node_population(node) == 0 {
+        return 0;
+    }
+    if let Some(&number) = numbers.get(&node) {
+        return number;
+    }
+
+    let level = hashlife.level(node);
+    if level == LEAF_LEVEL {
+        let mut leaf = [[false; LEAF_SIZE]; LEAF_SIZE];
+        hashlife.fill_leaf(node, 0, 0, &mut leaf);
+        // Like Golly, leave out the empty rows at the bottom.
+        let rows = leaf
+            .iter()
+            .rposition(|row| row.contains(&true))
+            .map_or(0, |last| last + 1);
+        for row in leaf[..rows].iter() {
+            let end = row
+                .iter()
+                .rposition(|&alive| alive)
+                .map_or(0, |last| last + 1);
+            text.extend(
+                row[..end]
+                    .iter()
+                    .map(|&alive| if alive { '*' } else { '.' }),
+            );
+            text.push('$');
+        }
+    } else {
+        let children = hashlife.children(node);
+        let mut line = level.to_string();
+        for &child in children.iter() {
+            let number = write_node(hashlife, child, numbers, count, text);
+            line.push_str(&format!(" {}", number));
+        }
+        text.push_str(&line);
+    }
+    text.push('\n');
+
+    *count += 1;
+    numbers.insert(node, *count);
+    *count
+}
+
+impl HashLife {
+    /// Build the node of `level` whose top-left corner is at (`x`, `y`) in
+    /// `leaf`.
+    fn join_leaf(&mut self, leaf: &Leaf, x: usize, y: usize, level: u8) -> NodeId {
+        if level == 0 {
+            return if leaf[y][x] { ALIVE } else { DEAD };
+        }
+        let half = 1 << (level - 1);
+        let nw = self.join_leaf(leaf, x, y, level - 1);
+        let ne = self.join_leaf(leaf, x + half, y, level - 1);
+        let sw = self.join_leaf(leaf, x, y + half, level - 1);
+        let se = self.join_leaf(leaf, x + half, y + half, level - 1);
+        self.join(nw, ne, sw, se)
+    }
+
+    /// Copy `node` into `leaf` with its top-left corner at (`x`, `y`).
+    fn fill_leaf(&self, node: NodeId, x: usize, y: usize, leaf: &mut Leaf) {
+        let level = self.level(node);
+        if level == 0 {
+            leaf[y][x] = node == ALIVE;
+            return;
+        }
+        let half = 1 << (level - 1);
+        let [nw, ne, sw, se] = self.children(node);
+        self.fill_leaf(nw, x, y, leaf);
+        self.fill_leaf(ne, x + half, y, leaf);
+        self.fill_leaf(sw, x, y + half, leaf);
+        self.fill_leaf(se, x + half, y + half, leaf);
+    }
+}
@@ -0,0 +1,85 @@
+//! Undo and redo.
+
+use std::collections::VecDeque;
+
+/// How many states `History::default` keeps to undo.
+pub const DEFAULT_CAPACITY: usize = 100;
+
+/// Bounded undo and redo stacks of earlier states.
+#[derive(Clone, Debug)]
+pub struct History<T> {
+    /// States to undo to, oldest first.
+    undo: VecDeque<T>,
+    /// States to redo to, most recently undone last.
+    redo: Vec<T>,
+    capacity: usize,
+}
+
+impl<T> Default for History<T> {
+    fn default() -> History<T> {
+        History::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl<T> History<T> {
+    /// History that keeps at most `capacity` states to undo.
+    pub fn new(capacity: usize) -> History<T> {
+        History {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            capacity,
+        }
+    }
+
+    /// Remember `state` before changing it. Whatever was undone can no longer
+    /// be redone, and the oldest state is forgotten once there are
+    /// `capacity` of them.
+    pub fn push(&mut self, state: T) {
+        self.redo.clear();
+        self.push_undo(state);
+    }
+
+    /// Step back, given the `current` state. Returns the state to restore,
+    /// or `None` if there is nothing to undo.
+    pub fn undo(&mut self, current: T) -> Option<T> {
+        let state = self.undo.pop_back()?;
+        self.redo.push(current);
+        Some(state)
+    }
+
+    /// Step forward again after an undo, given the `current` state.
+    pub fn redo(&mut self, current: T) -> Option<T> {
+        let state = self.redo.pop()?;
+        self.push_undo(current);
+        Some(state)
+    }
+
+    /// Forget what was undone, as the state moves on in a way that isn't
+    /// pushed here.
+    pub fn forget_redo(&mut self) {
+        self.redo.clear();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    fn push_undo(&mut self, state: T) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.undo.len() == self.capacity {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(state);
+    }
+}
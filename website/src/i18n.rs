@@ -0,0 +1,565 @@
+//! The text the crate shows on the page and logs to the console, in every
+//! language it has been translated into.
+//!
+//! Each language is a table from `Message` to text, so adding one means
+//! adding a `Locale` and a table; the compiler points out anything left
+//! untranslated. Messages take their arguments as `{0}`, `{1}` and so on, so
+//! that translations can put them in whatever order reads best.
+
+use std::cell::Cell;
+use std::fmt::Display;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Locale {
+    English,
+    French,
+    Spanish,
+}
+
+/// Every piece of text the crate shows or logs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Message {
+    // Status text.
+    Stats,
+    FrameStats,
+    Summary,
+    SummaryLastChange,
+    SummaryCursor,
+    CellAlive,
+    CellDying,
+    CellDead,
+    CheckpointLabel,
+    Census,
+    CensusOther,
+    CensusNothing,
+    SearchProgress,
+    SearchRare,
+    SearchRareObject,
+    NetworkConnecting,
+    NetworkConnected,
+    NetworkDisconnected,
+    LifespanRunning,
+    LifespanSettled,
+    LifespanGaveUp,
+    LifespanPopulation,
+    LifespanExtent,
+
+    // Button labels.
+    Play,
+    Pause,
+    RecordGif,
+    StopRecording,
+    RecordVideo,
+    StopVideo,
+    RecordEdits,
+    StopRecordingEdits,
+    SearchSoups,
+    StopSearching,
+    RunToCompletion,
+    StopRunning,
+    Connect,
+    Disconnect,
+    MeasureLifespan,
+    StopMeasuring,
+
+    // Console messages.
+    Starting,
+    ReachedStillLife,
+    ReachedOscillator,
+    SettledIntoStillLife,
+    SettledIntoOscillator,
+    StillChanging,
+    CouldNotPlayGeneration,
+    CouldNotRecordFrame,
+    CouldNotReadPattern,
+    CouldNotReadMacrocell,
+    CouldNotWriteMacrocell,
+    InvalidDensity,
+    InvalidUniverseSize,
+    InvalidCellSize,
+    UnknownRenderMode,
+    UnknownRenderer,
+    RepaintsPerFrame,
+    NoThreads,
+    SaveWithoutName,
+    CouldNotSave,
+    NoSavedGame,
+    CouldNotLoad,
+    UnknownBrush,
+    InvalidBrushSize,
+    UnknownSymmetry,
+    UnknownTool,
+    CouldNotStartWorker,
+    InvalidJumpSize,
+    JumpTooLong,
+    RecordedFrames,
+    CouldNotRecord,
+    InvalidGifSettings,
+    CouldNotStopRecording,
+    InvalidVideoSettings,
+    CouldNotSaveVideo,
+    InvalidPngScale,
+    CouldNotExport,
+    InvalidAnimationSettings,
+    InvalidSoundSettings,
+    CouldNotPlaySound,
+    UnknownTopology,
+    CouldNotSetRule,
+    UnknownNeighborhood,
+    InvalidSpeed,
+    InvalidSeed,
+    InvalidCheckpointInterval,
+    InvalidGeneration,
+    CannotSeek,
+    RecordedEdits,
+    CannotReplay,
+    InvalidCensusInterval,
+    InvalidSearchSettings,
+    CouldNotStartSearch,
+    CouldNotMeasure,
+    InvalidSoupNumber,
+    NothingToTrack,
+    InvalidNoise,
+    CouldNotReadRuleTable,
+    InvalidElementaryRule,
+    InvalidTurmite,
+    UnknownMaterial,
+    CouldNotConnect,
+    CouldNotMerge,
+    CouldNotStartPeerSession,
+    InvalidPeerSignal,
+    EventCallbackFailed,
+    RuleHookFailed,
+}
+
+thread_local! {
+    static LOCALE: Cell<Locale> = const { Cell::new(Locale::English) };
+}
+
+/// The language messages are shown in.
+pub fn locale() -> Locale {
+    LOCALE.with(Cell::get)
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen(js_name = setLocale))]
+pub fn set_locale(locale: Locale) {
+    LOCALE.with(|current| current.set(locale));
+}
+
+impl Locale {
+    /// Every locale, in the order the page lists them.
+    pub const ALL: [Locale; 3] = [Locale::English, Locale::French, Locale::Spanish];
+
+    /// The language's BCP 47 code.
+    pub fn code(self) -> &'static str {
+        match self {
+            Locale::English => "en",
+            Locale::French => "fr",
+            Locale::Spanish => "es",
+        }
+    }
+
+    /// The locale for a BCP 47 language tag such as `"fr"` or `"es-MX"`,
+    /// going by the language alone.
+    pub fn from_code(code: &str) -> Option<Locale> {
+        let language = code.split(['-', '_']).next().unwrap_or_default();
+        Locale::ALL
+            .iter()
+            .copied()
+            .find(|locale| locale.code().eq_ignore_ascii_case(language))
+    }
+
+    /// The first of the user's preferred languages that has a translation,
+    /// or English if none do.
+    pub fn negotiate<S: AsRef<str>>(languages: &[S]) -> Locale {
+        languages
+            .iter()
+            .find_map(|language| Locale::from_code(language.as_ref()))
+            .unwrap_or(Locale::English)
+    }
+
+    /// The text of `message`, with its `{0}`, `{1}`... still in it.
+    pub fn text(self, message: Message) -> &'static str {
+        match self {
+            Locale::English => english(message),
+            Locale::French => french(message),
+            Locale::Spanish => spanish(message),
+        }
+    }
+
+    /// The text of `message` with each `{n}` replaced by `args[n]`.
+    pub fn format(self, message: Message, args: &[&dyn Display]) -> String {
+        let mut text = self.text(message);
+        let mut formatted = String::with_capacity(text.len());
+        while let Some(start) = text.find('{') {
+            formatted.push_str(&text[..start]);
+            let rest = &text[start + 1..];
+            let arg = rest
+                .find('}')
+                .and_then(|end| Some((rest[..end].parse::<usize>().ok()?, end)))
+                .and_then(|(index, end)| Some((args.get(index)?, end)));
+            match arg {
+                Some((arg, end)) => {
+                    formatted.push_str(&arg.to_string());
+                    text = &rest[end + 1..];
+                }
+                None => {
+                    formatted.push('{');
+                    text = rest;
+                }
+            }
+        }
+        formatted.push_str(text);
+        formatted
+    }
+}
+
+fn english(message: Message) -> &'static str {
+    use Message::*;
+    match message {
+        Stats => "Generation {0}, population {1} (+{2} -{3})",
+        FrameStats => "{0} fps, {1} generations/s, step {2}, draw {3}",
+        Summary => "Generation {0}, {1} cells alive",
+        SummaryLastChange => ", last change at row {0} col {1}",
+        SummaryCursor => ". Cursor at row {0} col {1}, {2}",
+        CellAlive => "alive",
+        CellDying => "dying",
+        CellDead => "dead",
+        CheckpointLabel => "Generation {0}",
+        Census => "Census at generation {0}: {1}",
+        CensusOther => "{0} unrecognized",
+        CensusNothing => "nothing",
+        SearchProgress => "{0} soups searched: {1}",
+        SearchRare => ". Rare: {0}",
+        SearchRareObject => "{0} (soup {1})",
+        NetworkConnecting => "Connecting…",
+        NetworkConnected => "Connected",
+        NetworkDisconnected => "Not connected",
+        LifespanRunning => "Still changing after {0} generations",
+        LifespanSettled => "Settled after {0} generations into a period {1} cycle",
+        LifespanGaveUp => "Gave up after {0} generations without settling",
+        LifespanPopulation => "Population {0} at first, {1} at most, {2} at the end",
+        LifespanExtent => "Bounding box {0} at first, {1} at largest, {2} at the end",
+
+        Play => "Play",
+        Pause => "Pause",
+        RecordGif => "Record GIF",
+        StopRecording => "Stop recording",
+        RecordVideo => "Record video",
+        StopVideo => "Stop video",
+        RecordEdits => "Record edits",
+        StopRecordingEdits => "Stop recording edits",
+        SearchSoups => "Search soups",
+        StopSearching => "Stop searching",
+        RunToCompletion => "Run to completion",
+        StopRunning => "Stop running",
+        Connect => "Connect",
+        Disconnect => "Disconnect",
+        MeasureLifespan => "Measure lifespan",
+        StopMeasuring => "Stop measuring",
+
+        Starting => "Starting our Game of Life!",
+        ReachedStillLife => "Reached a still life, pausing",
+        ReachedOscillator => "Reached a period {0} oscillator, pausing",
+        SettledIntoStillLife => "Settled into a still life after {0} generations",
+        SettledIntoOscillator => "Settled into a period {1} oscillator after {0} generations",
+        StillChanging => "Still changing after {0} generations, stopping",
+        CouldNotPlayGeneration => "Could not play a generation: {0}",
+        CouldNotRecordFrame => "Could not record a frame: {0}",
+        CouldNotReadPattern => "Could not read pattern: {0}",
+        CouldNotReadMacrocell => "Could not read macrocell: {0}",
+        CouldNotWriteMacrocell => "Could not write macrocell: {0}",
+        InvalidDensity => "Ignoring invalid density",
+        InvalidUniverseSize => "Ignoring invalid universe size",
+        InvalidCellSize => "Ignoring invalid cell size",
+        UnknownRenderMode => "Unknown render mode {0}",
+        UnknownRenderer => "Unknown renderer {0}",
+        RepaintsPerFrame => "Full repaints per frame: {0}",
+        NoThreads => "Threads aren't available, so ticks stay on one thread",
+        SaveWithoutName => "Ignoring save without a name",
+        CouldNotSave => "Couldn't save: {0}",
+        NoSavedGame => "No saved game named {0}",
+        CouldNotLoad => "Couldn't load {0}: {1}",
+        UnknownBrush => "Ignoring unknown brush {0}",
+        InvalidBrushSize => "Ignoring invalid brush size",
+        UnknownSymmetry => "Ignoring unknown symmetry {0}",
+        UnknownTool => "Ignoring unknown tool {0}",
+        CouldNotStartWorker => "Couldn't start the worker: {0}",
+        InvalidJumpSize => "Ignoring invalid jump size",
+        JumpTooLong => "Only HashLife jumps more than 2^{0} generations at once",
+        RecordedFrames => "Recorded {0} frames",
+        CouldNotRecord => "Could not record: {0}",
+        InvalidGifSettings => "Ignoring invalid GIF settings",
+        CouldNotStopRecording => "Could not stop recording: {0}",
+        InvalidVideoSettings => "Ignoring invalid video settings",
+        CouldNotSaveVideo => "Could not save the video: {0}",
+        InvalidPngScale => "Ignoring invalid PNG scale",
+        CouldNotExport => "Could not export: {0}",
+        InvalidAnimationSettings => "Ignoring invalid animation settings",
+        InvalidSoundSettings => "Ignoring invalid sound settings",
+        CouldNotPlaySound => "Could not play sound: {0}",
+        UnknownTopology => "Unknown topology {0}",
+        CouldNotSetRule => "Could not set rule: {0}",
+        UnknownNeighborhood => "Unknown neighborhood {0}",
+        InvalidSpeed => "Ignoring invalid speed {0}",
+        InvalidSeed => "Ignoring invalid seed",
+        InvalidCheckpointInterval => "Ignoring invalid checkpoint interval",
+        InvalidGeneration => "Ignoring invalid generation",
+        CannotSeek => "No checkpoint to go back to generation {0} from",
+        RecordedEdits => "Recorded {0} edits over {1} generations",
+        CannotReplay => "Could not play the replay: {0}",
+        InvalidCensusInterval => "Ignoring invalid census interval",
+        InvalidSearchSettings => "Ignoring invalid soup search settings",
+        CouldNotStartSearch => "Could not start the soup search: {0}",
+        CouldNotMeasure => "Could not start measuring the lifespan: {0}",
+        InvalidSoupNumber => "Ignoring invalid soup number",
+        NothingToTrack => "Select some live cells to track first",
+        InvalidNoise => "Ignoring invalid noise",
+        CouldNotReadRuleTable => "Could not read rule table: {0}",
+        InvalidElementaryRule => "Ignoring invalid elementary rule; use a number from 0 to 255",
+        InvalidTurmite => "Ignoring invalid turmite: {0}",
+        UnknownMaterial => "Ignoring unknown material {0}",
+        CouldNotConnect => "Could not connect to the relay server: {0}",
+        CouldNotMerge => "Could not merge a shared edit: {0}",
+        CouldNotStartPeerSession => "Could not start the peer-to-peer session: {0}",
+        InvalidPeerSignal => "Paste the offer or answer from the other page first",
+        EventCallbackFailed => "An event callback threw: {0}",
+        RuleHookFailed => "The rule hook threw and was removed: {0}",
+    }
+}
+
+fn french(message: Message) -> &'static str {
+    use Message::*;
+    match message {
+        Stats => "Génération {0}, population {1} (+{2} -{3})",
+        FrameStats => "{0} i/s, {1} générations/s, calcul {2}, dessin {3}",
+        Summary => "Génération {0}, {1} cellules vivantes",
+        SummaryLastChange => ", dernier changement ligne {0} colonne {1}",
+        SummaryCursor => ". Curseur ligne {0} colonne {1}, {2}",
+        CellAlive => "vivante",
+        CellDying => "mourante",
+        CellDead => "morte",
+        CheckpointLabel => "Génération {0}",
+        Census => "Recensement à la génération {0} : {1}",
+        CensusOther => "{0} non reconnus",
+        CensusNothing => "rien",
+        SearchProgress => "{0} soupes explorées : {1}",
+        SearchRare => ". Rares : {0}",
+        SearchRareObject => "{0} (soupe {1})",
+        NetworkConnecting => "Connexion…",
+        NetworkConnected => "Connecté",
+        NetworkDisconnected => "Non connecté",
+        LifespanRunning => "Toujours en évolution après {0} générations",
+        LifespanSettled => "Stabilisé après {0} générations en un cycle de période {1}",
+        LifespanGaveUp => "Abandon après {0} générations sans stabilisation",
+        LifespanPopulation => "Population de {0} au départ, {1} au plus, {2} à la fin",
+        LifespanExtent => "Cadre de {0} au départ, {1} au plus grand, {2} à la fin",
+
+        Play => "Lecture",
+        Pause => "Pause",
+        RecordGif => "Enregistrer un GIF",
+        StopRecording => "Arrêter l'enregistrement",
+        RecordVideo => "Enregistrer une vidéo",
+        StopVideo => "Arrêter la vidéo",
+        RecordEdits => "Enregistrer les modifications",
+        StopRecordingEdits => "Arrêter d'enregistrer les modifications",
+        SearchSoups => "Explorer des soupes",
+        StopSearching => "Arrêter l'exploration",
+        RunToCompletion => "Aller jusqu'au bout",
+        StopRunning => "Arrêter",
+        Connect => "Se connecter",
+        Disconnect => "Se déconnecter",
+        MeasureLifespan => "Mesurer la durée de vie",
+        StopMeasuring => "Arrêter la mesure",
+
+        Starting => "Lancement de notre jeu de la vie !",
+        ReachedStillLife => "Structure stable atteinte, pause",
+        ReachedOscillator => "Oscillateur de période {0} atteint, pause",
+        SettledIntoStillLife => "Structure stable atteinte après {0} générations",
+        SettledIntoOscillator => "Oscillateur de période {1} atteint après {0} générations",
+        StillChanging => "Toujours en évolution après {0} générations, arrêt",
+        CouldNotPlayGeneration => "Impossible de jouer une génération : {0}",
+        CouldNotRecordFrame => "Impossible d'enregistrer une image : {0}",
+        CouldNotReadPattern => "Impossible de lire le motif : {0}",
+        CouldNotReadMacrocell => "Impossible de lire le fichier macrocell : {0}",
+        CouldNotWriteMacrocell => "Impossible d'écrire le fichier macrocell : {0}",
+        InvalidDensity => "Densité invalide ignorée",
+        InvalidUniverseSize => "Taille d'univers invalide ignorée",
+        InvalidCellSize => "Taille de cellule invalide ignorée",
+        UnknownRenderMode => "Mode de coloration inconnu : {0}",
+        UnknownRenderer => "Moteur de rendu inconnu : {0}",
+        RepaintsPerFrame => "Rendus complets par image : {0}",
+        NoThreads => "Les threads ne sont pas disponibles, le calcul reste sur un seul thread",
+        SaveWithoutName => "Sauvegarde sans nom ignorée",
+        CouldNotSave => "Impossible de sauvegarder : {0}",
+        NoSavedGame => "Aucune partie sauvegardée nommée {0}",
+        CouldNotLoad => "Impossible de charger {0} : {1}",
+        UnknownBrush => "Pinceau inconnu ignoré : {0}",
+        InvalidBrushSize => "Taille de pinceau invalide ignorée",
+        UnknownSymmetry => "Symétrie inconnue ignorée : {0}",
+        UnknownTool => "Outil inconnu ignoré : {0}",
+        CouldNotStartWorker => "Impossible de démarrer le worker : {0}",
+        InvalidJumpSize => "Saut invalide ignoré",
+        JumpTooLong => "Seul HashLife saute plus de 2^{0} générations d'un coup",
+        RecordedFrames => "{0} images enregistrées",
+        CouldNotRecord => "Impossible d'enregistrer : {0}",
+        InvalidGifSettings => "Réglages GIF invalides ignorés",
+        CouldNotStopRecording => "Impossible d'arrêter l'enregistrement : {0}",
+        InvalidVideoSettings => "Réglages vidéo invalides ignorés",
+        CouldNotSaveVideo => "Impossible de sauvegarder la vidéo : {0}",
+        InvalidPngScale => "Échelle PNG invalide ignorée",
+        CouldNotExport => "Impossible d'exporter : {0}",
+        InvalidAnimationSettings => "Réglages d'animation invalides ignorés",
+        InvalidSoundSettings => "Réglages du son invalides ignorés",
+        CouldNotPlaySound => "Impossible de jouer le son : {0}",
+        UnknownTopology => "Topologie inconnue : {0}",
+        CouldNotSetRule => "Impossible d'appliquer la règle : {0}",
+        UnknownNeighborhood => "Voisinage inconnu : {0}",
+        InvalidSpeed => "Vitesse invalide ignorée : {0}",
+        InvalidSeed => "Graine invalide ignorée",
+        InvalidCheckpointInterval => "Intervalle de points de reprise invalide ignoré",
+        InvalidGeneration => "Génération invalide ignorée",
+        CannotSeek => "Aucun point de reprise pour revenir à la génération {0}",
+        RecordedEdits => "{0} modifications enregistrées sur {1} générations",
+        CannotReplay => "Impossible de lire la rediffusion : {0}",
+        InvalidCensusInterval => "Intervalle de recensement invalide ignoré",
+        InvalidSearchSettings => "Réglages d'exploration de soupes invalides ignorés",
+        CouldNotStartSearch => "Impossible de lancer l'exploration de soupes : {0}",
+        CouldNotMeasure => "Impossible de mesurer la durée de vie : {0}",
+        InvalidSoupNumber => "Numéro de soupe invalide ignoré",
+        NothingToTrack => "Sélectionnez d'abord des cellules vivantes à suivre",
+        InvalidNoise => "Bruit invalide ignoré",
+        CouldNotReadRuleTable => "Impossible de lire la table de règles : {0}",
+        InvalidElementaryRule => {
+            "Règle élémentaire invalide ignorée ; utilisez un nombre de 0 à 255"
+        }
+        InvalidTurmite => "Turmite invalide ignoré : {0}",
+        UnknownMaterial => "Matériau inconnu ignoré : {0}",
+        CouldNotConnect => "Impossible de se connecter au serveur relais : {0}",
+        CouldNotMerge => "Impossible de fusionner une modification partagée : {0}",
+        CouldNotStartPeerSession => "Impossible de lancer la session pair à pair : {0}",
+        InvalidPeerSignal => "Collez d'abord l'offre ou la réponse de l'autre page",
+        EventCallbackFailed => "Un rappel d'événement a levé une exception : {0}",
+        RuleHookFailed => "Le crochet de règle a levé une exception et a été retiré : {0}",
+    }
+}
+
+fn spanish(message: Message) -> &'static str {
+    use Message::*;
+    match message {
+        Stats => "Generación {0}, población {1} (+{2} -{3})",
+        FrameStats => "{0} fps, {1} generaciones/s, cálculo {2}, dibujo {3}",
+        Summary => "Generación {0}, {1} células vivas",
+        SummaryLastChange => ", último cambio en la fila {0} columna {1}",
+        SummaryCursor => ". Cursor en la fila {0} columna {1}, {2}",
+        CellAlive => "viva",
+        CellDying => "muriendo",
+        CellDead => "muerta",
+        CheckpointLabel => "Generación {0}",
+        Census => "Censo en la generación {0}: {1}",
+        CensusOther => "{0} sin reconocer",
+        CensusNothing => "nada",
+        SearchProgress => "{0} sopas exploradas: {1}",
+        SearchRare => ". Raros: {0}",
+        SearchRareObject => "{0} (sopa {1})",
+        NetworkConnecting => "Conectando…",
+        NetworkConnected => "Conectado",
+        NetworkDisconnected => "Sin conexión",
+        LifespanRunning => "Sigue cambiando tras {0} generaciones",
+        LifespanSettled => "Se estabilizó tras {0} generaciones en un ciclo de periodo {1}",
+        LifespanGaveUp => "Se abandonó tras {0} generaciones sin estabilizarse",
+        LifespanPopulation => "Población de {0} al principio, {1} como máximo, {2} al final",
+        LifespanExtent => "Recuadro de {0} al principio, {1} como máximo, {2} al final",
+
+        Play => "Reproducir",
+        Pause => "Pausa",
+        RecordGif => "Grabar GIF",
+        StopRecording => "Detener la grabación",
+        RecordVideo => "Grabar vídeo",
+        StopVideo => "Detener el vídeo",
+        RecordEdits => "Grabar cambios",
+        StopRecordingEdits => "Dejar de grabar cambios",
+        SearchSoups => "Explorar sopas",
+        StopSearching => "Dejar de explorar",
+        RunToCompletion => "Ejecutar hasta el final",
+        StopRunning => "Detener",
+        Connect => "Conectar",
+        Disconnect => "Desconectar",
+        MeasureLifespan => "Medir la vida",
+        StopMeasuring => "Dejar de medir",
+
+        Starting => "¡Empieza nuestro juego de la vida!",
+        ReachedStillLife => "Se alcanzó una vida estática, en pausa",
+        ReachedOscillator => "Se alcanzó un oscilador de periodo {0}, en pausa",
+        SettledIntoStillLife => "Se alcanzó una vida estática tras {0} generaciones",
+        SettledIntoOscillator => "Se alcanzó un oscilador de periodo {1} tras {0} generaciones",
+        StillChanging => "Sigue cambiando tras {0} generaciones, se detiene",
+        CouldNotPlayGeneration => "No se pudo reproducir una generación: {0}",
+        CouldNotRecordFrame => "No se pudo grabar un fotograma: {0}",
+        CouldNotReadPattern => "No se pudo leer el patrón: {0}",
+        CouldNotReadMacrocell => "No se pudo leer el archivo macrocell: {0}",
+        CouldNotWriteMacrocell => "No se pudo escribir el archivo macrocell: {0}",
+        InvalidDensity => "Se ignora una densidad no válida",
+        InvalidUniverseSize => "Se ignora un tamaño de universo no válido",
+        InvalidCellSize => "Se ignora un tamaño de celda no válido",
+        UnknownRenderMode => "Modo de coloreado desconocido: {0}",
+        UnknownRenderer => "Renderizador desconocido: {0}",
+        RepaintsPerFrame => "Repintados completos por fotograma: {0}",
+        NoThreads => "No hay hilos disponibles, así que cada paso usa un solo hilo",
+        SaveWithoutName => "Se ignora un guardado sin nombre",
+        CouldNotSave => "No se pudo guardar: {0}",
+        NoSavedGame => "No hay ninguna partida guardada llamada {0}",
+        CouldNotLoad => "No se pudo cargar {0}: {1}",
+        UnknownBrush => "Se ignora un pincel desconocido: {0}",
+        InvalidBrushSize => "Se ignora un tamaño de pincel no válido",
+        UnknownSymmetry => "Se ignora una simetría desconocida: {0}",
+        UnknownTool => "Se ignora una herramienta desconocida: {0}",
+        CouldNotStartWorker => "No se pudo iniciar el worker: {0}",
+        InvalidJumpSize => "Se ignora un salto no válido",
+        JumpTooLong => "Solo HashLife salta más de 2^{0} generaciones de una vez",
+        RecordedFrames => "Se grabaron {0} fotogramas",
+        CouldNotRecord => "No se pudo grabar: {0}",
+        InvalidGifSettings => "Se ignoran ajustes de GIF no válidos",
+        CouldNotStopRecording => "No se pudo detener la grabación: {0}",
+        InvalidVideoSettings => "Se ignoran ajustes de vídeo no válidos",
+        CouldNotSaveVideo => "No se pudo guardar el vídeo: {0}",
+        InvalidPngScale => "Se ignora una escala PNG no válida",
+        CouldNotExport => "No se pudo exportar: {0}",
+        InvalidAnimationSettings => "Se ignoran ajustes de animación no válidos",
+        InvalidSoundSettings => "Se ignoran ajustes de sonido no válidos",
+        CouldNotPlaySound => "No se pudo reproducir el sonido: {0}",
+        UnknownTopology => "Topología desconocida: {0}",
+        CouldNotSetRule => "No se pudo aplicar la regla: {0}",
+        UnknownNeighborhood => "Vecindad desconocida: {0}",
+        InvalidSpeed => "Se ignora una velocidad no válida: {0}",
+        InvalidSeed => "Se ignora una semilla no válida",
+        InvalidCheckpointInterval => "Se ignora un intervalo de puntos de control no válido",
+        InvalidGeneration => "Se ignora una generación no válida",
+        CannotSeek => "No hay ningún punto de control para volver a la generación {0}",
+        RecordedEdits => "{0} cambios grabados en {1} generaciones",
+        CannotReplay => "No se pudo reproducir la repetición: {0}",
+        InvalidCensusInterval => "Se ignora un intervalo de censo no válido",
+        InvalidSearchSettings => "Se ignoran unos ajustes de búsqueda de sopas no válidos",
+        CouldNotStartSearch => "No se pudo iniciar la búsqueda de sopas: {0}",
+        CouldNotMeasure => "No se pudo medir la vida: {0}",
+        InvalidSoupNumber => "Se ignora un número de sopa no válido",
+        NothingToTrack => "Selecciona primero algunas células vivas para seguirlas",
+        InvalidNoise => "Se ignora un ruido no válido",
+        CouldNotReadRuleTable => "No se pudo leer la tabla de reglas: {0}",
+        InvalidElementaryRule => {
+            "Se ignora una regla elemental no válida; use un número de 0 a 255"
+        }
+        InvalidTurmite => "Se ignora un turmite no válido: {0}",
+        UnknownMaterial => "Se ignora un material desconocido: {0}",
+        CouldNotConnect => "No se pudo conectar al servidor de retransmisión: {0}",
+        CouldNotMerge => "No se pudo fusionar una edición compartida: {0}",
+        CouldNotStartPeerSession => "No se pudo iniciar la sesión entre pares: {0}",
+        InvalidPeerSignal => "Pega primero la oferta o la respuesta de la otra página",
+        EventCallbackFailed => "Una función de evento lanzó una excepción: {0}",
+        RuleHookFailed => "La función de regla lanzó una excepción y se quitó: {0}",
+    }
+}
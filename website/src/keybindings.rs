@@ -0,0 +1,143 @@
+//! Keyboard shortcuts.
+
+use std::collections::HashMap;
+
+use crate::patterns::Flip;
+
+/// Something the user can trigger from the keyboard.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    PlayPause,
+    Step,
+    Clear,
+    Random,
+    /// Move the view by this many rows and columns.
+    Pan(i32, i32),
+    ZoomIn,
+    ZoomOut,
+    /// Zoom and scroll to frame every live cell.
+    Fit,
+    /// Stop placing a pattern, or drop the selection.
+    Cancel,
+    /// Copy the selection to the clipboard.
+    Copy,
+    /// Copy the selection to the clipboard and kill its cells.
+    Cut,
+    /// Start placing the pattern on the clipboard.
+    Paste,
+    /// Kill the selected cells.
+    Delete,
+    /// Turn the pattern being placed a right angle clockwise.
+    Rotate,
+    /// Mirror the pattern being placed.
+    Flip(Flip),
+    Undo,
+    Redo,
+    /// Move the keyboard cursor by this many rows and columns.
+    MoveCursor(i32, i32),
+    /// Toggle the cell under the keyboard cursor.
+    ToggleCursorCell,
+}
+
+/// Maps `KeyboardEvent.key` values to actions. Keys pressed with ctrl (or
+/// cmd) held are written with a `Ctrl+` prefix, as in `Ctrl+z`.
+#[derive(Clone, Debug)]
+pub struct Keybindings {
+    bindings: HashMap<String, Action>,
+    /// Bindings that take over while a pattern is being placed.
+    placing: HashMap<String, Action>,
+    /// Bindings that take over while the keyboard cursor is shown.
+    cursor: HashMap<String, Action>,
+}
+
+impl Default for Keybindings {
+    fn default() -> Keybindings {
+        let mut keybindings = Keybindings::empty();
+        keybindings.bind(" ", Action::PlayPause);
+        keybindings.bind("s", Action::Step);
+        keybindings.bind("c", Action::Clear);
+        keybindings.bind("r", Action::Random);
+        keybindings.bind("ArrowUp", Action::Pan(-1, 0));
+        keybindings.bind("ArrowDown", Action::Pan(1, 0));
+        keybindings.bind("ArrowLeft", Action::Pan(0, -1));
+        keybindings.bind("ArrowRight", Action::Pan(0, 1));
+        keybindings.bind("+", Action::ZoomIn);
+        keybindings.bind("=", Action::ZoomIn);
+        keybindings.bind("-", Action::ZoomOut);
+        keybindings.bind("f", Action::Fit);
+        keybindings.bind("Escape", Action::Cancel);
+        keybindings.bind("Ctrl+c", Action::Copy);
+        keybindings.bind("Ctrl+x", Action::Cut);
+        keybindings.bind("Ctrl+v", Action::Paste);
+        keybindings.bind("Delete", Action::Delete);
+        keybindings.bind("Backspace", Action::Delete);
+        keybindings.bind("Ctrl+z", Action::Undo);
+        keybindings.bind("Ctrl+y", Action::Redo);
+        keybindings.bind("Ctrl+Z", Action::Redo);
+        keybindings.bind_placing("r", Action::Rotate);
+        keybindings.bind_placing("f", Action::Flip(Flip::Horizontal));
+        keybindings.bind_placing("F", Action::Flip(Flip::Vertical));
+        keybindings.bind_cursor("ArrowUp", Action::MoveCursor(-1, 0));
+        keybindings.bind_cursor("ArrowDown", Action::MoveCursor(1, 0));
+        keybindings.bind_cursor("ArrowLeft", Action::MoveCursor(0, -1));
+        keybindings.bind_cursor("ArrowRight", Action::MoveCursor(0, 1));
+        keybindings.bind_cursor("Enter", Action::ToggleCursorCell);
+        keybindings
+    }
+}
+
+impl Keybindings {
+    /// Keybindings with nothing bound.
+    pub fn empty() -> Keybindings {
+        Keybindings {
+            bindings: HashMap::new(),
+            placing: HashMap::new(),
+            cursor: HashMap::new(),
+        }
+    }
+
+    /// Bind `key` to `action`, replacing whatever it was bound to before.
+    pub fn bind(&mut self, key: &str, action: Action) {
+        self.bindings.insert(key.to_string(), action);
+    }
+
+    /// Bind `key` to `action` while a pattern is being placed only.
+    pub fn bind_placing(&mut self, key: &str, action: Action) {
+        self.placing.insert(key.to_string(), action);
+    }
+
+    /// Bind `key` to `action` while the keyboard cursor is shown only.
+    pub fn bind_cursor(&mut self, key: &str, action: Action) {
+        self.cursor.insert(key.to_string(), action);
+    }
+
+    pub fn unbind(&mut self, key: &str) {
+        self.bindings.remove(key);
+        self.placing.remove(key);
+        self.cursor.remove(key);
+    }
+
+    /// The action bound to `key`. Letters match regardless of case.
+    pub fn action(&self, key: &str) -> Option<Action> {
+        lookup(&self.bindings, key)
+    }
+
+    /// The action bound to `key` while a pattern is being placed, falling
+    /// back to the ordinary bindings.
+    pub fn placing_action(&self, key: &str) -> Option<Action> {
+        lookup(&self.placing, key).or_else(|| self.action(key))
+    }
+
+    /// The action bound to `key` while the keyboard cursor is shown, falling
+    /// back to the ordinary bindings.
+    pub fn cursor_action(&self, key: &str) -> Option<Action> {
+        lookup(&self.cursor, key).or_else(|| self.action(key))
+    }
+}
+
+fn lookup(bindings: &HashMap<String, Action>, key: &str) -> Option<Action> {
+    bindings
+        .get(key)
+        .or_else(|| bindings.get(&key.to_lowercase()))
+        .copied()
+}
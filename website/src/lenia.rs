@@ -0,0 +1,180 @@
+//! Lenia, a continuous relative of the Game of Life. Cells hold a level
+//! between 0 and 1 instead of being alive or dead, and grow or shrink a
+//! little every step depending on a weighted average over a wide ring around
+//! them.
+//!
+//! This is the basic single-kernel Lenia: the ring's weights follow a smooth
+//! bump, and the growth is a Gaussian centred on `mu` with width `sigma`.
+
+use crate::engine::{SimulationEngine, Topology};
+use crate::{Cell, Position};
+
+/// The radius, growth centre, growth width and time step of the "Orbium"
+/// gliders.
+pub const DEFAULT_RADIUS: u32 = 13;
+pub const DEFAULT_MU: f32 = 0.15;
+pub const DEFAULT_SIGMA: f32 = 0.015;
+pub const DEFAULT_DT: f32 = 0.1;
+
+/// Levels at or above this count as alive when the board is seen as an
+/// ordinary one.
+const ALIVE_LEVEL: f32 = 0.5;
+
+#[derive(Clone, Debug)]
+pub struct Lenia {
+    width: u32,
+    height: u32,
+    topology: Topology,
+    /// The level of every cell in row-major order.
+    levels: Vec<f32>,
+    radius: u32,
+    mu: f32,
+    sigma: f32,
+    dt: f32,
+    /// The `(row, column)` offset and weight of every cell in the ring. The
+    /// weights add up to 1.
+    kernel: Vec<(i64, i64, f32)>,
+}
+
+impl Lenia {
+    /// An empty board with the default parameters.
+    pub fn new(width: u32, height: u32) -> Lenia {
+        Lenia {
+            width,
+            height,
+            topology: Topology::Torus,
+            levels: vec![0.0; (width * height) as usize],
+            radius: DEFAULT_RADIUS,
+            mu: DEFAULT_MU,
+            sigma: DEFAULT_SIGMA,
+            dt: DEFAULT_DT,
+            kernel: kernel(DEFAULT_RADIUS),
+        }
+    }
+
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+    }
+
+    /// Change the radius of the ring, the centre and width of the growth
+    /// function, and how far each step moves towards it.
+    pub fn set_parameters(&mut self, radius: u32, mu: f32, sigma: f32, dt: f32) {
+        self.radius = radius.max(1);
+        self.mu = mu;
+        self.sigma = sigma;
+        self.dt = dt;
+        self.kernel = kernel(self.radius);
+    }
+
+    pub fn level(&self, row: u32, column: u32) -> f32 {
+        self.levels[self.get_index(row, column)]
+    }
+
+    pub fn set_level(&mut self, row: u32, column: u32, level: f32) {
+        let idx = self.get_index(row, column);
+        self.levels[idx] = level.clamp(0.0, 1.0);
+    }
+
+    fn get_index(&self, row: u32, column: u32) -> usize {
+        (row * self.width + column) as usize
+    }
+
+    /// How much a cell whose ring averages `average` grows, from -1 to 1.
+    fn growth(&self, average: f32) -> f32 {
+        let distance = (average - self.mu) / self.sigma;
+        2.0 * (-distance * distance / 2.0).exp() - 1.0
+    }
+}
+
+/// The weights of a ring of `radius`: a smooth bump that is 0 at the centre
+/// and the edge and 1 halfway between, scaled to add up to 1.
+fn kernel(radius: u32) -> Vec<(i64, i64, f32)> {
+    let reach = radius as i64;
+    let mut kernel = Vec::new();
+    for row in -reach..=reach {
+        for column in -reach..=reach {
+            let r = ((row * row + column * column) as f32).sqrt() / radius as f32;
+            if r > 0.0 && r < 1.0 {
+                kernel.push((row, column, (4.0 - 1.0 / (r * (1.0 - r))).exp()));
+            }
+        }
+    }
+    let total: f32 = kernel.iter().map(|&(_, _, weight)| weight).sum();
+    for (_, _, weight) in &mut kernel {
+        *weight /= total;
+    }
+    kernel
+}
+
+impl SimulationEngine for Lenia {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn cell(&self, position: Position) -> Option<Cell> {
+        if !self.contains(position) {
+            return None;
+        }
+        Some(Cell::from(
+            self.level(position.row, position.column) >= ALIVE_LEVEL,
+        ))
+    }
+
+    /// Only cells that change between alive and dead are touched, so copying
+    /// in an edited board keeps the levels everywhere else.
+    fn set_cell(&mut self, position: Position, cell: Cell) -> Option<Cell> {
+        let was = self.cell(position)?;
+        let alive = cell == Cell::Alive;
+        if (was == Cell::Alive) != alive {
+            let level = if alive { 1.0 } else { 0.0 };
+            self.set_level(position.row, position.column, level);
+        }
+        Some(was)
+    }
+
+    fn tick(&mut self) {
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+
+        // Pad the board by the radius on every side, so that the ring of
+        // every cell can be read without wrapping coordinates.
+        let reach = self.radius as i64;
+        let padded_width = self.width as i64 + 2 * reach;
+        let padded_height = self.height as i64 + 2 * reach;
+        let columns: Vec<Option<u32>> = (0..padded_width)
+            .map(|x| self.topology.wrap(x - reach, self.width))
+            .collect();
+        let mut padded = Vec::with_capacity((padded_width * padded_height) as usize);
+        for y in 0..padded_height {
+            let row = self.topology.wrap(y - reach, self.height);
+            padded.extend(columns.iter().map(|&column| match (row, column) {
+                (Some(row), Some(column)) => self.level(row, column),
+                _ => 0.0,
+            }));
+        }
+
+        let offsets: Vec<(i64, f32)> = self
+            .kernel
+            .iter()
+            .map(|&(row, column, weight)| (row * padded_width + column, weight))
+            .collect();
+        let mut next = Vec::with_capacity(self.levels.len());
+        for row in 0..self.height as i64 {
+            for column in 0..self.width as i64 {
+                let centre = (row + reach) * padded_width + column + reach;
+                let average: f32 = offsets
+                    .iter()
+                    .map(|&(offset, weight)| padded[(centre + offset) as usize] * weight)
+                    .sum();
+                let level = padded[centre as usize] + self.dt * self.growth(average);
+                next.push(level.clamp(0.0, 1.0));
+            }
+        }
+        self.levels = next;
+    }
+}
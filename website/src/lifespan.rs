@@ -0,0 +1,176 @@
+//! Measuring how long a pattern lives before it settles into still lifes and
+//! oscillators, for hunting methuselahs: small patterns that take a long
+//! time to.
+//!
+//! The pattern runs on its own copy of the board, with the board's size,
+//! rule and topology, so on a torus whatever it throws off comes back round
+//! to hit it just as it would on the page.
+
+use std::fmt;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use crate::engine::Universe;
+use crate::i18n::{self, Message};
+
+/// How many generations the page measures a pattern for before giving up
+/// on it settling.
+pub const MAX_LIFESPAN: u32 = 100_000;
+
+/// The width and height of a bounding box, as `36x9`.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Extent {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Extent {
+    /// The smallest rectangle holding every live cell of `universe`, which
+    /// is empty for an empty board.
+    pub fn of(universe: &Universe) -> Extent {
+        match universe.bounding_box() {
+            Some((rows, columns)) => Extent {
+                width: columns.end - columns.start,
+                height: rows.end - rows.start,
+            },
+            None => Extent::default(),
+        }
+    }
+
+    pub fn area(self) -> u64 {
+        self.width as u64 * self.height as u64
+    }
+}
+
+impl fmt::Display for Extent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+/// What measuring a pattern has found, so far or in the end.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Lifespan {
+    /// Generations run so far.
+    pub generations: u32,
+    /// The period of the cycle the pattern settled into, or 0 while it
+    /// hasn't.
+    pub period: u32,
+    /// Whether measuring has stopped, because the pattern settled or ran out
+    /// of generations.
+    pub finished: bool,
+    pub initial_population: u32,
+    pub peak_population: u32,
+    pub population: u32,
+    pub initial_extent: Extent,
+    /// The bounding box with the largest area so far.
+    pub largest_extent: Extent,
+    pub extent: Extent,
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Lifespan {
+    pub fn is_settled(&self) -> bool {
+        self.period > 0
+    }
+
+    /// The generations until the pattern first reached the cycle it settled
+    /// into, or all of them while it hasn't.
+    pub fn lifespan(&self) -> u32 {
+        self.generations - self.period
+    }
+
+    /// A summary of the lifespan, population and bounding box, one line each.
+    pub fn describe(&self) -> String {
+        let locale = i18n::locale();
+        let lifespan = match (self.is_settled(), self.finished) {
+            (true, _) => locale.format(Message::LifespanSettled, &[&self.lifespan(), &self.period]),
+            (false, true) => locale.format(Message::LifespanGaveUp, &[&self.generations]),
+            (false, false) => locale.format(Message::LifespanRunning, &[&self.generations]),
+        };
+        let population = locale.format(
+            Message::LifespanPopulation,
+            &[
+                &self.initial_population,
+                &self.peak_population,
+                &self.population,
+            ],
+        );
+        let extent = locale.format(
+            Message::LifespanExtent,
+            &[&self.initial_extent, &self.largest_extent, &self.extent],
+        );
+        format!("{}\n{}\n{}", lifespan, population, extent)
+    }
+}
+
+/// A pattern being measured a batch of generations at a time.
+#[derive(Clone)]
+pub struct Measurement {
+    universe: Universe,
+    /// How many generations the pattern has to settle in.
+    max_generations: u32,
+    lifespan: Lifespan,
+}
+
+impl Measurement {
+    /// Start measuring the pattern on `universe`, giving up if it hasn't
+    /// settled after `max_generations`.
+    pub fn new(universe: Universe, max_generations: u32) -> Measurement {
+        let population = universe.population();
+        let extent = Extent::of(&universe);
+        Measurement {
+            universe,
+            max_generations,
+            lifespan: Lifespan {
+                initial_population: population,
+                peak_population: population,
+                population,
+                initial_extent: extent,
+                largest_extent: extent,
+                extent,
+                ..Lifespan::default()
+            },
+        }
+    }
+
+    /// Run up to `generations` more generations, stopping as soon as the
+    /// pattern settles. Returns what has been found so far.
+    pub fn run(&mut self, generations: u32) -> Lifespan {
+        for _ in 0..generations {
+            if self.lifespan.finished {
+                break;
+            }
+            // This only ticks while the board isn't repeating, so the
+            // generation it settles in is seen on the way round after.
+            let stabilization = self.universe.run_until_stable(1);
+            self.lifespan.generations += stabilization.generations;
+            self.lifespan.period = stabilization.period;
+            self.observe();
+        }
+        self.lifespan
+    }
+
+    pub fn lifespan(&self) -> Lifespan {
+        self.lifespan
+    }
+
+    fn observe(&mut self) {
+        let lifespan = &mut self.lifespan;
+        lifespan.population = self.universe.population();
+        lifespan.peak_population = lifespan.peak_population.max(lifespan.population);
+        lifespan.extent = Extent::of(&self.universe);
+        if lifespan.extent.area() > lifespan.largest_extent.area() {
+            lifespan.largest_extent = lifespan.extent;
+        }
+        let out_of_generations = lifespan.generations >= self.max_generations;
+        if out_of_generations && !lifespan.is_settled() {
+            // The last generation allowed might be the one that repeats.
+            lifespan.period = self.universe.run_until_stable(0).period;
+        }
+        lifespan.finished = lifespan.is_settled() || out_of_generations;
+    }
+}
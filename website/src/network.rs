@@ -0,0 +1,293 @@
+//! Sharing one universe between several visitors through a relay server,
+//! which passes every message sent over its WebSocket on to everyone else
+//! connected to it.
+//!
+//! Each message is a JSON object. Edits are sent as they are made, as
+//! `{ message: "edit", generation, ... }` with the rest of the members as in
+//! a replay log, and the whole board every `SYNC_INTERVAL` generations as
+//! `{ message: "sync", generation, board }`, where `board` is written by
+//! `Universe::serialize`. The hosts of peer-to-peer sessions, in `peer`,
+//! also send `{ message: "tick", generations }` whenever they step, and
+//! the simulation server, in the `server` crate, sends the part of its
+//! universe each page shows as
+//! `{ message: "board", generation, row, column, rows, columns, board }`
+//! for pages to draw instead of stepping their own. `row` and `column` are
+//! where `board` starts in a universe of `rows` by `columns` cells, and
+//! pages ask for another part with
+//! `{ message: "view", row, column, width, height }` as they pan.
+//!
+//! Boards that drift apart are merged without any one visitor being in
+//! charge. Generations are grouped into windows of `MERGE_WINDOW`, and a
+//! board synced within the same window as the local one has its live cells
+//! added to it, so that nobody's drawing is lost. A board from a later
+//! window replaces the local one, and one from an earlier window is ignored,
+//! since its sender will catch up when it sees a later sync.
+
+#[cfg(all(target_arch = "wasm32", feature = "web"))]
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+#[cfg(all(target_arch = "wasm32", feature = "web"))]
+use wasm_bindgen::prelude::*;
+#[cfg(all(target_arch = "wasm32", feature = "web"))]
+use wasm_bindgen::JsCast;
+
+use crate::engine::{Bounds, Cell, Position, SimulationEngine, Universe};
+use crate::patterns::Pattern;
+use crate::replay::{Edit, ReplayError};
+
+/// Generations between syncs of the whole board.
+pub const SYNC_INTERVAL: u64 = 64;
+
+/// How many generations boards are merged across rather than replaced.
+pub const MERGE_WINDOW: u64 = 16;
+
+/// The most rows and columns of a simulation server's universe a page is
+/// first sent.
+pub const SERVED_VIEW: u32 = 128;
+
+/// A message passed between visitors sharing a universe.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "message", rename_all = "lowercase")]
+pub enum NetworkMessage {
+    /// An edit made by hand in `generation`.
+    Edit {
+        generation: u64,
+        #[serde(flatten)]
+        edit: Edit,
+    },
+    /// The whole board as it was in `generation`.
+    Sync { generation: u64, board: String },
+    /// The sender stepped `generations` ahead. Only hosts of peer-to-peer
+    /// sessions send these.
+    Tick { generations: u64 },
+    /// The part of the board a simulation server has in `generation` that
+    /// shows through `window`, which replaces the local one rather than
+    /// being merged.
+    Board {
+        generation: u64,
+        #[serde(flatten)]
+        window: Window,
+        board: String,
+    },
+    /// The part of a simulation server's universe a page wants to be sent
+    /// from now on.
+    View(Bounds),
+}
+
+/// Where the board a simulation server sends a page lies in its universe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Window {
+    /// The cell of the universe at the top left of the board.
+    pub row: u32,
+    pub column: u32,
+    /// The size of the whole universe, in cells.
+    pub rows: u32,
+    pub columns: u32,
+}
+
+impl Window {
+    /// `edit`, made to the `width` by `height` board shown through the
+    /// window, as the server should make it to its universe. Edits to the
+    /// whole board only reach the part in the window, and the page can't
+    /// resize the universe.
+    pub fn edit_for_universe(&self, edit: Edit, width: u32, height: u32) -> Option<Edit> {
+        let (top, left) = (self.row, self.column);
+        let shift =
+            |(row, column): (u32, u32)| (row.saturating_add(top), column.saturating_add(left));
+        Some(match edit {
+            Edit::Toggle { row, column } => {
+                let (row, column) = shift((row, column));
+                Edit::Toggle { row, column }
+            }
+            Edit::Cells { cell, cells } => Edit::Cells {
+                cell,
+                cells: cells.into_iter().map(shift).collect(),
+            },
+            Edit::Stamp {
+                row,
+                column,
+                pattern,
+            } => {
+                let (row, column) = shift((row, column));
+                Edit::Stamp {
+                    row,
+                    column,
+                    pattern,
+                }
+            }
+            Edit::Clear => Edit::Stamp {
+                row: top,
+                column: left,
+                pattern: Pattern::from_cells(
+                    width,
+                    height,
+                    vec![Cell::Dead; width as usize * height as usize],
+                ),
+            },
+            Edit::Board { board } => Edit::Stamp {
+                row: top,
+                column: left,
+                pattern: Pattern::from_rle(&board).ok()?,
+            },
+            Edit::Resize { .. } => return None,
+            edit @ (Edit::Rule { .. } | Edit::Topology { .. } | Edit::Noise { .. }) => edit,
+        })
+    }
+
+    /// The part of the universe a `width` by `height` board would show
+    /// with the window moved `rows` down and `columns` right, as far as the
+    /// edges of the universe allow.
+    pub fn panned(&self, rows: i64, columns: i64, width: u32, height: u32) -> Bounds {
+        let clamp = |start: u32, by: i64, size: u32, total: u32| {
+            (start as i64 + by).clamp(0, total.saturating_sub(size) as i64) as u32
+        };
+        Bounds {
+            row: clamp(self.row, rows, height, self.rows),
+            column: clamp(self.column, columns, width, self.columns),
+            width,
+            height,
+        }
+    }
+}
+
+impl NetworkMessage {
+    pub fn to_json(&self) -> String {
+        // Every member is a string, number or array of them, so this can't
+        // fail.
+        serde_json::to_string(self).unwrap()
+    }
+
+    /// Read a message written by `to_json`.
+    pub fn from_json(text: &str) -> Result<NetworkMessage, ReplayError> {
+        Ok(serde_json::from_str(text)?)
+    }
+}
+
+/// Merge `board`, synced in `generation`, into `universe`. Returns whether
+/// `universe` changed.
+pub fn merge(universe: &mut Universe, generation: u64, board: &Universe) -> bool {
+    let (local, remote) = (
+        universe.generation() / MERGE_WINDOW,
+        generation / MERGE_WINDOW,
+    );
+    let same_size = board.width() == universe.width() && board.height() == universe.height();
+    if remote > local || (remote == local && !same_size) {
+        let generations = generation.saturating_sub(universe.generation());
+        replace(universe, generations, board);
+        return true;
+    }
+    if remote < local {
+        return false;
+    }
+    let mut changed = false;
+    for row in 0..board.height() {
+        for column in 0..board.width() {
+            let position = Position::new(row, column);
+            let dead = universe
+                .get(position)
+                .is_some_and(|cell| cell != Cell::Alive);
+            if board.get(position) == Some(Cell::Alive) && dead {
+                universe.set_cell(position, Cell::Alive);
+                changed = true;
+            }
+        }
+    }
+    changed
+}
+
+/// Replace the cells of `universe` with those of `board`, from
+/// `generation`, as guests of a peer-to-peer session do with the host's
+/// board and viewers with a simulation server's. The generation only ever
+/// moves forward.
+pub fn adopt(universe: &mut Universe, generation: u64, board: &Universe) {
+    let generations = generation.saturating_sub(universe.generation());
+    replace(universe, generations, board);
+}
+
+/// Replace the cells of `universe` with those of `board`, as though they
+/// were `generations` later.
+fn replace(universe: &mut Universe, generations: u64, board: &Universe) {
+    if board.width() != universe.width() || board.height() != universe.height() {
+        universe.set_width(board.width());
+        universe.set_height(board.height());
+    }
+    universe.skip_ahead(generations, |universe| universe.copy_from(board));
+}
+
+/// How the connection to the relay server is doing.
+#[cfg(all(target_arch = "wasm32", feature = "web"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectionStatus {
+    Connecting,
+    Connected,
+    Disconnected,
+}
+
+/// An open WebSocket to a relay server. Dropping it closes the socket.
+#[cfg(all(target_arch = "wasm32", feature = "web"))]
+pub struct Connection {
+    socket: web_sys::WebSocket,
+    /// Kept alive for as long as the socket can call them.
+    _on_open: Closure<dyn FnMut()>,
+    _on_close: Closure<dyn FnMut()>,
+    _on_message: Closure<dyn FnMut(web_sys::MessageEvent)>,
+}
+
+#[cfg(all(target_arch = "wasm32", feature = "web"))]
+impl Connection {
+    /// Connect to the relay server at `url`. `on_status` is called whenever
+    /// the connection opens or closes, and `on_message` with the text of
+    /// every message received.
+    pub fn open(
+        url: &str,
+        on_status: impl Fn(ConnectionStatus) + 'static,
+        mut on_message: impl FnMut(String) + 'static,
+    ) -> Result<Connection, JsValue> {
+        let socket = web_sys::WebSocket::new(url)?;
+        let on_status = Rc::new(on_status);
+        on_status(ConnectionStatus::Connecting);
+
+        let my_on_status = on_status.clone();
+        let on_open = Closure::wrap(Box::new(move || {
+            my_on_status(ConnectionStatus::Connected);
+        }) as Box<dyn FnMut()>);
+        socket.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+
+        // Errors are always followed by the socket closing.
+        let on_close = Closure::wrap(Box::new(move || {
+            on_status(ConnectionStatus::Disconnected);
+        }) as Box<dyn FnMut()>);
+        socket.set_onclose(Some(on_close.as_ref().unchecked_ref()));
+
+        let on_message = Closure::wrap(Box::new(move |event: web_sys::MessageEvent| {
+            if let Some(text) = event.data().as_string() {
+                on_message(text);
+            }
+        }) as Box<dyn FnMut(_)>);
+        socket.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+
+        Ok(Connection {
+            socket,
+            _on_open: on_open,
+            _on_close: on_close,
+            _on_message: on_message,
+        })
+    }
+
+    /// Send `message` to everyone else, if the connection is open.
+    pub fn send(&self, message: &NetworkMessage) {
+        if self.socket.ready_state() == web_sys::WebSocket::OPEN {
+            // Failures show up as the socket closing.
+            let _ = self.socket.send_with_str(&message.to_json());
+        }
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", feature = "web"))]
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.socket.set_onclose(None);
+        let _ = self.socket.close();
+    }
+}
@@ -0,0 +1,2763 @@
+//! Wiring the canvas up to the rest of the page.
+
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::audio::{Scale, Sonification};
+use crate::brush::{BrushShape, Symmetry};
+use crate::canvas::{
+    self, connect, host_peer, join_peer, start_worker, Canvas, RenderMode, Renderer, Tool,
+};
+use crate::compare::Comparison;
+use crate::events::Subscriptions;
+use crate::fps::{self, FrameStats};
+use crate::i18n::{self, Locale};
+use crate::keybindings::{Action, Keybindings};
+use crate::network::ConnectionStatus;
+use crate::patterns::Pattern;
+use crate::rule::Neighborhood;
+use crate::sand::Material;
+use crate::search::SoupSearch;
+use crate::speed::Speed;
+use crate::state::{AnimationCallback, AppState};
+use crate::theme::Theme;
+use crate::utils::{Listen, Listeners};
+use crate::video::VideoRecorder;
+use crate::widget::GameHandle;
+use crate::worker::{LifespanWorker, SearchProgress, SearchWorker};
+use crate::{hashlife, storage, table, utils};
+use crate::{Cell, Position, Topology, Universe};
+
+fn window() -> web_sys::Window {
+    web_sys::window().expect("no global `window` exists")
+}
+
+/// Handle a press at the point (`x`, `y`) on the canvas, in pixels:
+/// place the pattern being placed, start a selection, start dragging out a
+/// shape or start painting.
+pub(crate) fn pointer_down(
+    canvas: &RefCell<Canvas>,
+    stroke: &RefCell<Option<Cell>>,
+    x: f64,
+    y: f64,
+    forced: Option<Cell>,
+) {
+    if canvas.borrow().is_placing() {
+        canvas.borrow_mut().move_placement(x, y);
+        canvas.borrow_mut().place();
+    } else if canvas.borrow().tool() == Tool::Select {
+        canvas.borrow_mut().begin_selection(x, y);
+    } else if canvas.borrow().tool().draws_shapes() {
+        canvas.borrow_mut().begin_drawing(x, y);
+    } else {
+        begin_stroke(canvas, stroke, x, y, forced);
+        return;
+    }
+    canvas.borrow_mut().draw();
+}
+
+/// Handle the pointer moving to (`x`, `y`) on the canvas, pressed or not.
+pub(crate) fn pointer_move(
+    canvas: &RefCell<Canvas>,
+    stroke: &RefCell<Option<Cell>>,
+    x: f64,
+    y: f64,
+) {
+    if canvas.borrow().is_placing() {
+        canvas.borrow_mut().move_placement(x, y);
+    } else if !canvas.borrow_mut().drag_selection(x, y) && !canvas.borrow_mut().drag_drawing(x, y) {
+        continue_stroke(canvas, stroke, x, y);
+        return;
+    }
+    canvas.borrow_mut().draw();
+}
+
+pub(crate) fn pointer_up(canvas: &RefCell<Canvas>, stroke: &RefCell<Option<Cell>>) {
+    *stroke.borrow_mut() = None;
+    canvas.borrow_mut().end_selection();
+    if canvas.borrow_mut().end_drawing() {
+        canvas.borrow_mut().draw();
+    }
+}
+
+/// Something a button or key does to the canvas.
+type CanvasAction = fn(&Rc<RefCell<Canvas>>, &web_sys::Document);
+
+fn copy_selection(canvas: &Rc<RefCell<Canvas>>, document: &web_sys::Document) {
+    if let Some(rle) = canvas.borrow().copy_selection() {
+        copy_text(document, &rle);
+    }
+}
+
+fn cut_selection(canvas: &Rc<RefCell<Canvas>>, document: &web_sys::Document) {
+    let rle = canvas.borrow_mut().cut_selection();
+    if let Some(rle) = rle {
+        copy_text(document, &rle);
+    }
+}
+
+fn delete_selection(canvas: &Rc<RefCell<Canvas>>, _: &web_sys::Document) {
+    canvas.borrow_mut().delete_selection();
+}
+
+/// Put `text` on the clipboard, keeping a copy in `#pattern-text` in case
+/// clipboard access is denied.
+fn copy_text(document: &web_sys::Document, text: &str) {
+    document
+        .get_element_by_id("pattern-text")
+        .expect("should have #pattern-text on the page")
+        .dyn_into::<web_sys::HtmlTextAreaElement>()
+        .expect("#pattern-text should be an `HtmlTextAreaElement`")
+        .set_value(text);
+    let _ = window().navigator().clipboard().write_text(text);
+}
+
+/// Start placing the RLE pattern on the clipboard, or the one in
+/// `#pattern-text` if the clipboard can't be read.
+fn paste_text(canvas: &Rc<RefCell<Canvas>>, document: &web_sys::Document) {
+    let paste = |canvas: &RefCell<Canvas>, text: &str| {
+        match canvas.borrow_mut().paste(text) {
+            Ok(()) => {}
+            Err(err) => log!("{}", tr!(CouldNotReadPattern, err)),
+        }
+        canvas.borrow_mut().draw();
+    };
+
+    let my_canvas = canvas.clone();
+    let on_text = Closure::once(Box::new(move |text: JsValue| {
+        paste(&my_canvas, &text.as_string().unwrap_or_default());
+    }) as Box<dyn FnOnce(JsValue)>);
+    let my_canvas = canvas.clone();
+    let my_document = document.clone();
+    let on_error = Closure::once(Box::new(move |_: JsValue| {
+        let text = my_document
+            .get_element_by_id("pattern-text")
+            .expect("should have #pattern-text on the page")
+            .dyn_into::<web_sys::HtmlTextAreaElement>()
+            .expect("#pattern-text should be an `HtmlTextAreaElement`")
+            .value();
+        paste(&my_canvas, &text);
+    }) as Box<dyn FnOnce(JsValue)>);
+    let _ = window()
+        .navigator()
+        .clipboard()
+        .read_text()
+        .then2(&on_text, &on_error);
+    on_text.forget();
+    on_error.forget();
+}
+
+/// Start painting at the point (`x`, `y`) on the canvas, in pixels.
+///
+/// Paints `forced` if given, and otherwise the opposite of the cell under
+/// the point.
+fn begin_stroke(
+    canvas: &RefCell<Canvas>,
+    stroke: &RefCell<Option<Cell>>,
+    x: f64,
+    y: f64,
+    forced: Option<Cell>,
+) {
+    let (row, col) = match canvas.borrow().cell_at(x, y) {
+        Some(position) => position,
+        None => return,
+    };
+    let cell = forced.unwrap_or_else(|| {
+        if canvas.borrow().cell(Position::new(row, col)) == Some(Cell::Alive) {
+            Cell::Dead
+        } else {
+            Cell::Alive
+        }
+    });
+    *stroke.borrow_mut() = Some(cell);
+    canvas.borrow_mut().checkpoint();
+    canvas.borrow_mut().paint(row, col, cell);
+    canvas.borrow_mut().draw();
+}
+
+/// Paint under (`x`, `y`) if a stroke is in progress.
+fn continue_stroke(canvas: &RefCell<Canvas>, stroke: &RefCell<Option<Cell>>, x: f64, y: f64) {
+    let cell = match *stroke.borrow() {
+        Some(cell) => cell,
+        None => return,
+    };
+    let position = canvas.borrow().cell_at(x, y);
+    if let Some((row, col)) = position {
+        if canvas.borrow_mut().paint(row, col, cell) {
+            canvas.borrow_mut().draw();
+        }
+    }
+}
+
+/// The position of the first touch of `event` in canvas pixels, accounting
+/// for the canvas being scaled by CSS.
+pub(crate) fn touch_position(
+    canvas: &web_sys::HtmlCanvasElement,
+    event: &web_sys::TouchEvent,
+) -> Option<(f64, f64)> {
+    let touch = event.touches().get(0)?;
+    let rect = canvas.get_bounding_client_rect();
+    if rect.width() <= 0.0 || rect.height() <= 0.0 {
+        return None;
+    }
+    let x = (touch.client_x() as f64 - rect.left()) * canvas.width() as f64 / rect.width();
+    let y = (touch.client_y() as f64 - rect.top()) * canvas.height() as f64 / rect.height();
+    Some((x, y))
+}
+
+/// Point the link `#id` at a download of `bytes` as a file of type `mime`,
+/// and show it. Returns the link.
+fn offer_download(
+    document: &web_sys::Document,
+    id: &str,
+    bytes: &[u8],
+    mime: &str,
+) -> web_sys::HtmlAnchorElement {
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(bytes));
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type(mime);
+    let blob = web_sys::Blob::new_with_u8_array_sequence_and_options(&parts, &options).unwrap();
+    offer_blob_download(document, id, &blob)
+}
+
+/// Point the link `#id` at a download of `blob`, and show it. Returns the
+/// link.
+fn offer_blob_download(
+    document: &web_sys::Document,
+    id: &str,
+    blob: &web_sys::Blob,
+) -> web_sys::HtmlAnchorElement {
+    let link = document
+        .get_element_by_id(id)
+        .unwrap_or_else(|| panic!("should have #{} on the page", id))
+        .dyn_into::<web_sys::HtmlAnchorElement>()
+        .unwrap_or_else(|_| panic!("#{} should be an `HtmlAnchorElement`", id));
+    // Let go of the previous file, if there was one.
+    if link.href().starts_with("blob:") {
+        let _ = web_sys::Url::revoke_object_url(&link.href());
+    }
+    link.set_href(&web_sys::Url::create_object_url_with_blob(blob).unwrap());
+    link.set_hidden(false);
+    link
+}
+
+/// Step the main canvas and, in lockstep, the one it is compared with.
+fn step(canvas: &RefCell<Canvas>, comparison: &RefCell<Option<Comparison>>, generations: u32) {
+    canvas.borrow_mut().step(generations);
+    if let Some(comparison) = comparison.borrow_mut().as_mut() {
+        comparison.step(generations);
+        comparison.draw(&canvas.borrow());
+    }
+}
+
+fn set_input_value(document: &web_sys::Document, id: &str, value: &str) {
+    document
+        .get_element_by_id(id)
+        .unwrap_or_else(|| panic!("should have #{} on the page", id))
+        .dyn_into::<web_sys::HtmlInputElement>()
+        .unwrap_or_else(|_| panic!("#{} should be an `HtmlInputElement`", id))
+        .set_value(value);
+}
+
+/// Show the universe's rule in the rule input and the neighborhood dropdown.
+fn show_rule(canvas: &Canvas, document: &web_sys::Document) {
+    let rule = canvas.universe.rule();
+    set_input_value(document, "rule", &rule.to_string());
+    document
+        .get_element_by_id("neighborhood")
+        .expect("should have #neighborhood on the page")
+        .dyn_into::<web_sys::HtmlSelectElement>()
+        .expect("#neighborhood should be an `HtmlSelectElement`")
+        .set_value(rule.neighborhood().name());
+}
+
+/// Move the cell size slider to match a zoom from the keyboard or wheel.
+fn show_cell_size(canvas: &RefCell<Canvas>, document: &web_sys::Document) {
+    let cell_size = canvas.borrow().cell_size();
+    set_input_value(document, "cell-size", &cell_size.to_string());
+}
+
+const DARK_QUERY: &str = "(prefers-color-scheme: dark)";
+const HIGH_CONTRAST_QUERY: &str = "(prefers-contrast: more)";
+
+/// The theme the system settings ask for.
+fn preferred_theme() -> Theme {
+    let prefers = |query| {
+        window()
+            .match_media(query)
+            .ok()
+            .flatten()
+            .is_some_and(|list| list.matches())
+    };
+    if prefers(HIGH_CONTRAST_QUERY) {
+        Theme::high_contrast()
+    } else if prefers(DARK_QUERY) {
+        Theme::dark()
+    } else {
+        Theme::light()
+    }
+}
+
+/// Color the canvas and the page with the theme picked in `#theme`.
+fn apply_theme(canvas: &RefCell<Canvas>, document: &web_sys::Document) {
+    let name = document
+        .get_element_by_id("theme")
+        .expect("should have #theme on the page")
+        .dyn_into::<web_sys::HtmlSelectElement>()
+        .expect("#theme should be an `HtmlSelectElement`")
+        .value();
+    // Anything but a preset, like "auto", follows the system.
+    let theme = Theme::from_name(&name).unwrap_or_else(preferred_theme);
+    if let Some(body) = document.body() {
+        let style = body.style();
+        style
+            .set_property("background-color", &theme.background)
+            .unwrap();
+        style.set_property("color", &theme.text).unwrap();
+    }
+    canvas.borrow_mut().set_theme(&theme);
+}
+
+/// The first of the browser's preferred languages that has a translation.
+fn preferred_locale() -> Locale {
+    // Workers have a navigator too, but no window to find it through.
+    let navigator = match web_sys::window() {
+        Some(window) => window.navigator(),
+        None => return Locale::English,
+    };
+    let mut languages: Vec<String> = navigator
+        .languages()
+        .iter()
+        .filter_map(|language| language.as_string())
+        .collect();
+    languages.extend(navigator.language());
+    Locale::negotiate(&languages)
+}
+
+/// Show the crate's text in the language picked in `#language`.
+fn apply_locale(canvas: &RefCell<Canvas>, document: &web_sys::Document) {
+    let code = document
+        .get_element_by_id("language")
+        .expect("should have #language on the page")
+        .dyn_into::<web_sys::HtmlSelectElement>()
+        .expect("#language should be an `HtmlSelectElement`")
+        .value();
+    // Anything but a translation, like "auto", follows the browser.
+    let locale = Locale::from_code(&code).unwrap_or_else(preferred_locale);
+    i18n::set_locale(locale);
+    if let Some(root) = document.document_element() {
+        root.set_attribute("lang", locale.code()).unwrap();
+    }
+    canvas.borrow_mut().draw();
+}
+
+/// Read a parameter from the query string of the page's URL.
+fn url_param(name: &str) -> Option<String> {
+    let search = window().location().search().ok()?;
+    web_sys::UrlSearchParams::new_with_str(&search)
+        .ok()?
+        .get(name)
+}
+
+/// Update the query string of the page's URL without reloading it.
+fn set_url_params(params: &[(&str, &str)]) {
+    let search = window().location().search().unwrap_or_default();
+    let query = web_sys::UrlSearchParams::new_with_str(&search).unwrap();
+    for (name, value) in params {
+        query.set(name, value);
+    }
+    let url = format!("?{}", String::from(query.to_string()));
+    window()
+        .history()
+        .unwrap()
+        .replace_state_with_url(&JsValue::NULL, "", Some(&url))
+        .unwrap();
+}
+
+/// A random seed small enough to survive the round trip through a JS number.
+fn new_seed() -> u64 {
+    (js_sys::Math::random() * (1u64 << 53) as f64) as u64
+}
+
+/// Randomize the board from `seed` and the density input, and record both in
+/// the seed input and the URL so the board can be shared.
+fn randomize_from_seed(canvas: &RefCell<Canvas>, document: &web_sys::Document, seed: u64) {
+    let density = match input_value(document, "density").parse::<f64>() {
+        Ok(density) => density,
+        Err(_) => {
+            log!("{}", tr!(InvalidDensity));
+            return;
+        }
+    };
+    canvas.borrow_mut().randomize_with_seed(seed, density);
+    canvas.borrow_mut().draw();
+
+    set_input_value(document, "seed", &seed.to_string());
+    set_url_params(&[
+        ("seed", &seed.to_string()),
+        ("density", &density.to_string()),
+    ]);
+}
+
+/// Show how far a soup search has got, and the rare objects it found.
+fn show_search_progress(document: &web_sys::Document, progress: &SearchProgress) {
+    let mut found = progress.found.clone();
+    if progress.other > 0 {
+        if !found.is_empty() {
+            found.push_str(", ");
+        }
+        found.push_str(&tr!(CensusOther, progress.other));
+    }
+    if found.is_empty() {
+        found = tr!(CensusNothing);
+    }
+    let mut status = tr!(SearchProgress, progress.soups, found);
+    if !progress.rare.is_empty() {
+        let rare: Vec<String> = progress
+            .rare
+            .iter()
+            .map(|(name, soup)| tr!(SearchRareObject, name, soup))
+            .collect();
+        status.push_str(&tr!(SearchRare, rare.join(", ")));
+    }
+    document
+        .get_element_by_id("search-status")
+        .expect("should have #search-status on the page")
+        .set_text_content(Some(&status));
+}
+
+fn saved_games_select(document: &web_sys::Document) -> web_sys::HtmlSelectElement {
+    document
+        .get_element_by_id("saved-games")
+        .expect("should have #saved-games on the page")
+        .dyn_into::<web_sys::HtmlSelectElement>()
+        .expect("#saved-games should be an `HtmlSelectElement`")
+}
+
+fn checkpoints_select(document: &web_sys::Document) -> web_sys::HtmlSelectElement {
+    document
+        .get_element_by_id("checkpoints")
+        .expect("should have #checkpoints on the page")
+        .dyn_into::<web_sys::HtmlSelectElement>()
+        .expect("#checkpoints should be an `HtmlSelectElement`")
+}
+
+/// Fill the `#checkpoints` dropdown with the universe's checkpoints, newest
+/// first.
+fn refresh_checkpoints(canvas: &RefCell<Canvas>, document: &web_sys::Document) {
+    let select = checkpoints_select(document);
+    select.set_inner_html("");
+    let canvas = canvas.borrow();
+    for id in canvas.universe.checkpoint_ids().into_iter().rev() {
+        let generation = canvas.universe.checkpoint_generation(id).unwrap();
+        let option = document.create_element("option").unwrap();
+        option.set_attribute("value", &id.to_string()).unwrap();
+        option.set_text_content(Some(&tr!(CheckpointLabel, generation)));
+        select.append_child(&option).unwrap();
+    }
+}
+
+/// Fill the `#saved-games` dropdown with the names in `localStorage`.
+fn refresh_saved_games(document: &web_sys::Document) {
+    let select = saved_games_select(document);
+    select.set_inner_html("");
+    for name in storage::saved_names().unwrap_or_default() {
+        let option = document.create_element("option").unwrap();
+        option.set_attribute("value", &name).unwrap();
+        option.set_text_content(Some(&name));
+        select.append_child(&option).unwrap();
+    }
+}
+
+fn input_value(document: &web_sys::Document, id: &str) -> String {
+    document
+        .get_element_by_id(id)
+        .unwrap_or_else(|| panic!("should have #{} on the page", id))
+        .dyn_into::<web_sys::HtmlInputElement>()
+        .unwrap_or_else(|_| panic!("#{} should be an `HtmlInputElement`", id))
+        .value()
+}
+
+/// The longest cycle the page looks for before pausing.
+const MAX_DETECTED_PERIOD: u32 = 60;
+
+/// How many generations "Run to completion" runs before giving up on the
+/// board settling.
+const MAX_RUN_GENERATIONS: u32 = 100_000;
+
+/// How many generations "Run to completion" runs at a time, drawing the
+/// board and letting the page handle events in between.
+const RUN_CHUNK_GENERATIONS: u32 = 250;
+
+/// How many full repaints the renderer benchmark times with each renderer.
+const BENCHMARK_FRAMES: u32 = 50;
+
+/// How often `#fps` is filled in, in milliseconds, so that it can be read.
+const FRAME_STATS_INTERVAL: f64 = 500.0;
+
+/// Fill in `#fps`, if the page has it, unless it was filled in less than
+/// `FRAME_STATS_INTERVAL` before `now`.
+fn show_frame_stats(
+    document: &web_sys::Document,
+    stats: &FrameStats,
+    shown: &mut Option<f64>,
+    now: f64,
+) {
+    if matches!(*shown, Some(at) if now - at < FRAME_STATS_INTERVAL) {
+        return;
+    }
+    if let Some(element) = document.get_element_by_id("fps") {
+        element.set_text_content(Some(&stats.describe()));
+    }
+    *shown = Some(now);
+}
+
+/// Ask for `callback` to run once the page has handled its waiting events,
+/// returning the timeout's id.
+fn set_timeout(callback: &AnimationCallback) -> Option<i32> {
+    let window = web_sys::window()?;
+    let callback = callback.borrow();
+    window
+        .set_timeout_with_callback(callback.as_ref()?.as_ref().unchecked_ref())
+        .ok()
+}
+
+/// The element with `id` on the page as a `T`, or an error saying which one
+/// is missing or what it should have been.
+fn element<T: JsCast>(document: &web_sys::Document, id: &str) -> Result<T, JsError> {
+    let element = document
+        .get_element_by_id(id)
+        .ok_or_else(|| JsError::new(&format!("the page is missing #{}", id)))?;
+    element.dyn_into::<T>().map_err(|_| {
+        let name = std::any::type_name::<T>().rsplit("::").next().unwrap();
+        JsError::new(&format!("#{} should be an `{}`", id, name))
+    })
+}
+
+/// Set the page up, or fail with an error naming the element that is
+/// missing or wrong, which is thrown to the page loading the module.
+#[wasm_bindgen(start, skip_typescript)]
+pub fn start() -> Result<(), JsError> {
+    utils::set_panic_hook();
+    #[cfg(feature = "tracing")]
+    crate::trace::init();
+    i18n::set_locale(preferred_locale());
+
+    // Inside a worker there is no page to set up; `worker_start` runs there
+    // instead. Pages that only embed games with `widget::init` don't have
+    // the game's own canvas, and set up their widgets themselves.
+    let document = match web_sys::window().and_then(|window| window.document()) {
+        Some(document) => document,
+        None => return Ok(()),
+    };
+    let canvas = match document
+        .get_element_by_id("canvas")
+        .and_then(|canvas| canvas.dyn_into::<web_sys::HtmlCanvasElement>().ok())
+    {
+        Some(canvas) => canvas,
+        None => return Ok(()),
+    };
+    log!("{}", tr!(Starting));
+
+    let mut universe = Universe::new();
+    universe.seed_rng(new_seed());
+    let canvas = Rc::new(RefCell::new(Canvas::new(universe, canvas)?));
+    canvas.borrow_mut().draw();
+
+    // Boards shared by URL start from their seed.
+    if let Some(density) = url_param("density") {
+        set_input_value(&document, "density", &density);
+    }
+    if let Some(seed) = url_param("seed").and_then(|seed| seed.parse::<u64>().ok()) {
+        randomize_from_seed(&canvas, &document, seed);
+    }
+
+    let play_pause = element::<web_sys::Element>(&document, "play-pause")?;
+    let state = Rc::new(RefCell::new(AppState::new(Some(play_pause.clone()))));
+    let speed = Rc::new(RefCell::new(Speed::default()));
+    let comparison: Rc<RefCell<Option<Comparison>>> = Rc::new(RefCell::new(None));
+
+    // Every listener the page adds, so that disposing of it takes them off.
+    let mut listeners = Listeners::default();
+
+    // Create the animation callback.
+    let animation_callback: Rc<AnimationCallback> = Rc::new(RefCell::new(None));
+    {
+        let state = state.clone();
+        let canvas = canvas.clone();
+        let speed = speed.clone();
+        let comparison = comparison.clone();
+        let document = document.clone();
+
+        let callback: Weak<AnimationCallback> = Rc::downgrade(&animation_callback);
+        // Whether the current cycle was already reported, so that pressing
+        // play again keeps it running.
+        let mut reported = false;
+        let mut frame_stats = FrameStats::default();
+        let mut stats_shown = None;
+        *animation_callback.borrow_mut() = Some(Closure::wrap(Box::new(move || {
+            state.borrow_mut().begin_frame();
+            let ticks = speed.borrow_mut().ticks_for_frame();
+            let started = fps::now();
+            frame_stats.frame(started, ticks as u64);
+            if ticks > 0 {
+                step(&canvas, &comparison, ticks);
+                let stepped = fps::now();
+                canvas.borrow_mut().draw();
+                frame_stats.simulated(stepped - started);
+                frame_stats.rendered(fps::now() - stepped);
+
+                match canvas.borrow().detect_period(MAX_DETECTED_PERIOD) {
+                    0 => reported = false,
+                    _ if reported => {}
+                    period => {
+                        if period == 1 {
+                            log!("{}", tr!(ReachedStillLife));
+                        } else {
+                            log!("{}", tr!(ReachedOscillator, period));
+                        }
+                        reported = true;
+                        state.borrow_mut().pause();
+                    }
+                }
+            } else if canvas.borrow().is_tracking() {
+                // Keep gliding after what is tracked between slow steps.
+                canvas.borrow_mut().draw();
+            }
+            show_frame_stats(&document, &frame_stats, &mut stats_shown, started);
+
+            // Schedule ourself for another requestAnimationFrame callback.
+            if let Some(callback) = callback.upgrade() {
+                state.borrow_mut().next_frame(&callback);
+            }
+        }) as Box<dyn FnMut()>));
+    }
+
+    // Create the play button callback.
+    {
+        let state = state.clone();
+        let animation_callback = animation_callback.clone();
+        let play_callback = Closure::wrap(Box::new(move || {
+            state.borrow_mut().toggle(&animation_callback);
+        }) as Box<dyn FnMut()>);
+        play_pause.listen(&mut listeners, "click", &play_callback);
+        listeners.keep(play_callback);
+    }
+
+    // Create the run to completion callbacks. The board is run a chunk at a
+    // time, drawn after each, until it settles or has run
+    // `MAX_RUN_GENERATIONS`. Pressing the button again, or play, stops it.
+    {
+        let button = element::<web_sys::HtmlElement>(&document, "run-to-completion")?;
+        // The generations run so far, while running.
+        let run: Rc<RefCell<Option<u32>>> = Rc::new(RefCell::new(None));
+        let timeout: Rc<RefCell<Option<i32>>> = Rc::new(RefCell::new(None));
+        let chunk_callback: Rc<AnimationCallback> = Rc::new(RefCell::new(None));
+
+        let my_state = state.clone();
+        let my_canvas = canvas.clone();
+        let my_comparison = comparison.clone();
+        let my_button = button.clone();
+        let my_run = run.clone();
+        let my_timeout = timeout.clone();
+        let callback: Weak<AnimationCallback> = Rc::downgrade(&chunk_callback);
+        *chunk_callback.borrow_mut() = Some(Closure::wrap(Box::new(move || {
+            my_timeout.borrow_mut().take();
+            let generations = match *my_run.borrow() {
+                Some(generations) if !my_state.borrow().is_running() => generations,
+                _ => {
+                    my_run.borrow_mut().take();
+                    my_button.set_text_content(Some(&tr!(RunToCompletion)));
+                    return;
+                }
+            };
+            let chunk = RUN_CHUNK_GENERATIONS.min(MAX_RUN_GENERATIONS - generations);
+            let stabilization = my_canvas.borrow_mut().run_until_stable(chunk);
+            if let Some(comparison) = my_comparison.borrow_mut().as_mut() {
+                comparison.step(stabilization.generations);
+                comparison.draw(&my_canvas.borrow());
+            }
+            my_canvas.borrow_mut().draw();
+
+            let generations = generations + stabilization.generations;
+            if stabilization.is_stable() || generations >= MAX_RUN_GENERATIONS {
+                match stabilization.period {
+                    0 => log!("{}", tr!(StillChanging, generations)),
+                    1 => log!("{}", tr!(SettledIntoStillLife, generations)),
+                    period => log!("{}", tr!(SettledIntoOscillator, generations, period)),
+                }
+                my_run.borrow_mut().take();
+                my_button.set_text_content(Some(&tr!(RunToCompletion)));
+            } else if let Some(callback) = callback.upgrade() {
+                *my_run.borrow_mut() = Some(generations);
+                *my_timeout.borrow_mut() = set_timeout(&callback);
+            }
+        }) as Box<dyn FnMut()>));
+
+        let my_state = state.clone();
+        let my_button = button.clone();
+        let run_callback = Closure::wrap(Box::new(move || {
+            if run.borrow_mut().take().is_some() {
+                if let (Some(window), Some(timeout)) =
+                    (web_sys::window(), timeout.borrow_mut().take())
+                {
+                    window.clear_timeout_with_handle(timeout);
+                }
+                my_button.set_text_content(Some(&tr!(RunToCompletion)));
+                return;
+            }
+            my_state.borrow_mut().pause();
+            *run.borrow_mut() = Some(0);
+            *timeout.borrow_mut() = set_timeout(&chunk_callback);
+            my_button.set_text_content(Some(&tr!(StopRunning)));
+        }) as Box<dyn FnMut()>);
+        button.listen(&mut listeners, "click", &run_callback);
+        listeners.keep(run_callback);
+    }
+
+    // Create the painting callbacks. Pressing the mouse picks the state to
+    // paint: the opposite of the cell under the cursor, or forced dead with
+    // shift held and alive with ctrl held. Dragging then paints that state.
+    // Touches paint the same way as an unmodified mouse.
+    {
+        let stroke: Rc<RefCell<Option<Cell>>> = Rc::new(RefCell::new(None));
+
+        let my_canvas = canvas.clone();
+        let my_stroke = stroke.clone();
+        let mousedown_callback = Closure::wrap(Box::new(move |event: web_sys::MouseEvent| {
+            // Alt and the middle button pan the view instead.
+            if event.button() != 0 || event.alt_key() {
+                return;
+            }
+            let (x, y) = (event.offset_x() as f64, event.offset_y() as f64);
+            let forced = if event.shift_key() {
+                Some(Cell::Dead)
+            } else if event.ctrl_key() {
+                Some(Cell::Alive)
+            } else {
+                None
+            };
+            pointer_down(&my_canvas, &my_stroke, x, y, forced);
+        }) as Box<dyn FnMut(_)>);
+        canvas
+            .borrow()
+            .canvas
+            .listen(&mut listeners, "mousedown", &mousedown_callback);
+        listeners.keep(mousedown_callback);
+
+        let my_canvas = canvas.clone();
+        let my_stroke = stroke.clone();
+        let mousemove_callback = Closure::wrap(Box::new(move |event: web_sys::MouseEvent| {
+            let (x, y) = (event.offset_x() as f64, event.offset_y() as f64);
+            pointer_move(&my_canvas, &my_stroke, x, y);
+        }) as Box<dyn FnMut(_)>);
+        canvas
+            .borrow()
+            .canvas
+            .listen(&mut listeners, "mousemove", &mousemove_callback);
+        listeners.keep(mousemove_callback);
+
+        let my_canvas = canvas.clone();
+        let my_stroke = stroke.clone();
+        let touchstart_callback = Closure::wrap(Box::new(move |event: web_sys::TouchEvent| {
+            // Keep the page from scrolling or zooming while painting.
+            event.prevent_default();
+            if let Some((x, y)) = touch_position(&my_canvas.borrow().canvas, &event) {
+                pointer_down(&my_canvas, &my_stroke, x, y, None);
+            }
+        }) as Box<dyn FnMut(_)>);
+        canvas
+            .borrow()
+            .canvas
+            .listen(&mut listeners, "touchstart", &touchstart_callback);
+        listeners.keep(touchstart_callback);
+
+        let my_canvas = canvas.clone();
+        let my_stroke = stroke.clone();
+        let touchmove_callback = Closure::wrap(Box::new(move |event: web_sys::TouchEvent| {
+            event.prevent_default();
+            if let Some((x, y)) = touch_position(&my_canvas.borrow().canvas, &event) {
+                pointer_move(&my_canvas, &my_stroke, x, y);
+            }
+        }) as Box<dyn FnMut(_)>);
+        canvas
+            .borrow()
+            .canvas
+            .listen(&mut listeners, "touchmove", &touchmove_callback);
+        listeners.keep(touchmove_callback);
+
+        let my_canvas = canvas.clone();
+        let end_callback = Closure::wrap(Box::new(move || {
+            pointer_up(&my_canvas, &stroke);
+        }) as Box<dyn FnMut()>);
+        for event in ["mouseup", "mouseleave", "touchend", "touchcancel"] {
+            canvas
+                .borrow()
+                .canvas
+                .listen(&mut listeners, event, &end_callback);
+        }
+        listeners.keep(end_callback);
+    }
+
+    // Create the resize callback, shared by the width and height inputs.
+    {
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let resize_callback = Closure::wrap(Box::new(move || {
+            let width = input_value(&my_document, "width").parse::<u32>();
+            let height = input_value(&my_document, "height").parse::<u32>();
+            match (width, height) {
+                (Ok(width), Ok(height)) if width > 0 && height > 0 => {
+                    my_canvas.borrow_mut().set_size(width, height);
+                }
+                _ => log!("{}", tr!(InvalidUniverseSize)),
+            }
+        }) as Box<dyn FnMut()>);
+        for id in ["width", "height"] {
+            element::<web_sys::Element>(&document, id)?.listen(
+                &mut listeners,
+                "change",
+                &resize_callback,
+            );
+        }
+        listeners.keep(resize_callback);
+    }
+
+    // Create the cell size callback.
+    {
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let cell_size_callback = Closure::wrap(Box::new(move || {
+            match input_value(&my_document, "cell-size").parse::<u32>() {
+                Ok(cell_size) => my_canvas.borrow_mut().set_cell_size(cell_size),
+                _ => log!("{}", tr!(InvalidCellSize)),
+            }
+        }) as Box<dyn FnMut()>);
+        element::<web_sys::Element>(&document, "cell-size")?.listen(
+            &mut listeners,
+            "input",
+            &cell_size_callback,
+        );
+        listeners.keep(cell_size_callback);
+    }
+
+    // Create the theme callbacks. Matching the system follows changes to
+    // its settings while the page is open.
+    {
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let theme_callback = Closure::wrap(Box::new(move || {
+            apply_theme(&my_canvas, &my_document);
+        }) as Box<dyn FnMut()>);
+        element::<web_sys::Element>(&document, "theme")?.listen(
+            &mut listeners,
+            "change",
+            &theme_callback,
+        );
+        for query in [DARK_QUERY, HIGH_CONTRAST_QUERY] {
+            if let Ok(Some(list)) = window().match_media(query) {
+                list.listen(&mut listeners, "change", &theme_callback);
+            }
+        }
+        listeners.keep(theme_callback);
+        apply_theme(&canvas, &document);
+    }
+
+    // Create the language callback.
+    {
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let my_state = state.clone();
+        let language_callback = Closure::wrap(Box::new(move || {
+            apply_locale(&my_canvas, &my_document);
+            my_state.borrow().show();
+        }) as Box<dyn FnMut()>);
+        element::<web_sys::Element>(&document, "language")?.listen(
+            &mut listeners,
+            "change",
+            &language_callback,
+        );
+        listeners.keep(language_callback);
+        apply_locale(&canvas, &document);
+        state.borrow().show();
+    }
+
+    // Create the render mode callback.
+    {
+        let my_canvas = canvas.clone();
+        let render_mode_callback = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let select = event
+                .target()
+                .unwrap()
+                .dyn_into::<web_sys::HtmlSelectElement>()
+                .unwrap();
+            match RenderMode::from_name(&select.value()) {
+                Some(mode) => my_canvas.borrow_mut().set_render_mode(mode),
+                None => log!("{}", tr!(UnknownRenderMode, select.value())),
+            }
+        }) as Box<dyn FnMut(_)>);
+        element::<web_sys::Element>(&document, "render-mode")?.listen(
+            &mut listeners,
+            "change",
+            &render_mode_callback,
+        );
+        listeners.keep(render_mode_callback);
+    }
+
+    // Create the renderer callbacks.
+    {
+        let my_canvas = canvas.clone();
+        let renderer_callback = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let select = event
+                .target()
+                .unwrap()
+                .dyn_into::<web_sys::HtmlSelectElement>()
+                .unwrap();
+            match Renderer::from_name(&select.value()) {
+                Some(renderer) => my_canvas.borrow_mut().set_renderer(renderer),
+                None => log!("{}", tr!(UnknownRenderer, select.value())),
+            }
+        }) as Box<dyn FnMut(_)>);
+        element::<web_sys::Element>(&document, "renderer")?.listen(
+            &mut listeners,
+            "change",
+            &renderer_callback,
+        );
+        listeners.keep(renderer_callback);
+
+        let my_canvas = canvas.clone();
+        let benchmark_callback = Closure::wrap(Box::new(move || {
+            let results = my_canvas.borrow_mut().benchmark_renderers(BENCHMARK_FRAMES);
+            log!("{}", tr!(RepaintsPerFrame, results));
+        }) as Box<dyn FnMut()>);
+        element::<web_sys::HtmlElement>(&document, "benchmark-renderers")?.listen(
+            &mut listeners,
+            "click",
+            &benchmark_callback,
+        );
+        listeners.keep(benchmark_callback);
+    }
+
+    // Create the activity overlay callback.
+    {
+        let my_canvas = canvas.clone();
+        let activity_callback = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let checkbox = event
+                .target()
+                .unwrap()
+                .dyn_into::<web_sys::HtmlInputElement>()
+                .unwrap();
+            my_canvas.borrow_mut().set_show_activity(checkbox.checked());
+        }) as Box<dyn FnMut(_)>);
+        element::<web_sys::Element>(&document, "show-activity")?.listen(
+            &mut listeners,
+            "change",
+            &activity_callback,
+        );
+        listeners.keep(activity_callback);
+    }
+
+    // Create the rewind callbacks. The timeline's right end is the newest
+    // step, so its value counts steps from the oldest one remembered.
+    {
+        let my_canvas = canvas.clone();
+        let rewind_callback = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let checkbox = event
+                .target()
+                .unwrap()
+                .dyn_into::<web_sys::HtmlInputElement>()
+                .unwrap();
+            my_canvas.borrow_mut().set_rewind(checkbox.checked());
+        }) as Box<dyn FnMut(_)>);
+        element::<web_sys::Element>(&document, "rewind")?.listen(
+            &mut listeners,
+            "change",
+            &rewind_callback,
+        );
+        listeners.keep(rewind_callback);
+
+        let my_canvas = canvas.clone();
+        let timeline_callback = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let slider = event
+                .target()
+                .unwrap()
+                .dyn_into::<web_sys::HtmlInputElement>()
+                .unwrap();
+            let position = slider.value_as_number() as u32;
+            let steps = my_canvas.borrow().universe.rewind_steps();
+            my_canvas
+                .borrow_mut()
+                .rewind_to(steps.saturating_sub(position));
+        }) as Box<dyn FnMut(_)>);
+        element::<web_sys::Element>(&document, "timeline")?.listen(
+            &mut listeners,
+            "input",
+            &timeline_callback,
+        );
+        listeners.keep(timeline_callback);
+    }
+
+    // Create the minimap callback, which moves the view to wherever the
+    // minimap is clicked.
+    {
+        let my_canvas = canvas.clone();
+        let minimap_callback = Closure::wrap(Box::new(move |event: web_sys::MouseEvent| {
+            let (x, y) = (event.offset_x() as f64, event.offset_y() as f64);
+            my_canvas.borrow_mut().jump_to_minimap(x, y);
+        }) as Box<dyn FnMut(_)>);
+        element::<web_sys::Element>(&document, "minimap")?.listen(
+            &mut listeners,
+            "click",
+            &minimap_callback,
+        );
+        listeners.keep(minimap_callback);
+    }
+
+    // Create the noise callback.
+    {
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let noise_callback = Closure::wrap(Box::new(move || {
+            match input_value(&my_document, "noise").parse::<f64>() {
+                Ok(noise) => my_canvas.borrow_mut().set_noise(noise),
+                Err(_) => log!("{}", tr!(InvalidNoise)),
+            }
+        }) as Box<dyn FnMut()>);
+        element::<web_sys::Element>(&document, "noise")?.listen(
+            &mut listeners,
+            "input",
+            &noise_callback,
+        );
+        listeners.keep(noise_callback);
+    }
+
+    // Create the grid lines callback.
+    {
+        let my_canvas = canvas.clone();
+        let grid_callback = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let checkbox = event
+                .target()
+                .unwrap()
+                .dyn_into::<web_sys::HtmlInputElement>()
+                .unwrap();
+            my_canvas.borrow_mut().set_show_grid(checkbox.checked());
+        }) as Box<dyn FnMut(_)>);
+        element::<web_sys::Element>(&document, "show-grid")?.listen(
+            &mut listeners,
+            "change",
+            &grid_callback,
+        );
+        listeners.keep(grid_callback);
+    }
+
+    // Create the fit callback.
+    {
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let fit_callback = Closure::wrap(Box::new(move || {
+            my_canvas.borrow_mut().fit_to_pattern();
+            my_canvas.borrow_mut().draw();
+            show_cell_size(&my_canvas, &my_document);
+        }) as Box<dyn FnMut()>);
+        element::<web_sys::Element>(&document, "fit")?.listen(
+            &mut listeners,
+            "click",
+            &fit_callback,
+        );
+        listeners.keep(fit_callback);
+    }
+
+    // Create the follow callback.
+    {
+        let my_canvas = canvas.clone();
+        let follow_callback = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let checkbox = event
+                .target()
+                .unwrap()
+                .dyn_into::<web_sys::HtmlInputElement>()
+                .unwrap();
+            my_canvas.borrow_mut().set_follow(checkbox.checked());
+        }) as Box<dyn FnMut(_)>);
+        element::<web_sys::Element>(&document, "follow")?.listen(
+            &mut listeners,
+            "change",
+            &follow_callback,
+        );
+        listeners.keep(follow_callback);
+    }
+
+    // Create the tracking callback.
+    {
+        let my_canvas = canvas.clone();
+        let tracking_callback = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let select = event
+                .target()
+                .unwrap()
+                .dyn_into::<web_sys::HtmlSelectElement>()
+                .unwrap();
+            let mut canvas = my_canvas.borrow_mut();
+            match select.value().as_str() {
+                "centroid" => canvas.track_centroid(),
+                "selection" => {
+                    if !canvas.track_selection() {
+                        log!("{}", tr!(NothingToTrack));
+                        canvas.stop_tracking();
+                        select.set_value("none");
+                    }
+                }
+                _ => canvas.stop_tracking(),
+            }
+        }) as Box<dyn FnMut(_)>);
+        element::<web_sys::Element>(&document, "tracking")?.listen(
+            &mut listeners,
+            "change",
+            &tracking_callback,
+        );
+        listeners.keep(tracking_callback);
+    }
+
+    {
+        let my_canvas = canvas.clone();
+        let accessible_callback = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let checkbox = event
+                .target()
+                .unwrap()
+                .dyn_into::<web_sys::HtmlInputElement>()
+                .unwrap();
+            my_canvas.borrow_mut().set_accessible(checkbox.checked());
+        }) as Box<dyn FnMut(_)>);
+        element::<web_sys::Element>(&document, "accessible")?.listen(
+            &mut listeners,
+            "change",
+            &accessible_callback,
+        );
+        listeners.keep(accessible_callback);
+    }
+
+    // Create the threads callback.
+    {
+        let my_canvas = canvas.clone();
+        let parallel_callback = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let checkbox = event
+                .target()
+                .unwrap()
+                .dyn_into::<web_sys::HtmlInputElement>()
+                .unwrap();
+            if !my_canvas.borrow_mut().set_parallel(checkbox.checked()) && checkbox.checked() {
+                log!("{}", tr!(NoThreads));
+                checkbox.set_checked(false);
+            }
+        }) as Box<dyn FnMut(_)>);
+        element::<web_sys::Element>(&document, "parallel")?.listen(
+            &mut listeners,
+            "change",
+            &parallel_callback,
+        );
+        listeners.keep(parallel_callback);
+    }
+
+    // Create the save and load callbacks.
+    {
+        refresh_saved_games(&document);
+
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let save_callback = Closure::wrap(Box::new(move || {
+            let name = input_value(&my_document, "save-name");
+            let name = name.trim();
+            if name.is_empty() {
+                log!("{}", tr!(SaveWithoutName));
+                return;
+            }
+            match my_canvas.borrow().save_to_storage(name) {
+                Ok(()) => {
+                    refresh_saved_games(&my_document);
+                    saved_games_select(&my_document).set_value(name);
+                }
+                Err(error) => log!("{}", tr!(CouldNotSave, format!("{:?}", error))),
+            }
+        }) as Box<dyn FnMut()>);
+        element::<web_sys::HtmlElement>(&document, "save")?.listen(
+            &mut listeners,
+            "click",
+            &save_callback,
+        );
+        listeners.keep(save_callback);
+
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let load_callback = Closure::wrap(Box::new(move || {
+            let name = saved_games_select(&my_document).value();
+            let loaded = my_canvas.borrow_mut().load_from_storage(&name);
+            match loaded {
+                Ok(true) => {
+                    let canvas = my_canvas.borrow();
+                    set_input_value(&my_document, "width", &canvas.width().to_string());
+                    set_input_value(&my_document, "height", &canvas.height().to_string());
+                    show_rule(&canvas, &my_document);
+                    set_input_value(&my_document, "save-name", &name);
+                }
+                Ok(false) => log!("{}", tr!(NoSavedGame, format!("{:?}", name))),
+                Err(error) => log!(
+                    "{}",
+                    tr!(CouldNotLoad, format!("{:?}", name), format!("{:?}", error))
+                ),
+            }
+            my_canvas.borrow_mut().draw();
+        }) as Box<dyn FnMut()>);
+        element::<web_sys::HtmlElement>(&document, "load")?.listen(
+            &mut listeners,
+            "click",
+            &load_callback,
+        );
+        listeners.keep(load_callback);
+    }
+
+    // Create the pattern library callback. Picking a pattern previews it
+    // under the cursor until a click places it.
+    {
+        let select = element::<web_sys::HtmlSelectElement>(&document, "library")?;
+        for name in Pattern::list() {
+            let option = document.create_element("option").unwrap();
+            option.set_attribute("value", &name).unwrap();
+            option.set_text_content(Some(&name));
+            select.append_child(&option).unwrap();
+        }
+
+        let my_canvas = canvas.clone();
+        let library_callback = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let select = event
+                .target()
+                .unwrap()
+                .dyn_into::<web_sys::HtmlSelectElement>()
+                .unwrap();
+            if let Some(pattern) = Pattern::from_library(&select.value()) {
+                my_canvas.borrow_mut().start_placing(pattern);
+            }
+            select.set_value("");
+        }) as Box<dyn FnMut(_)>);
+        select.listen(&mut listeners, "change", &library_callback);
+        listeners.keep(library_callback);
+    }
+
+    // Create the brush callback.
+    {
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let brush_callback = Closure::wrap(Box::new(move || {
+            let shape = my_document
+                .get_element_by_id("brush")
+                .expect("should have #brush on the page")
+                .dyn_into::<web_sys::HtmlSelectElement>()
+                .expect("#brush should be an `HtmlSelectElement`")
+                .value();
+            let shape = match BrushShape::from_name(&shape) {
+                Some(shape) => shape,
+                None => return log!("{}", tr!(UnknownBrush, shape)),
+            };
+            match input_value(&my_document, "brush-size").parse::<u32>() {
+                Ok(radius) => my_canvas.borrow_mut().set_brush(shape, radius),
+                _ => log!("{}", tr!(InvalidBrushSize)),
+            }
+        }) as Box<dyn FnMut()>);
+        for &id in ["brush", "brush-size"].iter() {
+            element::<web_sys::Element>(&document, id)?.listen(
+                &mut listeners,
+                "change",
+                &brush_callback,
+            );
+        }
+        listeners.keep(brush_callback);
+    }
+
+    // Create the material callback.
+    {
+        let my_canvas = canvas.clone();
+        let material_callback = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let select = event
+                .target()
+                .unwrap()
+                .dyn_into::<web_sys::HtmlSelectElement>()
+                .unwrap();
+            match Material::from_name(&select.value()) {
+                Some(material) => my_canvas.borrow_mut().set_material(material),
+                None => log!("{}", tr!(UnknownMaterial, select.value())),
+            }
+        }) as Box<dyn FnMut(_)>);
+        element::<web_sys::Element>(&document, "material")?.listen(
+            &mut listeners,
+            "change",
+            &material_callback,
+        );
+        listeners.keep(material_callback);
+    }
+
+    // Create the symmetry callback.
+    {
+        let my_canvas = canvas.clone();
+        let symmetry_callback = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let select = event
+                .target()
+                .unwrap()
+                .dyn_into::<web_sys::HtmlSelectElement>()
+                .unwrap();
+            match Symmetry::from_name(&select.value()) {
+                Some(symmetry) => my_canvas.borrow_mut().set_symmetry(symmetry),
+                None => log!("{}", tr!(UnknownSymmetry, select.value())),
+            }
+        }) as Box<dyn FnMut(_)>);
+        element::<web_sys::Element>(&document, "symmetry")?.listen(
+            &mut listeners,
+            "change",
+            &symmetry_callback,
+        );
+        listeners.keep(symmetry_callback);
+    }
+
+    // Create the tool and selection callbacks.
+    {
+        let my_canvas = canvas.clone();
+        let tool_callback = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let select = event
+                .target()
+                .unwrap()
+                .dyn_into::<web_sys::HtmlSelectElement>()
+                .unwrap();
+            match Tool::from_name(&select.value()) {
+                Some(tool) => my_canvas.borrow_mut().set_tool(tool),
+                None => log!("{}", tr!(UnknownTool, select.value())),
+            }
+            my_canvas.borrow_mut().draw();
+        }) as Box<dyn FnMut(_)>);
+        element::<web_sys::Element>(&document, "tool")?.listen(
+            &mut listeners,
+            "change",
+            &tool_callback,
+        );
+        listeners.keep(tool_callback);
+
+        let buttons: [(&str, CanvasAction); 4] = [
+            ("cut", cut_selection),
+            ("copy", copy_selection),
+            ("paste", paste_text),
+            ("delete", delete_selection),
+        ];
+        for (id, action) in buttons {
+            let my_canvas = canvas.clone();
+            let my_document = document.clone();
+            let button_callback = Closure::wrap(Box::new(move || {
+                action(&my_canvas, &my_document);
+                my_canvas.borrow_mut().draw();
+            }) as Box<dyn FnMut()>);
+            element::<web_sys::HtmlElement>(&document, id)?.listen(
+                &mut listeners,
+                "click",
+                &button_callback,
+            );
+            listeners.keep(button_callback);
+        }
+    }
+
+    // Create the pattern callbacks.
+    {
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let place_callback = Closure::wrap(Box::new(move || {
+            let text = my_document
+                .get_element_by_id("pattern-text")
+                .expect("should have #pattern-text on the page")
+                .dyn_into::<web_sys::HtmlTextAreaElement>()
+                .expect("#pattern-text should be an `HtmlTextAreaElement`")
+                .value();
+            match Pattern::parse(&text) {
+                Ok(pattern) => my_canvas.borrow_mut().start_placing(pattern),
+                Err(err) => {
+                    trace_event!(warn, error = ?err, "could not import pattern");
+                    log!("{}", tr!(CouldNotReadPattern, err));
+                }
+            }
+        }) as Box<dyn FnMut()>);
+        element::<web_sys::HtmlElement>(&document, "place-pattern")?.listen(
+            &mut listeners,
+            "click",
+            &place_callback,
+        );
+        listeners.keep(place_callback);
+
+        // Loading a file copies its contents into the textarea so that it can
+        // be reviewed before being placed.
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let file_callback = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let input = event
+                .target()
+                .unwrap()
+                .dyn_into::<web_sys::HtmlInputElement>()
+                .unwrap();
+            let file = match input.files().and_then(|files| files.get(0)) {
+                Some(file) => file,
+                None => return,
+            };
+            let reader = web_sys::FileReader::new().unwrap();
+            let my_reader = reader.clone();
+            let my_canvas = my_canvas.clone();
+            let my_document = my_document.clone();
+            let onload = Closure::once(Box::new(move || {
+                let text = my_reader.result().unwrap().as_string().unwrap_or_default();
+                // Macrocell files can describe far more cells than the
+                // textarea could hold, so they go straight to HashLife.
+                if text.trim_start().starts_with(hashlife::MACROCELL_HEADER) {
+                    if let Err(err) = my_canvas.borrow_mut().load_macrocell(&text) {
+                        log!("{}", tr!(CouldNotReadMacrocell, err));
+                        return;
+                    }
+                    if let Some(select) = my_document
+                        .get_element_by_id("engine")
+                        .and_then(|element| element.dyn_into::<web_sys::HtmlSelectElement>().ok())
+                    {
+                        select.set_value("hashlife");
+                    }
+                    my_canvas.borrow_mut().draw();
+                    return;
+                }
+                // Rule files change how the board runs rather than what is
+                // on it.
+                if text.trim_start().starts_with(table::RULE_HEADER) {
+                    if let Err(err) = my_canvas.borrow_mut().load_rule_table(&text) {
+                        log!("{}", tr!(CouldNotReadRuleTable, err));
+                        return;
+                    }
+                    if let Some(select) = my_document
+                        .get_element_by_id("engine")
+                        .and_then(|element| element.dyn_into::<web_sys::HtmlSelectElement>().ok())
+                    {
+                        select.set_value("table");
+                    }
+                    return;
+                }
+                my_document
+                    .get_element_by_id("pattern-text")
+                    .expect("should have #pattern-text on the page")
+                    .dyn_into::<web_sys::HtmlTextAreaElement>()
+                    .expect("#pattern-text should be an `HtmlTextAreaElement`")
+                    .set_value(&text);
+            }) as Box<dyn FnOnce()>);
+            reader.set_onload(Some(onload.as_ref().unchecked_ref()));
+            reader.read_as_text(&file).unwrap();
+            onload.forget();
+        }) as Box<dyn FnMut(_)>);
+        element::<web_sys::Element>(&document, "pattern-file")?.listen(
+            &mut listeners,
+            "change",
+            &file_callback,
+        );
+        listeners.keep(file_callback);
+    }
+
+    // Create the copy pattern callback.
+    {
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let copy_callback = Closure::wrap(Box::new(move || {
+            let rle = my_canvas.borrow().to_rle();
+            copy_text(&my_document, &rle);
+        }) as Box<dyn FnMut()>);
+        element::<web_sys::HtmlElement>(&document, "copy-pattern")?.listen(
+            &mut listeners,
+            "click",
+            &copy_callback,
+        );
+        listeners.keep(copy_callback);
+
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let macrocell_callback =
+            Closure::wrap(Box::new(move || match my_canvas.borrow().to_macrocell() {
+                Ok(macrocell) => copy_text(&my_document, &macrocell),
+                Err(err) => log!("{}", tr!(CouldNotWriteMacrocell, err)),
+            }) as Box<dyn FnMut()>);
+        element::<web_sys::HtmlElement>(&document, "copy-macrocell")?.listen(
+            &mut listeners,
+            "click",
+            &macrocell_callback,
+        );
+        listeners.keep(macrocell_callback);
+    }
+
+    // Create the elementary automaton callbacks.
+    {
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let rule_callback = Closure::wrap(Box::new(move || {
+            match input_value(&my_document, "elementary-rule").parse::<u8>() {
+                Ok(rule) => my_canvas.borrow_mut().set_elementary_rule(rule),
+                Err(_) => log!("{}", tr!(InvalidElementaryRule)),
+            }
+        }) as Box<dyn FnMut()>);
+        element::<web_sys::Element>(&document, "elementary-rule")?.listen(
+            &mut listeners,
+            "change",
+            &rule_callback,
+        );
+        listeners.keep(rule_callback);
+
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let start_callback = Closure::wrap(Box::new(move || {
+            let rule = match input_value(&my_document, "elementary-rule").parse::<u8>() {
+                Ok(rule) => rule,
+                Err(_) => {
+                    log!("{}", tr!(InvalidElementaryRule));
+                    return;
+                }
+            };
+            my_canvas.borrow_mut().start_elementary(rule);
+            if let Some(select) = my_document
+                .get_element_by_id("engine")
+                .and_then(|element| element.dyn_into::<web_sys::HtmlSelectElement>().ok())
+            {
+                select.set_value("elementary");
+            }
+        }) as Box<dyn FnMut()>);
+        element::<web_sys::HtmlElement>(&document, "elementary-start")?.listen(
+            &mut listeners,
+            "click",
+            &start_callback,
+        );
+        listeners.keep(start_callback);
+    }
+
+    // Create the turmite callback.
+    {
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let turmite_callback = Closure::wrap(Box::new(move || {
+            let spec = input_value(&my_document, "turmite-spec");
+            if let Err(err) = my_canvas.borrow_mut().start_turmite(&spec) {
+                log!("{}", tr!(InvalidTurmite, err));
+                return;
+            }
+            if let Some(select) = my_document
+                .get_element_by_id("engine")
+                .and_then(|element| element.dyn_into::<web_sys::HtmlSelectElement>().ok())
+            {
+                select.set_value("turmite");
+            }
+        }) as Box<dyn FnMut()>);
+        element::<web_sys::HtmlElement>(&document, "turmite-start")?.listen(
+            &mut listeners,
+            "click",
+            &turmite_callback,
+        );
+        listeners.keep(turmite_callback);
+    }
+
+    // Create the engine callbacks.
+    {
+        let my_canvas = canvas.clone();
+        let my_comparison = comparison.clone();
+        let engine_callback = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let select = event
+                .target()
+                .unwrap()
+                .dyn_into::<web_sys::HtmlSelectElement>()
+                .unwrap();
+            let engine = select.value();
+            if engine == "worker" {
+                if let Err(error) = start_worker(&my_canvas) {
+                    log!("{}", tr!(CouldNotStartWorker, format!("{:?}", error)));
+                }
+            } else {
+                my_canvas.borrow_mut().set_engine(&engine);
+            }
+            // The compared board keeps up with jumps on the same engine,
+            // short of a worker of its own.
+            if let Some(comparison) = my_comparison.borrow_mut().as_mut() {
+                comparison.set_engine(&engine);
+            }
+        }) as Box<dyn FnMut(_)>);
+        element::<web_sys::Element>(&document, "engine")?.listen(
+            &mut listeners,
+            "change",
+            &engine_callback,
+        );
+        listeners.keep(engine_callback);
+
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let my_comparison = comparison.clone();
+        let jump_callback = Closure::wrap(Box::new(move || {
+            match input_value(&my_document, "jump-exponent").parse::<u8>() {
+                Ok(exponent) if exponent < 64 => {
+                    if !my_canvas.borrow_mut().step_pow2(exponent) {
+                        log!("{}", tr!(JumpTooLong, canvas::MAX_STEPPED_JUMP));
+                        return;
+                    }
+                    if let Some(comparison) = my_comparison.borrow_mut().as_mut() {
+                        comparison.step_pow2(exponent);
+                        comparison.draw(&my_canvas.borrow());
+                    }
+                    my_canvas.borrow_mut().draw();
+                }
+                _ => log!("{}", tr!(InvalidJumpSize)),
+            }
+        }) as Box<dyn FnMut()>);
+        element::<web_sys::HtmlElement>(&document, "jump")?.listen(
+            &mut listeners,
+            "click",
+            &jump_callback,
+        );
+        listeners.keep(jump_callback);
+    }
+
+    // Create the census interval callback.
+    {
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let census_callback = Closure::wrap(Box::new(move || {
+            match input_value(&my_document, "census-interval").parse::<u32>() {
+                Ok(interval) => my_canvas.borrow_mut().set_census_interval(interval),
+                Err(_) => log!("{}", tr!(InvalidCensusInterval)),
+            }
+        }) as Box<dyn FnMut()>);
+        element::<web_sys::Element>(&document, "census-interval")?.listen(
+            &mut listeners,
+            "change",
+            &census_callback,
+        );
+        listeners.keep(census_callback);
+    }
+
+    // Create the soup search callbacks. The search runs in a worker of its
+    // own, and any soup it reports can be brought onto the board to watch.
+    {
+        let search: Rc<RefCell<Option<SearchWorker>>> = Rc::new(RefCell::new(None));
+        let searched: Rc<RefCell<Option<SoupSearch>>> = Rc::new(RefCell::new(None));
+
+        let my_document = document.clone();
+        let my_searched = searched.clone();
+        let search_callback = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let button = event
+                .target()
+                .unwrap()
+                .dyn_into::<web_sys::HtmlElement>()
+                .unwrap();
+            let running = search.borrow_mut().take();
+            if let Some(worker) = running {
+                if !worker.is_finished() {
+                    button.set_text_content(Some(&tr!(SearchSoups)));
+                    return;
+                }
+            }
+            let soups = input_value(&my_document, "search-soups").parse::<u32>();
+            let symmetry = Symmetry::from_name(&input_value(&my_document, "search-symmetry"));
+            let (soups, symmetry) = match (soups, symmetry) {
+                (Ok(soups), Some(symmetry)) if soups > 0 => (soups, symmetry),
+                _ => {
+                    log!("{}", tr!(InvalidSearchSettings));
+                    return;
+                }
+            };
+            let seed = new_seed();
+            let progress_document = my_document.clone();
+            let progress_button = button.clone();
+            let worker = SearchWorker::new(seed, symmetry, soups, move |progress| {
+                show_search_progress(&progress_document, &progress);
+                if progress.soups >= soups as u64 {
+                    progress_button.set_text_content(Some(&tr!(SearchSoups)));
+                }
+            });
+            match worker {
+                Ok(worker) => {
+                    *search.borrow_mut() = Some(worker);
+                    *my_searched.borrow_mut() = Some(SoupSearch::new(seed, symmetry));
+                    button.set_text_content(Some(&tr!(StopSearching)));
+                }
+                Err(error) => log!("{}", tr!(CouldNotStartSearch, format!("{:?}", error))),
+            }
+        }) as Box<dyn FnMut(_)>);
+        element::<web_sys::Element>(&document, "search")?.listen(
+            &mut listeners,
+            "click",
+            &search_callback,
+        );
+        listeners.keep(search_callback);
+
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let show_callback = Closure::wrap(Box::new(move || {
+            let searched = searched.borrow();
+            let search = match searched.as_ref() {
+                Some(search) => search,
+                None => return,
+            };
+            match input_value(&my_document, "search-soup-number").parse::<u64>() {
+                Ok(soup) => {
+                    my_canvas.borrow_mut().set_universe(search.soup(soup));
+                    my_canvas.borrow_mut().draw();
+                }
+                Err(_) => log!("{}", tr!(InvalidSoupNumber)),
+            }
+        }) as Box<dyn FnMut()>);
+        element::<web_sys::Element>(&document, "search-show")?.listen(
+            &mut listeners,
+            "click",
+            &show_callback,
+        );
+        listeners.keep(show_callback);
+    }
+
+    // Create the lifespan callback. The pattern on the board is measured in
+    // a worker, on a copy of the board, and the summary filled in as it
+    // reports.
+    {
+        let measuring: Rc<RefCell<Option<LifespanWorker>>> = Rc::new(RefCell::new(None));
+
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let lifespan_callback = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let button = event
+                .target()
+                .unwrap()
+                .dyn_into::<web_sys::HtmlElement>()
+                .unwrap();
+            let running = measuring.borrow_mut().take();
+            if let Some(worker) = running {
+                if !worker.is_finished() {
+                    button.set_text_content(Some(&tr!(MeasureLifespan)));
+                    return;
+                }
+            }
+            let summary = my_document
+                .get_element_by_id("lifespan-summary")
+                .expect("should have #lifespan-summary on the page");
+            let progress_button = button.clone();
+            let worker = LifespanWorker::new(&my_canvas.borrow().universe, move |lifespan| {
+                summary.set_text_content(Some(&lifespan.describe()));
+                if lifespan.finished {
+                    progress_button.set_text_content(Some(&tr!(MeasureLifespan)));
+                }
+            });
+            match worker {
+                Ok(worker) => {
+                    *measuring.borrow_mut() = Some(worker);
+                    button.set_text_content(Some(&tr!(StopMeasuring)));
+                }
+                Err(error) => log!("{}", tr!(CouldNotMeasure, format!("{:?}", error))),
+            }
+        }) as Box<dyn FnMut(_)>);
+        element::<web_sys::Element>(&document, "lifespan")?.listen(
+            &mut listeners,
+            "click",
+            &lifespan_callback,
+        );
+        listeners.keep(lifespan_callback);
+    }
+
+    // Create the checkpoint callbacks. The list of checkpoints is filled in
+    // whenever it is opened, since automatic ones come and go as it runs.
+    {
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let interval_callback = Closure::wrap(Box::new(move || {
+            match input_value(&my_document, "checkpoint-interval").parse::<u32>() {
+                Ok(interval) => my_canvas.borrow_mut().set_checkpoint_interval(interval),
+                Err(_) => log!("{}", tr!(InvalidCheckpointInterval)),
+            }
+        }) as Box<dyn FnMut()>);
+        element::<web_sys::Element>(&document, "checkpoint-interval")?.listen(
+            &mut listeners,
+            "change",
+            &interval_callback,
+        );
+        listeners.keep(interval_callback);
+
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let save_callback = Closure::wrap(Box::new(move || {
+            my_canvas.borrow_mut().save_checkpoint();
+            refresh_checkpoints(&my_canvas, &my_document);
+        }) as Box<dyn FnMut()>);
+        element::<web_sys::HtmlElement>(&document, "save-checkpoint")?.listen(
+            &mut listeners,
+            "click",
+            &save_callback,
+        );
+        listeners.keep(save_callback);
+
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let list_callback = Closure::wrap(Box::new(move || {
+            refresh_checkpoints(&my_canvas, &my_document);
+        }) as Box<dyn FnMut()>);
+        checkpoints_select(&document).listen(&mut listeners, "focus", &list_callback);
+        listeners.keep(list_callback);
+
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let restore_callback = Closure::wrap(Box::new(move || {
+            if let Ok(id) = checkpoints_select(&my_document).value().parse::<u32>() {
+                my_canvas.borrow_mut().restore_checkpoint(id);
+                my_canvas.borrow_mut().draw();
+            }
+        }) as Box<dyn FnMut()>);
+        element::<web_sys::HtmlElement>(&document, "restore-checkpoint")?.listen(
+            &mut listeners,
+            "click",
+            &restore_callback,
+        );
+        listeners.keep(restore_callback);
+
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let diff_callback = Closure::wrap(Box::new(move || {
+            let checked = my_document
+                .get_element_by_id("diff-checkpoint")
+                .expect("should have #diff-checkpoint on the page")
+                .dyn_into::<web_sys::HtmlInputElement>()
+                .expect("#diff-checkpoint should be an `HtmlInputElement`")
+                .checked();
+            let id = checkpoints_select(&my_document).value().parse::<u32>().ok();
+            my_canvas
+                .borrow_mut()
+                .diff_from_checkpoint(id.filter(|_| checked));
+        }) as Box<dyn FnMut()>);
+        // Picking another checkpoint tints the differences from that one.
+        for id in ["diff-checkpoint", "checkpoints"] {
+            element::<web_sys::Element>(&document, id)?.listen(
+                &mut listeners,
+                "change",
+                &diff_callback,
+            );
+        }
+        listeners.keep(diff_callback);
+
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let seek_callback = Closure::wrap(Box::new(move || {
+            let generation = match input_value(&my_document, "seek-generation").parse::<u64>() {
+                Ok(generation) => generation,
+                Err(_) => return log!("{}", tr!(InvalidGeneration)),
+            };
+            if my_canvas.borrow_mut().seek(generation) {
+                my_canvas.borrow_mut().draw();
+            } else {
+                log!("{}", tr!(CannotSeek, generation));
+            }
+        }) as Box<dyn FnMut()>);
+        element::<web_sys::HtmlElement>(&document, "seek")?.listen(
+            &mut listeners,
+            "click",
+            &seek_callback,
+        );
+        listeners.keep(seek_callback);
+    }
+
+    // Create the edit recording callback, which starts logging edits or
+    // stops and offers the log for download.
+    {
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let record_edits_callback = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let button = event
+                .target()
+                .unwrap()
+                .dyn_into::<web_sys::HtmlElement>()
+                .unwrap();
+            let log = my_canvas.borrow_mut().stop_replay_log();
+            match log {
+                Some(log) => {
+                    let json = log.to_json();
+                    offer_download(
+                        &my_document,
+                        "replay-download",
+                        json.as_bytes(),
+                        "application/json",
+                    );
+                    log!("{}", tr!(RecordedEdits, log.len(), log.generations()));
+                    button.set_text_content(Some(&tr!(RecordEdits)));
+                }
+                None => {
+                    my_canvas.borrow_mut().start_replay_log();
+                    button.set_text_content(Some(&tr!(StopRecordingEdits)));
+                }
+            }
+        }) as Box<dyn FnMut(_)>);
+        element::<web_sys::Element>(&document, "record-edits")?.listen(
+            &mut listeners,
+            "click",
+            &record_edits_callback,
+        );
+        listeners.keep(record_edits_callback);
+    }
+
+    // Create the replay callback, which plays back a log of edits chosen
+    // from a file.
+    {
+        let my_canvas = canvas.clone();
+        let replay_callback = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let input = event
+                .target()
+                .unwrap()
+                .dyn_into::<web_sys::HtmlInputElement>()
+                .unwrap();
+            let file = match input.files().and_then(|files| files.get(0)) {
+                Some(file) => file,
+                None => return,
+            };
+            let reader = web_sys::FileReader::new().unwrap();
+            let my_reader = reader.clone();
+            let my_canvas = my_canvas.clone();
+            let onload = Closure::once(Box::new(move || {
+                let text = my_reader.result().unwrap().as_string().unwrap_or_default();
+                if let Err(err) = my_canvas.borrow_mut().play_replay(&text) {
+                    log!("{}", tr!(CannotReplay, err));
+                }
+            }) as Box<dyn FnOnce()>);
+            reader.set_onload(Some(onload.as_ref().unchecked_ref()));
+            reader.read_as_text(&file).unwrap();
+            onload.forget();
+        }) as Box<dyn FnMut(_)>);
+        element::<web_sys::Element>(&document, "replay-file")?.listen(
+            &mut listeners,
+            "change",
+            &replay_callback,
+        );
+        listeners.keep(replay_callback);
+    }
+
+    // Create the shared universe callback, which connects to the relay
+    // server or disconnects from it.
+    {
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let connect_callback = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let button = event
+                .target()
+                .unwrap()
+                .dyn_into::<web_sys::HtmlElement>()
+                .unwrap();
+            let status = my_document
+                .get_element_by_id("network-status")
+                .expect("should have #network-status on the page");
+            if my_canvas.borrow().is_connected() {
+                my_canvas.borrow_mut().disconnect();
+                status.set_text_content(Some(&tr!(NetworkDisconnected)));
+                button.set_text_content(Some(&tr!(Connect)));
+                return;
+            }
+            let url = input_value(&my_document, "relay-url");
+            let on_status = move |connection: ConnectionStatus| {
+                status.set_text_content(Some(&match connection {
+                    ConnectionStatus::Connecting => tr!(NetworkConnecting),
+                    ConnectionStatus::Connected => tr!(NetworkConnected),
+                    ConnectionStatus::Disconnected => tr!(NetworkDisconnected),
+                }));
+            };
+            match connect(&my_canvas, &url, on_status) {
+                Ok(()) => button.set_text_content(Some(&tr!(Disconnect))),
+                Err(err) => log!("{}", tr!(CouldNotConnect, format!("{:?}", err))),
+            }
+        }) as Box<dyn FnMut(_)>);
+        element::<web_sys::Element>(&document, "connect")?.listen(
+            &mut listeners,
+            "click",
+            &connect_callback,
+        );
+        listeners.keep(connect_callback);
+    }
+
+    // Create the peer-to-peer session callbacks. Offers and answers are
+    // passed between pages through #peer-signal.
+    {
+        let signal_area = |document: &web_sys::Document| {
+            document
+                .get_element_by_id("peer-signal")
+                .expect("should have #peer-signal on the page")
+                .dyn_into::<web_sys::HtmlTextAreaElement>()
+                .expect("#peer-signal should be an `HtmlTextAreaElement`")
+        };
+        let peer_status = |document: &web_sys::Document| {
+            let status = document
+                .get_element_by_id("peer-status")
+                .expect("should have #peer-status on the page");
+            move |connection: ConnectionStatus| {
+                status.set_text_content(Some(&match connection {
+                    ConnectionStatus::Connecting => tr!(NetworkConnecting),
+                    ConnectionStatus::Connected => tr!(NetworkConnected),
+                    ConnectionStatus::Disconnected => tr!(NetworkDisconnected),
+                }));
+            }
+        };
+
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let host_callback = Closure::wrap(Box::new(move || {
+            let area = signal_area(&my_document);
+            area.set_value("");
+            let on_signal = move |offer: String| area.set_value(&offer);
+            if let Err(err) = host_peer(&my_canvas, on_signal, peer_status(&my_document)) {
+                log!("{}", tr!(CouldNotStartPeerSession, format!("{:?}", err)));
+            }
+        }) as Box<dyn FnMut()>);
+
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let join_callback = Closure::wrap(Box::new(move || {
+            let area = signal_area(&my_document);
+            let offer = area.value();
+            area.set_value("");
+            let on_signal = move |answer: String| area.set_value(&answer);
+            let status = peer_status(&my_document);
+            if let Err(err) = join_peer(&my_canvas, &offer, on_signal, status) {
+                log!("{}", tr!(CouldNotStartPeerSession, format!("{:?}", err)));
+            }
+        }) as Box<dyn FnMut()>);
+
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let accept_callback = Closure::wrap(Box::new(move || {
+            let answer = signal_area(&my_document).value();
+            if !my_canvas.borrow_mut().accept_peer(&answer) {
+                log!("{}", tr!(InvalidPeerSignal));
+            }
+        }) as Box<dyn FnMut()>);
+
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let end_callback = Closure::wrap(Box::new(move || {
+            my_canvas.borrow_mut().end_peer_sessions();
+            peer_status(&my_document)(ConnectionStatus::Disconnected);
+        }) as Box<dyn FnMut()>);
+
+        for (id, callback) in [
+            ("peer-host", host_callback),
+            ("peer-join", join_callback),
+            ("peer-accept", accept_callback),
+            ("peer-end", end_callback),
+        ] {
+            element::<web_sys::HtmlElement>(&document, id)?.listen(
+                &mut listeners,
+                "click",
+                &callback,
+            );
+            listeners.keep(callback);
+        }
+    }
+
+    // Create the comparison callbacks. Turning comparison on copies the
+    // board to a second canvas that runs under its own rule; clicking it
+    // toggles cells there only.
+    {
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let my_comparison = comparison.clone();
+        let compare_callback = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let checkbox = event
+                .target()
+                .unwrap()
+                .dyn_into::<web_sys::HtmlInputElement>()
+                .unwrap();
+            let panel = my_document
+                .get_element_by_id("compare-panel")
+                .expect("should have #compare-panel on the page")
+                .dyn_into::<web_sys::HtmlElement>()
+                .expect("#compare-panel should be an `HtmlElement`");
+            if !checkbox.checked() {
+                *my_comparison.borrow_mut() = None;
+                panel.set_hidden(true);
+                return;
+            }
+            let element = my_document
+                .get_element_by_id("compare-canvas")
+                .expect("should have #compare-canvas on the page")
+                .dyn_into::<web_sys::HtmlCanvasElement>()
+                .expect("#compare-canvas should be an `HtmlCanvasElement`");
+            let canvas = match Canvas::new(my_canvas.borrow().universe.clone(), element) {
+                Ok(canvas) => canvas,
+                Err(err) => {
+                    checkbox.set_checked(false);
+                    return web_sys::console::error_1(&err.into());
+                }
+            };
+            let rule = input_value(&my_document, "compare-rule");
+            let mut comparison = match Comparison::new(&my_canvas.borrow(), canvas, &rule) {
+                Ok(comparison) => comparison,
+                Err(err) => {
+                    checkbox.set_checked(false);
+                    return log!("{}", tr!(CouldNotSetRule, err));
+                }
+            };
+            comparison.set_engine(&input_value(&my_document, "engine"));
+            let difference = my_document
+                .get_element_by_id("compare-difference")
+                .expect("should have #compare-difference on the page")
+                .dyn_into::<web_sys::HtmlInputElement>()
+                .expect("#compare-difference should be an `HtmlInputElement`");
+            comparison.set_show_difference(difference.checked());
+            comparison.draw(&my_canvas.borrow());
+            *my_comparison.borrow_mut() = Some(comparison);
+            panel.set_hidden(false);
+        }) as Box<dyn FnMut(_)>);
+        element::<web_sys::Element>(&document, "compare")?.listen(
+            &mut listeners,
+            "change",
+            &compare_callback,
+        );
+        listeners.keep(compare_callback);
+
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let my_comparison = comparison.clone();
+        let compare_rule_callback = Closure::wrap(Box::new(move || {
+            if let Some(comparison) = my_comparison.borrow_mut().as_mut() {
+                let rule = input_value(&my_document, "compare-rule");
+                if let Err(err) = comparison.set_rule(&rule) {
+                    return log!("{}", tr!(CouldNotSetRule, err));
+                }
+                comparison.draw(&my_canvas.borrow());
+            }
+        }) as Box<dyn FnMut()>);
+        element::<web_sys::Element>(&document, "compare-rule")?.listen(
+            &mut listeners,
+            "change",
+            &compare_rule_callback,
+        );
+        listeners.keep(compare_rule_callback);
+
+        let my_canvas = canvas.clone();
+        let my_comparison = comparison.clone();
+        let difference_callback = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let checkbox = event
+                .target()
+                .unwrap()
+                .dyn_into::<web_sys::HtmlInputElement>()
+                .unwrap();
+            if let Some(comparison) = my_comparison.borrow_mut().as_mut() {
+                comparison.set_show_difference(checkbox.checked());
+                comparison.draw(&my_canvas.borrow());
+            }
+        }) as Box<dyn FnMut(_)>);
+        element::<web_sys::Element>(&document, "compare-difference")?.listen(
+            &mut listeners,
+            "change",
+            &difference_callback,
+        );
+        listeners.keep(difference_callback);
+
+        let my_canvas = canvas.clone();
+        let my_comparison = comparison.clone();
+        let reset_callback = Closure::wrap(Box::new(move || {
+            if let Some(comparison) = my_comparison.borrow_mut().as_mut() {
+                comparison.reset(&my_canvas.borrow());
+                comparison.draw(&my_canvas.borrow());
+            }
+        }) as Box<dyn FnMut()>);
+        element::<web_sys::HtmlElement>(&document, "compare-reset")?.listen(
+            &mut listeners,
+            "click",
+            &reset_callback,
+        );
+        listeners.keep(reset_callback);
+
+        let my_canvas = canvas.clone();
+        let my_comparison = comparison.clone();
+        let compare_click_callback = Closure::wrap(Box::new(move |event: web_sys::MouseEvent| {
+            if let Some(comparison) = my_comparison.borrow_mut().as_mut() {
+                let (x, y) = (event.offset_x() as f64, event.offset_y() as f64);
+                comparison.toggle_cell_at(x, y);
+                comparison.draw(&my_canvas.borrow());
+            }
+        }) as Box<dyn FnMut(_)>);
+        element::<web_sys::Element>(&document, "compare-canvas")?.listen(
+            &mut listeners,
+            "click",
+            &compare_click_callback,
+        );
+        listeners.keep(compare_click_callback);
+    }
+
+    // Create the GIF recording callback, which starts a recording or stops
+    // the current one and offers it for download.
+    {
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let record_callback = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let button = event
+                .target()
+                .unwrap()
+                .dyn_into::<web_sys::HtmlElement>()
+                .unwrap();
+            if my_canvas.borrow().is_recording() {
+                let frames = my_canvas.borrow().recorded_frames();
+                match my_canvas.borrow_mut().stop_recording() {
+                    Ok(gif) => {
+                        offer_download(&my_document, "gif-download", &gif, "image/gif");
+                        log!("{}", tr!(RecordedFrames, frames));
+                    }
+                    Err(err) => log!("{}", tr!(CouldNotRecord, format!("{:?}", err))),
+                }
+                button.set_text_content(Some(&tr!(RecordGif)));
+                return;
+            }
+            let delay = input_value(&my_document, "gif-delay").parse::<u32>();
+            let max_frames = input_value(&my_document, "gif-max-frames").parse::<u32>();
+            let (delay, max_frames) = match (delay, max_frames) {
+                (Ok(delay), Ok(max_frames)) => (delay, max_frames),
+                _ => return log!("{}", tr!(InvalidGifSettings)),
+            };
+            match my_canvas.borrow_mut().start_recording(delay, max_frames) {
+                Ok(()) => button.set_text_content(Some(&tr!(StopRecording))),
+                Err(err) => log!("{}", tr!(CouldNotRecord, format!("{:?}", err))),
+            }
+        }) as Box<dyn FnMut(_)>);
+        element::<web_sys::Element>(&document, "record-gif")?.listen(
+            &mut listeners,
+            "click",
+            &record_callback,
+        );
+        listeners.keep(record_callback);
+    }
+
+    // Create the video recording callback, which starts recording the
+    // canvas or stops and offers the video for download.
+    {
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let recorder: RefCell<Option<VideoRecorder>> = RefCell::new(None);
+        let video_callback = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let button = event
+                .target()
+                .unwrap()
+                .dyn_into::<web_sys::HtmlElement>()
+                .unwrap();
+            if let Some(recording) = recorder.borrow_mut().take() {
+                let document = my_document.clone();
+                let stopped = recording.stop(move |video| {
+                    offer_blob_download(&document, "video-download", &video);
+                });
+                if let Err(err) = stopped {
+                    log!("{}", tr!(CouldNotStopRecording, format!("{:?}", err)));
+                }
+                button.set_text_content(Some(&tr!(RecordVideo)));
+                return;
+            }
+            let fps = input_value(&my_document, "video-fps").parse::<f64>();
+            let bitrate = input_value(&my_document, "video-bitrate").parse::<u32>();
+            let (fps, bitrate) = match (fps, bitrate) {
+                (Ok(fps), Ok(bitrate)) if fps > 0.0 => (fps, bitrate),
+                _ => return log!("{}", tr!(InvalidVideoSettings)),
+            };
+            // The bitrate is entered in kilobits per second.
+            let started = VideoRecorder::start(
+                &my_canvas.borrow().canvas,
+                fps,
+                bitrate.saturating_mul(1000),
+            );
+            match started {
+                Ok(recording) => {
+                    *recorder.borrow_mut() = Some(recording);
+                    button.set_text_content(Some(&tr!(StopVideo)));
+                }
+                Err(err) => log!("{}", tr!(CouldNotRecord, format!("{:?}", err))),
+            }
+        }) as Box<dyn FnMut(_)>);
+        element::<web_sys::Element>(&document, "record-video")?.listen(
+            &mut listeners,
+            "click",
+            &video_callback,
+        );
+        listeners.keep(video_callback);
+    }
+
+    // Create the PNG export callback.
+    {
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let png_callback = Closure::wrap(Box::new(move || {
+            let scale = match input_value(&my_document, "png-scale").parse::<u32>() {
+                Ok(scale) => scale,
+                Err(_) => return log!("{}", tr!(InvalidPngScale)),
+            };
+            let png = my_canvas.borrow().export_png(scale);
+            match png {
+                Ok(png) => offer_download(&my_document, "png-download", &png, "image/png").click(),
+                Err(err) => log!("{}", tr!(CouldNotExport, format!("{:?}", err))),
+            }
+        }) as Box<dyn FnMut()>);
+        element::<web_sys::HtmlElement>(&document, "export-png")?.listen(
+            &mut listeners,
+            "click",
+            &png_callback,
+        );
+        listeners.keep(png_callback);
+    }
+
+    // Create the animated PNG export callback.
+    {
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let apng_callback = Closure::wrap(Box::new(move || {
+            let generations = input_value(&my_document, "apng-generations").parse::<u32>();
+            let scale = input_value(&my_document, "png-scale").parse::<u32>();
+            let delay = input_value(&my_document, "apng-delay").parse::<u32>();
+            let (generations, scale, delay) = match (generations, scale, delay) {
+                (Ok(generations), Ok(scale), Ok(delay)) => (generations, scale, delay),
+                _ => return log!("{}", tr!(InvalidAnimationSettings)),
+            };
+            let apng = my_canvas.borrow().export_apng(generations, scale, delay);
+            match apng {
+                Ok(apng) => {
+                    offer_download(&my_document, "apng-download", &apng, "image/apng").click()
+                }
+                Err(err) => log!("{}", tr!(CouldNotExport, format!("{:?}", err))),
+            }
+        }) as Box<dyn FnMut()>);
+        element::<web_sys::HtmlElement>(&document, "export-apng")?.listen(
+            &mut listeners,
+            "click",
+            &apng_callback,
+        );
+        listeners.keep(apng_callback);
+    }
+
+    // Create the sound callback. Sound starts the first time the box is
+    // ticked, since browsers only allow it after a user's input.
+    {
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let sound_callback = Closure::wrap(Box::new(move || {
+            let select_value = |id: &str| {
+                my_document
+                    .get_element_by_id(id)
+                    .unwrap_or_else(|| panic!("should have #{} on the page", id))
+                    .dyn_into::<web_sys::HtmlSelectElement>()
+                    .unwrap_or_else(|_| panic!("#{} should be an `HtmlSelectElement`", id))
+                    .value()
+            };
+            let on = my_document
+                .get_element_by_id("sound")
+                .expect("should have #sound on the page")
+                .dyn_into::<web_sys::HtmlInputElement>()
+                .expect("#sound should be an `HtmlInputElement`")
+                .checked();
+            let volume = input_value(&my_document, "volume").parse::<f32>();
+            let scale = Scale::from_name(&select_value("sound-scale"));
+            let sonification = Sonification::from_name(&select_value("sonification"));
+            let (volume, scale, sonification) = match (volume, scale, sonification) {
+                (Ok(volume), Some(scale), Some(sonification)) => (volume, scale, sonification),
+                _ => return log!("{}", tr!(InvalidSoundSettings)),
+            };
+            // The slider goes from 0 to 100.
+            let set = my_canvas
+                .borrow_mut()
+                .set_sound(on, volume / 100.0, scale, sonification);
+            if let Err(err) = set {
+                log!("{}", tr!(CouldNotPlaySound, format!("{:?}", err)));
+            }
+        }) as Box<dyn FnMut()>);
+        for &id in ["sound", "volume", "sound-scale", "sonification"].iter() {
+            element::<web_sys::Element>(&document, id)?.listen(
+                &mut listeners,
+                "change",
+                &sound_callback,
+            );
+        }
+        listeners.keep(sound_callback);
+    }
+
+    // Create the topology callback.
+    {
+        let my_canvas = canvas.clone();
+        let topology_callback = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let select = event
+                .target()
+                .unwrap()
+                .dyn_into::<web_sys::HtmlSelectElement>()
+                .unwrap();
+            match Topology::from_name(&select.value()) {
+                Some(topology) => my_canvas.borrow_mut().set_topology(topology),
+                None => log!("{}", tr!(UnknownTopology, select.value())),
+            }
+        }) as Box<dyn FnMut(_)>);
+        element::<web_sys::Element>(&document, "topology")?.listen(
+            &mut listeners,
+            "change",
+            &topology_callback,
+        );
+        listeners.keep(topology_callback);
+    }
+
+    // Create the rule callback.
+    {
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let rule_callback = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let input = event
+                .target()
+                .unwrap()
+                .dyn_into::<web_sys::HtmlInputElement>()
+                .unwrap();
+            let mut canvas = my_canvas.borrow_mut();
+            match canvas.set_rule(&input.value()) {
+                Ok(()) => show_rule(&canvas, &my_document),
+                Err(err) => log!("{}", tr!(CouldNotSetRule, err)),
+            }
+        }) as Box<dyn FnMut(_)>);
+        element::<web_sys::Element>(&document, "rule")?.listen(
+            &mut listeners,
+            "change",
+            &rule_callback,
+        );
+        listeners.keep(rule_callback);
+    }
+
+    // Create the neighborhood callback.
+    {
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let neighborhood_callback = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let select = event
+                .target()
+                .unwrap()
+                .dyn_into::<web_sys::HtmlSelectElement>()
+                .unwrap();
+            match Neighborhood::from_name(&select.value()) {
+                Some(neighborhood) => {
+                    let mut canvas = my_canvas.borrow_mut();
+                    canvas.set_neighborhood(neighborhood);
+                    show_rule(&canvas, &my_document);
+                }
+                None => log!("{}", tr!(UnknownNeighborhood, select.value())),
+            }
+        }) as Box<dyn FnMut(_)>);
+        element::<web_sys::Element>(&document, "neighborhood")?.listen(
+            &mut listeners,
+            "change",
+            &neighborhood_callback,
+        );
+        listeners.keep(neighborhood_callback);
+    }
+
+    // Create the step callbacks.
+    for &generations in [1, 10, 100].iter() {
+        let my_canvas = canvas.clone();
+        let my_comparison = comparison.clone();
+        let step_callback = Closure::wrap(Box::new(move || {
+            step(&my_canvas, &my_comparison, generations);
+            my_canvas.borrow_mut().draw();
+        }) as Box<dyn FnMut()>);
+        let id = format!("step-{}", generations);
+        element::<web_sys::HtmlElement>(&document, &id)?.listen(
+            &mut listeners,
+            "click",
+            &step_callback,
+        );
+        listeners.keep(step_callback);
+    }
+
+    // Create the undo and redo callbacks.
+    {
+        let my_canvas = canvas.clone();
+        let back_callback = Closure::wrap(Box::new(move || {
+            my_canvas.borrow_mut().undo();
+            my_canvas.borrow_mut().draw();
+        }) as Box<dyn FnMut()>);
+        element::<web_sys::HtmlElement>(&document, "back")?.listen(
+            &mut listeners,
+            "click",
+            &back_callback,
+        );
+        listeners.keep(back_callback);
+
+        let my_canvas = canvas.clone();
+        let forward_callback = Closure::wrap(Box::new(move || {
+            my_canvas.borrow_mut().redo();
+            my_canvas.borrow_mut().draw();
+        }) as Box<dyn FnMut()>);
+        element::<web_sys::HtmlElement>(&document, "forward")?.listen(
+            &mut listeners,
+            "click",
+            &forward_callback,
+        );
+        listeners.keep(forward_callback);
+    }
+
+    // Create the speed callback.
+    {
+        let speed_callback = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let input = event
+                .target()
+                .unwrap()
+                .dyn_into::<web_sys::HtmlInputElement>()
+                .unwrap();
+            match input.value().parse::<i32>() {
+                Ok(value) => *speed.borrow_mut() = Speed::from_slider(value),
+                Err(_) => log!("{}", tr!(InvalidSpeed, input.value())),
+            }
+        }) as Box<dyn FnMut(_)>);
+        element::<web_sys::Element>(&document, "speed")?.listen(
+            &mut listeners,
+            "input",
+            &speed_callback,
+        );
+        listeners.keep(speed_callback);
+    }
+
+    // Create the random, clear and start pattern callbacks.
+    {
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let random_callback = Closure::wrap(Box::new(move || {
+            randomize_from_seed(&my_canvas, &my_document, new_seed());
+        }) as Box<dyn FnMut()>);
+        element::<web_sys::HtmlElement>(&document, "random")?.listen(
+            &mut listeners,
+            "click",
+            &random_callback,
+        );
+        listeners.keep(random_callback);
+
+        let my_canvas = canvas.clone();
+        let clear_callback = Closure::wrap(Box::new(move || {
+            my_canvas.borrow_mut().clear();
+            my_canvas.borrow_mut().draw();
+        }) as Box<dyn FnMut()>);
+        element::<web_sys::HtmlElement>(&document, "clear")?.listen(
+            &mut listeners,
+            "click",
+            &clear_callback,
+        );
+        listeners.keep(clear_callback);
+
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let start_callback = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let select = event
+                .target()
+                .unwrap()
+                .dyn_into::<web_sys::HtmlSelectElement>()
+                .unwrap();
+            match select.value().as_str() {
+                "stripes" => my_canvas.borrow_mut().fill_stripes(),
+                "random" => randomize_from_seed(&my_canvas, &my_document, new_seed()),
+                _ => my_canvas.borrow_mut().clear(),
+            }
+            my_canvas.borrow_mut().draw();
+        }) as Box<dyn FnMut(_)>);
+        element::<web_sys::Element>(&document, "start-pattern")?.listen(
+            &mut listeners,
+            "change",
+            &start_callback,
+        );
+        listeners.keep(start_callback);
+
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let seed_callback =
+            Closure::wrap(Box::new(
+                move || match input_value(&my_document, "seed").parse::<u64>() {
+                    Ok(seed) => randomize_from_seed(&my_canvas, &my_document, seed),
+                    Err(_) => log!("{}", tr!(InvalidSeed)),
+                },
+            ) as Box<dyn FnMut()>);
+        element::<web_sys::Element>(&document, "seed")?.listen(
+            &mut listeners,
+            "change",
+            &seed_callback,
+        );
+        listeners.keep(seed_callback);
+    }
+
+    // Create the keyboard shortcut callback.
+    {
+        let keybindings = Keybindings::default();
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let my_comparison = comparison.clone();
+        let state = state.clone();
+        let animation_callback = animation_callback.clone();
+        let keydown_callback = Closure::wrap(Box::new(move |event: web_sys::KeyboardEvent| {
+            // Leave keys alone while the user is typing into a form field.
+            if let Some(target) = event.target() {
+                if target.is_instance_of::<web_sys::HtmlInputElement>()
+                    || target.is_instance_of::<web_sys::HtmlTextAreaElement>()
+                    || target.is_instance_of::<web_sys::HtmlSelectElement>()
+                {
+                    return;
+                }
+            }
+            if event.alt_key() {
+                return;
+            }
+            let key = if event.ctrl_key() || event.meta_key() {
+                format!("Ctrl+{}", event.key())
+            } else {
+                event.key()
+            };
+            let action = if my_canvas.borrow().is_placing() {
+                keybindings.placing_action(&key)
+            } else if my_canvas.borrow().is_accessible() {
+                keybindings.cursor_action(&key)
+            } else {
+                keybindings.action(&key)
+            };
+            let action = match action {
+                Some(action) => action,
+                None => return,
+            };
+            // Without a selection, leave copying and deleting to the browser.
+            let selection_action = matches!(action, Action::Copy | Action::Cut | Action::Delete);
+            if selection_action && !my_canvas.borrow().has_selection() {
+                return;
+            }
+            event.prevent_default();
+
+            match action {
+                Action::PlayPause => state.borrow_mut().toggle(&animation_callback),
+                Action::Step => step(&my_canvas, &my_comparison, 1),
+                Action::Clear => my_canvas.borrow_mut().clear(),
+                Action::Random => randomize_from_seed(&my_canvas, &my_document, new_seed()),
+                Action::Pan(rows, columns) => {
+                    let pitch = my_canvas.borrow().viewport.pitch();
+                    my_canvas
+                        .borrow_mut()
+                        .pan(columns as f64 * pitch, rows as f64 * pitch);
+                }
+                Action::ZoomIn => {
+                    let cell_size = my_canvas.borrow().cell_size();
+                    my_canvas.borrow_mut().zoom(cell_size + 1);
+                    show_cell_size(&my_canvas, &my_document);
+                }
+                Action::ZoomOut => {
+                    let cell_size = my_canvas.borrow().cell_size();
+                    my_canvas.borrow_mut().zoom(cell_size.saturating_sub(1));
+                    show_cell_size(&my_canvas, &my_document);
+                }
+                Action::Fit => {
+                    my_canvas.borrow_mut().fit_to_pattern();
+                    show_cell_size(&my_canvas, &my_document);
+                }
+                Action::Cancel => {
+                    my_canvas.borrow_mut().cancel_placing();
+                    my_canvas.borrow_mut().deselect();
+                }
+                Action::Copy => copy_selection(&my_canvas, &my_document),
+                Action::Cut => cut_selection(&my_canvas, &my_document),
+                Action::Paste => paste_text(&my_canvas, &my_document),
+                Action::Delete => delete_selection(&my_canvas, &my_document),
+                Action::Rotate => my_canvas.borrow_mut().rotate_placement(),
+                Action::Flip(axis) => my_canvas.borrow_mut().flip_placement(axis),
+                Action::Undo => {
+                    my_canvas.borrow_mut().undo();
+                }
+                Action::Redo => {
+                    my_canvas.borrow_mut().redo();
+                }
+                Action::MoveCursor(rows, columns) => {
+                    my_canvas.borrow_mut().move_cursor(rows, columns)
+                }
+                Action::ToggleCursorCell => my_canvas.borrow_mut().toggle_cursor_cell(),
+            }
+            my_canvas.borrow_mut().draw();
+        }) as Box<dyn FnMut(_)>);
+        document.listen(&mut listeners, "keydown", &keydown_callback);
+        listeners.keep(keydown_callback);
+    }
+
+    // Create the zoom and pan callbacks. The mouse wheel zooms around the
+    // cursor, and dragging with the middle button or with alt held pans.
+    {
+        let my_canvas = canvas.clone();
+        let my_document = document.clone();
+        let wheel_callback = Closure::wrap(Box::new(move |event: web_sys::WheelEvent| {
+            event.prevent_default();
+            let cell_size = my_canvas.borrow().cell_size();
+            let cell_size = if event.delta_y() < 0.0 {
+                cell_size + 1
+            } else {
+                cell_size.saturating_sub(1)
+            };
+            let (x, y) = (event.offset_x() as f64, event.offset_y() as f64);
+            my_canvas.borrow_mut().zoom_at(cell_size, x, y);
+            my_canvas.borrow_mut().draw();
+            show_cell_size(&my_canvas, &my_document);
+        }) as Box<dyn FnMut(_)>);
+        canvas
+            .borrow()
+            .canvas
+            .listen(&mut listeners, "wheel", &wheel_callback);
+        listeners.keep(wheel_callback);
+
+        // The last mouse position of a pan in progress.
+        let pan_from: Rc<RefCell<Option<(f64, f64)>>> = Rc::new(RefCell::new(None));
+
+        let my_pan_from = pan_from.clone();
+        let mousedown_callback = Closure::wrap(Box::new(move |event: web_sys::MouseEvent| {
+            if event.button() == 1 || (event.button() == 0 && event.alt_key()) {
+                event.prevent_default();
+                *my_pan_from.borrow_mut() =
+                    Some((event.offset_x() as f64, event.offset_y() as f64));
+            }
+        }) as Box<dyn FnMut(_)>);
+        canvas
+            .borrow()
+            .canvas
+            .listen(&mut listeners, "mousedown", &mousedown_callback);
+        listeners.keep(mousedown_callback);
+
+        let my_canvas = canvas.clone();
+        let my_pan_from = pan_from.clone();
+        let mousemove_callback = Closure::wrap(Box::new(move |event: web_sys::MouseEvent| {
+            let (x, y) = (event.offset_x() as f64, event.offset_y() as f64);
+            let (from_x, from_y) = match my_pan_from.replace(Some((x, y))) {
+                Some(from) => from,
+                None => {
+                    *my_pan_from.borrow_mut() = None;
+                    return;
+                }
+            };
+            my_canvas.borrow_mut().pan(from_x - x, from_y - y);
+            my_canvas.borrow_mut().draw();
+        }) as Box<dyn FnMut(_)>);
+        canvas
+            .borrow()
+            .canvas
+            .listen(&mut listeners, "mousemove", &mousemove_callback);
+        listeners.keep(mousemove_callback);
+
+        let end_callback = Closure::wrap(Box::new(move || {
+            *pan_from.borrow_mut() = None;
+        }) as Box<dyn FnMut()>);
+        for event in ["mouseup", "mouseleave"] {
+            canvas
+                .borrow()
+                .canvas
+                .listen(&mut listeners, event, &end_callback);
+        }
+        listeners.keep(end_callback);
+    }
+
+    // The page's own loop doesn't send events, but `step` on the handle
+    // does.
+    let events = Rc::new(RefCell::new(Subscriptions::new(&canvas.borrow().universe)));
+    let handle = GameHandle::new(canvas, state, animation_callback, listeners, events);
+    PAGE.with(|page| page.replace(Some(handle)));
+    Ok(())
+}
+
+thread_local! {
+    /// The game `start` set up on the page, until it is taken to dispose of.
+    static PAGE: RefCell<Option<GameHandle>> = const { RefCell::new(None) };
+}
+
+/// Hand over the game on the page, so that it can be disposed of before the
+/// module is loaded again, as when reloading it while developing. Returns
+/// nothing on pages without one, or once it has been taken.
+#[wasm_bindgen]
+pub fn take_page_game() -> Option<GameHandle> {
+    PAGE.with(|page| page.borrow_mut().take())
+}
@@ -0,0 +1,29 @@
+//! Whether ticks can be split across threads.
+//!
+//! Natively Rayon starts its own threads, so they are available whenever
+//! the `parallel` feature is on. The browser has no threads of its own: the
+//! `wasm-threads` build exports `initThreadPool` to start a pool of workers,
+//! and the page calls `thread_pool_ready` once it is running.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(all(target_arch = "wasm32", feature = "wasm-threads"))]
+pub use wasm_bindgen_rayon::init_thread_pool;
+
+static THREADS: AtomicBool =
+    AtomicBool::new(cfg!(all(feature = "parallel", not(target_arch = "wasm32"))));
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+pub fn threads_available() -> bool {
+    THREADS.load(Ordering::Relaxed)
+}
+
+/// Called by the page once `initThreadPool` has started the pool.
+#[cfg(all(target_arch = "wasm32", feature = "wasm-threads"))]
+#[wasm_bindgen]
+pub fn thread_pool_ready() {
+    THREADS.store(true, Ordering::Relaxed);
+}
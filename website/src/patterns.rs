@@ -0,0 +1,456 @@
+//! Reading and writing Game of Life patterns.
+//!
+//! Patterns use the Run Length Encoded (RLE) format described at
+//! <https://conwaylife.com/wiki/Run_Length_Encoded>, and can also be read
+//! from and written to Life 1.06 and plaintext `.cells` files.
+
+pub mod library;
+mod life106;
+mod plaintext;
+
+use std::convert::TryFrom;
+use std::fmt;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use crate::rule::Rule;
+use crate::{Cell, Position};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// The `x = .., y = ..` header line was not found.
+    MissingHeader,
+    /// The header line could not be parsed.
+    InvalidHeader(String),
+    /// The pattern body contained a character that is not part of RLE.
+    UnexpectedCharacter(char),
+    /// The pattern body described more cells than the header allows.
+    OutOfBounds,
+    /// The header's `rule` was not a valid rulestring.
+    InvalidRule(String),
+    /// A Life 1.06 line was not a pair of integer coordinates.
+    InvalidCoordinate(String),
+    /// A macrocell line was neither a leaf nor a node built from earlier
+    /// lines.
+    InvalidNode(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::MissingHeader => write!(f, "missing `x = .., y = ..` header"),
+            ParseError::InvalidHeader(line) => write!(f, "invalid header: {}", line),
+            ParseError::UnexpectedCharacter(c) => write!(f, "unexpected character {:?}", c),
+            ParseError::OutOfBounds => write!(f, "pattern is larger than its header"),
+            ParseError::InvalidRule(rule) => write!(f, "invalid rule {:?}", rule),
+            ParseError::InvalidCoordinate(line) => write!(f, "invalid coordinates {:?}", line),
+            ParseError::InvalidNode(line) => write!(f, "invalid macrocell node {:?}", line),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[cfg(target_arch = "wasm32")]
+impl From<ParseError> for JsValue {
+    fn from(error: ParseError) -> JsValue {
+        JsValue::from_str(&error.to_string())
+    }
+}
+
+/// A mirror axis for `Pattern::flip`.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Flip {
+    /// Swap left and right.
+    Horizontal,
+    /// Swap top and bottom.
+    Vertical,
+}
+
+/// A rectangular block of cells that can be placed into a `Universe`.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pattern {
+    width: u32,
+    height: u32,
+    cells: Vec<Cell>,
+    /// The rule named in the RLE header, if any.
+    rule: Option<Rule>,
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Pattern {
+    /// Parse a pattern in any supported format, telling them apart by their
+    /// contents.
+    pub fn parse(text: &str) -> Result<Pattern, ParseError> {
+        if text.trim_start().starts_with(life106::HEADER) {
+            Pattern::from_life106(text)
+        } else if plaintext::detect(text) {
+            Pattern::from_plaintext(text)
+        } else {
+            Pattern::from_rle(text)
+        }
+    }
+
+    /// Parse a pattern from the contents of a Life 1.06 `.lif` file.
+    pub fn from_life106(text: &str) -> Result<Pattern, ParseError> {
+        life106::parse(text)
+    }
+
+    /// Serialize the live cells into the contents of a Life 1.06 file.
+    pub fn to_life106(&self) -> String {
+        life106::write(self)
+    }
+
+    /// Parse a pattern from the contents of a plaintext `.cells` file.
+    pub fn from_plaintext(text: &str) -> Result<Pattern, ParseError> {
+        plaintext::parse(text)
+    }
+
+    /// Serialize the pattern into the contents of a plaintext `.cells` file.
+    pub fn to_plaintext(&self) -> String {
+        plaintext::write(self)
+    }
+
+    /// Parse a pattern from the contents of an `.rle` file.
+    pub fn from_rle(text: &str) -> Result<Pattern, ParseError> {
+        let board = NumberedStates::from_rle(text)?;
+        Ok(Pattern {
+            width: board.width,
+            height: board.height,
+            cells: board.states.into_iter().map(Cell::from_state).collect(),
+            rule: board.rule,
+        })
+    }
+
+    /// Serialize the pattern into the contents of an `.rle` file.
+    pub fn to_rle(&self) -> String {
+        NumberedStates {
+            width: self.width,
+            height: self.height,
+            rule: self.rule,
+            states: self.cells.iter().map(|&cell| cell as u8).collect(),
+        }
+        .to_rle()
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The cell at `position`, or `None` outside the pattern.
+    pub fn cell(&self, position: Position) -> Option<Cell> {
+        if position.row >= self.height || position.column >= self.width {
+            return None;
+        }
+        Some(self.cell_at(position.row, position.column))
+    }
+
+    pub fn rule(&self) -> Option<Rule> {
+        self.rule
+    }
+
+    /// The pattern turned clockwise by `quarter_turns` right angles.
+    pub fn rotate(&self, quarter_turns: u8) -> Pattern {
+        let mut pattern = self.clone();
+        for _ in 0..quarter_turns % 4 {
+            let cells = (0..pattern.width)
+                .flat_map(|row| (0..pattern.height).map(move |col| (row, col)))
+                .map(|(row, col)| pattern.cell_at(pattern.height - 1 - col, row))
+                .collect();
+            pattern = Pattern {
+                width: pattern.height,
+                height: pattern.width,
+                cells,
+                rule: pattern.rule,
+            };
+        }
+        pattern
+    }
+
+    /// The pattern mirrored across `axis`.
+    pub fn flip(&self, axis: Flip) -> Pattern {
+        let cells = (0..self.height)
+            .flat_map(|row| (0..self.width).map(move |col| (row, col)))
+            .map(|(row, col)| match axis {
+                Flip::Horizontal => self.cell_at(row, self.width - 1 - col),
+                Flip::Vertical => self.cell_at(self.height - 1 - row, col),
+            })
+            .collect();
+        Pattern {
+            cells,
+            ..self.clone()
+        }
+    }
+
+    /// The names of the built-in patterns.
+    pub fn list() -> Vec<String> {
+        library::names().map(str::to_string).collect()
+    }
+
+    /// The built-in pattern called `name`, as listed by `list`.
+    pub fn from_library(name: &str) -> Option<Pattern> {
+        library::get(name)
+    }
+}
+
+impl Pattern {
+    pub(crate) fn from_cells(width: u32, height: u32, cells: Vec<Cell>) -> Pattern {
+        debug_assert_eq!(cells.len(), (width * height) as usize);
+        Pattern {
+            width,
+            height,
+            cells,
+            rule: None,
+        }
+    }
+
+    pub(crate) fn with_rule(mut self, rule: Rule) -> Pattern {
+        self.rule = Some(rule);
+        self
+    }
+
+    /// The cell at `row` and `column`, which must be in the pattern; `cell`
+    /// checks.
+    pub(crate) fn cell_at(&self, row: u32, column: u32) -> Cell {
+        self.cells[self.get_index(row, column)]
+    }
+
+    fn get_index(&self, row: u32, column: u32) -> usize {
+        row as usize * self.width as usize + column as usize
+    }
+}
+
+/// The most cells a pattern, or a universe, may span. Headers and Life
+/// 1.06 coordinates are only numbers, so a few bytes could otherwise ask
+/// for a huge grid.
+pub const MAX_CELLS: u64 = 1 << 26;
+
+/// RLE lines should not be longer than 70 characters.
+const MAX_LINE_LENGTH: usize = 70;
+
+/// The letters of multi-state RLE, `A` to `X` for states 1 to 24. Later
+/// states put `p` to `y` in front, so that 25 is `pA` and 49 is `qA`.
+const STATE_LETTERS: u32 = 24;
+
+/// A board of numbered states, as read from and written to RLE. Patterns
+/// only tell live and dying cells apart, while universes keep every
+/// Generations state and color.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct NumberedStates {
+    pub width: u32,
+    pub height: u32,
+    pub rule: Option<Rule>,
+    /// In row-major order.
+    pub states: Vec<u8>,
+}
+
+impl NumberedStates {
+    pub fn from_rle(text: &str) -> Result<NumberedStates, ParseError> {
+        let mut lines = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+
+        let header = lines.next().ok_or(ParseError::MissingHeader)?;
+        let (width, height, rule) = parse_header(header)?;
+        if width as u64 * height as u64 > MAX_CELLS {
+            return Err(ParseError::OutOfBounds);
+        }
+        let mut states = vec![0; width as usize * height as usize];
+
+        // Counts and positions beyond what a `u32` holds are certainly off
+        // the pattern, rather than something to wrap around.
+        let mut row: u32 = 0;
+        let mut column: u32 = 0;
+        let mut count: Option<u32> = None;
+        // The `p` to `y` in front of a state's letter, if one was just read.
+        let mut prefix: Option<u32> = None;
+        'body: for line in lines {
+            for c in line.chars() {
+                if prefix.is_some() && !c.is_ascii_uppercase() {
+                    return Err(ParseError::UnexpectedCharacter(c));
+                }
+                match c {
+                    '0'..='9' => {
+                        let digit = c.to_digit(10).unwrap();
+                        let digits = count.unwrap_or(0).checked_mul(10);
+                        let digits = digits.and_then(|count| count.checked_add(digit));
+                        count = Some(digits.ok_or(ParseError::OutOfBounds)?);
+                        continue;
+                    }
+                    'p'..='y' => {
+                        prefix = Some(c as u32 - 'p' as u32 + 1);
+                        continue;
+                    }
+                    'b' | '.' => {
+                        column = column
+                            .checked_add(count.unwrap_or(1))
+                            .ok_or(ParseError::OutOfBounds)?;
+                    }
+                    'o' | 'A'..='X' => {
+                        // Multi-state patterns use `A` for live cells and
+                        // later letters for the dying states or colors.
+                        let state = match c {
+                            'o' => 1,
+                            _ => {
+                                prefix.take().unwrap_or(0) * STATE_LETTERS + c as u32 - 'A' as u32
+                                    + 1
+                            }
+                        };
+                        let state =
+                            u8::try_from(state).map_err(|_| ParseError::UnexpectedCharacter(c))?;
+                        let run = count.unwrap_or(1);
+                        let end = column.checked_add(run).filter(|&end| end <= width);
+                        if row >= height || end.is_none() {
+                            return Err(ParseError::OutOfBounds);
+                        }
+                        let start = row as usize * width as usize + column as usize;
+                        states[start..start + run as usize].fill(state);
+                        column += run;
+                    }
+                    '$' => {
+                        row = row
+                            .checked_add(count.unwrap_or(1))
+                            .ok_or(ParseError::OutOfBounds)?;
+                        column = 0;
+                    }
+                    '!' => break 'body,
+                    c if c.is_whitespace() => {}
+                    c => return Err(ParseError::UnexpectedCharacter(c)),
+                }
+                count = None;
+            }
+        }
+
+        Ok(NumberedStates {
+            width,
+            height,
+            rule,
+            states,
+        })
+    }
+
+    /// Write the board as RLE, in multi-state RLE if any cell is in a
+    /// state after 1.
+    pub fn to_rle(&self) -> String {
+        let multi_state = self.states.iter().any(|&state| state > 1);
+        let mut body = String::new();
+        // Row separators are only written once a later row has live cells.
+        let mut pending_rows = 0;
+        for row in self.states.chunks(self.width.max(1) as usize) {
+            pending_rows += 1;
+            let mut runs = Vec::new();
+            let mut column = 0;
+            while column < row.len() {
+                let state = row[column];
+                let run = row[column..].iter().take_while(|&&s| s == state).count();
+                runs.push((run as u32, state));
+                column += run;
+            }
+            // Trailing dead cells are implied by the end of the row.
+            if let Some((_, 0)) = runs.last() {
+                runs.pop();
+            }
+
+            if runs.is_empty() {
+                continue;
+            }
+            if pending_rows > 1 {
+                push_run(&mut body, pending_rows - 1, "$");
+            }
+            pending_rows = 1;
+            for (run, state) in runs {
+                push_run(&mut body, run, &state_tag(state, multi_state));
+            }
+        }
+        body.push('!');
+
+        let rule = self.rule.unwrap_or_default();
+        let mut rle = format!("x = {}, y = {}, rule = {}\n", self.width, self.height, rule);
+        let mut line_length = 0;
+        // Counts and prefixes stay on the line of the state they belong to.
+        let tokens =
+            body.split_inclusive(|c: char| !c.is_ascii_digit() && !('p'..='y').contains(&c));
+        for token in tokens {
+            if line_length + token.len() > MAX_LINE_LENGTH {
+                rle.push('\n');
+                line_length = 0;
+            }
+            rle.push_str(token);
+            line_length += token.len();
+        }
+        rle.push('\n');
+        rle
+    }
+}
+
+/// How `state` is written in RLE: `b` and `o`, or in multi-state RLE `.`
+/// and letters.
+fn state_tag(state: u8, multi_state: bool) -> String {
+    match (state, multi_state) {
+        (0, false) => "b".to_string(),
+        (1, false) => "o".to_string(),
+        (0, true) => ".".to_string(),
+        (state, _) => {
+            let index = state as u32 - 1;
+            let letter = char::from_u32('A' as u32 + index % STATE_LETTERS).unwrap();
+            match index / STATE_LETTERS {
+                0 => letter.to_string(),
+                prefix => {
+                    let prefix = char::from_u32('p' as u32 + prefix - 1).unwrap();
+                    format!("{}{}", prefix, letter)
+                }
+            }
+        }
+    }
+}
+
+fn push_run(body: &mut String, run: u32, tag: &str) {
+    if run > 1 {
+        body.push_str(&run.to_string());
+    }
+    body.push_str(tag);
+}
+
+/// Parse the `x = 3, y = 3, rule = B3/S23` line at the top of an RLE file.
+fn parse_header(line: &str) -> Result<(u32, u32, Option<Rule>), ParseError> {
+    let invalid = || ParseError::InvalidHeader(line.to_string());
+
+    let mut width = None;
+    let mut height = None;
+    let mut rule = None;
+    let mut fields = line.split(',');
+    while let Some(field) = fields.next() {
+        let mut parts = field.splitn(2, '=');
+        let key = parts.next().ok_or_else(invalid)?.trim();
+        let value = parts.next().ok_or_else(invalid)?.trim();
+        match key {
+            "x" => width = Some(value.parse::<u32>().map_err(|_| invalid())?),
+            "y" => height = Some(value.parse::<u32>().map_err(|_| invalid())?),
+            "rule" => {
+                // Some rules have commas of their own, so the rule is the
+                // rest of the line.
+                let value = std::iter::once(value)
+                    .chain(fields.by_ref())
+                    .collect::<Vec<_>>()
+                    .join(",");
+                let value = value.trim();
+                let parsed = Rule::parse(value);
+                rule = Some(parsed.map_err(|_| ParseError::InvalidRule(value.to_string()))?);
+            }
+            _ => {}
+        }
+    }
+
+    match (width, height) {
+        (Some(width), Some(height)) => Ok((width, height, rule)),
+        _ => Err(ParseError::MissingHeader),
+    }
+}
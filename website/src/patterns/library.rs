@@ -0,0 +1,39 @@
+//! Classic patterns that ship with the page.
+
+use super::Pattern;
+
+/// The name and RLE text of every built-in pattern.
+pub const PATTERNS: &[(&str, &str)] = &[
+    ("Glider", "x = 3, y = 3, rule = B3/S23\nbob$2bo$3o!"),
+    (
+        "Lightweight spaceship",
+        "x = 5, y = 4, rule = B3/S23\nbo2bo$o4b$o3bo$4o!",
+    ),
+    (
+        "Gosper glider gun",
+        "x = 36, y = 9, rule = B3/S23\n\
+         24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$\n\
+         2o8bo3bob2o4bobo$10bo5bo7bo$11bo3bo$12b2o!",
+    ),
+    (
+        "Pulsar",
+        "x = 13, y = 13, rule = B3/S23\n\
+         2b3o3b3o2b2$o4bobo4bo$o4bobo4bo$o4bobo4bo$2b3o3b3o2b2$2b3o3b3o2b$\n\
+         o4bobo4bo$o4bobo4bo$o4bobo4bo2$2b3o3b3o!",
+    ),
+    ("R-pentomino", "x = 3, y = 3, rule = B3/S23\nb2o$2ob$bo!"),
+    ("Acorn", "x = 7, y = 3, rule = B3/S23\nbo5b$3bo3b$2o2b3o!"),
+];
+
+/// The names of the built-in patterns, in the order they are listed.
+pub fn names() -> impl Iterator<Item = &'static str> {
+    PATTERNS.iter().map(|&(name, _)| name)
+}
+
+/// The built-in pattern called `name`.
+pub fn get(name: &str) -> Option<Pattern> {
+    PATTERNS
+        .iter()
+        .find(|&&(pattern_name, _)| pattern_name == name)
+        .map(|&(_, rle)| Pattern::from_rle(rle).expect("built-in patterns should parse"))
+}
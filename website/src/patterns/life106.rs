@@ -0,0 +1,59 @@
+//! The Life 1.06 format: a `#Life 1.06` header followed by one `x y` line
+//! per live cell. See <https://conwaylife.com/wiki/Life_1.06>.
+
+use std::convert::TryFrom;
+
+use super::{ParseError, Pattern, MAX_CELLS};
+use crate::Cell;
+
+pub const HEADER: &str = "#Life 1.06";
+
+pub fn parse(text: &str) -> Result<Pattern, ParseError> {
+    let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+    match lines.next() {
+        Some(line) if line.starts_with(HEADER) => {}
+        _ => return Err(ParseError::MissingHeader),
+    }
+
+    let mut coordinates = Vec::new();
+    for line in lines.filter(|line| !line.starts_with('#')) {
+        let invalid = || ParseError::InvalidCoordinate(line.to_string());
+        let mut numbers = line.split_whitespace().map(str::parse::<i64>);
+        match (numbers.next(), numbers.next(), numbers.next()) {
+            (Some(Ok(x)), Some(Ok(y)), None) => coordinates.push((x, y)),
+            _ => return Err(invalid()),
+        }
+    }
+
+    // Coordinates may be negative, so shift them to start at 0.
+    let min_x = coordinates.iter().map(|&(x, _)| x).min().unwrap_or(0);
+    let min_y = coordinates.iter().map(|&(_, y)| y).min().unwrap_or(0);
+    let max_x = coordinates.iter().map(|&(x, _)| x + 1).max().unwrap_or(0);
+    let max_y = coordinates.iter().map(|&(_, y)| y + 1).max().unwrap_or(0);
+    let width = u32::try_from(max_x - min_x).map_err(|_| ParseError::OutOfBounds)?;
+    let height = u32::try_from(max_y - min_y).map_err(|_| ParseError::OutOfBounds)?;
+    if width as u64 * height as u64 > MAX_CELLS {
+        return Err(ParseError::OutOfBounds);
+    }
+
+    let mut cells = vec![Cell::Dead; width as usize * height as usize];
+    for (x, y) in coordinates {
+        let idx = (y - min_y) as usize * width as usize + (x - min_x) as usize;
+        cells[idx] = Cell::Alive;
+    }
+    Ok(Pattern::from_cells(width, height, cells))
+}
+
+/// Write the live cells of `pattern`, with its top-left corner at `0 0`.
+pub fn write(pattern: &Pattern) -> String {
+    let mut text = HEADER.to_string();
+    text.push('\n');
+    for row in 0..pattern.height {
+        for col in 0..pattern.width {
+            if pattern.cell_at(row, col) == Cell::Alive {
+                text.push_str(&format!("{} {}\n", col, row));
+            }
+        }
+    }
+    text
+}
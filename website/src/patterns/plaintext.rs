@@ -0,0 +1,62 @@
+//! The plaintext `.cells` format: `!` comment lines followed by one line
+//! per row, with `.` for dead cells and `O` for live ones. See
+//! <https://conwaylife.com/wiki/Plaintext>.
+
+use super::{ParseError, Pattern};
+use crate::Cell;
+
+pub fn parse(text: &str) -> Result<Pattern, ParseError> {
+    let rows: Vec<&str> = text
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.starts_with('!'))
+        .collect();
+    // Blank lines at the end are not rows.
+    let height = rows
+        .iter()
+        .rposition(|row| !row.is_empty())
+        .map_or(0, |last| last + 1);
+    let rows = &rows[..height];
+    let width = rows
+        .iter()
+        .map(|row| row.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let mut cells = vec![Cell::Dead; width * height];
+    for (row, line) in rows.iter().enumerate() {
+        for (col, c) in line.chars().enumerate() {
+            cells[row * width + col] = match c {
+                '.' => Cell::Dead,
+                'O' | '*' => Cell::Alive,
+                c => return Err(ParseError::UnexpectedCharacter(c)),
+            };
+        }
+    }
+    Ok(Pattern::from_cells(width as u32, height as u32, cells))
+}
+
+pub fn write(pattern: &Pattern) -> String {
+    let mut text = String::new();
+    for row in 0..pattern.height {
+        for col in 0..pattern.width {
+            text.push(match pattern.cell_at(row, col) {
+                Cell::Alive => 'O',
+                _ => '.',
+            });
+        }
+        text.push('\n');
+    }
+    text
+}
+
+/// Whether `text` looks like plaintext rather than RLE: every line is a
+/// comment or made of pattern characters only.
+pub fn detect(text: &str) -> bool {
+    text.lines()
+        .map(str::trim_end)
+        .all(|line| line.starts_with('!') || line.chars().all(|c| matches!(c, '.' | 'O' | '*')))
+        && text
+            .lines()
+            .any(|line| !line.starts_with('!') && !line.trim().is_empty())
+}
@@ -0,0 +1,313 @@
+//! Sharing a universe directly between browsers over WebRTC data channels,
+//! without a relay server.
+//!
+//! One visitor hosts and the others join as guests, each over a connection
+//! of their own. Connections are negotiated by copying and pasting: the
+//! host's offer is pasted into the guest's page, and the guest's answer back
+//! into the host's. Each is written as `{ type, sdp }` JSON once every ICE
+//! candidate is known, so that nothing else has to be passed along.
+//!
+//! Messages are those of the relay server, in `network`. The host sends a
+//! tick whenever it steps, which guests step along to, and the whole board
+//! every `network::SYNC_INTERVAL` generations. Edits go both ways, and the
+//! host passes every guest's edits on to the other guests.
+
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+
+use js_sys::{Array, Promise};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{
+    RtcDataChannel, RtcDataChannelEvent, RtcDataChannelState, RtcIceGatheringState,
+    RtcPeerConnection, RtcSdpType, RtcSessionDescriptionInit,
+};
+
+use crate::network::{ConnectionStatus, NetworkMessage};
+
+/// The STUN server asked for the address peers reach each other on.
+const STUN_SERVER: &str = "stun:stun.l.google.com:19302";
+
+/// The label of the data channel the host opens.
+const CHANNEL_LABEL: &str = "life";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PeerRole {
+    Host,
+    Guest,
+}
+
+/// An offer or answer, as pasted between pages.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Signal {
+    #[serde(rename = "type", with = "kind")]
+    pub offer: bool,
+    pub sdp: String,
+}
+
+impl Signal {
+    /// Read a signal written by `to_text`, or `None` if `text` isn't one.
+    pub fn parse(text: &str) -> Option<Signal> {
+        serde_json::from_str(text).ok()
+    }
+
+    pub fn to_text(&self) -> String {
+        // Both members are strings, so this can't fail.
+        serde_json::to_string(self).unwrap()
+    }
+
+    fn description(&self) -> RtcSessionDescriptionInit {
+        let kind = if self.offer {
+            RtcSdpType::Offer
+        } else {
+            RtcSdpType::Answer
+        };
+        let description = RtcSessionDescriptionInit::new(kind);
+        description.set_sdp(&self.sdp);
+        description
+    }
+}
+
+/// Whether a signal is an offer, written as its `type`: `"offer"` or
+/// `"answer"`.
+mod kind {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(offer: &bool, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(if *offer { "offer" } else { "answer" })
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+        match String::deserialize(deserializer)?.as_str() {
+            "offer" => Ok(true),
+            "answer" => Ok(false),
+            kind => Err(de::Error::unknown_variant(kind, &["offer", "answer"])),
+        }
+    }
+}
+
+/// The closures a session keeps alive for as long as the connection or
+/// channel can call them.
+type Callbacks = Rc<RefCell<Vec<Closure<dyn FnMut(JsValue)>>>>;
+
+/// One connection between the host and a guest. Dropping it closes the
+/// connection.
+pub struct PeerSession {
+    role: PeerRole,
+    connection: RtcPeerConnection,
+    /// The data channel, once the host has opened it or the guest has been
+    /// told about it.
+    channel: Rc<RefCell<Option<RtcDataChannel>>>,
+    callbacks: Callbacks,
+}
+
+impl PeerSession {
+    /// Start hosting a session. `on_signal` is called with the offer to
+    /// pass to the guest, `on_status` whenever the channel opens or closes,
+    /// and `on_message` with the text of every message received.
+    pub fn host(
+        on_signal: impl FnOnce(Signal) + 'static,
+        on_status: impl Fn(ConnectionStatus) + 'static,
+        on_message: impl FnMut(String) + 'static,
+    ) -> Result<PeerSession, JsValue> {
+        let session = PeerSession::new(PeerRole::Host, on_signal)?;
+        on_status(ConnectionStatus::Connecting);
+        let channel = session.connection.create_data_channel(CHANNEL_LABEL);
+        attach(
+            &session.channel,
+            &session.callbacks,
+            channel,
+            Rc::new(on_status),
+            Rc::new(RefCell::new(on_message)),
+        );
+        let my_connection = session.connection.clone();
+        on_settled(session.connection.create_offer(), move |offer| {
+            on_settled(
+                my_connection.set_local_description(offer.unchecked_ref()),
+                |_| {},
+            );
+        });
+        Ok(session)
+    }
+
+    /// Join the session offered in `offer`. `on_signal` is called with the
+    /// answer to pass back to the host, and the rest as for `host`.
+    pub fn join(
+        offer: &Signal,
+        on_signal: impl FnOnce(Signal) + 'static,
+        on_status: impl Fn(ConnectionStatus) + 'static,
+        on_message: impl FnMut(String) + 'static,
+    ) -> Result<PeerSession, JsValue> {
+        let session = PeerSession::new(PeerRole::Guest, on_signal)?;
+        let on_status: Rc<dyn Fn(ConnectionStatus)> = Rc::new(on_status);
+        let on_message: Rc<RefCell<dyn FnMut(String)>> = Rc::new(RefCell::new(on_message));
+        on_status(ConnectionStatus::Connecting);
+
+        let my_channel = session.channel.clone();
+        let callbacks: Weak<_> = Rc::downgrade(&session.callbacks);
+        let on_channel = Closure::wrap(Box::new(move |event: JsValue| {
+            let event: RtcDataChannelEvent = event.unchecked_into();
+            if let Some(callbacks) = callbacks.upgrade() {
+                let (on_status, on_message) = (on_status.clone(), on_message.clone());
+                attach(
+                    &my_channel,
+                    &callbacks,
+                    event.channel(),
+                    on_status,
+                    on_message,
+                );
+            }
+        }) as Box<dyn FnMut(_)>);
+        session
+            .connection
+            .set_ondatachannel(Some(on_channel.as_ref().unchecked_ref()));
+        session.callbacks.borrow_mut().push(on_channel);
+
+        let my_connection = session.connection.clone();
+        on_settled(
+            session
+                .connection
+                .set_remote_description(&offer.description()),
+            move |_| {
+                let connection = my_connection.clone();
+                on_settled(my_connection.create_answer(), move |answer| {
+                    on_settled(
+                        connection.set_local_description(answer.unchecked_ref()),
+                        |_| {},
+                    );
+                });
+            },
+        );
+        Ok(session)
+    }
+
+    /// Finish connecting a hosted session to the guest that sent `answer`.
+    pub fn accept(&self, answer: &Signal) {
+        on_settled(
+            self.connection
+                .set_remote_description(&answer.description()),
+            |_| {},
+        );
+    }
+
+    pub fn role(&self) -> PeerRole {
+        self.role
+    }
+
+    /// Whether the session is still waiting for the guest's answer.
+    pub fn is_waiting(&self) -> bool {
+        self.role == PeerRole::Host && self.connection.remote_description().is_none()
+    }
+
+    /// Send `message` to the other end, if the channel is open.
+    pub fn send(&self, message: &NetworkMessage) {
+        self.send_text(&message.to_json());
+    }
+
+    /// Send a message already written as JSON.
+    pub fn send_text(&self, text: &str) {
+        if let Some(channel) = &*self.channel.borrow() {
+            if channel.ready_state() == RtcDataChannelState::Open {
+                // Failures show up as the channel closing.
+                let _ = channel.send_with_str(text);
+            }
+        }
+    }
+
+    /// A connection that calls `on_signal` with its local description once
+    /// every ICE candidate has been gathered.
+    fn new(
+        role: PeerRole,
+        on_signal: impl FnOnce(Signal) + 'static,
+    ) -> Result<PeerSession, JsValue> {
+        let server = web_sys::RtcIceServer::new();
+        server.set_urls(&JsValue::from_str(STUN_SERVER));
+        let configuration = web_sys::RtcConfiguration::new();
+        configuration.set_ice_servers(&Array::of1(&server));
+        let connection = RtcPeerConnection::new_with_configuration(&configuration)?;
+
+        let my_connection = connection.clone();
+        let mut on_signal = Some(on_signal);
+        let on_gathering = Closure::wrap(Box::new(move |_: JsValue| {
+            if my_connection.ice_gathering_state() != RtcIceGatheringState::Complete {
+                return;
+            }
+            if let (Some(description), Some(on_signal)) =
+                (my_connection.local_description(), on_signal.take())
+            {
+                on_signal(Signal {
+                    offer: description.type_() == RtcSdpType::Offer,
+                    sdp: description.sdp(),
+                });
+            }
+        }) as Box<dyn FnMut(_)>);
+        connection.set_onicegatheringstatechange(Some(on_gathering.as_ref().unchecked_ref()));
+
+        Ok(PeerSession {
+            role,
+            connection,
+            channel: Rc::new(RefCell::new(None)),
+            callbacks: Rc::new(RefCell::new(vec![on_gathering])),
+        })
+    }
+}
+
+impl Drop for PeerSession {
+    fn drop(&mut self) {
+        if let Some(channel) = self.channel.borrow_mut().take() {
+            channel.set_onopen(None);
+            channel.set_onclose(None);
+            channel.set_onmessage(None);
+            channel.close();
+        }
+        self.connection.set_onicegatheringstatechange(None);
+        self.connection.set_ondatachannel(None);
+        self.connection.close();
+    }
+}
+
+/// Call `on_done` with what `promise` resolves to, or log why it failed.
+fn on_settled(promise: Promise, on_done: impl FnOnce(JsValue) + 'static) {
+    let on_done = Closure::once(Box::new(on_done) as Box<dyn FnOnce(JsValue)>);
+    let on_error = Closure::once(Box::new(|err: JsValue| {
+        log!("{}", tr!(CouldNotStartPeerSession, format!("{:?}", err)));
+    }) as Box<dyn FnOnce(JsValue)>);
+    let _ = promise.then2(&on_done, &on_error);
+    on_done.forget();
+    on_error.forget();
+}
+
+/// Start sending and receiving messages over `channel`, keeping it in
+/// `slot` and its callbacks in `callbacks`.
+fn attach(
+    slot: &Rc<RefCell<Option<RtcDataChannel>>>,
+    callbacks: &Callbacks,
+    channel: RtcDataChannel,
+    on_status: Rc<dyn Fn(ConnectionStatus)>,
+    on_message: Rc<RefCell<dyn FnMut(String)>>,
+) {
+    let my_on_status = on_status.clone();
+    let on_open = Closure::wrap(Box::new(move |_: JsValue| {
+        my_on_status(ConnectionStatus::Connected);
+    }) as Box<dyn FnMut(_)>);
+    channel.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+
+    let on_close = Closure::wrap(Box::new(move |_: JsValue| {
+        on_status(ConnectionStatus::Disconnected);
+    }) as Box<dyn FnMut(_)>);
+    channel.set_onclose(Some(on_close.as_ref().unchecked_ref()));
+
+    let on_channel_message = Closure::wrap(Box::new(move |event: JsValue| {
+        let event: web_sys::MessageEvent = event.unchecked_into();
+        if let Some(text) = event.data().as_string() {
+            (on_message.borrow_mut())(text);
+        }
+    }) as Box<dyn FnMut(_)>);
+    channel.set_onmessage(Some(on_channel_message.as_ref().unchecked_ref()));
+
+    callbacks
+        .borrow_mut()
+        .extend([on_open, on_close, on_channel_message]);
+    *slot.borrow_mut() = Some(channel);
+}
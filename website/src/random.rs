@@ -0,0 +1,31 @@
+//! A small, seedable pseudo-random number generator.
+//!
+//! Boards generated from the same seed are identical on every platform,
+//! which the `rand` crates don't guarantee across versions.
+
+/// Marsaglia's xorshift64* generator.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct XorShift {
+    state: u64,
+}
+
+impl XorShift {
+    pub fn new(seed: u64) -> XorShift {
+        // The state must never be zero, so mix the seed with an odd constant.
+        XorShift {
+            state: (seed ^ 0x9E37_79B9_7F4A_7C15) | 1,
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// A number in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
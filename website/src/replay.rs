@@ -0,0 +1,400 @@
+//! A log of every edit made to a board and the generation it was made in,
+//! which can be saved as JSON and played back from an empty board to show
+//! how a construction was built.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use crate::engine::{Cell, Position, Topology, Universe};
+use crate::patterns::{ParseError, Pattern, MAX_CELLS};
+use crate::rule::Rule;
+
+/// The most generations a log read back may cover. Playing it back steps
+/// through every one, so a few bytes of JSON could otherwise ask for years
+/// of work.
+pub const MAX_REPLAY_GENERATIONS: u64 = 1 << 24;
+
+/// One change made to the board by hand, written to JSON as an object
+/// whose `edit` member names the kind of edit, as in
+/// `{ "edit": "toggle", "row": 3, "column": 4 }`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "edit", rename_all = "lowercase")]
+pub enum Edit {
+    Toggle {
+        row: u32,
+        column: u32,
+    },
+    /// Set every listed (`row`, `column`) to `cell`, as painting, drawing a
+    /// shape or deleting a selection does.
+    Cells {
+        #[serde(rename = "state")]
+        cell: Cell,
+        cells: Vec<(u32, u32)>,
+    },
+    /// Copy `pattern` in with its top-left corner at (`row`, `column`).
+    Stamp {
+        row: u32,
+        column: u32,
+        #[serde(with = "rle")]
+        pattern: Pattern,
+    },
+    Clear,
+    Rule {
+        rule: Rule,
+    },
+    Topology {
+        topology: Topology,
+    },
+    /// Flip cells with `probability` on every tick, drawing the noise from
+    /// a generator restarted from `seed`.
+    Noise {
+        probability: f64,
+        seed: u64,
+    },
+    /// Resize the board, clearing every cell.
+    Resize {
+        width: u32,
+        height: u32,
+    },
+    /// Replace the whole board, as written by `Universe::serialize`. Edits
+    /// such as randomizing or undoing are logged this way, since replaying
+    /// them wouldn't give the same board.
+    Board {
+        board: String,
+    },
+}
+
+impl Edit {
+    /// Make the edit to `universe`.
+    pub fn apply(&self, universe: &mut Universe) -> Result<(), ReplayError> {
+        match self {
+            Edit::Toggle { row, column } => {
+                let position = Position::new(*row, *column);
+                universe
+                    .toggle_cell(position)
+                    .ok_or(ReplayError::OutOfBounds(position))?;
+            }
+            Edit::Cells { cell, cells } => {
+                for &(row, column) in cells {
+                    let position = Position::new(row, column);
+                    universe
+                        .set_cell(position, *cell)
+                        .ok_or(ReplayError::OutOfBounds(position))?;
+                }
+            }
+            Edit::Stamp {
+                row,
+                column,
+                pattern,
+            } => {
+                let position = Position::new(*row, *column);
+                if !universe.insert_pattern(position, pattern) {
+                    return Err(ReplayError::OutOfBounds(position));
+                }
+            }
+            Edit::Clear => universe.clear(),
+            Edit::Rule { rule } => universe.apply_rule(*rule),
+            Edit::Topology { topology } => universe.set_topology(*topology),
+            Edit::Noise { probability, seed } => {
+                universe.seed_rng(*seed);
+                universe.set_noise(*probability);
+            }
+            Edit::Resize { width, height } => {
+                universe.set_width(*width);
+                universe.set_height(*height);
+            }
+            Edit::Board { board } => {
+                let board = Universe::deserialize(board)?;
+                let topology = universe.topology();
+                *universe = board;
+                universe.set_topology(topology);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Why a replay log couldn't be read.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReplayError {
+    /// The text wasn't JSON, or didn't follow the schema.
+    Json(String),
+    /// A member was out of range.
+    Missing(&'static str),
+    Pattern(ParseError),
+    /// An edit reached past the edge of the board.
+    OutOfBounds(Position),
+    /// The board would have more than `MAX_CELLS` cells.
+    TooBig {
+        width: u32,
+        height: u32,
+    },
+    /// The log runs past `MAX_REPLAY_GENERATIONS`.
+    TooLong(u64),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::Json(error) => write!(f, "{}", error),
+            ReplayError::Missing(name) => write!(f, "missing or invalid {:?}", name),
+            ReplayError::Pattern(error) => write!(f, "{}", error),
+            ReplayError::OutOfBounds(Position { row, column }) => {
+                write!(f, "cell ({}, {}) is off the board", row, column)
+            }
+            ReplayError::TooBig { width, height } => {
+                write!(f, "a {}x{} board is too big to replay", width, height)
+            }
+            ReplayError::TooLong(generations) => {
+                write!(f, "{} generations are too many to replay", generations)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<serde_json::Error> for ReplayError {
+    fn from(error: serde_json::Error) -> ReplayError {
+        ReplayError::Json(error.to_string())
+    }
+}
+
+impl From<ParseError> for ReplayError {
+    fn from(error: ParseError) -> ReplayError {
+        ReplayError::Pattern(error)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl From<ReplayError> for JsValue {
+    fn from(error: ReplayError) -> JsValue {
+        JsValue::from_str(&error.to_string())
+    }
+}
+
+/// The board a log starts from and every edit since, each with how many
+/// generations had passed when it was made. Each edit is written to JSON
+/// with a `generation` member alongside its own.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReplayLog {
+    width: u32,
+    height: u32,
+    rule: Rule,
+    topology: Topology,
+    /// Generations stepped since the log started.
+    generations: u64,
+    /// Oldest first.
+    #[serde(with = "logged")]
+    edits: Vec<(u64, Edit)>,
+}
+
+impl ReplayLog {
+    /// Start logging edits to `universe`. Its live cells are logged as a
+    /// first edit, so that playing the log back from an empty board starts
+    /// from the same one.
+    pub fn new(universe: &Universe) -> ReplayLog {
+        let mut log = ReplayLog {
+            width: universe.width(),
+            height: universe.height(),
+            rule: universe.rule(),
+            topology: universe.topology(),
+            generations: 0,
+            edits: Vec::new(),
+        };
+        if universe.bounding_box().is_some() {
+            log.record(Edit::Board {
+                board: universe.serialize(),
+            });
+        }
+        log
+    }
+
+    /// Log an edit made now.
+    pub fn record(&mut self, edit: Edit) {
+        self.edits.push((self.generations, edit));
+    }
+
+    /// Note that the board stepped `generations` ahead.
+    pub fn advance(&mut self, generations: u64) {
+        self.generations += generations;
+    }
+
+    pub fn edits(&self) -> &[(u64, Edit)] {
+        &self.edits
+    }
+
+    /// Refuse a log too big or too long to play back.
+    fn check(&self) -> Result<(), ReplayError> {
+        if self.width as u64 * self.height as u64 > MAX_CELLS {
+            return Err(ReplayError::TooBig {
+                width: self.width,
+                height: self.height,
+            });
+        }
+        let last = self.edits.iter().map(|&(generation, _)| generation);
+        let generations = last.fold(self.generations, u64::max);
+        if generations > MAX_REPLAY_GENERATIONS {
+            return Err(ReplayError::TooLong(generations));
+        }
+        Ok(())
+    }
+
+    /// The empty board the log is played back onto.
+    pub fn empty_board(&self) -> Universe {
+        let mut universe = Universe::new_with_size(self.width, self.height);
+        universe.apply_rule(self.rule);
+        universe.set_topology(self.topology);
+        universe
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl ReplayLog {
+    /// How many generations the log covers.
+    pub fn generations(&self) -> u64 {
+        self.generations
+    }
+
+    pub fn len(&self) -> usize {
+        self.edits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    /// Write the log as JSON for `from_json`.
+    pub fn to_json(&self) -> String {
+        // Every member is a string, number or array of them, so this can't
+        // fail.
+        serde_json::to_string(self).unwrap()
+    }
+
+    /// Read a log written by `to_json`. Logs of boards over `MAX_CELLS`
+    /// cells, or running past `MAX_REPLAY_GENERATIONS`, are refused.
+    pub fn from_json(text: &str) -> Result<ReplayLog, ReplayError> {
+        let log: ReplayLog = serde_json::from_str(text)?;
+        log.check()?;
+        Ok(log)
+    }
+
+    /// Play the whole log back and return the board it ends on.
+    pub fn replay(&self) -> Result<Universe, ReplayError> {
+        let (mut replay, mut universe) = Replay::start(self.clone())?;
+        while replay.step(&mut universe)? {}
+        Ok(universe)
+    }
+}
+
+/// A log being played back one generation at a time.
+#[derive(Clone, Debug)]
+pub struct Replay {
+    log: ReplayLog,
+    generations: u64,
+    /// The first edit not made yet.
+    next: usize,
+}
+
+impl Replay {
+    /// Start playing `log` back, returning the board to step with `step`,
+    /// with the edits made before the first step already on it. Logs too
+    /// big or too long to play back are refused, as by `from_json`.
+    pub fn start(log: ReplayLog) -> Result<(Replay, Universe), ReplayError> {
+        log.check()?;
+        let mut universe = log.empty_board();
+        let mut replay = Replay {
+            log,
+            generations: 0,
+            next: 0,
+        };
+        replay.make_due_edits(&mut universe)?;
+        Ok((replay, universe))
+    }
+
+    /// Advance `universe` a generation and make the edits logged for it.
+    /// Returns false, without stepping, once the log is over.
+    pub fn step(&mut self, universe: &mut Universe) -> Result<bool, ReplayError> {
+        if self.is_finished() {
+            return Ok(false);
+        }
+        universe.tick();
+        self.generations += 1;
+        self.make_due_edits(universe)?;
+        Ok(true)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.generations >= self.log.generations && self.next == self.log.edits.len()
+    }
+
+    /// How many generations have been played back.
+    pub fn generations(&self) -> u64 {
+        self.generations
+    }
+
+    fn make_due_edits(&mut self, universe: &mut Universe) -> Result<(), ReplayError> {
+        while let Some((generation, edit)) = self.log.edits.get(self.next) {
+            if *generation > self.generations {
+                break;
+            }
+            edit.apply(universe)?;
+            self.next += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Patterns written as RLE.
+mod rle {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    use crate::patterns::Pattern;
+
+    pub fn serialize<S: Serializer>(pattern: &Pattern, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&pattern.to_rle())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pattern, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        Pattern::from_rle(&text).map_err(de::Error::custom)
+    }
+}
+
+/// The edits of a log, each written with the generation it was made in.
+mod logged {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::Edit;
+
+    #[derive(Serialize, Deserialize)]
+    struct Logged<E> {
+        generation: u64,
+        #[serde(flatten)]
+        edit: E,
+    }
+
+    pub fn serialize<S: Serializer>(
+        edits: &[(u64, Edit)],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(edits.iter().map(|(generation, edit)| Logged {
+            generation: *generation,
+            edit,
+        }))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<(u64, Edit)>, D::Error> {
+        let edits = Vec::<Logged<Edit>>::deserialize(deserializer)?;
+        Ok(edits
+            .into_iter()
+            .map(|logged| (logged.generation, logged.edit))
+            .collect())
+    }
+}
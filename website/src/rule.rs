@@ -0,0 +1,841 @@
+//! Outer totalistic "B/S" rules such as Conway's `B3/S23`, and their
+//! "Generations" extension `B/S/C` in which dying cells decay through extra
+//! states before they are dead.
+//!
+//! Rules count neighbors in the usual 3x3 Moore neighborhood unless the
+//! rulestring ends in `V`, for the von Neumann neighborhood of the four
+//! orthogonal cells, or `R2`, for the 5x5 Moore neighborhood of range 2.
+//! Counts above 9 are written as comma-separated numbers, as in
+//! `B6,7/S5,10,11R2`.
+//!
+//! Isotropic non-totalistic rules tell apart the different ways a number of
+//! Moore neighbors can be arranged, up to rotation and reflection. They use
+//! Hensel's notation, in which the letters after a count pick some of its
+//! arrangements, as in `B2a` for two neighbors next to each other, or leave
+//! some out, as in `B2-a`. "Just Friends" is `B2-a/S12`.
+//!
+//! Larger than Life rules reach further, and are born and survive on whole
+//! intervals of counts. They are written in Golly's notation, as in
+//! `R5,C0,M1,S34..58,B34..45,NM` for "Bugs": the range, the number of
+//! states (0 meaning 2), whether a cell counts itself, the survival and birth
+//! intervals, and `NM` or `NN` for the Moore or von Neumann neighborhood.
+//!
+//! Immigration and QuadLife give live cells two or four colors, and newborn
+//! cells take the color most of their parents have. They are written as
+//! `Immigration` and `QuadLife` for Conway's counts, or after the counts of
+//! another Life-like rule, as in `B36/S23/QuadLife`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use crate::Cell;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RuleError {
+    /// The rulestring was not of the form `B3/S23`, `23/3` or `B2/S/C3`.
+    InvalidFormat(String),
+    /// A neighbor count was not a number between 0 and the size of the
+    /// neighborhood.
+    InvalidNeighborCount(String),
+    /// The number of Generations states was not between 2 and 255.
+    InvalidStateCount(String),
+    /// A Larger than Life range was not between 1 and `MAX_RANGE`.
+    InvalidRange(String),
+    /// The rule has `B0`, which fills the empty space around every pattern
+    /// and so can't be run on a plane without edges.
+    BirthOnZero(String),
+    /// The rule isn't a two-state rule counting the eight Moore neighbors,
+    /// which is all HashLife and the chunked plane can run.
+    NotLifeLike(String),
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuleError::InvalidFormat(rule) => write!(f, "invalid rule {:?}", rule),
+            RuleError::InvalidNeighborCount(c) => write!(f, "invalid neighbor count {:?}", c),
+            RuleError::InvalidStateCount(states) => write!(f, "invalid state count {:?}", states),
+            RuleError::InvalidRange(range) => write!(f, "invalid range {:?}", range),
+            RuleError::BirthOnZero(rule) => write!(f, "rule {:?} has B0, which needs edges", rule),
+            RuleError::NotLifeLike(rule) => write!(f, "rule {:?} isn't Life-like", rule),
+        }
+    }
+}
+
+impl std::error::Error for RuleError {}
+
+#[cfg(target_arch = "wasm32")]
+impl From<RuleError> for JsValue {
+    fn from(error: RuleError) -> JsValue {
+        JsValue::from_str(&error.to_string())
+    }
+}
+
+/// The furthest a Larger than Life neighborhood can reach.
+pub const MAX_RANGE: u8 = 10;
+
+/// The Moore neighbors, clockwise from north. Bit `n` of a neighbor
+/// configuration is set when the `n`th of them is alive.
+pub const CONFIGURATION_OFFSETS: [(i64, i64); 8] = [
+    (-1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+];
+
+/// Hensel's letters for the arrangements of each number of live neighbors.
+const HENSEL_LETTERS: [&str; 9] = [
+    "",
+    "ce",
+    "cekain",
+    "cekainyqjr",
+    "cekainyqjrtwz",
+    "cekainyqjr",
+    "cekain",
+    "ce",
+    "",
+];
+
+/// One configuration for each of the letters of up to four live neighbors.
+/// The letters of more neighbors stand for the same configurations with
+/// live and dead swapped.
+const HENSEL_CONFIGURATIONS: [&[u8]; 5] = [
+    &[],
+    &[0x02, 0x01],
+    &[0x0A, 0x05, 0x09, 0x03, 0x11, 0x22],
+    &[0x2A, 0x15, 0x25, 0x07, 0x83, 0x0B, 0x29, 0x23, 0x43, 0x13],
+    &[
+        0xAA, 0x55, 0x4B, 0x0F, 0x1B, 0x8B, 0x2B, 0x27, 0x53, 0x17, 0x93, 0x63, 0x33,
+    ],
+];
+
+/// The cells around a cell that count as its neighbors.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Neighborhood {
+    /// The eight cells surrounding a cell.
+    Moore,
+    /// The four cells orthogonally next to a cell.
+    VonNeumann,
+    /// The 24 other cells of the 5x5 square centred on a cell.
+    Moore2,
+}
+
+impl Neighborhood {
+    /// Parse the name used by the page's neighborhood dropdown.
+    pub fn from_name(name: &str) -> Option<Neighborhood> {
+        match name {
+            "moore" => Some(Neighborhood::Moore),
+            "von-neumann" => Some(Neighborhood::VonNeumann),
+            "moore-2" => Some(Neighborhood::Moore2),
+            _ => None,
+        }
+    }
+
+    /// The name used by the page's neighborhood dropdown.
+    pub fn name(self) -> &'static str {
+        match self {
+            Neighborhood::Moore => "moore",
+            Neighborhood::VonNeumann => "von-neumann",
+            Neighborhood::Moore2 => "moore-2",
+        }
+    }
+
+    /// The number of neighbors every cell has.
+    pub fn size(self) -> u8 {
+        match self {
+            Neighborhood::Moore => 8,
+            Neighborhood::VonNeumann => 4,
+            Neighborhood::Moore2 => 24,
+        }
+    }
+
+    /// The `(row, column)` offsets of the neighbors of a cell.
+    pub fn offsets(self) -> impl Iterator<Item = (i64, i64)> {
+        let range = self.range() as i64;
+        (-range..=range)
+            .flat_map(move |row| (-range..=range).map(move |col| (row, col)))
+            .filter(move |&(row, col)| match self {
+                Neighborhood::VonNeumann => row.abs() + col.abs() == 1,
+                _ => (row, col) != (0, 0),
+            })
+    }
+
+    fn range(self) -> u8 {
+        match self {
+            Neighborhood::Moore2 => 2,
+            _ => 1,
+        }
+    }
+
+    /// The number of neighbors within `range` of a cell, in this shape of
+    /// neighborhood.
+    fn cells(self, range: u8) -> u16 {
+        let range = range as u16;
+        match self {
+            Neighborhood::VonNeumann => 2 * range * (range + 1),
+            _ => (2 * range + 1) * (2 * range + 1) - 1,
+        }
+    }
+
+    /// The suffix that selects this neighborhood in a rulestring.
+    fn suffix(self) -> &'static str {
+        match self {
+            Neighborhood::Moore => "",
+            Neighborhood::VonNeumann => "V",
+            Neighborhood::Moore2 => "R2",
+        }
+    }
+
+    /// Bits for every possible neighbor count.
+    fn mask(self) -> u32 {
+        (1 << (self.size() + 1)) - 1
+    }
+}
+
+/// The neighbor counts that cause a birth, or let a cell survive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Counts {
+    /// Bit `n` is set for `n` live neighbors.
+    Set(u32),
+    /// Every count from the first to the second, as in Larger than Life
+    /// rules.
+    Interval(u16, u16),
+    /// Bit `n` is set for neighbor configuration `n`, as in isotropic
+    /// non-totalistic rules.
+    Isotropic([u64; 4]),
+}
+
+impl Counts {
+    fn contains(self, live_neighbors: u16) -> bool {
+        match self {
+            Counts::Set(bits) => live_neighbors < 32 && bits & (1 << live_neighbors) != 0,
+            Counts::Interval(min, max) => (min..=max).contains(&live_neighbors),
+            Counts::Isotropic(bits) => {
+                live_neighbors < 256
+                    && bits[live_neighbors as usize / 64] & (1 << (live_neighbors % 64)) != 0
+            }
+        }
+    }
+}
+
+/// Which neighbor counts cause a dead cell to be born and a live cell to
+/// survive.
+///
+/// Cell states are numbered 0 for dead, 1 for alive and 2 up to `states - 1`
+/// for cells that are dying. A live cell that doesn't survive moves to state
+/// 2, and each dying cell moves one state further every generation until it
+/// is dead. Only dead cells can be born, and only live cells count as
+/// neighbors.
+///
+/// Under multi-color rules, states 1 up to `colors` are instead live cells
+/// of each color, and there are no dying states.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rule {
+    birth: Counts,
+    survival: Counts,
+    /// The total number of states, 2 for ordinary Life-like rules.
+    states: u8,
+    neighborhood: Neighborhood,
+    /// How many cells away the neighborhood reaches.
+    range: u8,
+    /// Whether a live cell counts as its own neighbor, as it can in Larger
+    /// than Life rules.
+    middle: bool,
+    /// How many colors live cells come in, 1 except under Immigration and
+    /// QuadLife.
+    colors: u8,
+}
+
+impl Default for Rule {
+    fn default() -> Rule {
+        Rule::conway()
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Rule {
+    /// Conway's Game of Life, `B3/S23`.
+    pub fn conway() -> Rule {
+        Rule {
+            birth: Counts::Set(1 << 3),
+            survival: Counts::Set(1 << 2 | 1 << 3),
+            states: 2,
+            neighborhood: Neighborhood::Moore,
+            range: 1,
+            middle: false,
+            colors: 1,
+        }
+    }
+
+    /// Conway's counts with live cells in two colors.
+    pub fn immigration() -> Rule {
+        Rule::conway().with_colors(2)
+    }
+
+    /// Conway's counts with live cells in four colors.
+    pub fn quad_life() -> Rule {
+        Rule::conway().with_colors(4)
+    }
+
+    /// Parse a rulestring in `B3/S23` notation, or the older `23/3`
+    /// survival/birth notation. Generations rules add the number of states
+    /// as a third part, as in `B2/S/C3` or `/2/3`, and a `V` or `R2` suffix
+    /// picks another neighborhood. Larger than Life rules are written as in
+    /// `R5,C0,M1,S34..58,B34..45,NM`. Isotropic non-totalistic rules follow
+    /// counts with Hensel's letters, as in `B2-a/S12`. Multi-color rules are `Immigration`,
+    /// `QuadLife`, or either name after the counts, as in `B36/S23/QuadLife`.
+    pub fn parse(rule: &str) -> Result<Rule, RuleError> {
+        let invalid = || RuleError::InvalidFormat(rule.to_string());
+        if rule.contains("..") {
+            return parse_larger_than_life(rule);
+        }
+        let (counts, name) = match rule.trim().rsplit_once('/') {
+            Some((counts, name)) => (Some(counts), name),
+            None => (None, rule.trim()),
+        };
+        if let Some(colors) = colors_named(name) {
+            let counts = match counts {
+                Some(counts) => Rule::parse(counts)?,
+                None => Rule::conway(),
+            };
+            if !counts.is_life_like() {
+                return Err(invalid());
+            }
+            return Ok(counts.with_colors(colors));
+        }
+
+        let (rule_counts, neighborhood) = strip_neighborhood(rule.trim());
+        let mut parts = rule_counts.split('/');
+        let first = parts.next().ok_or_else(invalid)?.trim();
+        let second = parts.next().ok_or_else(invalid)?.trim();
+        let states = match parts.next() {
+            Some(states) => parse_states(states.trim())?,
+            None => 2,
+        };
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+
+        let (birth, survival) = match (strip_prefix(first, 'B'), strip_prefix(second, 'S')) {
+            (Some(birth), Some(survival)) => (birth, survival),
+            _ => match (strip_prefix(first, 'S'), strip_prefix(second, 'B')) {
+                (Some(survival), Some(birth)) => (birth, survival),
+                // Without letters the survival counts come first.
+                _ => (second, first),
+            },
+        };
+
+        let isotropic = neighborhood == Neighborhood::Moore
+            && [birth, survival]
+                .iter()
+                .any(|counts| counts.contains(|c: char| c.is_ascii_alphabetic() || c == '-'));
+        let (birth, survival) = if isotropic {
+            let (birth, survival) = (parse_isotropic(birth)?, parse_isotropic(survival)?);
+            // Letters that pick every arrangement of their counts make an
+            // ordinary rule after all.
+            match (totalistic(birth), totalistic(survival)) {
+                (Some(birth), Some(survival)) => (Counts::Set(birth), Counts::Set(survival)),
+                _ => (Counts::Isotropic(birth), Counts::Isotropic(survival)),
+            }
+        } else {
+            (
+                Counts::Set(parse_counts(birth, neighborhood)?),
+                Counts::Set(parse_counts(survival, neighborhood)?),
+            )
+        };
+
+        Ok(Rule {
+            birth,
+            survival,
+            states,
+            neighborhood,
+            range: neighborhood.range(),
+            middle: false,
+            colors: 1,
+        })
+    }
+
+    /// The total number of cell states, 2 unless this is a Generations or
+    /// multi-color rule.
+    pub fn states(&self) -> u8 {
+        self.states
+    }
+
+    /// How many colors live cells come in.
+    pub fn colors(&self) -> u8 {
+        self.colors
+    }
+
+    /// Whether cells in `state` are alive, in any color.
+    pub fn is_alive(&self, state: u8) -> bool {
+        (1..=self.colors).contains(&state)
+    }
+
+    pub fn neighborhood(&self) -> Neighborhood {
+        self.neighborhood
+    }
+
+    /// How many cells away the neighborhood reaches.
+    pub fn range(&self) -> u8 {
+        self.range
+    }
+
+    /// Whether a live cell counts as its own neighbor.
+    pub fn counts_middle(&self) -> bool {
+        self.middle
+    }
+
+    /// Whether this is a two-state rule counting the eight Moore neighbors,
+    /// like Conway's, and not telling apart how they are arranged or what
+    /// color they are.
+    pub fn is_life_like(&self) -> bool {
+        self.states == 2
+            && self.colors == 1
+            && self.neighborhood == Neighborhood::Moore
+            && self.range == 1
+            && !self.middle
+            && matches!(self.birth, Counts::Set(_))
+    }
+
+    /// Whether dead cells with no live neighbors are born, as under rules
+    /// with `B0`.
+    pub fn births_on_zero(&self) -> bool {
+        self.birth.contains(0)
+    }
+
+    /// Whether births and survivals depend on how the live Moore neighbors
+    /// are arranged, not just on how many there are. The neighbor counts
+    /// passed to these rules are neighbor configurations instead, with bits
+    /// for the live cells of `CONFIGURATION_OFFSETS`.
+    pub fn is_isotropic(&self) -> bool {
+        matches!(self.birth, Counts::Isotropic(_))
+    }
+
+    /// The same rule counting a different neighborhood. Counts too large for
+    /// the new neighborhood are dropped. Larger than Life rules keep their
+    /// range, so for them `Moore2` is the same as `Moore`. Isotropic rules
+    /// only know the Moore neighborhood and stay as they are.
+    pub fn with_neighborhood(&self, neighborhood: Neighborhood) -> Rule {
+        match (self.birth, self.survival) {
+            _ if self.is_isotropic() => *self,
+            (Counts::Set(birth), Counts::Set(survival)) => Rule {
+                birth: Counts::Set(birth & neighborhood.mask()),
+                survival: Counts::Set(survival & neighborhood.mask()),
+                neighborhood,
+                range: neighborhood.range(),
+                ..*self
+            },
+            _ => Rule {
+                neighborhood: match neighborhood {
+                    Neighborhood::Moore2 => Neighborhood::Moore,
+                    _ => neighborhood,
+                },
+                ..*self
+            },
+        }
+    }
+
+    /// Whether a dead cell with `live_neighbors` live neighbors is born.
+    pub fn is_birth(&self, live_neighbors: u16) -> bool {
+        self.birth.contains(live_neighbors)
+    }
+
+    /// Whether a live cell with `live_neighbors` live neighbors survives.
+    pub fn is_survival(&self, live_neighbors: u16) -> bool {
+        self.survival.contains(live_neighbors)
+    }
+
+    /// The rule in `B3/S23` notation.
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen(js_name = toString))]
+    pub fn to_rulestring(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl Rule {
+    /// The state of a cell in the next generation.
+    ///
+    /// Dying cells are treated as being in the first dying state.
+    pub fn next(&self, cell: Cell, live_neighbors: u16) -> Cell {
+        Cell::from_state(self.next_state(cell as u8, live_neighbors))
+    }
+
+    /// The numbered state of a cell in the next generation. Cells are born
+    /// in the first color; `birth_color` picks the right one.
+    pub fn next_state(&self, state: u8, live_neighbors: u16) -> u8 {
+        match state {
+            0 if self.is_birth(live_neighbors) => 1,
+            0 => 0,
+            _ if self.is_alive(state) && self.is_survival(live_neighbors) => state,
+            _ if self.colors > 1 => 0,
+            _ if state < self.states - 1 => state + 1,
+            _ => 0,
+        }
+    }
+
+    /// The color, as a state, of a cell born to parents in the states
+    /// `parents`: the color most of them have, or under QuadLife, when three
+    /// parents all differ, the fourth color. Other ties go to the earliest
+    /// color.
+    pub fn birth_color(&self, parents: impl IntoIterator<Item = u8>) -> u8 {
+        let mut counts = [0u16; 256];
+        for state in parents {
+            counts[state as usize] += 1;
+        }
+        let colors = &counts[1..=self.colors as usize];
+        let most = colors.iter().copied().max().unwrap_or(0);
+        let distinct = colors.iter().filter(|&&n| n == 1).count();
+        if self.colors == 4 && most == 1 && distinct == 3 {
+            if let Some(missing) = colors.iter().position(|&n| n == 0) {
+                return missing as u8 + 1;
+            }
+        }
+        colors.iter().position(|&n| n == most).unwrap_or(0) as u8 + 1
+    }
+
+    /// The same counts with live cells in `colors` colors.
+    fn with_colors(&self, colors: u8) -> Rule {
+        Rule {
+            states: colors + 1,
+            colors,
+            ..*self
+        }
+    }
+}
+
+impl FromStr for Rule {
+    type Err = RuleError;
+
+    fn from_str(rule: &str) -> Result<Rule, RuleError> {
+        Rule::parse(rule)
+    }
+}
+
+/// Rules are written to JSON as their rulestrings.
+impl Serialize for Rule {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Rule {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Rule, D::Error> {
+        let rule = String::deserialize(deserializer)?;
+        Rule::parse(&rule).map_err(de::Error::custom)
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let (Counts::Interval(s_min, s_max), Counts::Interval(b_min, b_max)) =
+            (self.survival, self.birth)
+        {
+            return write!(
+                f,
+                "R{},C{},M{},S{}..{},B{}..{},N{}",
+                self.range,
+                if self.states > 2 { self.states } else { 0 },
+                self.middle as u8,
+                s_min,
+                s_max,
+                b_min,
+                b_max,
+                match self.neighborhood {
+                    Neighborhood::VonNeumann => 'N',
+                    _ => 'M',
+                },
+            );
+        }
+
+        let counts = |counts: Counts| -> String {
+            let bits = match counts {
+                Counts::Set(bits) => bits,
+                Counts::Isotropic(bits) => return hensel(bits),
+                Counts::Interval(..) => 0,
+            };
+            let counts: Vec<String> = (0..=self.neighborhood.size())
+                .filter(|n| bits & (1 << n) != 0)
+                .map(|n| n.to_string())
+                .collect();
+            // Single digits are run together, but larger counts need commas.
+            if bits >> 10 == 0 {
+                counts.concat()
+            } else {
+                counts.join(",")
+            }
+        };
+        let name = match self.colors {
+            2 => Some("Immigration"),
+            4 => Some("QuadLife"),
+            _ => None,
+        };
+        if let Some(name) = name {
+            let counts = Rule {
+                states: 2,
+                colors: 1,
+                ..*self
+            };
+            return if counts == Rule::conway() {
+                write!(f, "{}", name)
+            } else {
+                write!(f, "{}/{}", counts, name)
+            };
+        }
+
+        write!(f, "B{}/S{}", counts(self.birth), counts(self.survival))?;
+        if self.states > 2 {
+            write!(f, "/C{}", self.states)?;
+        }
+        write!(f, "{}", self.neighborhood.suffix())
+    }
+}
+
+fn strip_prefix(part: &str, letter: char) -> Option<&str> {
+    part.strip_prefix(letter)
+        .or_else(|| part.strip_prefix(letter.to_ascii_lowercase()))
+}
+
+/// Split a neighborhood suffix off the end of a rulestring.
+fn strip_neighborhood(rule: &str) -> (&str, Neighborhood) {
+    for &neighborhood in [Neighborhood::VonNeumann, Neighborhood::Moore2].iter() {
+        let suffix = neighborhood.suffix();
+        let split = rule.len().saturating_sub(suffix.len());
+        if rule.is_char_boundary(split) && rule[split..].eq_ignore_ascii_case(suffix) {
+            return (&rule[..split], neighborhood);
+        }
+    }
+    (rule, Neighborhood::Moore)
+}
+
+/// Parse a Larger than Life rule such as `R5,C0,M1,S34..58,B34..45,NM`.
+fn parse_larger_than_life(rule: &str) -> Result<Rule, RuleError> {
+    let invalid = || RuleError::InvalidFormat(rule.to_string());
+
+    let mut range = None;
+    let mut states = 2;
+    let mut middle = false;
+    let mut survival = None;
+    let mut birth = None;
+    let mut neighborhood = Neighborhood::Moore;
+    for field in rule.trim().split(',') {
+        let field = field.trim();
+        let mut chars = field.chars();
+        let key = chars.next().ok_or_else(invalid)?.to_ascii_uppercase();
+        let value = chars.as_str();
+        match key {
+            'R' => match value.parse::<u8>() {
+                Ok(n) if (1..=MAX_RANGE).contains(&n) => range = Some(n),
+                _ => return Err(RuleError::InvalidRange(value.to_string())),
+            },
+            // Golly writes two-state rules with 0 states.
+            'C' => match value.parse::<u8>() {
+                Ok(n) if n <= 2 => states = 2,
+                _ => states = parse_states(value)?,
+            },
+            'M' => match value {
+                "0" => middle = false,
+                "1" => middle = true,
+                _ => return Err(invalid()),
+            },
+            'S' => survival = Some(parse_interval(value)?),
+            'B' => birth = Some(parse_interval(value)?),
+            'N' => match value {
+                "M" | "m" => neighborhood = Neighborhood::Moore,
+                "N" | "n" => neighborhood = Neighborhood::VonNeumann,
+                _ => return Err(invalid()),
+            },
+            _ => return Err(invalid()),
+        }
+    }
+
+    let (range, survival, birth) = match (range, survival, birth) {
+        (Some(range), Some(survival), Some(birth)) => (range, survival, birth),
+        _ => return Err(invalid()),
+    };
+    let most = neighborhood.cells(range) + middle as u16;
+    for &(min, max) in [survival, birth].iter() {
+        if min > max || max > most {
+            return Err(RuleError::InvalidNeighborCount(format!("{}..{}", min, max)));
+        }
+    }
+    Ok(Rule {
+        birth: Counts::Interval(birth.0, birth.1),
+        survival: Counts::Interval(survival.0, survival.1),
+        states,
+        neighborhood,
+        range,
+        middle,
+        colors: 1,
+    })
+}
+
+/// The number of colors of the multi-color rule called `name`, if it is one.
+fn colors_named(name: &str) -> Option<u8> {
+    if name.eq_ignore_ascii_case("Immigration") {
+        Some(2)
+    } else if name.eq_ignore_ascii_case("QuadLife") {
+        Some(4)
+    } else {
+        None
+    }
+}
+
+/// Parse an interval of neighbor counts such as `34..58`.
+fn parse_interval(interval: &str) -> Result<(u16, u16), RuleError> {
+    let invalid = || RuleError::InvalidNeighborCount(interval.to_string());
+    let mut ends = interval.splitn(2, "..");
+    let min = ends
+        .next()
+        .ok_or_else(invalid)?
+        .parse()
+        .map_err(|_| invalid())?;
+    let max = ends
+        .next()
+        .ok_or_else(invalid)?
+        .parse()
+        .map_err(|_| invalid())?;
+    Ok((min, max))
+}
+
+fn parse_states(states: &str) -> Result<u8, RuleError> {
+    let digits = strip_prefix(states, 'C').unwrap_or(states);
+    match digits.parse::<u8>() {
+        Ok(states) if states >= 2 => Ok(states),
+        _ => Err(RuleError::InvalidStateCount(states.to_string())),
+    }
+}
+
+/// Parse counts in Hensel's notation, such as `2-i34q`, into a bit for
+/// each neighbor configuration they pick.
+fn parse_isotropic(counts: &str) -> Result<[u64; 4], RuleError> {
+    let letters = hensel_letters();
+    let mut bits = [0; 4];
+    let mut chars = counts.trim().chars().peekable();
+    while let Some(c) = chars.next() {
+        let n = match c.to_digit(10) {
+            Some(n) if n <= 8 => n,
+            _ => return Err(RuleError::InvalidNeighborCount(c.to_string())),
+        };
+        let without = chars.next_if_eq(&'-').is_some();
+        let mut picked = String::new();
+        while let Some(letter) = chars.next_if(char::is_ascii_alphabetic) {
+            let letter = letter.to_ascii_lowercase();
+            if !HENSEL_LETTERS[n as usize].contains(letter) {
+                return Err(RuleError::InvalidNeighborCount(format!("{}{}", n, letter)));
+            }
+            picked.push(letter);
+        }
+        for configuration in 0..=255u8 {
+            if configuration.count_ones() != n {
+                continue;
+            }
+            let listed = letters[configuration as usize].is_some_and(|l| picked.contains(l));
+            if picked.is_empty() || listed != without {
+                bits[configuration as usize / 64] |= 1 << (configuration % 64);
+            }
+        }
+    }
+    Ok(bits)
+}
+
+/// The counts picked by `bits` in Hensel's notation, with each count's
+/// letters or the letters it leaves out, whichever is shorter.
+fn hensel(bits: [u64; 4]) -> String {
+    let letters = hensel_letters();
+    let picks =
+        |configuration: u8| bits[configuration as usize / 64] & (1 << (configuration % 64)) != 0;
+    let mut hensel = String::new();
+    for n in 0..=8 {
+        let configurations: Vec<u8> = (0..=255u8).filter(|c| c.count_ones() == n).collect();
+        if !configurations.iter().any(|&c| picks(c)) {
+            continue;
+        }
+        hensel.push_str(&n.to_string());
+        if configurations.iter().all(|&c| picks(c)) {
+            continue;
+        }
+        let (picked, left_out): (String, String) =
+            HENSEL_LETTERS[n as usize].chars().partition(|&letter| {
+                configurations
+                    .iter()
+                    .any(|&c| letters[c as usize] == Some(letter) && picks(c))
+            });
+        if left_out.len() < picked.len() {
+            hensel.push('-');
+            hensel.push_str(&left_out);
+        } else {
+            hensel.push_str(&picked);
+        }
+    }
+    hensel
+}
+
+/// The counts of `bits`, if it picks every configuration of each count it
+/// picks any of.
+fn totalistic(bits: [u64; 4]) -> Option<u32> {
+    let mut counts = 0;
+    let mut skipped = 0;
+    for configuration in 0..=255u8 {
+        let n = configuration.count_ones();
+        if bits[configuration as usize / 64] & (1 << (configuration % 64)) != 0 {
+            counts |= 1 << n;
+        } else {
+            skipped |= 1 << n;
+        }
+    }
+    (counts & skipped == 0).then_some(counts)
+}
+
+/// Hensel's letter for the arrangement of each neighbor configuration, or
+/// `None` for no live neighbors or all of them.
+fn hensel_letters() -> [Option<char>; 256] {
+    let mut letters = [None; 256];
+    for n in 1..8 {
+        for (i, letter) in HENSEL_LETTERS[n].chars().enumerate() {
+            let configuration = if n <= 4 {
+                HENSEL_CONFIGURATIONS[n][i]
+            } else {
+                !HENSEL_CONFIGURATIONS[8 - n][i]
+            };
+            for turns in 0..4 {
+                let turned = configuration.rotate_left(2 * turns);
+                letters[turned as usize] = Some(letter);
+                letters[reflect(turned) as usize] = Some(letter);
+            }
+        }
+    }
+    letters
+}
+
+/// `configuration` mirrored left to right.
+fn reflect(configuration: u8) -> u8 {
+    (0..8)
+        .filter(|n| configuration & 1 << n != 0)
+        .fold(0, |reflected, n| reflected | 1 << ((8 - n) % 8))
+}
+
+fn parse_counts(counts: &str, neighborhood: Neighborhood) -> Result<u32, RuleError> {
+    let parse = |count: &str| match count.trim().parse::<u8>() {
+        Ok(n) if n <= neighborhood.size() => Ok(1 << n),
+        _ => Err(RuleError::InvalidNeighborCount(count.to_string())),
+    };
+    if counts.contains(',') {
+        counts
+            .split(',')
+            .try_fold(0, |bits, count| Ok(bits | parse(count)?))
+    } else {
+        counts.char_indices().try_fold(0, |bits, (i, c)| {
+            Ok(bits | parse(&counts[i..i + c.len_utf8()])?)
+        })
+    }
+}
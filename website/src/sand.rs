@@ -0,0 +1,185 @@
+//! A falling-sand playground, where every cell holds a material that moves
+//! the way it would under gravity rather than living or dying.
+//!
+//! Sand falls straight down, or slides down a slope to either side, and
+//! sinks through water. Water falls the same way but also spreads sideways
+//! over whatever it lands on. Walls stay where they are painted. The edges
+//! of the board are walls whatever its topology.
+
+use std::fmt;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use crate::engine::SimulationEngine;
+use crate::{Cell, Position};
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Material {
+    Empty,
+    Sand,
+    Water,
+    Wall,
+}
+
+impl Material {
+    /// Parse the name used by the page's material dropdown.
+    pub fn from_name(name: &str) -> Option<Material> {
+        match name {
+            "empty" => Some(Material::Empty),
+            "sand" => Some(Material::Sand),
+            "water" => Some(Material::Water),
+            "wall" => Some(Material::Wall),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Material::Empty => "empty",
+            Material::Sand => "sand",
+            Material::Water => "water",
+            Material::Wall => "wall",
+        }
+    }
+
+    /// The color the material is drawn in, whatever the theme.
+    pub fn color(self) -> &'static str {
+        match self {
+            Material::Empty => "#000000",
+            Material::Sand => "#D8B863",
+            Material::Water => "#3A7BD5",
+            Material::Wall => "#7F7F7F",
+        }
+    }
+
+    /// Whether a grain of `self` falling onto `other` swaps places with it.
+    fn sinks_into(self, other: Material) -> bool {
+        matches!(
+            (self, other),
+            (Material::Sand | Material::Water, Material::Empty) | (Material::Sand, Material::Water)
+        )
+    }
+}
+
+impl fmt::Display for Material {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Sand {
+    width: u32,
+    height: u32,
+    /// The material of every cell in row-major order.
+    cells: Vec<Material>,
+    /// What cells that come alive are filled with.
+    material: Material,
+    /// Which way grains try first alternates between generations, so piles
+    /// grow evenly on both sides.
+    generation: u64,
+}
+
+impl Sand {
+    /// An empty board, painted with sand.
+    pub fn new(width: u32, height: u32) -> Sand {
+        Sand {
+            width,
+            height,
+            cells: vec![Material::Empty; (width * height) as usize],
+            material: Material::Sand,
+            generation: 0,
+        }
+    }
+
+    pub fn material(&self, row: u32, column: u32) -> Material {
+        self.cells[self.get_index(row, column)]
+    }
+
+    /// Fill cells that come alive with `material` from now on.
+    pub fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+
+    fn get_index(&self, row: u32, column: u32) -> usize {
+        (row * self.width + column) as usize
+    }
+
+    /// The index of the cell `delta_row` and `delta_col` away from (`row`,
+    /// `column`), or `None` past the edge.
+    fn neighbor(&self, row: u32, column: u32, delta_row: i64, delta_col: i64) -> Option<usize> {
+        let (row, column) = (row as i64 + delta_row, column as i64 + delta_col);
+        if row < 0 || column < 0 || row >= self.height as i64 || column >= self.width as i64 {
+            return None;
+        }
+        Some(self.get_index(row as u32, column as u32))
+    }
+}
+
+impl SimulationEngine for Sand {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Every material but `Empty` counts as alive.
+    fn cell(&self, position: Position) -> Option<Cell> {
+        if !self.contains(position) {
+            return None;
+        }
+        let material = self.material(position.row, position.column);
+        Some(Cell::from(material != Material::Empty))
+    }
+
+    /// Only cells that change between alive and dead are touched, so copying
+    /// in an edited board keeps the materials everywhere else.
+    fn set_cell(&mut self, position: Position, cell: Cell) -> Option<Cell> {
+        let was = self.cell(position)?;
+        let alive = cell == Cell::Alive;
+        if (was == Cell::Alive) != alive {
+            let idx = self.get_index(position.row, position.column);
+            self.cells[idx] = if alive {
+                self.material
+            } else {
+                Material::Empty
+            };
+        }
+        Some(was)
+    }
+
+    /// Move every grain at most once, from the bottom row up, so that a
+    /// grain that has fallen isn't moved again in the row below.
+    fn tick(&mut self) {
+        let mut moved = vec![false; self.cells.len()];
+        for row in (0..self.height).rev() {
+            let left_first = (self.generation + row as u64).is_multiple_of(2);
+            let side = if left_first { -1 } else { 1 };
+            for i in 0..self.width {
+                let column = if left_first { i } else { self.width - 1 - i };
+                let idx = self.get_index(row, column);
+                let material = self.cells[idx];
+                if moved[idx] || material == Material::Empty || material == Material::Wall {
+                    continue;
+                }
+                let mut targets = vec![(1, 0), (1, side), (1, -side)];
+                if material == Material::Water {
+                    targets.extend([(0, side), (0, -side)]);
+                }
+                let target = targets.into_iter().find_map(|(delta_row, delta_col)| {
+                    self.neighbor(row, column, delta_row, delta_col)
+                        .filter(|&target| material.sinks_into(self.cells[target]))
+                });
+                if let Some(target) = target {
+                    self.cells.swap(idx, target);
+                    moved[target] = true;
+                }
+            }
+        }
+        self.generation += 1;
+    }
+}
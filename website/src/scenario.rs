@@ -0,0 +1,251 @@
+//! The state of the whole game as one JSON document, so that pages
+//! embedding it can save a scenario, edit it and set it up again.
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "board": "x = 64, y = 64, rule = B3/S23\n...",
+//!   "rule": "B3/S23",
+//!   "topology": "torus",
+//!   "generation": 120,
+//!   "viewport": { "cellSize": 8, "showGrid": true, "row": 31.5, "column": 31.5 },
+//!   "theme": { "background": "#FFFFFF", "alive": "#000000", "colors": ["#D00000"] }
+//! }
+//! ```
+//!
+//! `board` is written by `Universe::serialize`, and `rule` replaces any
+//! rule in its header. The viewport is centered on the cell at `row` and
+//! `column`, which needn't be whole. `theme` has a member for each color
+//! of `Theme`, written in camel case, or is the name of a preset such as
+//! `"dark"`; colors that are left out are those of the light theme.
+//!
+//! Only `board` is required. Without `rule` the board's own rule is used,
+//! without `topology` it wraps around as a torus and without `generation`
+//! it starts from 0. A missing `viewport` or `theme` leaves the one already
+//! shown in place.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use crate::engine::{Topology, Universe};
+use crate::patterns::ParseError;
+use crate::rule::{Rule, RuleError};
+use crate::theme::{NamedTheme, Theme};
+use crate::viewport::Viewport;
+
+/// The version of the schema `to_json` writes.
+pub const VERSION: u64 = 1;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ScenarioError {
+    /// The document wasn't JSON, or didn't follow the schema.
+    Json(String),
+    /// The document was written for another version of the schema.
+    Version(u64),
+    Board(ParseError),
+    Rule(RuleError),
+    /// `topology` named no topology, or `theme` no preset.
+    UnknownTopology(String),
+    UnknownTheme(String),
+}
+
+impl fmt::Display for ScenarioError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScenarioError::Json(error) => write!(f, "invalid scenario: {}", error),
+            ScenarioError::Version(version) => {
+                write!(f, "scenario version {} isn't {}", version, VERSION)
+            }
+            ScenarioError::Board(error) => write!(f, "{}", error),
+            ScenarioError::Rule(error) => write!(f, "{}", error),
+            ScenarioError::UnknownTopology(name) => write!(f, "unknown topology {:?}", name),
+            ScenarioError::UnknownTheme(name) => write!(f, "unknown theme {:?}", name),
+        }
+    }
+}
+
+impl std::error::Error for ScenarioError {}
+
+impl From<serde_json::Error> for ScenarioError {
+    fn from(error: serde_json::Error) -> ScenarioError {
+        ScenarioError::Json(error.to_string())
+    }
+}
+
+impl From<ParseError> for ScenarioError {
+    fn from(error: ParseError) -> ScenarioError {
+        ScenarioError::Board(error)
+    }
+}
+
+impl From<RuleError> for ScenarioError {
+    fn from(error: RuleError) -> ScenarioError {
+        ScenarioError::Rule(error)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl From<ScenarioError> for JsValue {
+    fn from(error: ScenarioError) -> JsValue {
+        JsValue::from_str(&error.to_string())
+    }
+}
+
+/// How the board is shown.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct View {
+    pub cell_size: u32,
+    pub show_grid: bool,
+    /// The (`row`, `column`) in the middle of the view.
+    pub center: (f64, f64),
+}
+
+impl View {
+    pub fn of(viewport: &Viewport) -> View {
+        View {
+            cell_size: viewport.cell_size(),
+            show_grid: viewport.show_grid(),
+            center: viewport.center(),
+        }
+    }
+}
+
+/// The schema above, as serde reads and writes it.
+#[derive(Serialize, Deserialize)]
+struct Document {
+    version: Option<u64>,
+    board: String,
+    rule: Option<String>,
+    topology: Option<String>,
+    generation: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    viewport: Option<ViewDocument>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    theme: Option<NamedTheme>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ViewDocument {
+    cell_size: u32,
+    #[serde(default = "shown")]
+    show_grid: bool,
+    row: f64,
+    column: f64,
+}
+
+fn shown() -> bool {
+    true
+}
+
+impl From<View> for ViewDocument {
+    fn from(view: View) -> ViewDocument {
+        ViewDocument {
+            cell_size: view.cell_size,
+            show_grid: view.show_grid,
+            row: view.center.0,
+            column: view.center.1,
+        }
+    }
+}
+
+impl From<ViewDocument> for View {
+    fn from(view: ViewDocument) -> View {
+        View {
+            cell_size: view.cell_size,
+            show_grid: view.show_grid,
+            center: (view.row, view.column),
+        }
+    }
+}
+
+/// A board along with everything about how it is simulated and shown.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone)]
+pub struct Scenario {
+    /// The board, with its rule, topology and generation.
+    pub(crate) universe: Universe,
+    pub(crate) view: Option<View>,
+    pub(crate) theme: Option<Theme>,
+}
+
+impl Scenario {
+    pub fn new(universe: Universe, view: Option<View>, theme: Option<Theme>) -> Scenario {
+        Scenario {
+            universe,
+            view,
+            theme,
+        }
+    }
+
+    pub fn view(&self) -> Option<View> {
+        self.view
+    }
+
+    pub fn theme(&self) -> Option<&Theme> {
+        self.theme.as_ref()
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Scenario {
+    /// Read a scenario in the schema above.
+    pub fn from_json(text: &str) -> Result<Scenario, ScenarioError> {
+        let document: Document = serde_json::from_str(text)?;
+        if let Some(version) = document.version.filter(|&version| version != VERSION) {
+            return Err(ScenarioError::Version(version));
+        }
+        let mut universe = Universe::deserialize(&document.board)?;
+        if let Some(rule) = document.rule {
+            universe.apply_rule(Rule::parse(&rule)?);
+        }
+        if let Some(name) = document.topology {
+            let topology =
+                Topology::from_name(&name).ok_or(ScenarioError::UnknownTopology(name))?;
+            universe.set_topology(topology);
+        }
+        if let Some(generation) = document.generation {
+            universe.set_generation(generation);
+        }
+        let theme = match document.theme {
+            Some(theme) => Some(theme.theme().map_err(ScenarioError::UnknownTheme)?),
+            None => None,
+        };
+        Ok(Scenario::new(
+            universe,
+            document.viewport.map(View::from),
+            theme,
+        ))
+    }
+
+    /// Write the scenario in the schema above.
+    pub fn to_json(&self) -> String {
+        let universe = &self.universe;
+        let document = Document {
+            version: Some(VERSION),
+            board: universe.serialize(),
+            rule: Some(universe.rule().to_string()),
+            topology: Some(universe.topology().name().to_string()),
+            generation: Some(universe.generation()),
+            viewport: self.view.map(ViewDocument::from),
+            theme: self
+                .theme
+                .clone()
+                .map(|theme| NamedTheme::Theme(Box::new(theme))),
+        };
+        // Every member is a string, number or `bool`, so this can't fail.
+        serde_json::to_string(&document).unwrap()
+    }
+
+    /// A copy of the board.
+    pub fn universe(&self) -> Universe {
+        self.universe.clone()
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.universe.generation()
+    }
+}
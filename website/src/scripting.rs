@@ -0,0 +1,196 @@
+//! Letting a script have the last word on each generation, to prototype a
+//! rule or meddle with one without recompiling:
+//!
+//! ```js
+//! // Nothing may be born in the left half of the board.
+//! game.set_rule_hook((next) => {
+//!   for (const idx of next.changed()) {
+//!     const [row, column] = [Math.floor(idx / next.width()), idx % next.width()];
+//!     if (column < next.width() / 2 && next.before(row, column) === Cell.Dead) {
+//!       next.veto(row, column);
+//!     }
+//!   }
+//! });
+//! ```
+//!
+//! The board's own rule runs first, and the hook is handed what it did as a
+//! `NextGeneration`: the cells it changed, by index, and both boards to
+//! read. Whatever the hook vetoes or sets is then applied as an edit, so
+//! the generation can be replayed and shared like any other. Only changes
+//! made while the hook runs count.
+//!
+//! In Rust the same is done by `Universe::tick_with`, and from Node by
+//! `Universe.step_with`.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use crate::engine::Universe;
+use crate::rule::Rule;
+use crate::Cell;
+
+/// A generation the rule has worked out, for a hook to change before it is
+/// final. Copies share their changes, so the one handed to JS can be read
+/// back once the hook returns.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone, Debug)]
+pub struct NextGeneration {
+    proposal: Rc<RefCell<Proposal>>,
+}
+
+#[derive(Debug)]
+struct Proposal {
+    generation: u64,
+    width: u32,
+    height: u32,
+    rule: Rule,
+    /// Every cell's state before the rule ran, and after.
+    before: Vec<u8>,
+    after: Vec<u8>,
+    /// How many live neighbors every cell had before the rule ran, as the
+    /// rule counts them.
+    live_neighbors: Vec<u16>,
+    /// The cells whose state the rule changed, in order.
+    changed: Vec<u32>,
+    /// The states the hook gave cells, by index.
+    overrides: BTreeMap<u32, u8>,
+    /// Whether the hook's changes have been taken, after which no more can
+    /// be made.
+    finished: bool,
+}
+
+impl NextGeneration {
+    /// What the rule did to `universe`, which held the states `before` a
+    /// generation ago, with the `live_neighbors` it counted then.
+    pub fn new(before: Vec<u8>, live_neighbors: Vec<u16>, universe: &Universe) -> NextGeneration {
+        let after = universe.cells();
+        let changed = (0..after.len() as u32)
+            .filter(|&idx| before[idx as usize] != after[idx as usize])
+            .collect();
+        NextGeneration {
+            proposal: Rc::new(RefCell::new(Proposal {
+                generation: universe.generation(),
+                width: universe.width(),
+                height: universe.height(),
+                rule: universe.rule(),
+                before,
+                after,
+                live_neighbors,
+                changed,
+                overrides: BTreeMap::new(),
+                finished: false,
+            })),
+        }
+    }
+
+    /// The cells the hook changed, by index, with their states a generation
+    /// ago and the states the hook gave them, leaving out any it set to what
+    /// the rule had already made them. No more changes can be made after.
+    pub(crate) fn take_overrides(&self) -> Vec<(usize, u8, u8)> {
+        let mut proposal = self.proposal.borrow_mut();
+        // The hook's copy may outlive it, but nothing it does now counts.
+        proposal.finished = true;
+        std::mem::take(&mut proposal.overrides)
+            .into_iter()
+            .map(|(idx, state)| (idx as usize, proposal.before[idx as usize], state))
+            .filter(|&(idx, _, state)| proposal.after[idx] != state)
+            .collect()
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl NextGeneration {
+    /// The generation being made.
+    pub fn generation(&self) -> u64 {
+        self.proposal.borrow().generation
+    }
+
+    pub fn width(&self) -> u32 {
+        self.proposal.borrow().width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.proposal.borrow().height
+    }
+
+    /// The cells the rule changed, as `row * width + column`, in order.
+    pub fn changed(&self) -> Vec<u32> {
+        self.proposal.borrow().changed.clone()
+    }
+
+    /// The cell as it was a generation ago. Cells off the board read as
+    /// dead, here and below.
+    pub fn before(&self, row: u32, column: u32) -> Cell {
+        let proposal = self.proposal.borrow();
+        proposal
+            .index(row, column)
+            .map_or(Cell::Dead, |idx| Cell::from_state(proposal.before[idx]))
+    }
+
+    /// The cell as the rule made it.
+    pub fn proposed(&self, row: u32, column: u32) -> Cell {
+        let proposal = self.proposal.borrow();
+        proposal
+            .index(row, column)
+            .map_or(Cell::Dead, |idx| Cell::from_state(proposal.after[idx]))
+    }
+
+    /// The cell as it will be, with the hook's changes so far.
+    pub fn next(&self, row: u32, column: u32) -> Cell {
+        let proposal = self.proposal.borrow();
+        proposal.index(row, column).map_or(Cell::Dead, |idx| {
+            let state = proposal.overrides.get(&(idx as u32));
+            Cell::from_state(*state.unwrap_or(&proposal.after[idx]))
+        })
+    }
+
+    /// How many of the cell's neighbors were alive a generation ago, counted
+    /// as the rule counts them: across its neighborhood and range, across
+    /// the board's edges as its topology says, and the cell itself too if
+    /// the rule says so. For hooks that work out a rule of their own.
+    pub fn live_neighbors(&self, row: u32, column: u32) -> u32 {
+        let proposal = self.proposal.borrow();
+        proposal
+            .index(row, column)
+            .map_or(0, |idx| proposal.live_neighbors[idx] as u32)
+    }
+
+    /// Keep the cell as it was a generation ago, whatever the rule said.
+    pub fn veto(&mut self, row: u32, column: u32) {
+        let mut proposal = self.proposal.borrow_mut();
+        if let Some(idx) = proposal.index(row, column).filter(|_| !proposal.finished) {
+            let state = proposal.before[idx];
+            proposal.overrides.insert(idx as u32, state);
+        }
+    }
+
+    /// Make the cell `cell`, whatever the rule said. Dying cells are dead
+    /// under rules without dying states, such as those whose live cells come
+    /// in colors, where the state a dying cell would have is a color.
+    pub fn set(&mut self, row: u32, column: u32, cell: Cell) {
+        let mut proposal = self.proposal.borrow_mut();
+        if let Some(idx) = proposal.index(row, column).filter(|_| !proposal.finished) {
+            let state = match cell {
+                Cell::Dying if proposal.rule.states() <= proposal.rule.colors() + 1 => {
+                    Cell::Dead as u8
+                }
+                cell => cell as u8,
+            };
+            proposal.overrides.insert(idx as u32, state);
+        }
+    }
+}
+
+impl Proposal {
+    fn index(&self, row: u32, column: u32) -> Option<usize> {
+        if row < self.height && column < self.width {
+            Some((row * self.width + column) as usize)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,216 @@
+//! Searching random soups for the objects they settle into, in the spirit
+//! of apgsearch.
+//!
+//! Each soup is a `SOUP_SIZE` square of random cells, mirrored under a
+//! `Symmetry`, in the middle of an empty board with dead edges. It runs
+//! under Conway's Life until it repeats, and then the census of what is
+//! left, the ash, is added to the running totals. Spaceships heading for
+//! the edge are counted and taken off the board before they reach it, so
+//! that they don't crash into the edge and leave debris behind.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use crate::brush::Symmetry;
+use crate::census;
+use crate::engine::{Cell, Position, Topology, Universe};
+use crate::random::XorShift;
+
+/// The width and height of the random part of each soup.
+pub const SOUP_SIZE: u32 = 16;
+
+/// Empty cells around the soup, for it to spread into.
+const MARGIN: u32 = 48;
+
+/// Soups that haven't repeated after this many generations are given up on.
+pub const MAX_SOUP_GENERATIONS: u32 = 5000;
+
+/// Generations between checks for a repeat and for spaceships leaving.
+const CHECK_INTERVAL: u32 = 60;
+
+/// The longest period the check for a repeat looks for.
+const MAX_PERIOD: u32 = 60;
+
+/// How close to the edge, in cells, a spaceship is taken off the board.
+const ESCAPE_DISTANCE: u32 = 8;
+
+/// The objects that fly away rather than settle.
+const SPACESHIPS: &[&str] = &["glider", "lwss"];
+
+/// Objects making up less than this fraction of everything found are rare.
+const RARE_FRACTION: f64 = 0.01;
+
+/// A search through numbered soups, with the totals of everything found so
+/// far.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone, Debug)]
+pub struct SoupSearch {
+    seed: u64,
+    symmetry: Symmetry,
+    /// How many soups have been searched. The next one is numbered this.
+    soups: u64,
+    counts: BTreeMap<&'static str, u64>,
+    /// The number of the first soup each known object was found in.
+    first_found: BTreeMap<&'static str, u64>,
+    /// Objects that weren't any of the known ones.
+    other: u64,
+    /// Soups given up on after `MAX_SOUP_GENERATIONS`.
+    unsettled: u64,
+}
+
+impl SoupSearch {
+    /// Each known object found and how many of it, the most common first.
+    pub fn counts(&self) -> Vec<(&'static str, u64)> {
+        let mut counts: Vec<_> = self.counts.iter().map(|(&name, &n)| (name, n)).collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        counts
+    }
+
+    /// The known objects that make up less than `RARE_FRACTION` of
+    /// everything found, each with the number of the first soup it was found
+    /// in.
+    pub fn rare(&self) -> Vec<(&'static str, u64)> {
+        let total = self.counts.values().sum::<u64>() + self.other;
+        self.counts()
+            .into_iter()
+            .rev()
+            .filter(|&(_, count)| (count as f64) < RARE_FRACTION * total as f64)
+            .map(|(name, _)| (name, self.first_found[name]))
+            .collect()
+    }
+
+    /// Run soup number `soup` until it settles and count its ash.
+    fn search_soup(&mut self, soup: u64) {
+        let mut universe = self.soup(soup);
+        let mut found: Vec<&'static str> = Vec::new();
+        let mut settled = false;
+        while universe.generation() < MAX_SOUP_GENERATIONS as u64 {
+            universe.step(CHECK_INTERVAL);
+            found.extend(remove_escaping_spaceships(&mut universe));
+            if universe.detect_period(MAX_PERIOD) > 0 {
+                settled = true;
+                break;
+            }
+        }
+        if !settled {
+            self.unsettled += 1;
+        }
+        for (name, _) in census::identify(&universe) {
+            match name {
+                Some(name) => found.push(name),
+                None => self.other += 1,
+            }
+        }
+        for name in found {
+            *self.counts.entry(name).or_default() += 1;
+            self.first_found.entry(name).or_insert(soup);
+        }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl SoupSearch {
+    /// Start a search whose soups are drawn from `seed` and mirrored under
+    /// `symmetry`.
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen(constructor))]
+    pub fn new(seed: u64, symmetry: Symmetry) -> SoupSearch {
+        SoupSearch {
+            seed,
+            symmetry,
+            soups: 0,
+            counts: BTreeMap::new(),
+            first_found: BTreeMap::new(),
+            other: 0,
+            unsettled: 0,
+        }
+    }
+
+    /// The board soup number `soup` starts from, the same every time.
+    pub fn soup(&self, soup: u64) -> Universe {
+        let size = SOUP_SIZE + 2 * MARGIN;
+        let mut universe = Universe::new_with_size(size, size);
+        universe.set_topology(Topology::Dead);
+        // Spread the soup numbers out, since seeds that differ only in their
+        // lowest bit start the generator off the same.
+        let mut rng = XorShift::new(self.seed ^ soup.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        for row in 0..SOUP_SIZE {
+            for column in 0..SOUP_SIZE {
+                if rng.next_f64() < 0.5 {
+                    for (row, column) in self.symmetry.images(row, column, SOUP_SIZE, SOUP_SIZE) {
+                        universe
+                            .set_cell(Position::new(MARGIN + row, MARGIN + column), Cell::Alive);
+                    }
+                }
+            }
+        }
+        universe
+    }
+
+    /// Search the next `soups` soups.
+    pub fn search(&mut self, soups: u32) {
+        for _ in 0..soups {
+            self.search_soup(self.soups);
+            self.soups += 1;
+        }
+    }
+
+    /// How many soups have been searched.
+    pub fn soups(&self) -> u64 {
+        self.soups
+    }
+
+    /// How many of the known object called `name` have been found.
+    pub fn count(&self, name: &str) -> u64 {
+        self.counts.get(name).copied().unwrap_or(0)
+    }
+
+    /// How many objects weren't recognized.
+    pub fn other(&self) -> u64 {
+        self.other
+    }
+
+    /// How many soups were given up on before they settled.
+    pub fn unsettled(&self) -> u64 {
+        self.unsettled
+    }
+}
+
+/// Lists the totals as `name count`, the most common first.
+impl fmt::Display for SoupSearch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, (name, count)) in self.counts().into_iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{} {}", name, count)?;
+        }
+        Ok(())
+    }
+}
+
+/// Take spaceships near the edge off the board, returning their names.
+fn remove_escaping_spaceships(universe: &mut Universe) -> Vec<&'static str> {
+    let (width, height) = (universe.width(), universe.height());
+    let near_edge = |&(row, column): &(u32, u32)| {
+        row < ESCAPE_DISTANCE
+            || column < ESCAPE_DISTANCE
+            || row + ESCAPE_DISTANCE >= height
+            || column + ESCAPE_DISTANCE >= width
+    };
+    let mut escaped = Vec::new();
+    for (name, cells) in census::identify(universe) {
+        match name {
+            Some(name) if SPACESHIPS.contains(&name) && cells.iter().any(near_edge) => {
+                for (row, column) in cells {
+                    universe.set_cell(Position::new(row, column), Cell::Dead);
+                }
+                escaped.push(name);
+            }
+            _ => {}
+        }
+    }
+    escaped
+}
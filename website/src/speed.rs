@@ -0,0 +1,57 @@
+//! Throttling the simulation independently of the display refresh rate.
+
+/// How many generations the animation loop runs, and how often.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Speed {
+    ticks_per_frame: u32,
+    frames_per_tick: u32,
+    /// Frames drawn since the last tick.
+    frame: u32,
+}
+
+impl Default for Speed {
+    fn default() -> Speed {
+        Speed::new(1, 1)
+    }
+}
+
+impl Speed {
+    pub fn new(ticks_per_frame: u32, frames_per_tick: u32) -> Speed {
+        Speed {
+            ticks_per_frame: ticks_per_frame.max(1),
+            frames_per_tick: frames_per_tick.max(1),
+            frame: 0,
+        }
+    }
+
+    /// Convert the value of the page's speed slider.
+    ///
+    /// Positive values are generations per frame. Zero and below slow the
+    /// simulation down instead, with 0 ticking every second frame, -1 every
+    /// third frame and so on.
+    pub fn from_slider(value: i32) -> Speed {
+        if value > 0 {
+            Speed::new(value as u32, 1)
+        } else {
+            Speed::new(1, (2 - value) as u32)
+        }
+    }
+
+    pub fn ticks_per_frame(&self) -> u32 {
+        self.ticks_per_frame
+    }
+
+    pub fn frames_per_tick(&self) -> u32 {
+        self.frames_per_tick
+    }
+
+    /// Called once per animation frame, returns how many generations to run.
+    pub fn ticks_for_frame(&mut self) -> u32 {
+        self.frame += 1;
+        if self.frame < self.frames_per_tick {
+            return 0;
+        }
+        self.frame = 0;
+        self.ticks_per_frame
+    }
+}
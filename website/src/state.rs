@@ -0,0 +1,115 @@
+//! Whether a game is running, kept in one place so that its play button and
+//! its animation loop always agree.
+
+use std::cell::RefCell;
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// The closure the animation loop runs each frame, shared so that it can ask
+/// for the next frame itself.
+pub(crate) type AnimationCallback = RefCell<Option<Closure<dyn FnMut()>>>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RunState {
+    Paused,
+    Running,
+}
+
+/// The run state of a game and the one animation frame it is waiting on.
+///
+/// Frames are only asked for through `play` and `next_frame`, which never
+/// ask while one is pending, so however often the game is played and paused
+/// there is never more than one animation loop.
+pub(crate) struct AppState {
+    run_state: RunState,
+    /// The id of the frame asked for next.
+    frame: Option<i32>,
+    /// Labelled with what pressing it does: play while paused and pause while
+    /// running.
+    button: Option<web_sys::Element>,
+}
+
+impl AppState {
+    pub(crate) fn new(button: Option<web_sys::Element>) -> AppState {
+        let state = AppState {
+            run_state: RunState::Paused,
+            frame: None,
+            button,
+        };
+        state.show();
+        state
+    }
+
+    pub(crate) fn is_running(&self) -> bool {
+        self.run_state == RunState::Running
+    }
+
+    /// Start the animation loop, unless it is running already.
+    pub(crate) fn play(&mut self, callback: &AnimationCallback) {
+        if self.is_running() {
+            return;
+        }
+        self.run_state = RunState::Running;
+        self.request_frame(callback);
+        self.show();
+    }
+
+    /// Stop the animation loop, cancelling the frame it is waiting on.
+    pub(crate) fn pause(&mut self) {
+        self.run_state = RunState::Paused;
+        if let Some(frame) = self.frame.take() {
+            if let Some(window) = web_sys::window() {
+                let _ = window.cancel_animation_frame(frame);
+            }
+        }
+        self.show();
+    }
+
+    pub(crate) fn toggle(&mut self, callback: &AnimationCallback) {
+        match self.run_state {
+            RunState::Paused => self.play(callback),
+            RunState::Running => self.pause(),
+        }
+    }
+
+    /// Called by the animation loop as each frame starts, since the frame it
+    /// was waiting on has now come.
+    pub(crate) fn begin_frame(&mut self) {
+        self.frame = None;
+    }
+
+    /// Called by the animation loop as each frame ends, to carry on into the
+    /// next one if still running.
+    pub(crate) fn next_frame(&mut self, callback: &AnimationCallback) {
+        if self.is_running() {
+            self.request_frame(callback);
+        }
+    }
+
+    /// Label the play button in the current language.
+    pub(crate) fn show(&self) {
+        if let Some(button) = &self.button {
+            let label = match self.run_state {
+                RunState::Paused => tr!(Play),
+                RunState::Running => tr!(Pause),
+            };
+            button.set_text_content(Some(&label));
+        }
+    }
+
+    fn request_frame(&mut self, callback: &AnimationCallback) {
+        if self.frame.is_none() {
+            self.frame = request_animation_frame(callback);
+        }
+    }
+}
+
+/// Ask for `callback` to run on the next frame, returning the request's id.
+fn request_animation_frame(callback: &AnimationCallback) -> Option<i32> {
+    let window = web_sys::window()?;
+    let callback = callback.borrow();
+    window
+        .request_animation_frame(callback.as_ref()?.as_ref().unchecked_ref())
+        .ok()
+}
@@ -0,0 +1,143 @@
+//! A live graph of the population over time.
+
+#[cfg(all(target_arch = "wasm32", feature = "web"))]
+use wasm_bindgen::JsCast;
+
+/// How many generations the graph looks back over.
+pub const HISTORY_LENGTH: usize = 200;
+
+/// The most recent population counts, oldest first, in a fixed-size ring
+/// buffer.
+#[derive(Clone, Debug)]
+pub struct PopulationHistory {
+    samples: Vec<u32>,
+    /// Index of the oldest sample once the buffer is full.
+    start: usize,
+    capacity: usize,
+}
+
+impl PopulationHistory {
+    pub fn new(capacity: usize) -> PopulationHistory {
+        PopulationHistory {
+            samples: Vec::with_capacity(capacity),
+            start: 0,
+            capacity,
+        }
+    }
+
+    /// Record a sample, dropping the oldest one if the buffer is full.
+    pub fn push(&mut self, population: u32) {
+        if self.samples.len() < self.capacity {
+            self.samples.push(population);
+        } else if self.capacity > 0 {
+            self.samples[self.start] = population;
+            self.start = (self.start + 1) % self.capacity;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+        self.start = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The samples from oldest to newest.
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        let (newer, older) = self.samples.split_at(self.start);
+        older.iter().chain(newer).copied()
+    }
+
+    pub fn max(&self) -> u32 {
+        self.iter().max().unwrap_or(0)
+    }
+}
+
+/// Draws a `PopulationHistory` as a line onto its own canvas element.
+#[cfg(all(target_arch = "wasm32", feature = "web"))]
+pub struct Sparkline {
+    canvas: web_sys::HtmlCanvasElement,
+    context: web_sys::CanvasRenderingContext2d,
+    history: PopulationHistory,
+    /// The generation of the last sample, to notice when the board is reset.
+    generation: Option<u64>,
+    color: String,
+}
+
+#[cfg(all(target_arch = "wasm32", feature = "web"))]
+impl Sparkline {
+    /// The sparkline for `#stats-canvas`, or `None` if the page doesn't have
+    /// one.
+    pub fn from_document(document: &web_sys::Document) -> Option<Sparkline> {
+        let canvas = document
+            .get_element_by_id("stats-canvas")?
+            .dyn_into::<web_sys::HtmlCanvasElement>()
+            .ok()?;
+        let context = canvas
+            .get_context("2d")
+            .ok()??
+            .dyn_into::<web_sys::CanvasRenderingContext2d>()
+            .ok()?;
+        Some(Sparkline {
+            canvas,
+            context,
+            history: PopulationHistory::new(HISTORY_LENGTH),
+            generation: None,
+            color: "#000000".to_string(),
+        })
+    }
+
+    /// Record the population at `generation` and redraw. Repeated calls for
+    /// the same generation are ignored, and going back in time starts a new
+    /// graph.
+    pub fn record(&mut self, generation: u64, population: u32) {
+        match self.generation {
+            Some(last) if last == generation => return,
+            Some(last) if last > generation => self.history.clear(),
+            _ => {}
+        }
+        self.generation = Some(generation);
+        self.history.push(population);
+        self.draw();
+    }
+
+    pub fn set_color(&mut self, color: &str) {
+        self.color = color.to_string();
+        self.draw();
+    }
+
+    fn draw(&self) {
+        let width = self.canvas.width() as f64;
+        let height = self.canvas.height() as f64;
+        self.context.clear_rect(0.0, 0.0, width, height);
+        if self.history.len() < 2 {
+            return;
+        }
+
+        // Leave a pixel at the top and bottom so the line isn't clipped.
+        let max = self.history.max().max(1) as f64;
+        let step = width / (self.history.capacity() - 1) as f64;
+        self.context.begin_path();
+        for (i, population) in self.history.iter().enumerate() {
+            let x = i as f64 * step;
+            let y = 1.0 + (height - 2.0) * (1.0 - population as f64 / max);
+            if i == 0 {
+                self.context.move_to(x, y);
+            } else {
+                self.context.line_to(x, y);
+            }
+        }
+        self.context.set_stroke_style_str(&self.color);
+        self.context.stroke();
+    }
+}
@@ -0,0 +1,47 @@
+//! Saving universes in the browser's `localStorage`.
+
+use wasm_bindgen::prelude::*;
+
+use crate::Universe;
+
+/// Keeps saved games apart from anything else the page's origin stores.
+const KEY_PREFIX: &str = "game-of-life/";
+
+fn local_storage() -> Result<web_sys::Storage, JsValue> {
+    web_sys::window()
+        .ok_or_else(|| JsValue::from_str("no window"))?
+        .local_storage()?
+        .ok_or_else(|| JsValue::from_str("localStorage is not available"))
+}
+
+/// Save `universe` under `name`, replacing any earlier save with that name.
+#[wasm_bindgen]
+pub fn save_to_storage(universe: &Universe, name: &str) -> Result<(), JsValue> {
+    local_storage()?.set_item(&(KEY_PREFIX.to_string() + name), &universe.serialize())
+}
+
+/// Load the universe saved under `name`, or `None` if there isn't one.
+#[wasm_bindgen]
+pub fn load_from_storage(name: &str) -> Result<Option<Universe>, JsValue> {
+    match local_storage()?.get_item(&(KEY_PREFIX.to_string() + name))? {
+        Some(text) => Ok(Some(Universe::deserialize(&text)?)),
+        None => Ok(None),
+    }
+}
+
+/// The names of every saved universe, sorted.
+#[wasm_bindgen]
+pub fn saved_names() -> Result<Vec<String>, JsValue> {
+    let storage = local_storage()?;
+    let mut names = Vec::new();
+    for i in 0..storage.length()? {
+        if let Some(name) = storage
+            .key(i)?
+            .and_then(|key| key.strip_prefix(KEY_PREFIX).map(str::to_string))
+        {
+            names.push(name);
+        }
+    }
+    names.sort();
+    Ok(names)
+}
@@ -0,0 +1,524 @@
+//! Rules given as tables of transitions, loaded from Golly's `.rule` files.
+//!
+//! The `@TABLE` section of a rule file gives the number of states, the
+//! neighborhood and the symmetries, declares some variables, and then lists
+//! one transition per line: the state of a cell, the states of its
+//! neighbors clockwise from north, and the state the cell moves to, as in
+//! `0,1,1,1,0,0,0,0,0,1`. Tables with fewer than 11 states can leave out
+//! the commas. A variable such as `var a={0,1,2}` stands for any of its
+//! states, but for the same one everywhere it appears in a transition. The
+//! symmetries add the rotations and reflections of every transition, or
+//! with `permute`, every order of the neighbors.
+//!
+//! The first transition that matches a cell decides its next state, and a
+//! cell that none match stays as it is. Sections other than `@TABLE` are
+//! skipped, apart from the state colors in `@COLORS`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+use crate::engine::{SimulationEngine, Topology};
+use crate::rule::{Neighborhood, CONFIGURATION_OFFSETS};
+use crate::{Cell, Position};
+
+/// The first line of a rule file.
+pub const RULE_HEADER: &str = "@RULE";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TableError {
+    /// The file had no `@TABLE` section.
+    MissingTable,
+    /// `n_states` was not a number between 2 and 255.
+    InvalidStateCount(String),
+    /// The neighborhood was not `Moore` or `vonNeumann`.
+    UnsupportedNeighborhood(String),
+    /// The symmetries were not ones the neighborhood has.
+    UnsupportedSymmetry(String),
+    /// A line of the table, numbered from 1, could not be read.
+    InvalidLine(usize, String),
+}
+
+impl fmt::Display for TableError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TableError::MissingTable => write!(f, "no @TABLE section"),
+            TableError::InvalidStateCount(states) => write!(f, "invalid state count {:?}", states),
+            TableError::UnsupportedNeighborhood(neighborhood) => {
+                write!(f, "unsupported neighborhood {:?}", neighborhood)
+            }
+            TableError::UnsupportedSymmetry(symmetry) => {
+                write!(f, "unsupported symmetry {:?}", symmetry)
+            }
+            TableError::InvalidLine(number, line) => write!(f, "line {}: {:?}", number, line),
+        }
+    }
+}
+
+impl std::error::Error for TableError {}
+
+#[cfg(target_arch = "wasm32")]
+impl From<TableError> for JsValue {
+    fn from(error: TableError) -> JsValue {
+        JsValue::from_str(&error.to_string())
+    }
+}
+
+/// One entry of a transition: a state, or a numbered variable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Input {
+    State(u8),
+    Variable(usize),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Transition {
+    /// The cell and then its neighbors.
+    inputs: Vec<Input>,
+    output: Input,
+}
+
+impl Transition {
+    /// The state a cell in `cell` with neighbors in `neighbors` moves to,
+    /// if this transition matches it. `bound` holds the state each variable
+    /// has taken so far.
+    fn apply(
+        &self,
+        cell: u8,
+        neighbors: &[u8],
+        variables: &[Vec<u8>],
+        bound: &mut [Option<u8>],
+    ) -> Option<u8> {
+        bound.iter_mut().for_each(|state| *state = None);
+        let states = std::iter::once(&cell).chain(neighbors);
+        for (&input, &state) in self.inputs.iter().zip(states) {
+            let matches = match input {
+                Input::State(expected) => expected == state,
+                Input::Variable(variable) => match bound[variable] {
+                    Some(expected) => expected == state,
+                    None if variables[variable].contains(&state) => {
+                        bound[variable] = Some(state);
+                        true
+                    }
+                    None => false,
+                },
+            };
+            if !matches {
+                return None;
+            }
+        }
+        match self.output {
+            Input::State(state) => Some(state),
+            Input::Variable(variable) => bound[variable],
+        }
+    }
+}
+
+/// The ways a transition's neighbors can be rearranged and still apply.
+#[derive(Clone, Debug)]
+enum Symmetries {
+    /// Each rearrangement, as the index of the old neighbor that goes in
+    /// each place.
+    Rearrangements(Vec<Vec<usize>>),
+    /// Every order of the neighbors.
+    Permute,
+}
+
+/// A rule table read from a `.rule` file.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone, Debug)]
+pub struct RuleTable {
+    name: String,
+    states: u8,
+    neighborhood: Neighborhood,
+    /// The states each variable stands for.
+    variables: Vec<Vec<u8>>,
+    /// Every transition with its rearrangements, in the order they are
+    /// tried.
+    transitions: Vec<Transition>,
+    /// The `#RRGGBB` colors given to states in `@COLORS`.
+    colors: BTreeMap<u8, String>,
+}
+
+impl RuleTable {
+    /// The state a cell in `cell` with neighbors in `neighbors`, clockwise
+    /// from north, moves to.
+    pub fn next_state(&self, cell: u8, neighbors: &[u8]) -> u8 {
+        let mut bound = vec![None; self.variables.len()];
+        self.transitions
+            .iter()
+            .find_map(|transition| transition.apply(cell, neighbors, &self.variables, &mut bound))
+            .unwrap_or(cell)
+    }
+
+    /// The color `@COLORS` gives `state`, if any.
+    pub fn color(&self, state: u8) -> Option<&str> {
+        self.colors.get(&state).map(String::as_str)
+    }
+
+    /// Read one line of the `@TABLE` section: a setting, a variable or a
+    /// transition. `names` holds the names of the variables so far.
+    fn parse_line(
+        &mut self,
+        line: &str,
+        names: &mut Vec<String>,
+        symmetries: &mut Option<Symmetries>,
+    ) -> Result<(), Option<TableError>> {
+        if let Some((key, value)) = line.split_once(':') {
+            let value = value.trim();
+            match key.trim() {
+                "n_states" => match value.parse::<u8>() {
+                    Ok(states) if states >= 2 => self.states = states,
+                    _ => return Err(Some(TableError::InvalidStateCount(value.to_string()))),
+                },
+                "neighborhood" if value.eq_ignore_ascii_case("Moore") => {
+                    self.neighborhood = Neighborhood::Moore
+                }
+                "neighborhood" if value.eq_ignore_ascii_case("vonNeumann") => {
+                    self.neighborhood = Neighborhood::VonNeumann
+                }
+                "neighborhood" => {
+                    return Err(Some(TableError::UnsupportedNeighborhood(value.to_string())))
+                }
+                "symmetries" => match symmetries_named(value, self.neighbors()) {
+                    Some(named) => *symmetries = Some(named),
+                    None => return Err(Some(TableError::UnsupportedSymmetry(value.to_string()))),
+                },
+                _ => return Err(None),
+            }
+            return Ok(());
+        }
+        if self.states == 0 {
+            return Err(None);
+        }
+
+        if let Some(variable) = line.strip_prefix("var ") {
+            let (name, states) = variable.split_once('=').ok_or(None)?;
+            let states = states.trim();
+            let states = states
+                .strip_prefix('{')
+                .and_then(|states| states.strip_suffix('}'))
+                .unwrap_or(states);
+            let mut values = Vec::new();
+            for value in states.split(',') {
+                match self.input(value, names).ok_or(None)? {
+                    Input::State(state) => values.push(state),
+                    Input::Variable(variable) => values.extend(&self.variables[variable]),
+                }
+            }
+            names.push(name.trim().to_string());
+            self.variables.push(values);
+            return Ok(());
+        }
+
+        let entries: Vec<&str> = if line.contains(',') {
+            line.split(',').collect()
+        } else if self.states <= 10 {
+            (0..line.len()).filter_map(|i| line.get(i..i + 1)).collect()
+        } else {
+            return Err(None);
+        };
+        if entries.len() != self.neighbors() + 2 {
+            return Err(None);
+        }
+        let mut inputs = Vec::with_capacity(entries.len());
+        for entry in entries {
+            inputs.push(self.input(entry, names).ok_or(None)?);
+        }
+        let output = inputs.pop().ok_or(None)?;
+        // A variable can only be copied into the output once it has a state.
+        if let Input::Variable(_) = output {
+            if !inputs.contains(&output) {
+                return Err(None);
+            }
+        }
+        let symmetries =
+            symmetries.get_or_insert_with(|| symmetries_named("none", self.neighbors()).unwrap());
+        for inputs in rearrange(&inputs, symmetries) {
+            self.transitions.push(Transition { inputs, output });
+        }
+        Ok(())
+    }
+
+    /// A state, or the most recent variable called `entry`.
+    fn input(&self, entry: &str, names: &[String]) -> Option<Input> {
+        let entry = entry.trim();
+        match entry.parse::<u8>() {
+            Ok(state) if state < self.states => Some(Input::State(state)),
+            Ok(_) => None,
+            Err(_) => names
+                .iter()
+                .rposition(|name| name == entry)
+                .map(Input::Variable),
+        }
+    }
+
+    /// How many neighbors each cell has.
+    fn neighbors(&self) -> usize {
+        self.neighborhood.size() as usize
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl RuleTable {
+    /// Read the `@TABLE` and `@COLORS` sections of the contents of a
+    /// `.rule` file. Text without sections is taken to be a table.
+    pub fn parse(text: &str) -> Result<RuleTable, TableError> {
+        let mut table = RuleTable {
+            name: String::new(),
+            states: 0,
+            neighborhood: Neighborhood::Moore,
+            variables: Vec::new(),
+            transitions: Vec::new(),
+            colors: BTreeMap::new(),
+        };
+        let mut names = Vec::new();
+        let mut symmetries = None;
+        // Old `.table` files are just the table.
+        let mut section = "@TABLE";
+        let mut found_table = false;
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('@') {
+                section = line.split_whitespace().next().unwrap_or_default();
+                if let Some(name) = line.strip_prefix(RULE_HEADER) {
+                    table.name = name.trim().to_string();
+                }
+                continue;
+            }
+            let invalid = || TableError::InvalidLine(number + 1, line.to_string());
+            match section {
+                "@TABLE" => {
+                    found_table = true;
+                    table
+                        .parse_line(line, &mut names, &mut symmetries)
+                        .map_err(|error| error.unwrap_or_else(invalid))?;
+                }
+                "@COLORS" => {
+                    let numbers: Vec<u8> = line
+                        .split_whitespace()
+                        .map(|number| number.parse().map_err(|_| invalid()))
+                        .collect::<Result<_, _>>()?;
+                    // Gradients over ranges of states are left to the theme.
+                    if let [state, red, green, blue] = numbers[..] {
+                        let color = format!("#{:02X}{:02X}{:02X}", red, green, blue);
+                        table.colors.insert(state, color);
+                    }
+                }
+                _ => {}
+            }
+        }
+        if !found_table || table.states == 0 {
+            return Err(TableError::MissingTable);
+        }
+        Ok(table)
+    }
+
+    /// The name after `@RULE`, or nothing if the file didn't give one.
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    /// The number of states, from 0 up to one less than this.
+    pub fn states(&self) -> u8 {
+        self.states
+    }
+
+    pub fn neighborhood(&self) -> Neighborhood {
+        self.neighborhood
+    }
+}
+
+/// The symmetries called `name` for cells with `neighbors` neighbors.
+fn symmetries_named(name: &str, neighbors: usize) -> Option<Symmetries> {
+    let rotations = |step: usize| -> Vec<Vec<usize>> {
+        (0..neighbors)
+            .step_by(step)
+            .map(|turn| (0..neighbors).map(|i| (i + turn) % neighbors).collect())
+            .collect()
+    };
+    let quarter = neighbors / 4;
+    let with_reflections = |rearrangements: Vec<Vec<usize>>| -> Vec<Vec<usize>> {
+        let reflections: Vec<Vec<usize>> = rearrangements
+            .iter()
+            .map(|order| {
+                (0..neighbors)
+                    .map(|i| order[(neighbors - i) % neighbors])
+                    .collect()
+            })
+            .collect();
+        rearrangements.into_iter().chain(reflections).collect()
+    };
+    let rearrangements = match name {
+        "none" => rotations(neighbors),
+        "rotate4" => rotations(quarter),
+        "rotate8" if neighbors == 8 => rotations(1),
+        "reflect_horizontal" => with_reflections(rotations(neighbors)),
+        "rotate4reflect" => with_reflections(rotations(quarter)),
+        "rotate8reflect" if neighbors == 8 => with_reflections(rotations(1)),
+        "permute" => return Some(Symmetries::Permute),
+        _ => return None,
+    };
+    Some(Symmetries::Rearrangements(rearrangements))
+}
+
+/// `inputs`, the cell and then its neighbors, under each of `symmetries`,
+/// without repeats.
+fn rearrange(inputs: &[Input], symmetries: &Symmetries) -> Vec<Vec<Input>> {
+    let (cell, neighbors) = (inputs[0], &inputs[1..]);
+    let mut rearranged: Vec<Vec<Input>> = Vec::new();
+    match symmetries {
+        Symmetries::Rearrangements(rearrangements) => {
+            for order in rearrangements {
+                let inputs = std::iter::once(cell)
+                    .chain(order.iter().map(|&i| neighbors[i]))
+                    .collect();
+                if !rearranged.contains(&inputs) {
+                    rearranged.push(inputs);
+                }
+            }
+        }
+        // Stepping through the orders in sorted order never repeats one.
+        Symmetries::Permute => {
+            let mut order = neighbors.to_vec();
+            order.sort_unstable();
+            loop {
+                rearranged.push(std::iter::once(cell).chain(order.iter().copied()).collect());
+                if !next_permutation(&mut order) {
+                    break;
+                }
+            }
+        }
+    }
+    rearranged
+}
+
+/// Step `items` on to the next of its orders, sorted from least to
+/// greatest, or return false if it is already the last.
+fn next_permutation<T: Ord>(items: &mut [T]) -> bool {
+    let pivot = match (1..items.len()).rev().find(|&i| items[i - 1] < items[i]) {
+        Some(i) => i - 1,
+        None => return false,
+    };
+    let swap = (pivot + 1..items.len())
+        .rev()
+        .find(|&i| items[pivot] < items[i])
+        .unwrap();
+    items.swap(pivot, swap);
+    items[pivot + 1..].reverse();
+    true
+}
+
+/// A board run by a rule table, with a state from 0 up for every cell.
+#[derive(Clone, Debug)]
+pub struct TableBoard {
+    width: u32,
+    height: u32,
+    topology: Topology,
+    table: RuleTable,
+    /// The state of every cell in row-major order.
+    states: Vec<u8>,
+    /// The next state of every cell and neighbors looked up so far, since
+    /// most boards only ever show a few of them.
+    lookups: HashMap<[u8; 9], u8>,
+}
+
+impl TableBoard {
+    /// An empty board run by `table`.
+    pub fn new(width: u32, height: u32, table: RuleTable) -> TableBoard {
+        TableBoard {
+            width,
+            height,
+            topology: Topology::Torus,
+            table,
+            states: vec![0; (width * height) as usize],
+            lookups: HashMap::new(),
+        }
+    }
+
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+    }
+
+    pub fn table(&self) -> &RuleTable {
+        &self.table
+    }
+
+    pub fn state(&self, row: u32, column: u32) -> u8 {
+        self.states[self.get_index(row, column)]
+    }
+
+    /// Set the state of a cell, up to the last state of the table.
+    pub fn set_state(&mut self, row: u32, column: u32, state: u8) {
+        let idx = self.get_index(row, column);
+        self.states[idx] = state.min(self.table.states - 1);
+    }
+
+    fn get_index(&self, row: u32, column: u32) -> usize {
+        (row * self.width + column) as usize
+    }
+}
+
+impl SimulationEngine for TableBoard {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Every state but 0 counts as alive.
+    fn cell(&self, position: Position) -> Option<Cell> {
+        if !self.contains(position) {
+            return None;
+        }
+        Some(Cell::from(self.state(position.row, position.column) != 0))
+    }
+
+    /// Only cells that change between alive and dead are touched, so copying
+    /// in an edited board keeps the states everywhere else.
+    fn set_cell(&mut self, position: Position, cell: Cell) -> Option<Cell> {
+        let was = self.cell(position)?;
+        let alive = cell == Cell::Alive;
+        if (was == Cell::Alive) != alive {
+            self.set_state(position.row, position.column, alive as u8);
+        }
+        Some(was)
+    }
+
+    fn tick(&mut self) {
+        let step = 8 / self.table.neighbors();
+        let offsets: Vec<(i64, i64)> = CONFIGURATION_OFFSETS
+            .iter()
+            .copied()
+            .step_by(step)
+            .collect();
+        let mut next = Vec::with_capacity(self.states.len());
+        for row in 0..self.height {
+            for column in 0..self.width {
+                let mut key = [0; 9];
+                key[0] = self.state(row, column);
+                for (i, &(delta_row, delta_col)) in offsets.iter().enumerate() {
+                    let neighbor_row = self.topology.wrap(row as i64 + delta_row, self.height);
+                    let neighbor_col = self.topology.wrap(column as i64 + delta_col, self.width);
+                    if let (Some(neighbor_row), Some(neighbor_col)) = (neighbor_row, neighbor_col) {
+                        key[i + 1] = self.state(neighbor_row, neighbor_col);
+                    }
+                }
+                let table = &self.table;
+                let state = *self
+                    .lookups
+                    .entry(key)
+                    .or_insert_with(|| table.next_state(key[0], &key[1..=offsets.len()]));
+                next.push(state);
+            }
+        }
+        self.states = next;
+    }
+}
@@ -0,0 +1,165 @@
+//! Colors for the canvas and the page around it.
+
+use serde::{Deserialize, Serialize};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+/// Every color the page draws with. The cell colors are `#RRGGBB` so that
+/// dying cells can be blended between them; the overlays are drawn on top
+/// of the cells and may be translucent.
+///
+/// As JSON, each color is a member named after it in camel case, with the
+/// colors of multi-color rules as an array. Colors that are left out are
+/// those of the light theme.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Theme {
+    /// Behind everything on the page.
+    pub(crate) background: String,
+    /// Text on the page and the population graph.
+    pub(crate) text: String,
+    /// The lines between cells, and every tenth line.
+    pub(crate) grid: String,
+    pub(crate) major_grid: String,
+    pub(crate) dead: String,
+    pub(crate) alive: String,
+    /// The colors of live cells after the first under Immigration and
+    /// QuadLife.
+    pub(crate) colors: Vec<String>,
+    /// A pattern that is being placed.
+    pub(crate) ghost: String,
+    /// The cells picked with the select tool.
+    pub(crate) selection: String,
+    /// The ends of the gradient live cells are shaded along by age, from
+    /// newborn to old.
+    pub(crate) young: String,
+    pub(crate) old: String,
+    /// Tints active cells, more opaquely the more active they are.
+    pub(crate) heat: String,
+    /// The outline around the keyboard cursor.
+    pub(crate) focus: String,
+    /// Tints the cells that differ from the board being compared with.
+    pub(crate) difference: String,
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+impl Theme {
+    /// Black cells on white.
+    pub fn light() -> Theme {
+        Theme {
+            background: "#FFFFFF".to_string(),
+            text: "#000000".to_string(),
+            grid: "#CCCCCC".to_string(),
+            major_grid: "#999999".to_string(),
+            dead: "#FFFFFF".to_string(),
+            alive: "#000000".to_string(),
+            colors: vec![
+                "#D00000".to_string(),
+                "#0050D0".to_string(),
+                "#008000".to_string(),
+            ],
+            ghost: "rgba(64, 128, 255, 0.6)".to_string(),
+            selection: "rgba(255, 192, 0, 0.4)".to_string(),
+            young: "#FFB000".to_string(),
+            old: "#600000".to_string(),
+            heat: "#FF3000".to_string(),
+            focus: "#0060DF".to_string(),
+            difference: "rgba(255, 0, 128, 0.5)".to_string(),
+        }
+    }
+
+    /// Light cells on a dark background.
+    pub fn dark() -> Theme {
+        Theme {
+            background: "#1E1E1E".to_string(),
+            text: "#DDDDDD".to_string(),
+            grid: "#333333".to_string(),
+            major_grid: "#555555".to_string(),
+            dead: "#121212".to_string(),
+            alive: "#E0E0E0".to_string(),
+            colors: vec![
+                "#FF6060".to_string(),
+                "#60A0FF".to_string(),
+                "#60D060".to_string(),
+            ],
+            ghost: "rgba(96, 160, 255, 0.6)".to_string(),
+            selection: "rgba(255, 192, 0, 0.35)".to_string(),
+            young: "#FFF0A0".to_string(),
+            old: "#A02000".to_string(),
+            heat: "#FF6030".to_string(),
+            focus: "#4DA3FF".to_string(),
+            difference: "rgba(255, 64, 160, 0.5)".to_string(),
+        }
+    }
+
+    /// Yellow cells on black with bold overlays, for low vision.
+    pub fn high_contrast() -> Theme {
+        Theme {
+            background: "#000000".to_string(),
+            text: "#FFFFFF".to_string(),
+            grid: "#808080".to_string(),
+            major_grid: "#C0C0C0".to_string(),
+            dead: "#000000".to_string(),
+            alive: "#FFFF00".to_string(),
+            colors: vec![
+                "#FF0000".to_string(),
+                "#00FFFF".to_string(),
+                "#00FF00".to_string(),
+            ],
+            ghost: "rgba(0, 255, 255, 0.7)".to_string(),
+            selection: "rgba(255, 0, 255, 0.5)".to_string(),
+            young: "#FFFF00".to_string(),
+            old: "#FF0000".to_string(),
+            heat: "#FF00FF".to_string(),
+            focus: "#00FF00".to_string(),
+            difference: "rgba(255, 128, 0, 0.7)".to_string(),
+        }
+    }
+
+    /// A copy of the theme that shades cells by age from `young` to `old`,
+    /// both `#RRGGBB` colors.
+    pub fn with_age_gradient(&self, young: &str, old: &str) -> Theme {
+        Theme {
+            young: young.to_string(),
+            old: old.to_string(),
+            ..self.clone()
+        }
+    }
+
+    /// The preset used by the page's theme dropdown: `"light"`, `"dark"` or
+    /// `"high-contrast"`.
+    pub fn from_name(name: &str) -> Option<Theme> {
+        match name {
+            "light" => Some(Theme::light()),
+            "dark" => Some(Theme::dark()),
+            "high-contrast" => Some(Theme::high_contrast()),
+            _ => None,
+        }
+    }
+}
+
+/// A theme as JSON gives it: either the name of a preset, as `from_name`
+/// takes, or the theme itself.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum NamedTheme {
+    Preset(String),
+    Theme(Box<Theme>),
+}
+
+impl NamedTheme {
+    /// The theme, or the name given if it names no preset.
+    pub(crate) fn theme(self) -> Result<Theme, String> {
+        match self {
+            NamedTheme::Preset(name) => Theme::from_name(&name).ok_or(name),
+            NamedTheme::Theme(theme) => Ok(*theme),
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme::light()
+    }
+}
@@ -0,0 +1,155 @@
+//! A `tracing` subscriber that writes to the browser console, for the
+//! `tracing` feature.
+//!
+//! Spans are timed from when they are entered to when they are left, and
+//! logged with `console.debug` as they are left, so each `tick` and `render`
+//! says how long it took. Events are logged at the console level matching
+//! theirs, with their fields after the message:
+//!
+//! ```text
+//! render took 2.41 ms renderer="rects"
+//! wasm_game_of_life::canvas: placing pattern width=36 height=9
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Metadata, Subscriber};
+
+use crate::fps;
+
+/// Only this crate's spans and events are logged.
+const TARGET: &str = "wasm_game_of_life";
+
+/// Log `tracing` spans and events to the console from now on. Only the
+/// first call does anything.
+pub fn init() {
+    let _ = tracing::subscriber::set_global_default(ConsoleSubscriber::default());
+}
+
+struct Span {
+    name: &'static str,
+    fields: Fields,
+    /// When the span was last entered, by `performance.now()`.
+    entered: Option<f64>,
+    /// The handles to the span still open.
+    references: usize,
+}
+
+#[derive(Default)]
+struct ConsoleSubscriber {
+    spans: Mutex<HashMap<u64, Span>>,
+    next_id: AtomicU64,
+}
+
+impl Subscriber for ConsoleSubscriber {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.target().starts_with(TARGET)
+    }
+
+    fn new_span(&self, attributes: &Attributes<'_>) -> Id {
+        let mut fields = Fields::default();
+        attributes.record(&mut fields);
+        // Ids can't be 0.
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let span = Span {
+            name: attributes.metadata().name(),
+            fields,
+            entered: None,
+            references: 1,
+        };
+        self.spans.lock().unwrap().insert(id, span);
+        Id::from_u64(id)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        if let Some(span) = self.spans.lock().unwrap().get_mut(&span.into_u64()) {
+            values.record(&mut span.fields);
+        }
+    }
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let text = format!("{}: {}", event.metadata().target(), fields).into();
+        match *event.metadata().level() {
+            Level::ERROR => web_sys::console::error_1(&text),
+            Level::WARN => web_sys::console::warn_1(&text),
+            Level::INFO => web_sys::console::info_1(&text),
+            _ => web_sys::console::debug_1(&text),
+        }
+    }
+
+    fn enter(&self, span: &Id) {
+        if let Some(span) = self.spans.lock().unwrap().get_mut(&span.into_u64()) {
+            span.entered = Some(fps::now());
+        }
+    }
+
+    fn exit(&self, span: &Id) {
+        let spans = self.spans.lock().unwrap();
+        if let Some(span) = spans.get(&span.into_u64()) {
+            if let Some(entered) = span.entered {
+                let text = format!(
+                    "{} took {:.2} ms{}",
+                    span.name,
+                    fps::now() - entered,
+                    span.fields
+                );
+                web_sys::console::debug_1(&text.into());
+            }
+        }
+    }
+
+    fn clone_span(&self, span: &Id) -> Id {
+        if let Some(span) = self.spans.lock().unwrap().get_mut(&span.into_u64()) {
+            span.references += 1;
+        }
+        span.clone()
+    }
+
+    fn try_close(&self, span: Id) -> bool {
+        let mut spans = self.spans.lock().unwrap();
+        let id = span.into_u64();
+        let closed = match spans.get_mut(&id) {
+            Some(span) => {
+                span.references -= 1;
+                span.references == 0
+            }
+            None => false,
+        };
+        if closed {
+            spans.remove(&id);
+        }
+        closed
+    }
+}
+
+/// The message of a span or event, then its other fields as `name=value`.
+#[derive(Default)]
+struct Fields {
+    message: String,
+    rest: String,
+}
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+        } else {
+            let _ = write!(self.rest, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+impl fmt::Display for Fields {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.message, self.rest)
+    }
+}
@@ -0,0 +1,378 @@
+//! Turmites, Turing machines whose tape is the board. A head walks around
+//! the board, and at each step reads the color of its cell, writes a new
+//! color there, turns and moves forward a cell. What it writes, which way
+//! it turns and the state it moves to depend on its state and the color it
+//! read.
+//!
+//! Turmites are written the way Golly writes them, as a list of states,
+//! each a list of `{write, turn, next state}` triples, one for each color,
+//! with turns of 1 for none, 2 for right, 4 for back and 8 for left.
+//! Langton's ant is `{{{1,2,0},{0,8,0}}}`. Ants with a single state can
+//! also be written as the turn for each color, as in `RL` for Langton's
+//! ant, with `N` for none and `U` for back; each color is then painted
+//! over with the next.
+
+use std::fmt;
+
+use crate::engine::{SimulationEngine, Topology};
+use crate::{Cell, Position};
+
+/// The most colors a tape can have.
+pub const MAX_COLORS: usize = 256;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TurmiteError {
+    /// The specification was neither nested lists of triples nor a string
+    /// of turns.
+    InvalidFormat(String),
+    /// A triple wrote a color or moved to a state that doesn't exist, or
+    /// had a turn other than 1, 2, 4 or 8.
+    InvalidTransition(String),
+}
+
+impl fmt::Display for TurmiteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TurmiteError::InvalidFormat(spec) => write!(f, "invalid turmite {:?}", spec),
+            TurmiteError::InvalidTransition(triple) => {
+                write!(f, "invalid turmite transition {:?}", triple)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TurmiteError {}
+
+#[cfg(target_arch = "wasm32")]
+impl From<TurmiteError> for wasm_bindgen::JsValue {
+    fn from(error: TurmiteError) -> wasm_bindgen::JsValue {
+        wasm_bindgen::JsValue::from_str(&error.to_string())
+    }
+}
+
+/// Which way the head turns, by the number of right angles clockwise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Turn {
+    None = 0,
+    Right = 1,
+    Back = 2,
+    Left = 3,
+}
+
+impl Turn {
+    /// The turn with Golly's code `code`.
+    fn from_code(code: u32) -> Option<Turn> {
+        match code {
+            1 => Some(Turn::None),
+            2 => Some(Turn::Right),
+            4 => Some(Turn::Back),
+            8 => Some(Turn::Left),
+            _ => None,
+        }
+    }
+
+    fn code(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+/// What the head does on reading a color in a state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Transition {
+    write: u8,
+    turn: Turn,
+    next: usize,
+}
+
+/// The transitions of a turmite.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TurmiteSpec {
+    /// One transition for each state and then each color.
+    states: Vec<Vec<Transition>>,
+}
+
+impl TurmiteSpec {
+    /// Langton's ant, `{{{1,2,0},{0,8,0}}}`.
+    pub fn langtons_ant() -> TurmiteSpec {
+        TurmiteSpec::parse("RL").unwrap()
+    }
+
+    /// Parse a turmite in Golly's notation, or a string of turns such as
+    /// `RL` or `LLRR`.
+    pub fn parse(spec: &str) -> Result<TurmiteSpec, TurmiteError> {
+        let spec = spec.trim();
+        let invalid = || TurmiteError::InvalidFormat(spec.to_string());
+        if !spec.starts_with('{') {
+            let turns: Vec<Turn> = spec
+                .chars()
+                .map(|turn| match turn.to_ascii_uppercase() {
+                    'N' => Some(Turn::None),
+                    'R' => Some(Turn::Right),
+                    'U' => Some(Turn::Back),
+                    'L' => Some(Turn::Left),
+                    _ => None,
+                })
+                .collect::<Option<_>>()
+                .ok_or_else(invalid)?;
+            if turns.len() < 2 || turns.len() > MAX_COLORS {
+                return Err(invalid());
+            }
+            let colors = turns.len();
+            let transitions = turns
+                .into_iter()
+                .enumerate()
+                .map(|(color, turn)| Transition {
+                    write: ((color + 1) % colors) as u8,
+                    turn,
+                    next: 0,
+                })
+                .collect();
+            return Ok(TurmiteSpec {
+                states: vec![transitions],
+            });
+        }
+
+        let list = parse_list(spec).ok_or_else(invalid)?;
+        let states = match list {
+            List::List(states) if !states.is_empty() => states,
+            _ => return Err(invalid()),
+        };
+        let mut triples = Vec::new();
+        for state in &states {
+            let colors = match state {
+                List::List(colors) => colors,
+                List::Number(_) => return Err(invalid()),
+            };
+            let mut row = Vec::new();
+            for triple in colors {
+                match triple {
+                    List::List(triple) => match triple[..] {
+                        [List::Number(write), List::Number(turn), List::Number(next)] => {
+                            row.push((write, turn, next))
+                        }
+                        _ => return Err(invalid()),
+                    },
+                    List::Number(_) => return Err(invalid()),
+                }
+            }
+            triples.push(row);
+        }
+        let colors = triples[0].len();
+        if colors == 0 || colors > MAX_COLORS || triples.iter().any(|row| row.len() != colors) {
+            return Err(invalid());
+        }
+        let states = triples
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&(write, turn, next)| {
+                        let invalid = || {
+                            TurmiteError::InvalidTransition(format!(
+                                "{{{},{},{}}}",
+                                write, turn, next
+                            ))
+                        };
+                        if write as usize >= colors || next as usize >= triples.len() {
+                            return Err(invalid());
+                        }
+                        Ok(Transition {
+                            write: write as u8,
+                            turn: Turn::from_code(turn).ok_or_else(invalid)?,
+                            next: next as usize,
+                        })
+                    })
+                    .collect()
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(TurmiteSpec { states })
+    }
+
+    /// How many colors the tape uses.
+    pub fn colors(&self) -> usize {
+        self.states[0].len()
+    }
+
+    pub fn states(&self) -> usize {
+        self.states.len()
+    }
+}
+
+impl Default for TurmiteSpec {
+    fn default() -> TurmiteSpec {
+        TurmiteSpec::langtons_ant()
+    }
+}
+
+/// Writes the turmite in Golly's notation.
+impl fmt::Display for TurmiteSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{{")?;
+        for (i, state) in self.states.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{{")?;
+            for (j, transition) in state.iter().enumerate() {
+                if j > 0 {
+                    write!(f, ",")?;
+                }
+                let Transition { write, turn, next } = transition;
+                write!(f, "{{{},{},{}}}", write, turn.code(), next)?;
+            }
+            write!(f, "}}")?;
+        }
+        write!(f, "}}")
+    }
+}
+
+/// A number, or a list in braces.
+enum List {
+    Number(u32),
+    List(Vec<List>),
+}
+
+/// Parse the whole of `text` as a number or a list, ignoring spaces.
+fn parse_list(text: &str) -> Option<List> {
+    let text: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    let (list, rest) = parse_item(&text)?;
+    rest.is_empty().then_some(list)
+}
+
+/// Parse the number or list at the start of `text`, returning what is left
+/// after it.
+fn parse_item(text: &str) -> Option<(List, &str)> {
+    if let Some(mut rest) = text.strip_prefix('{') {
+        let mut items = Vec::new();
+        if let Some(rest) = rest.strip_prefix('}') {
+            return Some((List::List(items), rest));
+        }
+        loop {
+            let (item, after) = parse_item(rest)?;
+            items.push(item);
+            match after.chars().next()? {
+                ',' => rest = &after[1..],
+                '}' => return Some((List::List(items), &after[1..])),
+                _ => return None,
+            }
+        }
+    }
+    let digits = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let number = text[..digits].parse().ok()?;
+    Some((List::Number(number), &text[digits..]))
+}
+
+/// The head of a turmite: where it is, which way it faces, by right angles
+/// clockwise from north, and its state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Head {
+    row: u32,
+    column: u32,
+    direction: u8,
+    state: usize,
+}
+
+/// A board with a color for every cell, walked over by a turmite.
+#[derive(Clone, Debug)]
+pub struct Turmite {
+    width: u32,
+    height: u32,
+    topology: Topology,
+    spec: TurmiteSpec,
+    /// The color of every cell in row-major order.
+    colors: Vec<u8>,
+    /// The head, or `None` once it has walked off a board with dead edges.
+    head: Option<Head>,
+}
+
+impl Turmite {
+    /// A blank board with the head in the middle, facing north in state 0.
+    pub fn new(width: u32, height: u32, spec: TurmiteSpec) -> Turmite {
+        let head = (width > 0 && height > 0).then_some(Head {
+            row: height / 2,
+            column: width / 2,
+            direction: 0,
+            state: 0,
+        });
+        Turmite {
+            width,
+            height,
+            topology: Topology::Torus,
+            spec,
+            colors: vec![0; (width * height) as usize],
+            head,
+        }
+    }
+
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+    }
+
+    pub fn color(&self, row: u32, column: u32) -> u8 {
+        self.colors[self.get_index(row, column)]
+    }
+
+    /// Where the head is, if it is still on the board.
+    pub fn head(&self) -> Option<(u32, u32)> {
+        self.head.map(|head| (head.row, head.column))
+    }
+
+    fn get_index(&self, row: u32, column: u32) -> usize {
+        (row * self.width + column) as usize
+    }
+}
+
+impl SimulationEngine for Turmite {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Every color but the first counts as alive.
+    fn cell(&self, position: Position) -> Option<Cell> {
+        if !self.contains(position) {
+            return None;
+        }
+        Some(Cell::from(self.color(position.row, position.column) != 0))
+    }
+
+    /// Only cells that change between alive and dead are touched, so copying
+    /// in an edited board keeps the colors everywhere else.
+    fn set_cell(&mut self, position: Position, cell: Cell) -> Option<Cell> {
+        let was = self.cell(position)?;
+        let alive = cell == Cell::Alive;
+        if (was == Cell::Alive) != alive {
+            let idx = self.get_index(position.row, position.column);
+            self.colors[idx] = (alive && self.spec.colors() > 1) as u8;
+        }
+        Some(was)
+    }
+
+    fn tick(&mut self) {
+        let mut head = match self.head {
+            Some(head) => head,
+            None => return,
+        };
+        let idx = self.get_index(head.row, head.column);
+        let color = (self.colors[idx] as usize).min(self.spec.colors() - 1);
+        let transition = self.spec.states[head.state][color];
+        self.colors[idx] = transition.write;
+        head.direction = (head.direction + transition.turn as u8) % 4;
+        head.state = transition.next;
+        let (delta_row, delta_col) = [(-1, 0), (0, 1), (1, 0), (0, -1)][head.direction as usize];
+        let row = self.topology.wrap(head.row as i64 + delta_row, self.height);
+        let column = self
+            .topology
+            .wrap(head.column as i64 + delta_col, self.width);
+        self.head = match (row, column) {
+            (Some(row), Some(column)) => Some(Head {
+                row,
+                column,
+                ..head
+            }),
+            _ => None,
+        };
+    }
+}
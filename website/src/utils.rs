@@ -1,3 +1,6 @@
+use wasm_bindgen::closure::{Closure, WasmClosure};
+use wasm_bindgen::{JsCast, JsValue};
+
 pub fn set_panic_hook() {
     // When the `console_error_panic_hook` feature is enabled, we can call the
     // `set_panic_hook` function at least once during initialization, and then
@@ -8,3 +11,65 @@ pub fn set_panic_hook() {
     #[cfg(feature = "console_error_panic_hook")]
     console_error_panic_hook::set_once();
 }
+
+/// Event listeners along with the closures they call, so that they can be
+/// taken off again. Dropping it removes every listener and then the closures.
+#[derive(Default)]
+pub struct Listeners {
+    added: Vec<(web_sys::EventTarget, &'static str, js_sys::Function)>,
+    closures: Vec<Box<dyn AsRef<JsValue>>>,
+}
+
+impl Listeners {
+    /// Call `callback` whenever `target` fires `event`. The closure must be
+    /// kept alive with `keep`.
+    pub fn add<T: ?Sized>(
+        &mut self,
+        target: &web_sys::EventTarget,
+        event: &'static str,
+        callback: &Closure<T>,
+    ) {
+        let function: &js_sys::Function = callback.as_ref().unchecked_ref();
+        target
+            .add_event_listener_with_callback(event, function)
+            .unwrap_or_else(|_| panic!("should listen for `{}` OK", event));
+        self.added.push((target.clone(), event, function.clone()));
+    }
+
+    /// Hold on to `callback` until the listeners are dropped, rather than
+    /// forgetting it.
+    pub fn keep<T: WasmClosure + ?Sized + 'static>(&mut self, callback: Closure<T>) {
+        self.closures.push(Box::new(callback));
+    }
+}
+
+impl Drop for Listeners {
+    fn drop(&mut self) {
+        for (target, event, function) in self.added.drain(..) {
+            let _ = target.remove_event_listener_with_callback(event, &function);
+        }
+        self.closures.clear();
+    }
+}
+
+/// Lets listeners be added at the end of a chain of calls that finds the
+/// target, the way `add_event_listener_with_callback` is.
+pub trait Listen {
+    fn listen<T: ?Sized>(
+        &self,
+        listeners: &mut Listeners,
+        event: &'static str,
+        callback: &Closure<T>,
+    );
+}
+
+impl Listen for web_sys::EventTarget {
+    fn listen<T: ?Sized>(
+        &self,
+        listeners: &mut Listeners,
+        event: &'static str,
+        callback: &Closure<T>,
+    ) {
+        listeners.add(self, event, callback);
+    }
+}
@@ -0,0 +1,83 @@
+//! Recording the canvas as it is drawn into a WebM video, with the browser's
+//! `MediaRecorder`.
+//!
+//! Unlike GIF recordings, videos show the canvas exactly as it appears, zoom
+//! and overlays included, at whatever rate it is repainted.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// The type of the recorded files.
+pub const VIDEO_TYPE: &str = "video/webm";
+
+/// A recording of a canvas in progress.
+pub struct VideoRecorder {
+    recorder: web_sys::MediaRecorder,
+    /// The parts of the video handed over by the recorder so far.
+    chunks: Rc<RefCell<Vec<web_sys::Blob>>>,
+    /// Kept alive for as long as the recorder can hand over data.
+    _on_data: Closure<dyn FnMut(web_sys::BlobEvent)>,
+}
+
+impl VideoRecorder {
+    /// Start recording `canvas`, capturing up to `fps` frames a second and
+    /// encoding them at about `bitrate` bits per second.
+    pub fn start(
+        canvas: &web_sys::HtmlCanvasElement,
+        fps: f64,
+        bitrate: u32,
+    ) -> Result<VideoRecorder, JsValue> {
+        if !web_sys::MediaRecorder::is_type_supported(VIDEO_TYPE) {
+            return Err(JsValue::from_str("this browser can't record WebM videos"));
+        }
+        let stream = canvas.capture_stream_with_frame_request_rate(fps)?;
+        let options = web_sys::MediaRecorderOptions::new();
+        options.set_mime_type(VIDEO_TYPE);
+        options.set_video_bits_per_second(bitrate);
+        let recorder = web_sys::MediaRecorder::new_with_media_stream_and_media_recorder_options(
+            &stream, &options,
+        )?;
+
+        let chunks = Rc::new(RefCell::new(Vec::new()));
+        let my_chunks = chunks.clone();
+        let on_data = Closure::wrap(Box::new(move |event: web_sys::BlobEvent| {
+            if let Some(blob) = event.data() {
+                my_chunks.borrow_mut().push(blob);
+            }
+        }) as Box<dyn FnMut(_)>);
+        recorder.set_ondataavailable(Some(on_data.as_ref().unchecked_ref()));
+        recorder.start()?;
+
+        Ok(VideoRecorder {
+            recorder,
+            chunks,
+            _on_data: on_data,
+        })
+    }
+
+    /// Stop recording. Once the recorder has handed over the rest of the
+    /// video, `on_video` is called with the whole file.
+    pub fn stop(self, on_video: impl FnOnce(web_sys::Blob) + 'static) -> Result<(), JsValue> {
+        let VideoRecorder {
+            recorder,
+            chunks,
+            _on_data: on_data,
+        } = self;
+        let on_stop = Closure::once_into_js(move || {
+            // The last data arrives before the recorder stops.
+            drop(on_data);
+            let parts: js_sys::Array = chunks.borrow().iter().collect();
+            let options = web_sys::BlobPropertyBag::new();
+            options.set_type(VIDEO_TYPE);
+            match web_sys::Blob::new_with_blob_sequence_and_options(&parts, &options) {
+                Ok(video) => on_video(video),
+                Err(err) => log!("{}", tr!(CouldNotSaveVideo, format!("{:?}", err))),
+            }
+        });
+        recorder.set_onstop(Some(on_stop.unchecked_ref()));
+        recorder.stop()
+    }
+}
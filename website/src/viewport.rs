@@ -0,0 +1,267 @@
+//! Mapping between universe cells and canvas pixels.
+
+use std::ops::Range;
+
+/// The largest canvas element the viewport will ask for, in pixels. Bigger
+/// universes are panned around instead.
+pub const MAX_CANVAS_SIZE: u32 = 800;
+
+pub const MIN_CELL_SIZE: u32 = 1;
+pub const MAX_CELL_SIZE: u32 = 50;
+
+/// Below this cell size the grid lines would take up too much of the
+/// picture, so they are left out even when they are shown.
+pub const MIN_GRID_CELL_SIZE: u32 = 4;
+
+/// The part of the universe shown on the canvas.
+///
+/// Every cell is drawn as a `cell_size` square followed by a one pixel grid
+/// line, unless the grid is hidden, and the canvas shows the universe's
+/// pixels starting `x`, `y` pixels from its top-left corner.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    x: f64,
+    y: f64,
+    cell_size: u32,
+    /// Whether grid lines are wanted, at cell sizes big enough for them.
+    show_grid: bool,
+    /// Size of the canvas element, in pixels.
+    width: u32,
+    height: u32,
+    /// Size of the universe, in cells.
+    columns: u32,
+    rows: u32,
+    /// Whether the view is kept inside the universe. Engines that simulate
+    /// the plane around it let it wander off.
+    bounded: bool,
+}
+
+impl Viewport {
+    pub fn new(cell_size: u32, columns: u32, rows: u32) -> Viewport {
+        let mut viewport = Viewport {
+            x: 0.0,
+            y: 0.0,
+            cell_size: cell_size.clamp(MIN_CELL_SIZE, MAX_CELL_SIZE),
+            show_grid: true,
+            width: 0,
+            height: 0,
+            columns,
+            rows,
+            bounded: true,
+        };
+        viewport.fit();
+        viewport
+    }
+
+    pub fn cell_size(&self) -> u32 {
+        self.cell_size
+    }
+
+    /// Whether grid lines are wanted, even if cells are too small for them.
+    pub fn show_grid(&self) -> bool {
+        self.show_grid
+    }
+
+    /// Whether grid lines are drawn between cells.
+    pub fn grid_lines(&self) -> bool {
+        self.show_grid && self.cell_size >= MIN_GRID_CELL_SIZE
+    }
+
+    /// Show or hide the grid lines, keeping the middle of the canvas in
+    /// place.
+    pub fn set_show_grid(&mut self, show_grid: bool) {
+        let (x, y) = (self.width as f64 / 2.0, self.height as f64 / 2.0);
+        let old_pitch = self.pitch();
+        self.show_grid = show_grid;
+        self.rescale(old_pitch, x, y);
+    }
+
+    /// Distance between the starts of neighboring cells, in pixels.
+    pub fn pitch(&self) -> f64 {
+        (self.cell_size + self.line()) as f64
+    }
+
+    /// Width of the canvas element, in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Height of the canvas element, in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Track a change in the size of the universe.
+    pub fn set_universe_size(&mut self, columns: u32, rows: u32) {
+        self.columns = columns;
+        self.rows = rows;
+        self.fit();
+    }
+
+    /// Keep the view inside the universe, or let it be panned anywhere on
+    /// the plane around it.
+    pub fn set_bounded(&mut self, bounded: bool) {
+        self.bounded = bounded;
+        self.clamp();
+    }
+
+    /// Change the cell size, keeping the cell under the canvas pixel
+    /// (`anchor_x`, `anchor_y`) in place.
+    pub fn zoom_at(&mut self, cell_size: u32, anchor_x: f64, anchor_y: f64) {
+        let old_pitch = self.pitch();
+        self.cell_size = cell_size.clamp(MIN_CELL_SIZE, MAX_CELL_SIZE);
+        self.rescale(old_pitch, anchor_x, anchor_y);
+    }
+
+    /// Change the cell size, keeping the middle of the canvas in place.
+    pub fn zoom(&mut self, cell_size: u32) {
+        let (x, y) = (self.width as f64 / 2.0, self.height as f64 / 2.0);
+        self.zoom_at(cell_size, x, y);
+    }
+
+    /// Move the view by the given number of pixels.
+    pub fn pan(&mut self, dx: f64, dy: f64) {
+        self.x += dx;
+        self.y += dy;
+        self.clamp();
+    }
+
+    /// Zoom in as far as still shows all of `rows` and `columns`, and
+    /// center on them.
+    pub fn frame(&mut self, rows: Range<u32>, columns: Range<u32>) {
+        for cell_size in (MIN_CELL_SIZE..=MAX_CELL_SIZE).rev() {
+            self.cell_size = cell_size;
+            self.fit();
+            if self.shows(&rows, &columns) {
+                break;
+            }
+        }
+        self.center_on(rows, columns);
+    }
+
+    /// Whether `rows` and `columns` are small enough to fit on the canvas
+    /// at the current cell size.
+    pub fn shows(&self, rows: &Range<u32>, columns: &Range<u32>) -> bool {
+        let (pitch, line) = (self.pitch(), self.line() as f64);
+        rows.len() as f64 * pitch + line <= self.height as f64
+            && columns.len() as f64 * pitch + line <= self.width as f64
+    }
+
+    /// Move the view so the middle of `rows` and `columns` is in the middle
+    /// of the canvas, or as near as the edges of the universe allow.
+    pub fn center_on(&mut self, rows: Range<u32>, columns: Range<u32>) {
+        let pitch = self.pitch();
+        self.x = (columns.start + columns.end) as f64 / 2.0 * pitch - self.width as f64 / 2.0;
+        self.y = (rows.start + rows.end) as f64 / 2.0 * pitch - self.height as f64 / 2.0;
+        self.clamp();
+    }
+
+    /// Move the view `fraction` of the way towards centering on the middle
+    /// of the cell at (`row`, `column`), which needn't be whole.
+    pub fn glide_to(&mut self, row: f64, column: f64, fraction: f64) {
+        let pitch = self.pitch();
+        let x = (column + 0.5) * pitch - self.width as f64 / 2.0;
+        let y = (row + 0.5) * pitch - self.height as f64 / 2.0;
+        self.x += (x - self.x) * fraction;
+        self.y += (y - self.y) * fraction;
+        self.clamp();
+    }
+
+    /// The (`row`, `column`) the view is centered on, as `glide_to` takes
+    /// it.
+    pub fn center(&self) -> (f64, f64) {
+        let pitch = self.pitch();
+        (
+            (self.y + self.height as f64 / 2.0) / pitch - 0.5,
+            (self.x + self.width as f64 / 2.0) / pitch - 0.5,
+        )
+    }
+
+    /// The (`row`, `column`) of the cell under a canvas pixel, if any.
+    pub fn cell_at(&self, x: f64, y: f64) -> Option<(u32, u32)> {
+        let row = ((y + self.y) / self.pitch()).floor();
+        let column = ((x + self.x) / self.pitch()).floor();
+        if row < 0.0 || column < 0.0 || row >= self.rows as f64 || column >= self.columns as f64 {
+            return None;
+        }
+        Some((row as u32, column as u32))
+    }
+
+    /// The canvas pixel at the top-left corner of a cell, inside its grid
+    /// lines.
+    pub fn cell_origin(&self, row: u32, column: u32) -> (f64, f64) {
+        self.plane_origin(row as i64, column as i64)
+    }
+
+    /// Like `cell_origin`, for a cell anywhere on the plane around the
+    /// universe.
+    pub fn plane_origin(&self, row: i64, column: i64) -> (f64, f64) {
+        let line = self.line() as f64;
+        (
+            column as f64 * self.pitch() + line - self.x,
+            row as f64 * self.pitch() + line - self.y,
+        )
+    }
+
+    /// The rows that are at least partly visible.
+    pub fn visible_rows(&self) -> Range<u32> {
+        visible(self.y, self.height, self.pitch(), self.rows)
+    }
+
+    /// The columns that are at least partly visible.
+    pub fn visible_columns(&self) -> Range<u32> {
+        visible(self.x, self.width, self.pitch(), self.columns)
+    }
+
+    /// The rows and columns that are at least partly visible, including
+    /// those beyond the edges of the universe when the view isn't bounded.
+    pub fn visible_area(&self) -> (Range<i64>, Range<i64>) {
+        let pitch = self.pitch();
+        let span = |offset: f64, size: u32| {
+            (offset / pitch).floor() as i64..((offset + size as f64) / pitch).ceil() as i64
+        };
+        (span(self.y, self.height), span(self.x, self.width))
+    }
+
+    /// The width of a grid line, in pixels.
+    fn line(&self) -> u32 {
+        self.grid_lines() as u32
+    }
+
+    /// Follow a change of pitch from `old_pitch`, keeping the canvas pixel
+    /// (`anchor_x`, `anchor_y`) over the same part of the universe.
+    fn rescale(&mut self, old_pitch: f64, anchor_x: f64, anchor_y: f64) {
+        let scale = self.pitch() / old_pitch;
+        self.x = (self.x + anchor_x) * scale - anchor_x;
+        self.y = (self.y + anchor_y) * scale - anchor_y;
+        self.fit();
+    }
+
+    /// Size the canvas to the universe, up to `MAX_CANVAS_SIZE`.
+    fn fit(&mut self) {
+        let (pitch, line) = (self.cell_size + self.line(), self.line());
+        self.width = (pitch * self.columns + line).min(MAX_CANVAS_SIZE);
+        self.height = (pitch * self.rows + line).min(MAX_CANVAS_SIZE);
+        self.clamp();
+    }
+
+    /// Keep the view inside the universe, if it is bounded, and on whole
+    /// pixels.
+    fn clamp(&mut self) {
+        if self.bounded {
+            let (pitch, line) = (self.pitch(), self.line() as f64);
+            let max_x = (pitch * self.columns as f64 + line - self.width as f64).max(0.0);
+            let max_y = (pitch * self.rows as f64 + line - self.height as f64).max(0.0);
+            self.x = self.x.clamp(0.0, max_x);
+            self.y = self.y.clamp(0.0, max_y);
+        }
+        self.x = self.x.round();
+        self.y = self.y.round();
+    }
+}
+
+fn visible(offset: f64, size: u32, pitch: f64, count: u32) -> Range<u32> {
+    let first = (offset / pitch).floor().max(0.0) as u32;
+    let last = ((offset + size as f64) / pitch).ceil() as u32;
+    first.min(count)..last.min(count)
+}
@@ -0,0 +1,514 @@
+//! Embedding the game in other pages, as many times over as they like.
+//!
+//! `init` turns a canvas into a game of its own, which can be painted on
+//! with the mouse or a finger, and hands back a `GameHandle` to run it:
+//!
+//! ```js
+//! import load, { init } from "./wasm_game_of_life.js";
+//!
+//! await load();
+//! const game = init({
+//!   canvas: "#life",
+//!   pattern: "x = 3, y = 3\nbo$2bo$3o!",
+//!   rule: "B3/S23",
+//!   width: 64,
+//!   height: 48,
+//!   autoplay: true,
+//!   theme: "dark",
+//! });
+//! game.pause();
+//! game.step(10);
+//! game.dispose();
+//! ```
+//!
+//! Only `canvas` is required, as an element or a selector for one. The
+//! pattern is any format `Pattern::parse` reads and is placed in the middle
+//! of a `width` by `height` board, 64 by 64 unless given; without one the
+//! board starts striped, as the game's own page does. `rule` replaces the
+//! pattern's rule, and `theme` is the name of a preset or its colors, as in
+//! a scenario. Widgets leave the rest of the page alone, so any number of
+//! them can share it.
+//!
+//! The handle's `on_tick`, `on_cell_changed` and `on_stabilized` subscribe
+//! callbacks to what happens on the board; see `events`.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::{Rc, Weak};
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::canvas::Canvas;
+use crate::events::{Event, Subscriptions};
+use crate::page::{pointer_down, pointer_move, pointer_up, touch_position};
+use crate::patterns::{ParseError, Pattern};
+use crate::rule::RuleError;
+use crate::scenario::Scenario;
+use crate::state::{AnimationCallback, AppState};
+use crate::theme::{NamedTheme, Theme};
+use crate::utils::{Listen, Listeners};
+use crate::{Cell, Position, Universe};
+
+/// The size of the board when the configuration doesn't give one.
+const DEFAULT_SIZE: u32 = 64;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum WidgetError {
+    /// `canvas` was missing, matched nothing or matched something else.
+    NoCanvas,
+    /// A member of the configuration had the wrong type or value.
+    Invalid(&'static str),
+    Pattern(ParseError),
+    Rule(RuleError),
+}
+
+impl fmt::Display for WidgetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WidgetError::NoCanvas => {
+                write!(f, "\"canvas\" should be a canvas or a selector for one")
+            }
+            WidgetError::Invalid(name) => write!(f, "invalid {:?}", name),
+            WidgetError::Pattern(error) => write!(f, "{}", error),
+            WidgetError::Rule(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for WidgetError {}
+
+impl From<ParseError> for WidgetError {
+    fn from(error: ParseError) -> WidgetError {
+        WidgetError::Pattern(error)
+    }
+}
+
+impl From<RuleError> for WidgetError {
+    fn from(error: RuleError) -> WidgetError {
+        WidgetError::Rule(error)
+    }
+}
+
+impl From<WidgetError> for JsValue {
+    fn from(error: WidgetError) -> JsValue {
+        JsValue::from_str(&error.to_string())
+    }
+}
+
+/// Everything `init` can be told, read from the object it is passed.
+struct WidgetConfig {
+    canvas: web_sys::HtmlCanvasElement,
+    pattern: Option<Pattern>,
+    rule: Option<String>,
+    width: u32,
+    height: u32,
+    autoplay: bool,
+    theme: Option<Theme>,
+}
+
+impl WidgetConfig {
+    fn read(config: &JsValue) -> Result<WidgetConfig, WidgetError> {
+        let canvas = match member(config, "canvas") {
+            Some(canvas) if canvas.is_string() => web_sys::window()
+                .and_then(|window| window.document())
+                .and_then(|document| {
+                    document
+                        .query_selector(&canvas.as_string().unwrap())
+                        .ok()
+                        .flatten()
+                }),
+            Some(canvas) => canvas.dyn_into::<web_sys::Element>().ok(),
+            None => None,
+        };
+        let canvas = canvas
+            .and_then(|canvas| canvas.dyn_into::<web_sys::HtmlCanvasElement>().ok())
+            .ok_or(WidgetError::NoCanvas)?;
+
+        let pattern = match string(config, "pattern")? {
+            Some(text) => Some(Pattern::parse(&text)?),
+            None => None,
+        };
+        let theme = match member(config, "theme") {
+            Some(theme) => {
+                let text = js_sys::JSON::stringify(&theme)
+                    .ok()
+                    .and_then(|text| text.as_string());
+                let named = text.and_then(|text| serde_json::from_str::<NamedTheme>(&text).ok());
+                Some(
+                    named
+                        .and_then(|named| named.theme().ok())
+                        .ok_or(WidgetError::Invalid("theme"))?,
+                )
+            }
+            None => None,
+        };
+        Ok(WidgetConfig {
+            canvas,
+            pattern,
+            rule: string(config, "rule")?,
+            width: size(config, "width")?,
+            height: size(config, "height")?,
+            autoplay: match member(config, "autoplay") {
+                Some(autoplay) => autoplay.as_bool().ok_or(WidgetError::Invalid("autoplay"))?,
+                None => false,
+            },
+            theme,
+        })
+    }
+
+    /// The board to start from.
+    fn universe(&self) -> Universe {
+        let mut universe = Universe::new_with_size(self.width, self.height);
+        match &self.pattern {
+            Some(pattern) => {
+                if let Some(rule) = pattern.rule() {
+                    universe.apply_rule(rule);
+                }
+                let row = self.height.saturating_sub(pattern.height()) / 2;
+                let column = self.width.saturating_sub(pattern.width()) / 2;
+                universe.insert_pattern(Position::new(row, column), pattern);
+            }
+            None => universe.fill_stripes(),
+        }
+        universe
+    }
+}
+
+/// The member `name` of `config`, unless it is missing, `undefined` or
+/// `null`.
+fn member(config: &JsValue, name: &str) -> Option<JsValue> {
+    js_sys::Reflect::get(config, &JsValue::from_str(name))
+        .ok()
+        .filter(|value| !value.is_undefined() && !value.is_null())
+}
+
+fn string(config: &JsValue, name: &'static str) -> Result<Option<String>, WidgetError> {
+    match member(config, name) {
+        Some(value) => value
+            .as_string()
+            .map(Some)
+            .ok_or(WidgetError::Invalid(name)),
+        None => Ok(None),
+    }
+}
+
+fn size(config: &JsValue, name: &'static str) -> Result<u32, WidgetError> {
+    match member(config, name) {
+        Some(value) => value
+            .as_f64()
+            .filter(|size| *size >= 1.0 && size.fract() == 0.0 && *size <= u32::MAX as f64)
+            .map(|size| size as u32)
+            .ok_or(WidgetError::Invalid(name)),
+        None => Ok(DEFAULT_SIZE),
+    }
+}
+
+type EventCallback = Closure<dyn FnMut(web_sys::Event)>;
+
+/// A game embedded with `init`. Disposing of it, or freeing it, stops it
+/// and lets go of its canvas.
+#[wasm_bindgen]
+pub struct GameHandle {
+    canvas: Rc<RefCell<Canvas>>,
+    state: Rc<RefCell<AppState>>,
+    animation_callback: Rc<AnimationCallback>,
+    listeners: Listeners,
+    events: Rc<RefCell<Subscriptions>>,
+}
+
+#[wasm_bindgen(typescript_custom_section)]
+const GAME_CONFIG: &'static str = r#"
+/**
+ * What `init` can be told. Only `canvas` is required.
+ */
+export interface GameConfig {
+    /** The canvas to play on, or a selector for it. */
+    canvas: HTMLCanvasElement | string;
+    /** A pattern in any format `Pattern.parse` reads. */
+    pattern?: string;
+    rule?: string;
+    width?: number;
+    height?: number;
+    autoplay?: boolean;
+    /** The name of a preset theme, or its colors. */
+    theme?: string | { [color: string]: string | string[] };
+}
+"#;
+
+/// Turn the canvas in `config` into a game of its own. See the module
+/// documentation for what `config` can hold.
+#[wasm_bindgen]
+pub fn init(
+    #[wasm_bindgen(unchecked_param_type = "GameConfig")] config: JsValue,
+) -> Result<GameHandle, JsValue> {
+    let config = WidgetConfig::read(&config)?;
+    let mut canvas = Canvas::new(config.universe(), config.canvas.clone())?;
+    canvas.make_embedded();
+    if let Some(rule) = &config.rule {
+        canvas.set_rule(rule).map_err(WidgetError::from)?;
+    }
+    if let Some(theme) = &config.theme {
+        canvas.set_theme(theme);
+    }
+    canvas.draw();
+
+    let events = Rc::new(RefCell::new(Subscriptions::new(&canvas.universe)));
+    let canvas = Rc::new(RefCell::new(canvas));
+    let state = Rc::new(RefCell::new(AppState::new(None)));
+
+    // Step once a frame for as long as the game runs.
+    let animation_callback: Rc<AnimationCallback> = Rc::new(RefCell::new(None));
+    {
+        let canvas = canvas.clone();
+        let state = state.clone();
+        let events = events.clone();
+        let callback: Weak<AnimationCallback> = Rc::downgrade(&animation_callback);
+        *animation_callback.borrow_mut() = Some(Closure::wrap(Box::new(move || {
+            state.borrow_mut().begin_frame();
+            if !state.borrow().is_running() {
+                return;
+            }
+            advance(&canvas, &events, 1);
+            canvas.borrow_mut().draw();
+            if let Some(callback) = callback.upgrade() {
+                state.borrow_mut().next_frame(&callback);
+            }
+            // After asking for the next frame, so that a subscriber can
+            // pause the game.
+            notify(&canvas, &events);
+        }) as Box<dyn FnMut()>));
+    }
+
+    // Paint as the page does, with the mouse or a finger.
+    let stroke: Rc<RefCell<Option<Cell>>> = Rc::new(RefCell::new(None));
+    let mut callbacks: Vec<(&str, EventCallback)> = Vec::new();
+    {
+        let (canvas, stroke, events) = (canvas.clone(), stroke.clone(), events.clone());
+        callbacks.push((
+            "mousedown",
+            Closure::wrap(Box::new(move |event: web_sys::Event| {
+                let event: web_sys::MouseEvent = event.unchecked_into();
+                if event.button() == 0 {
+                    let (x, y) = (event.offset_x() as f64, event.offset_y() as f64);
+                    pointer_down(&canvas, &stroke, x, y, None);
+                    notify(&canvas, &events);
+                }
+            }) as Box<dyn FnMut(_)>),
+        ));
+    }
+    {
+        let (canvas, stroke, events) = (canvas.clone(), stroke.clone(), events.clone());
+        callbacks.push((
+            "mousemove",
+            Closure::wrap(Box::new(move |event: web_sys::Event| {
+                let event: web_sys::MouseEvent = event.unchecked_into();
+                let (x, y) = (event.offset_x() as f64, event.offset_y() as f64);
+                pointer_move(&canvas, &stroke, x, y);
+                notify(&canvas, &events);
+            }) as Box<dyn FnMut(_)>),
+        ));
+    }
+    for name in ["touchstart", "touchmove"] {
+        let (canvas, stroke, events) = (canvas.clone(), stroke.clone(), events.clone());
+        callbacks.push((
+            name,
+            Closure::wrap(Box::new(move |event: web_sys::Event| {
+                // Keep the page from scrolling or zooming while painting.
+                event.prevent_default();
+                let event: web_sys::TouchEvent = event.unchecked_into();
+                let position = touch_position(&canvas.borrow().canvas, &event);
+                if let Some((x, y)) = position {
+                    if name == "touchstart" {
+                        pointer_down(&canvas, &stroke, x, y, None);
+                    } else {
+                        pointer_move(&canvas, &stroke, x, y);
+                    }
+                    notify(&canvas, &events);
+                }
+            }) as Box<dyn FnMut(_)>),
+        ));
+    }
+    for name in ["mouseup", "mouseleave", "touchend", "touchcancel"] {
+        let (canvas, stroke, events) = (canvas.clone(), stroke.clone(), events.clone());
+        callbacks.push((
+            name,
+            Closure::wrap(Box::new(move |_: web_sys::Event| {
+                pointer_up(&canvas, &stroke);
+                notify(&canvas, &events);
+            }) as Box<dyn FnMut(_)>),
+        ));
+    }
+    let mut listeners = Listeners::default();
+    for (name, callback) in callbacks {
+        config.canvas.listen(&mut listeners, name, &callback);
+        listeners.keep(callback);
+    }
+
+    let mut handle = GameHandle::new(canvas, state, animation_callback, listeners, events);
+    if config.autoplay {
+        handle.play();
+    }
+    Ok(handle)
+}
+
+impl GameHandle {
+    pub(crate) fn new(
+        canvas: Rc<RefCell<Canvas>>,
+        state: Rc<RefCell<AppState>>,
+        animation_callback: Rc<AnimationCallback>,
+        listeners: Listeners,
+        events: Rc<RefCell<Subscriptions>>,
+    ) -> GameHandle {
+        GameHandle {
+            canvas,
+            state,
+            animation_callback,
+            listeners,
+            events,
+        }
+    }
+
+    fn subscribe(&mut self, event: Event, callback: js_sys::Function) -> u32 {
+        let canvas = self.canvas.borrow();
+        self.events
+            .borrow_mut()
+            .subscribe(&canvas.universe, event, callback)
+    }
+}
+
+/// Step `generations` ahead, one at a time through the rule hook if the
+/// game has one.
+fn advance(canvas: &RefCell<Canvas>, events: &RefCell<Subscriptions>, generations: u32) {
+    let hook = match events.borrow().rule_hook() {
+        Some(hook) => hook,
+        None => return canvas.borrow_mut().step(generations),
+    };
+    for done in 1..=generations {
+        let next = canvas.borrow_mut().propose_generation();
+        // Nothing is borrowed while the hook runs, so it can use the game.
+        let result = hook.call1(&JsValue::NULL, &next.clone().into());
+        canvas.borrow_mut().finish_generation(&next);
+        if let Err(err) = result {
+            // Rather than throwing again every frame.
+            events.borrow_mut().set_rule_hook(None);
+            log!("{}", tr!(RuleHookFailed, format!("{:?}", err)));
+            return canvas.borrow_mut().step(generations - done);
+        }
+    }
+}
+
+/// Tell the subscribers of a game what has happened to its board since
+/// they were last told.
+fn notify(canvas: &RefCell<Canvas>, events: &RefCell<Subscriptions>) {
+    let pending = events.borrow_mut().check(&mut canvas.borrow_mut().universe);
+    // Nothing is borrowed any more, so the callbacks can use the game.
+    pending.send();
+}
+
+#[wasm_bindgen]
+impl GameHandle {
+    /// Start stepping once a frame, if stopped.
+    pub fn play(&mut self) {
+        self.state.borrow_mut().play(&self.animation_callback);
+    }
+
+    /// Stop stepping, cancelling the frame already asked for.
+    pub fn pause(&mut self) {
+        self.state.borrow_mut().pause();
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.state.borrow().is_running()
+    }
+
+    /// Step `generations` ahead at once and repaint, running or not.
+    pub fn step(&mut self, generations: u32) {
+        advance(&self.canvas, &self.events, generations);
+        self.canvas.borrow_mut().draw();
+        notify(&self.canvas, &self.events);
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.canvas.borrow().generation()
+    }
+
+    pub fn population(&self) -> u32 {
+        self.canvas.borrow().population()
+    }
+
+    /// The board and how it is shown, to save as JSON.
+    pub fn scenario(&self) -> Scenario {
+        self.canvas.borrow().scenario()
+    }
+
+    /// A copy of the board.
+    pub fn universe(&self) -> Universe {
+        self.canvas.borrow().universe.clone()
+    }
+
+    pub fn load_scenario(&mut self, scenario: &Scenario) {
+        self.canvas.borrow_mut().load_scenario(scenario);
+        notify(&self.canvas, &self.events);
+    }
+
+    /// Call `callback` with a `TickEvent` whenever the board moves on.
+    /// Returns an id to pass to `off`.
+    pub fn on_tick(
+        &mut self,
+        #[wasm_bindgen(unchecked_param_type = "(event: TickEvent) => void")]
+        callback: js_sys::Function,
+    ) -> u32 {
+        self.subscribe(Event::Tick, callback)
+    }
+
+    /// Call `callback` with an array of `CellChange`s whenever cells change,
+    /// whether by ticking or painting. Returns an id to pass to `off`.
+    pub fn on_cell_changed(
+        &mut self,
+        #[wasm_bindgen(unchecked_param_type = "(changes: CellChange[]) => void")]
+        callback: js_sys::Function,
+    ) -> u32 {
+        self.subscribe(Event::CellChanged, callback)
+    }
+
+    /// Call `callback` with a `StabilizedEvent` whenever the board settles
+    /// into a still life or oscillator. Returns an id to pass to `off`.
+    pub fn on_stabilized(
+        &mut self,
+        #[wasm_bindgen(unchecked_param_type = "(event: StabilizedEvent) => void")]
+        callback: js_sys::Function,
+    ) -> u32 {
+        self.subscribe(Event::Stabilized, callback)
+    }
+
+    /// Call `hook` with each `NextGeneration` before it is final, to veto
+    /// or override what the rule did to any cell; see `scripting`. Passing
+    /// nothing removes the hook, as does it throwing.
+    pub fn set_rule_hook(
+        &mut self,
+        #[wasm_bindgen(unchecked_param_type = "((next: NextGeneration) => void) | undefined")]
+        hook: Option<js_sys::Function>,
+    ) {
+        self.events.borrow_mut().set_rule_hook(hook);
+    }
+
+    /// Stop calling the callback subscribed under `id`. Returns whether
+    /// there was one.
+    pub fn off(&mut self, id: u32) -> bool {
+        let canvas = self.canvas.borrow();
+        self.events.borrow_mut().unsubscribe(&canvas.universe, id)
+    }
+
+    /// Stop the game, take its listeners off the canvas and let go of
+    /// everything it holds. The handle can't be used afterwards.
+    pub fn dispose(self) {}
+}
+
+impl Drop for GameHandle {
+    fn drop(&mut self) {
+        self.pause();
+        self.listeners = Listeners::default();
+        self.animation_callback.borrow_mut().take();
+    }
+}
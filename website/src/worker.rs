@@ -0,0 +1,473 @@
+//! Running the simulation in a Web Worker so that long steps don't block the
+//! page.
+//!
+//! The page posts `{ generations, board }` messages, where the optional
+//! `board` carries the universe whenever it changed since the last message:
+//! `{ width, height, rule, topology, noise, cells }`. The worker steps its
+//! copy of the universe and answers each message with the resulting cell
+//! states as a `Uint8Array` in the format of `Universe::cells`. Drawing stays
+//! on the page.
+//!
+//! Soup searches run in a worker of their own. The page posts one
+//! `{ seed, symmetry, soups }` message, and the worker answers every
+//! `SEARCH_BATCH` soups with `{ soups, found, other, rare }`, where `rare`
+//! is an array of `[name, soup]` pairs.
+//!
+//! Lifespans are measured in a worker of their own too. The page posts one
+//! `{ board }` message, and the worker answers every `LIFESPAN_BATCH`
+//! generations until the pattern settles with `{ generations, period,
+//! finished, population, extents }`, where `population` is the initial, peak
+//! and current population and `extents` the initial, largest and current
+//! bounding boxes as `[width, height]` pairs.
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use js_sys::{Object, Reflect, Uint8Array};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::brush::Symmetry;
+use crate::lifespan::{Extent, Lifespan, Measurement, MAX_LIFESPAN};
+use crate::search::SoupSearch;
+use crate::{Topology, Universe};
+
+/// The script that loads the module inside the worker, relative to the page.
+const WORKER_SCRIPT: &str = "./worker.js";
+
+/// The script that loads the module inside a soup search worker.
+const SEARCH_WORKER_SCRIPT: &str = "./search-worker.js";
+
+/// Soups searched between progress reports.
+const SEARCH_BATCH: u32 = 10;
+
+/// The script that loads the module inside a lifespan worker.
+const LIFESPAN_WORKER_SCRIPT: &str = "./lifespan-worker.js";
+
+/// Generations measured between progress reports.
+const LIFESPAN_BATCH: u32 = 500;
+
+/// The page's handle on a simulation running in a worker.
+pub struct WorkerEngine {
+    worker: web_sys::Worker,
+    /// Kept alive for as long as the worker can answer.
+    _on_message: Closure<dyn FnMut(web_sys::MessageEvent)>,
+    /// Whether a message has been posted that hasn't been answered yet.
+    pending: bool,
+    /// Generations asked for by the pending message.
+    in_flight: u64,
+    /// Generations requested while a message was pending.
+    queued: u64,
+    /// Whether the universe was edited since the worker last saw it.
+    dirty: bool,
+}
+
+impl WorkerEngine {
+    /// Start a worker. `on_cells` is called with the cell states of every
+    /// answer.
+    pub fn new(mut on_cells: impl FnMut(Vec<u8>) + 'static) -> Result<WorkerEngine, JsValue> {
+        let options = web_sys::WorkerOptions::new();
+        options.set_type(web_sys::WorkerType::Module);
+        let worker = web_sys::Worker::new_with_options(WORKER_SCRIPT, &options)?;
+
+        let on_message = Closure::wrap(Box::new(move |event: web_sys::MessageEvent| {
+            if let Ok(cells) = event.data().dyn_into::<Uint8Array>() {
+                on_cells(cells.to_vec());
+            }
+        }) as Box<dyn FnMut(_)>);
+        worker.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+
+        Ok(WorkerEngine {
+            worker,
+            _on_message: on_message,
+            pending: false,
+            in_flight: 0,
+            queued: 0,
+            dirty: true,
+        })
+    }
+
+    /// Called after the universe is edited so the next message carries it.
+    pub fn invalidate(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Ask the worker to advance by `generations`, or queue them until the
+    /// worker answers the message it is busy with.
+    pub fn step(&mut self, universe: &Universe, generations: u64) {
+        if self.pending {
+            self.queued += generations;
+        } else {
+            self.post(universe, generations);
+        }
+    }
+
+    /// Handle an answer from the worker, posting any queued generations.
+    ///
+    /// Returns how many generations the answer is ahead of `universe`, or
+    /// `None` if it is stale because it was computed before an edit.
+    pub fn receive(&mut self, universe: &Universe) -> Option<u64> {
+        self.pending = false;
+        let current = if self.dirty {
+            None
+        } else {
+            Some(self.in_flight)
+        };
+        if self.queued > 0 {
+            let queued = std::mem::take(&mut self.queued);
+            self.post(universe, queued);
+        }
+        current
+    }
+
+    fn post(&mut self, universe: &Universe, generations: u64) {
+        let message = Object::new();
+        set(
+            &message,
+            "generations",
+            &JsValue::from_f64(generations as f64),
+        );
+        if self.dirty {
+            set(&message, "board", &board(universe));
+            self.dirty = false;
+        }
+        self.worker.post_message(&message).unwrap();
+        self.pending = true;
+        self.in_flight = generations;
+    }
+}
+
+impl Drop for WorkerEngine {
+    fn drop(&mut self) {
+        self.worker.terminate();
+    }
+}
+
+/// Entry point inside the worker, called by `worker.js`.
+#[wasm_bindgen(skip_typescript)]
+pub fn worker_start() {
+    let scope: web_sys::DedicatedWorkerGlobalScope = js_sys::global().unchecked_into();
+    let universe = Rc::new(RefCell::new(Universe::new_with_size(0, 0)));
+
+    let my_scope = scope.clone();
+    let on_message = Closure::wrap(Box::new(move |event: web_sys::MessageEvent| {
+        let message = event.data();
+        let mut universe = universe.borrow_mut();
+
+        let board = get(&message, "board");
+        if !board.is_undefined() {
+            load_board(&mut universe, &board);
+        }
+        let generations = get(&message, "generations").as_f64().unwrap_or(0.0) as u32;
+        universe.step(generations);
+
+        let cells = Uint8Array::from(universe.cells().as_slice());
+        my_scope
+            .post_message_with_transfer(&cells, &js_sys::Array::of1(&cells.buffer()))
+            .unwrap();
+    }) as Box<dyn FnMut(_)>);
+    scope.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+    on_message.forget();
+}
+
+/// How far a soup search has got, as reported by its worker.
+pub struct SearchProgress {
+    /// How many soups have been searched.
+    pub soups: u64,
+    /// The known objects found so far, as `SoupSearch` displays them.
+    pub found: String,
+    /// How many objects weren't recognized.
+    pub other: u64,
+    /// The rare objects, each with the first soup it was found in.
+    pub rare: Vec<(String, u64)>,
+}
+
+/// The page's handle on a soup search running in a worker. Dropping it stops
+/// the search.
+pub struct SearchWorker {
+    worker: web_sys::Worker,
+    /// Kept alive for as long as the worker can answer.
+    _on_message: Closure<dyn FnMut(web_sys::MessageEvent)>,
+    /// Set once the worker has reported searching every soup.
+    finished: Rc<Cell<bool>>,
+}
+
+impl SearchWorker {
+    /// Start searching `soups` soups from `seed`, mirrored under `symmetry`.
+    /// `on_progress` is called with every report.
+    pub fn new(
+        seed: u64,
+        symmetry: Symmetry,
+        soups: u32,
+        mut on_progress: impl FnMut(SearchProgress) + 'static,
+    ) -> Result<SearchWorker, JsValue> {
+        let options = web_sys::WorkerOptions::new();
+        options.set_type(web_sys::WorkerType::Module);
+        let worker = web_sys::Worker::new_with_options(SEARCH_WORKER_SCRIPT, &options)?;
+
+        let finished = Rc::new(Cell::new(false));
+        let my_finished = finished.clone();
+        let on_message = Closure::wrap(Box::new(move |event: web_sys::MessageEvent| {
+            let message = event.data();
+            let number = |key| get(&message, key).as_f64().unwrap_or(0.0) as u64;
+            let rare = js_sys::Array::from(&get(&message, "rare"))
+                .iter()
+                .map(|pair| {
+                    let pair = js_sys::Array::from(&pair);
+                    let name = pair.get(0).as_string().unwrap_or_default();
+                    (name, pair.get(1).as_f64().unwrap_or(0.0) as u64)
+                })
+                .collect();
+            my_finished.set(number("soups") >= soups as u64);
+            on_progress(SearchProgress {
+                soups: number("soups"),
+                found: get(&message, "found").as_string().unwrap_or_default(),
+                other: number("other"),
+                rare,
+            });
+        }) as Box<dyn FnMut(_)>);
+        worker.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+
+        let message = Object::new();
+        set(&message, "seed", &JsValue::from_f64(seed as f64));
+        set(&message, "symmetry", &symmetry.name().into());
+        set(&message, "soups", &soups.into());
+        worker.post_message(&message)?;
+
+        Ok(SearchWorker {
+            worker,
+            _on_message: on_message,
+            finished,
+        })
+    }
+
+    /// Whether every soup asked for has been searched.
+    pub fn is_finished(&self) -> bool {
+        self.finished.get()
+    }
+}
+
+impl Drop for SearchWorker {
+    fn drop(&mut self) {
+        self.worker.terminate();
+    }
+}
+
+/// Entry point inside a soup search worker, called by `search-worker.js`.
+#[wasm_bindgen(skip_typescript)]
+pub fn search_worker_start() {
+    let scope: web_sys::DedicatedWorkerGlobalScope = js_sys::global().unchecked_into();
+
+    let my_scope = scope.clone();
+    let on_message = Closure::wrap(Box::new(move |event: web_sys::MessageEvent| {
+        let message = event.data();
+        let seed = get(&message, "seed").as_f64().unwrap_or(0.0) as u64;
+        let symmetry = get(&message, "symmetry")
+            .as_string()
+            .and_then(|name| Symmetry::from_name(&name))
+            .unwrap_or(Symmetry::None);
+        let soups = get(&message, "soups").as_f64().unwrap_or(0.0) as u64;
+
+        let mut search = SoupSearch::new(seed, symmetry);
+        while search.soups() < soups {
+            let batch = (soups - search.soups()).min(SEARCH_BATCH as u64);
+            search.search(batch as u32);
+
+            let rare = js_sys::Array::new();
+            for (name, soup) in search.rare() {
+                rare.push(&js_sys::Array::of2(
+                    &name.into(),
+                    &JsValue::from_f64(soup as f64),
+                ));
+            }
+            let progress = Object::new();
+            set(
+                &progress,
+                "soups",
+                &JsValue::from_f64(search.soups() as f64),
+            );
+            set(&progress, "found", &search.to_string().into());
+            set(
+                &progress,
+                "other",
+                &JsValue::from_f64(search.other() as f64),
+            );
+            set(&progress, "rare", &rare);
+            my_scope.post_message(&progress).unwrap();
+        }
+    }) as Box<dyn FnMut(_)>);
+    scope.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+    on_message.forget();
+}
+
+/// The page's handle on a lifespan being measured in a worker. Dropping it
+/// stops the measurement.
+pub struct LifespanWorker {
+    worker: web_sys::Worker,
+    /// Kept alive for as long as the worker can answer.
+    _on_message: Closure<dyn FnMut(web_sys::MessageEvent)>,
+    /// Set once the worker has reported the pattern settling or being given
+    /// up on.
+    finished: Rc<Cell<bool>>,
+}
+
+impl LifespanWorker {
+    /// Start measuring the pattern on `universe`. `on_progress` is called
+    /// with every report.
+    pub fn new(
+        universe: &Universe,
+        mut on_progress: impl FnMut(Lifespan) + 'static,
+    ) -> Result<LifespanWorker, JsValue> {
+        let options = web_sys::WorkerOptions::new();
+        options.set_type(web_sys::WorkerType::Module);
+        let worker = web_sys::Worker::new_with_options(LIFESPAN_WORKER_SCRIPT, &options)?;
+
+        let finished = Rc::new(Cell::new(false));
+        let my_finished = finished.clone();
+        let on_message = Closure::wrap(Box::new(move |event: web_sys::MessageEvent| {
+            let lifespan = read_lifespan(&event.data());
+            my_finished.set(lifespan.finished);
+            on_progress(lifespan);
+        }) as Box<dyn FnMut(_)>);
+        worker.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+
+        let message = Object::new();
+        set(&message, "board", &board(universe));
+        worker.post_message(&message)?;
+
+        Ok(LifespanWorker {
+            worker,
+            _on_message: on_message,
+            finished,
+        })
+    }
+
+    /// Whether the pattern has settled or been given up on.
+    pub fn is_finished(&self) -> bool {
+        self.finished.get()
+    }
+}
+
+impl Drop for LifespanWorker {
+    fn drop(&mut self) {
+        self.worker.terminate();
+    }
+}
+
+/// Entry point inside a lifespan worker, called by `lifespan-worker.js`.
+#[wasm_bindgen(skip_typescript)]
+pub fn lifespan_worker_start() {
+    let scope: web_sys::DedicatedWorkerGlobalScope = js_sys::global().unchecked_into();
+
+    let my_scope = scope.clone();
+    let on_message = Closure::wrap(Box::new(move |event: web_sys::MessageEvent| {
+        let mut universe = Universe::new_with_size(0, 0);
+        load_board(&mut universe, &get(&event.data(), "board"));
+
+        let mut measurement = Measurement::new(universe, MAX_LIFESPAN);
+        loop {
+            let lifespan = measurement.run(LIFESPAN_BATCH);
+            my_scope.post_message(&lifespan_message(&lifespan)).unwrap();
+            if lifespan.finished {
+                break;
+            }
+        }
+    }) as Box<dyn FnMut(_)>);
+    scope.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+    on_message.forget();
+}
+
+fn lifespan_message(lifespan: &Lifespan) -> Object {
+    let extent = |extent: Extent| js_sys::Array::of2(&extent.width.into(), &extent.height.into());
+    let message = Object::new();
+    set(&message, "generations", &lifespan.generations.into());
+    set(&message, "period", &lifespan.period.into());
+    set(&message, "finished", &lifespan.finished.into());
+    set(
+        &message,
+        "population",
+        &js_sys::Array::of3(
+            &lifespan.initial_population.into(),
+            &lifespan.peak_population.into(),
+            &lifespan.population.into(),
+        ),
+    );
+    set(
+        &message,
+        "extents",
+        &js_sys::Array::of3(
+            &extent(lifespan.initial_extent),
+            &extent(lifespan.largest_extent),
+            &extent(lifespan.extent),
+        ),
+    );
+    message
+}
+
+fn read_lifespan(message: &JsValue) -> Lifespan {
+    let number = |value: JsValue| value.as_f64().unwrap_or(0.0) as u32;
+    let population = js_sys::Array::from(&get(message, "population"));
+    let extents = js_sys::Array::from(&get(message, "extents"));
+    let extent = |index| {
+        let extent = js_sys::Array::from(&extents.get(index));
+        Extent {
+            width: number(extent.get(0)),
+            height: number(extent.get(1)),
+        }
+    };
+    Lifespan {
+        generations: number(get(message, "generations")),
+        period: number(get(message, "period")),
+        finished: get(message, "finished").as_bool().unwrap_or(false),
+        initial_population: number(population.get(0)),
+        peak_population: number(population.get(1)),
+        population: number(population.get(2)),
+        initial_extent: extent(0),
+        largest_extent: extent(1),
+        extent: extent(2),
+    }
+}
+
+fn board(universe: &Universe) -> Object {
+    let board = Object::new();
+    set(&board, "width", &universe.width().into());
+    set(&board, "height", &universe.height().into());
+    set(&board, "rule", &universe.rule().to_string().into());
+    set(&board, "topology", &universe.topology().name().into());
+    set(&board, "noise", &universe.noise().into());
+    set(
+        &board,
+        "cells",
+        &Uint8Array::from(universe.cells().as_slice()),
+    );
+    board
+}
+
+fn load_board(universe: &mut Universe, board: &JsValue) {
+    let number = |key| get(board, key).as_f64().unwrap_or(0.0) as u32;
+    *universe = Universe::new_with_size(number("width"), number("height"));
+    // The page only ever sends rules it has already parsed.
+    if let Some(rule) = get(board, "rule").as_string() {
+        let _ = universe.set_rule(&rule);
+    }
+    if let Some(topology) = get(board, "topology")
+        .as_string()
+        .and_then(|name| Topology::from_name(&name))
+    {
+        universe.set_topology(topology);
+    }
+    if let Some(noise) = get(board, "noise").as_f64() {
+        universe.set_noise(noise);
+    }
+    if let Ok(cells) = get(board, "cells").dyn_into::<Uint8Array>() {
+        universe.set_cells(&cells.to_vec());
+    }
+}
+
+fn get(object: &JsValue, key: &str) -> JsValue {
+    Reflect::get(object, &JsValue::from_str(key)).unwrap_or(JsValue::UNDEFINED)
+}
+
+fn set(object: &Object, key: &str, value: &JsValue) {
+    Reflect::set(object, &JsValue::from_str(key), value).unwrap();
+}
@@ -0,0 +1,116 @@
+//! The engines the golden and differential tests run side by side.
+
+use wasm_game_of_life::chunks::ChunkedPlane;
+use wasm_game_of_life::engine::SimulationEngine;
+use wasm_game_of_life::rule::Rule;
+use wasm_game_of_life::{Cell, Position, Universe};
+
+/// Builds an engine, under the name failures report, holding the board of
+/// a universe.
+pub type Engine = (&'static str, fn(&Universe) -> Box<dyn SimulationEngine>);
+
+/// Every engine that runs Life-like rules. The naive engine comes first, as
+/// the one the others are compared with.
+pub const ENGINES: [Engine; 5] = [
+    ("naive engine", |board| {
+        Box::new(Naive::from_universe(board))
+    }),
+    ("universe", |board| Box::new(board.clone())),
+    ("universe tracking activity", |board| {
+        let mut universe = board.clone();
+        universe.track_activity(8);
+        Box::new(universe)
+    }),
+    // Without the `parallel` feature, or threads to run on, this ticks
+    // serially like the universe above.
+    ("parallel universe", |board| {
+        let mut universe = board.clone();
+        universe.set_parallel(true);
+        Box::new(universe)
+    }),
+    ("chunked plane", |board| {
+        let mut plane = ChunkedPlane::new(board.width(), board.height());
+        plane.set_rule(board.rule()).unwrap();
+        plane.copy_from(board);
+        Box::new(plane)
+    }),
+];
+
+/// The visible region of `engine` in the plaintext style, to compare and
+/// print.
+pub fn draw(engine: &dyn SimulationEngine) -> String {
+    let mut board = Universe::new_with_size(engine.width(), engine.height());
+    board.copy_from(engine);
+    board.to_ascii()
+}
+
+/// The rules written down as plainly as possible: every cell counts its
+/// neighbors one at a time, and everything beyond the edges is dead.
+pub struct Naive {
+    width: u32,
+    height: u32,
+    rule: Rule,
+    cells: Vec<bool>,
+}
+
+impl Naive {
+    pub fn from_universe(universe: &Universe) -> Naive {
+        let mut naive = Naive {
+            width: universe.width(),
+            height: universe.height(),
+            rule: universe.rule(),
+            cells: vec![false; (universe.width() * universe.height()) as usize],
+        };
+        naive.copy_from(universe);
+        naive
+    }
+
+    fn alive(&self, row: i64, column: i64) -> bool {
+        (0..self.height as i64).contains(&row)
+            && (0..self.width as i64).contains(&column)
+            && self.cells[(row * self.width as i64 + column) as usize]
+    }
+}
+
+impl SimulationEngine for Naive {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn cell(&self, position: Position) -> Option<Cell> {
+        if !self.contains(position) {
+            return None;
+        }
+        Some(Cell::from(
+            self.alive(position.row as i64, position.column as i64),
+        ))
+    }
+
+    fn set_cell(&mut self, position: Position, cell: Cell) -> Option<Cell> {
+        let was = self.cell(position)?;
+        let idx = (position.row * self.width + position.column) as usize;
+        self.cells[idx] = cell == Cell::Alive;
+        Some(was)
+    }
+
+    fn tick(&mut self) {
+        let mut next = Vec::with_capacity(self.cells.len());
+        for row in 0..self.height as i64 {
+            for column in 0..self.width as i64 {
+                let neighbors = self
+                    .rule
+                    .neighborhood()
+                    .offsets()
+                    .filter(|&(dy, dx)| self.alive(row + dy, column + dx))
+                    .count();
+                let cell = Cell::from(self.alive(row, column));
+                next.push(self.rule.next(cell, neighbors as u16) == Cell::Alive);
+            }
+        }
+        self.cells = next;
+    }
+}
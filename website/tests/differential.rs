@@ -0,0 +1,116 @@
+//! Every engine in `common` run in lockstep on random boards, checking after
+//! each generation that they all agree with the naive engine.
+//!
+//! ```text
+//! cargo test -p wasm-game-of-life --test differential
+//! ```
+//!
+//! When they don't, proptest shrinks the soup, rule and generations to the
+//! smallest case it can find that still disagrees, and the failure names the
+//! engine and generation and prints both boards.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use proptest::prelude::*;
+
+use wasm_game_of_life::{Cell, Position, Topology, Universe};
+
+use common::{draw, ENGINES};
+
+mod common;
+
+/// Life-like rules without B0, which is all the chunked plane runs, from the
+/// quiet to the explosive.
+const RULES: [&str; 8] = [
+    "B3/S23",
+    "B36/S23",
+    "B2/S",
+    "B1/S1",
+    "B3678/S34678",
+    "B1357/S1357",
+    "B35678/S5678",
+    "B345/S5",
+];
+
+const MAX_GENERATIONS: u32 = 12;
+
+/// A `width` by `height` soup, row by row.
+fn soup() -> impl Strategy<Value = (u32, u32, Vec<bool>)> {
+    (1..10u32, 1..10u32).prop_flat_map(|(width, height)| {
+        prop::collection::vec(any::<bool>(), (width * height) as usize)
+            .prop_map(move |cells| (width, height, cells))
+    })
+}
+
+/// The soup in the middle of a board with a margin of dead cells wider than
+/// the soup can grow in `generations`. Nothing then comes near the edges, so
+/// the engines with edges and the plane without them have to agree.
+fn board((width, height, cells): &(u32, u32, Vec<bool>), rule: &str, generations: u32) -> Universe {
+    let margin = generations + 1;
+    let mut universe = Universe::new_with_size(width + 2 * margin, height + 2 * margin);
+    universe.set_rule(rule).unwrap();
+    universe.set_topology(Topology::Dead);
+    for (idx, &alive) in cells.iter().enumerate() {
+        let (row, column) = (idx as u32 / width, idx as u32 % width);
+        universe.set_cell(
+            Position::new(margin + row, margin + column),
+            Cell::from(alive),
+        );
+    }
+    universe
+}
+
+proptest! {
+    #[test]
+    fn engines_agree_on_random_soups(
+        soup in soup(),
+        rule in prop::sample::select(&RULES[..]),
+        generations in 1..=MAX_GENERATIONS,
+    ) {
+        let board = board(&soup, rule, generations);
+        let mut engines: Vec<_> = ENGINES
+            .iter()
+            .map(|&(name, build)| (name, build(&board)))
+            .collect();
+        for generation in 1..=generations {
+            for (_, engine) in &mut engines {
+                engine.tick();
+            }
+            let (reference_name, reference) = &engines[0];
+            let expected = draw(reference.as_ref());
+            for (name, engine) in &engines[1..] {
+                prop_assert_eq!(
+                    draw(engine.as_ref()),
+                    expected.clone(),
+                    "the {} and the {} disagree at generation {} of {} from\n{}",
+                    name,
+                    reference_name,
+                    generation,
+                    rule,
+                    board.to_ascii()
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn engines_leave_cells_off_the_board_alone() {
+    let board = board(&(3, 3, vec![true; 9]), "B3/S23", 0);
+    for (name, build) in ENGINES {
+        let mut engine = build(&board);
+        let (width, height) = (engine.width(), engine.height());
+        for position in [Position::new(height, 0), Position::new(0, width)] {
+            assert_eq!(engine.cell(position), None, "{}", name);
+            assert_eq!(engine.set_cell(position, Cell::Alive), None, "{}", name);
+        }
+        let corner = Position::new(height - 1, width - 1);
+        assert_eq!(
+            engine.set_cell(corner, Cell::Alive),
+            Some(Cell::Dead),
+            "{}",
+            name
+        );
+        assert_eq!(engine.cell(corner), Some(Cell::Alive), "{}", name);
+    }
+}
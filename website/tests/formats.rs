@@ -0,0 +1,452 @@
+//! Patterns, replay logs, network messages, scenarios and exported images
+//! written and read back on the host.
+//!
+//! ```text
+//! cargo test -p wasm-game-of-life --test formats
+//! ```
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use wasm_game_of_life::engine::SimulationEngine;
+use wasm_game_of_life::export::gif::GifRecorder;
+use wasm_game_of_life::export::png::{encode_apng, encode_png};
+use wasm_game_of_life::network::{self, NetworkMessage, Window, MERGE_WINDOW};
+use wasm_game_of_life::patterns::{ParseError, Pattern};
+use wasm_game_of_life::replay::{Edit, ReplayError, ReplayLog};
+use wasm_game_of_life::rule::Rule;
+use wasm_game_of_life::scenario::{Scenario, ScenarioError, View};
+use wasm_game_of_life::theme::Theme;
+use wasm_game_of_life::{Bounds, Cell, Position, Topology, Universe};
+
+/// A dead universe of the given size with `cells` alive.
+fn universe(width: u32, height: u32, cells: &[(u32, u32)]) -> Universe {
+    let mut universe = Universe::new_with_size(width, height);
+    for &(row, column) in cells {
+        universe.set_cell(Position::new(row, column), Cell::Alive);
+    }
+    universe
+}
+
+/// The (`row`, `column`) of every live cell in row-major order.
+fn live_cells(universe: &Universe) -> Vec<(u32, u32)> {
+    let mut cells = Vec::new();
+    for row in 0..universe.height() {
+        for column in 0..universe.width() {
+            if universe.cell(Position::new(row, column)) == Some(Cell::Alive) {
+                cells.push((row, column));
+            }
+        }
+    }
+    cells
+}
+
+#[test]
+fn replay_logs_rebuild_the_board_from_scratch() {
+    let mut universe = universe(16, 16, &[(1, 1), (1, 2), (2, 1), (2, 2)]);
+    let mut log = ReplayLog::new(&universe);
+    let edit = |universe: &mut Universe, log: &mut ReplayLog, edit: Edit| {
+        edit.apply(universe).unwrap();
+        log.record(edit);
+    };
+
+    universe.step(3);
+    log.advance(3);
+    let glider = Pattern::from_rle("x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!").unwrap();
+    edit(
+        &mut universe,
+        &mut log,
+        Edit::Stamp {
+            row: 8,
+            column: 8,
+            pattern: glider,
+        },
+    );
+    edit(&mut universe, &mut log, Edit::Toggle { row: 1, column: 1 });
+    edit(
+        &mut universe,
+        &mut log,
+        Edit::Noise {
+            probability: 0.05,
+            seed: 7,
+        },
+    );
+    universe.step(2);
+    log.advance(2);
+    edit(
+        &mut universe,
+        &mut log,
+        Edit::Rule {
+            rule: Rule::parse("B36/S23").unwrap(),
+        },
+    );
+    edit(
+        &mut universe,
+        &mut log,
+        Edit::Cells {
+            cell: Cell::Alive,
+            cells: vec![(14, 0), (14, 1), (14, 2)],
+        },
+    );
+    universe.step(4);
+    log.advance(4);
+
+    let read = ReplayLog::from_json(&log.to_json()).unwrap();
+    assert_eq!(read, log);
+    assert_eq!(read.generations(), 9);
+    let replayed = read.replay().unwrap();
+    assert_eq!(replayed.cells(), universe.cells());
+    assert_eq!(replayed.rule(), universe.rule());
+    assert!(ReplayLog::from_json("{\"edits\": []}").is_err());
+}
+
+#[test]
+fn replay_logs_too_big_or_too_long_are_refused() {
+    let log = |width: u64, height: u64, generations: u64, edit: u64| {
+        format!(
+            "{{\"width\":{},\"height\":{},\"rule\":\"B3/S23\",\"topology\":\"torus\",\
+             \"generations\":{},\"edits\":[{{\"generation\":{},\"edit\":\"clear\"}}]}}",
+            width, height, generations, edit
+        )
+    };
+    assert!(ReplayLog::from_json(&log(64, 64, 10, 5)).is_ok());
+    assert_eq!(
+        ReplayLog::from_json(&log(1 << 20, 1 << 20, 10, 5)),
+        Err(ReplayError::TooBig {
+            width: 1 << 20,
+            height: 1 << 20
+        })
+    );
+    assert_eq!(
+        ReplayLog::from_json(&log(64, 64, u64::from(u32::MAX), 5)),
+        Err(ReplayError::TooLong(u64::from(u32::MAX)))
+    );
+    // An edit logged past the end would be played back all the same.
+    assert_eq!(
+        ReplayLog::from_json(&log(64, 64, 10, 1 << 40)),
+        Err(ReplayError::TooLong(1 << 40))
+    );
+}
+
+#[test]
+fn messages_keep_their_members_and_refuse_what_isnt_json() {
+    let message = NetworkMessage::Edit {
+        generation: 3,
+        edit: Edit::Cells {
+            cell: Cell::Alive,
+            cells: vec![(1, 2)],
+        },
+    };
+    let text = r#"{"message":"edit","generation":3,"edit":"cells","state":1,"cells":[[1,2]]}"#;
+    assert_eq!(message.to_json(), text);
+    assert_eq!(NetworkMessage::from_json(text), Ok(message));
+    let topology = r#"{"message":"edit","generation":0,"edit":"topology","topology":"mirror"}"#;
+    assert_eq!(
+        NetworkMessage::from_json(topology),
+        Ok(NetworkMessage::Edit {
+            generation: 0,
+            edit: Edit::Topology {
+                topology: Topology::Mirror
+            },
+        })
+    );
+
+    let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+    assert!(matches!(
+        NetworkMessage::from_json(&nested(100_000)),
+        Err(ReplayError::Json(_))
+    ));
+    for text in [
+        r#"{"message":"tick","generations":1e999}"#,
+        r#"{"message":"tick","generations":NaN}"#,
+        r#"{"message":"sync","generation":0,"board":"\u+041"}"#,
+        r#"{"message":"sync","generation":-1,"board":""}"#,
+        r#"{"message":"edit","generation":0,"edit":"paint"}"#,
+    ] {
+        assert!(NetworkMessage::from_json(text).is_err(), "{}", text);
+    }
+}
+
+#[test]
+fn shared_boards_merge_within_a_window() {
+    let message = NetworkMessage::Edit {
+        generation: 7,
+        edit: Edit::Toggle { row: 1, column: 2 },
+    };
+    assert_eq!(NetworkMessage::from_json(&message.to_json()), Ok(message));
+
+    let mut local = Universe::new_with_size(8, 8);
+    local.set_cell(Position::new(1, 1), Cell::Alive);
+    let mut remote = Universe::new_with_size(8, 8);
+    remote.set_cell(Position::new(5, 5), Cell::Alive);
+
+    // Boards in the same window keep everyone's live cells.
+    assert!(network::merge(&mut local, MERGE_WINDOW - 1, &remote));
+    assert_eq!(local.cell(Position::new(1, 1)), Some(Cell::Alive));
+    assert_eq!(local.cell(Position::new(5, 5)), Some(Cell::Alive));
+    assert!(!network::merge(&mut local, 0, &remote));
+
+    // A board from a later window replaces the local one and its generation.
+    let empty = Universe::new_with_size(8, 8);
+    assert!(network::merge(&mut local, MERGE_WINDOW, &empty));
+    assert_eq!(local.population(), 0);
+    assert_eq!(local.generation(), MERGE_WINDOW);
+    assert!(!network::merge(&mut local, 0, &remote));
+}
+
+#[test]
+fn served_windows_carry_edits_into_the_universe() {
+    let window = Window {
+        row: 100,
+        column: 40,
+        rows: 1000,
+        columns: 500,
+    };
+    let board = NetworkMessage::Board {
+        generation: 9,
+        window,
+        board: "x = 2, y = 1\n2o!".to_string(),
+    };
+    assert_eq!(NetworkMessage::from_json(&board.to_json()), Ok(board));
+    let view = NetworkMessage::View(Bounds {
+        row: 3,
+        column: 4,
+        width: 64,
+        height: 32,
+    });
+    assert_eq!(NetworkMessage::from_json(&view.to_json()), Ok(view));
+
+    // Edits to the board shown are moved to where it lies in the universe.
+    assert_eq!(
+        window.edit_for_universe(Edit::Toggle { row: 1, column: 2 }, 8, 8),
+        Some(Edit::Toggle {
+            row: 101,
+            column: 42
+        })
+    );
+    let cleared = window.edit_for_universe(Edit::Clear, 8, 4).unwrap();
+    let mut universe = Universe::new_with_size(500, 1000);
+    universe.set_cell(Position::new(101, 41), Cell::Alive);
+    universe.set_cell(Position::new(99, 41), Cell::Alive);
+    cleared.apply(&mut universe).unwrap();
+    assert_eq!(universe.get(Position::new(101, 41)), Some(Cell::Dead));
+    assert_eq!(universe.get(Position::new(99, 41)), Some(Cell::Alive));
+    assert_eq!(
+        window.edit_for_universe(
+            Edit::Resize {
+                width: 8,
+                height: 8
+            },
+            8,
+            8
+        ),
+        None
+    );
+
+    // Panning stops at the edges of the universe.
+    assert_eq!(
+        window.panned(-150, 10, 64, 32),
+        Bounds {
+            row: 0,
+            column: 50,
+            width: 64,
+            height: 32
+        }
+    );
+    assert_eq!(window.panned(5000, 5000, 64, 32).row, 1000 - 32);
+    assert_eq!(window.panned(5000, 5000, 64, 32).column, 500 - 64);
+}
+
+#[test]
+fn scenarios_round_trip_through_json() {
+    let mut board = universe(8, 6, &[(1, 2), (2, 3), (3, 1), (3, 2), (3, 3)]);
+    board.set_rule("B36/S23").unwrap();
+    board.set_topology(Topology::Dead);
+    board.step(4);
+    let view = View {
+        cell_size: 12,
+        show_grid: false,
+        center: (2.5, 3.25),
+    };
+    let scenario = Scenario::new(board.clone(), Some(view), Some(Theme::dark()));
+
+    let read = Scenario::from_json(&scenario.to_json()).unwrap();
+    let universe = read.universe();
+    assert_eq!(live_cells(&universe), live_cells(&board));
+    assert_eq!(universe.rule(), board.rule());
+    assert_eq!(universe.topology(), Topology::Dead);
+    assert_eq!(universe.generation(), 4);
+    assert_eq!(read.view(), Some(view));
+    assert_eq!(read.theme(), Some(&Theme::dark()));
+
+    // Only the board is required, and themes can be named or partial.
+    let read = Scenario::from_json(r#"{"board":"x = 3, y = 1\n3o!","theme":"dark"}"#).unwrap();
+    assert_eq!(read.universe().population(), 3);
+    assert_eq!(read.view(), None);
+    assert_eq!(read.theme(), Some(&Theme::dark()));
+    let theme = r##"{"young":"#00FF00","old":"#0000FF"}"##;
+    let board = r#""board":"x = 1, y = 1\nb!""#;
+    let read = Scenario::from_json(&format!("{{{},\"theme\":{}}}", board, theme)).unwrap();
+    assert_eq!(
+        read.theme(),
+        Some(&Theme::light().with_age_gradient("#00FF00", "#0000FF"))
+    );
+    assert_eq!(
+        Scenario::from_json(r#"{"version":2,"board":"x = 1, y = 1\nb!"}"#).err(),
+        Some(ScenarioError::Version(2))
+    );
+    assert_eq!(
+        Scenario::from_json(r#"{"board":"x = 1, y = 1\nb!","topology":"klein"}"#).err(),
+        Some(ScenarioError::UnknownTopology("klein".to_string()))
+    );
+    assert!(matches!(
+        Scenario::from_json(r#"{"board":"x = 1, y = 1\nb!","generation":-1}"#),
+        Err(ScenarioError::Json(_))
+    ));
+}
+
+#[test]
+fn gif_recordings_stop_at_max_frames() {
+    let mut universe = universe(5, 5, &[(2, 1), (2, 2), (2, 3)]);
+    let mut recorder = GifRecorder::new(5, 5, 2, 100, 2, "#FFFFFF", "#000000").unwrap();
+    for _ in 0..3 {
+        recorder.capture(&universe).unwrap();
+        universe.tick();
+    }
+    assert_eq!(recorder.frames(), 2);
+
+    let gif = recorder.finish().unwrap();
+    let mut decoder = gif::DecodeOptions::new().read_info(&gif[..]).unwrap();
+    assert_eq!((decoder.width(), decoder.height()), (10, 10));
+    let first = decoder.read_next_frame().unwrap().unwrap();
+    assert_eq!(first.delay, 10);
+    assert_eq!(first.buffer[4 * 10 + 2], 1);
+    assert!(decoder.read_next_frame().unwrap().is_some());
+    assert!(decoder.read_next_frame().unwrap().is_none());
+}
+
+#[test]
+fn png_exports_scale_every_cell() {
+    let universe = universe(4, 3, &[(1, 2)]);
+    let png = encode_png(&universe, 5, "#FFFFFF", "#000000").unwrap();
+    let mut reader = png::Decoder::new(&png[..]).read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).unwrap();
+    assert_eq!((info.width, info.height), (20, 15));
+    assert_eq!(pixels[7 * 20 + 12], 1);
+    assert_eq!(pixels[7 * 20 + 7], 0);
+}
+
+#[test]
+fn animated_pngs_crop_to_the_whole_run() {
+    let universe = universe(10, 10, &[(4, 3), (4, 4), (4, 5)]);
+    let apng = encode_apng(&universe, 2, 1, 100, "#FFFFFF", "#000000").unwrap();
+    let reader = png::Decoder::new(&apng[..]).read_info().unwrap();
+    let info = reader.info();
+    assert_eq!((info.width, info.height), (3, 3));
+    assert_eq!(info.animation_control.unwrap().num_frames, 3);
+    assert_eq!(universe.generation(), 0);
+}
+
+#[test]
+fn imports_rle_pattern() {
+    let pattern = Pattern::from_rle("#N Glider\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!").unwrap();
+    assert_eq!((pattern.width(), pattern.height()), (3, 3));
+    assert_eq!(pattern.cell(Position::new(0, 1)), Some(Cell::Alive));
+    assert_eq!(pattern.cell(Position::new(0, 0)), Some(Cell::Dead));
+    assert_eq!(pattern.cell(Position::new(3, 0)), None);
+    assert_eq!(pattern.cell(Position::new(0, 3)), None);
+
+    let mut universe = universe(10, 10, &[]);
+    universe.insert_pattern(Position::new(2, 3), &pattern);
+    assert_eq!(
+        live_cells(&universe),
+        vec![(2, 4), (3, 5), (4, 3), (4, 4), (4, 5)]
+    );
+}
+
+#[test]
+fn imports_other_formats() {
+    let plaintext = Pattern::parse("!Name: Blinker\nOOO\n").unwrap();
+    let life106 = Pattern::parse("#Life 1.06\n0 0\n1 0\n2 0\n").unwrap();
+    assert_eq!(plaintext.to_rle(), life106.to_rle());
+}
+
+#[test]
+fn serialized_boards_keep_every_state_and_color() {
+    let board = |rule: &str, states: &[u8]| {
+        let mut universe = Universe::new_with_size(states.len() as u32, 2);
+        universe.set_rule(rule).unwrap();
+        let mut cells = states.to_vec();
+        cells.extend(states.iter().rev());
+        universe.set_cells(&cells);
+        universe
+    };
+    // Generations rules keep each dying state, past `X` as well.
+    let generations = board("B2/S/C30", &[0, 1, 2, 3, 24, 25, 29, 0]);
+    let text = generations.serialize();
+    assert!(text.contains(".ABCXpApE"), "{}", text);
+    let read = Universe::deserialize(&text).unwrap();
+    assert_eq!(read.cells(), generations.cells());
+    assert_eq!(read.rule(), generations.rule());
+    assert!(generations.to_rle().contains("ABCXpApE"));
+
+    // Immigration and QuadLife keep each color.
+    let quadlife = board("QuadLife", &[1, 2, 0, 3, 4, 4]);
+    let text = quadlife.serialize();
+    assert!(text.contains("AB.C2D"), "{}", text);
+    let read = Universe::deserialize(&text).unwrap();
+    assert_eq!(read.cells(), quadlife.cells());
+    assert_eq!(read.rule(), quadlife.rule());
+    assert_eq!(
+        Universe::deserialize(&quadlife.to_rle()).unwrap().cells(),
+        quadlife.cells()
+    );
+
+    // Two-state boards are still written with `b` and `o`.
+    let life = universe(3, 1, &[(0, 0), (0, 2)]);
+    assert_eq!(life.serialize(), "x = 3, y = 1, rule = B3/S23\nobo!\n");
+    assert!(Pattern::from_rle("x = 1, y = 1\nyX!").is_err());
+}
+
+#[test]
+fn ascii_boards_read_and_write_plaintext() {
+    let mut blinker = Universe::from_ascii(
+        "!Name: Blinker
+.....
+..O..
+..O..
+..O..
+.....
+",
+    )
+    .unwrap();
+    assert_eq!((blinker.width(), blinker.height()), (5, 5));
+    blinker.tick();
+    assert_eq!(blinker.to_ascii(), ".....\n.....\n.OOO.\n.....\n.....\n");
+
+    // Short lines are padded with dead cells.
+    let ragged = Universe::from_ascii("O\n..O\n").unwrap();
+    assert_eq!(ragged.to_ascii(), "O..\n..O\n");
+    assert!(Universe::from_ascii("O#O").is_err());
+}
+
+#[test]
+fn rejects_invalid_rle() {
+    assert!(Pattern::from_rle("bo$2bo$3o!").is_err());
+    assert!(Pattern::from_rle("x = 2, y = 1\n3o!").is_err());
+    // Numbers too large for the board, or for a `u32`, are out of bounds
+    // rather than overflowing.
+    for rle in [
+        "x = 70000, y = 70000\no!",
+        "x = 3, y = 3\n99999999999o!",
+        "x = 3, y = 3\n4294967295b2o!",
+        "x = 3, y = 3\n4294967295$4294967295$o!",
+    ] {
+        assert_eq!(
+            Pattern::from_rle(rle),
+            Err(ParseError::OutOfBounds),
+            "{}",
+            rle
+        );
+    }
+}
@@ -0,0 +1,94 @@
+//! Famous patterns checked against the boards they should reach, on every
+//! engine in `common` that runs Life-like rules.
+//!
+//! ```text
+//! cargo test -p wasm-game-of-life --test golden
+//! ```
+//!
+//! Each directory in `tests/golden` holds one pattern's boards, written as
+//! `Universe::serialize` writes them and named after their generation:
+//! `0.rle` is where the pattern starts and every other file is what it must
+//! look like that many generations later. The glider and lightweight
+//! spaceship move by one and two cells in four generations, the pulsar is
+//! back where it started after three and the Gosper glider gun has fired
+//! one more glider every 30.
+//!
+//! The plane has no edges, so every pattern has room to run on its board
+//! without anything reaching the edges before its last generation. Adding a
+//! directory adds a pattern.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use wasm_game_of_life::Universe;
+
+use common::{draw, ENGINES};
+
+mod common;
+
+fn fixtures() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+fn read_board(path: &Path) -> Universe {
+    let text = fs::read_to_string(path)
+        .unwrap_or_else(|err| panic!("couldn't read {}: {}", path.display(), err));
+    Universe::deserialize(&text)
+        .unwrap_or_else(|err| panic!("couldn't parse {}: {}", path.display(), err))
+}
+
+/// The boards of the pattern in `directory` by generation, in order.
+fn generations(directory: &Path) -> Vec<(u64, Universe)> {
+    let mut boards: Vec<(u64, Universe)> = fs::read_dir(directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "rle"))
+        .map(|path| {
+            let generation = path
+                .file_stem()
+                .and_then(|stem| stem.to_str()?.parse().ok())
+                .unwrap_or_else(|| panic!("{} isn't named after a generation", path.display()));
+            (generation, read_board(&path))
+        })
+        .collect();
+    boards.sort_by_key(|&(generation, _)| generation);
+    boards
+}
+
+#[test]
+fn famous_patterns_reach_their_golden_boards() {
+    let mut patterns: Vec<PathBuf> = fs::read_dir(fixtures())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.is_dir())
+        .collect();
+    patterns.sort();
+    assert!(!patterns.is_empty(), "no golden patterns found");
+
+    for directory in patterns {
+        let name = directory.file_name().unwrap().to_string_lossy();
+        let boards = generations(&directory);
+        let (start, initial) = match boards.first() {
+            Some((0, initial)) => (0, initial),
+            _ => panic!("{} has no 0.rle", name),
+        };
+        for (engine_name, build) in ENGINES {
+            let mut engine = build(initial);
+            let mut generation = start;
+            for (expected_generation, expected) in &boards[1..] {
+                engine.step(expected_generation - generation);
+                generation = *expected_generation;
+                assert_eq!(
+                    draw(engine.as_ref()),
+                    expected.to_ascii(),
+                    "{} on the {} at generation {}",
+                    name,
+                    engine_name,
+                    generation
+                );
+            }
+        }
+    }
+}
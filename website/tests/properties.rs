@@ -0,0 +1,152 @@
+//! Invariants of the engine checked against random boards on the host, so a
+//! rewrite of the tick has to keep them.
+//!
+//! ```text
+//! cargo test -p wasm-game-of-life --test properties
+//! ```
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use proptest::prelude::*;
+
+use wasm_game_of_life::patterns::{Flip, Pattern};
+use wasm_game_of_life::{Topology, Universe};
+
+/// Moore neighborhood rules without B0, so an empty board has nothing to
+/// give birth to. Every one of them treats all eight neighbors alike, so
+/// turning or mirroring a board can't change what it does.
+const RULES: [&str; 6] = [
+    "B3/S23",
+    "B36/S23",
+    "B2/S",
+    "B3678/S34678",
+    "B1357/S1357",
+    "B35678/S5678",
+];
+
+const TOPOLOGIES: [Topology; 3] = [Topology::Torus, Topology::Dead, Topology::Mirror];
+
+/// How many generations each board runs, enough for changes to reach the
+/// edges of the small boards tested.
+const GENERATIONS: u32 = 8;
+
+/// A `width` by `height` board in the plaintext style `Universe::from_ascii`
+/// reads.
+fn board(width: u32, height: u32) -> impl Strategy<Value = String> {
+    prop::collection::vec(any::<bool>(), (width * height) as usize).prop_map(move |cells| {
+        cells
+            .chunks(width as usize)
+            .map(|row| {
+                let mut line: String = row
+                    .iter()
+                    .map(|&alive| if alive { 'O' } else { '.' })
+                    .collect();
+                line.push('\n');
+                line
+            })
+            .collect()
+    })
+}
+
+fn sized_board() -> impl Strategy<Value = String> {
+    (1..12u32, 1..12u32).prop_flat_map(|(width, height)| board(width, height))
+}
+
+fn square_board() -> impl Strategy<Value = String> {
+    (1..12u32).prop_flat_map(|size| board(size, size))
+}
+
+fn rule() -> impl Strategy<Value = &'static str> {
+    prop::sample::select(&RULES[..])
+}
+
+fn topology() -> impl Strategy<Value = Topology> {
+    prop::sample::select(&TOPOLOGIES[..])
+}
+
+/// The universe drawn by `text`, running `rule` on `topology`.
+fn universe(text: &str, rule: &str, topology: Topology) -> Universe {
+    let mut universe = Universe::from_ascii(text).unwrap();
+    universe.set_rule(rule).unwrap();
+    universe.set_topology(topology);
+    universe
+}
+
+/// The whole board of `universe` as a pattern, to turn and mirror.
+fn whole_board(universe: &Universe) -> Pattern {
+    Pattern::from_plaintext(&universe.to_ascii()).unwrap()
+}
+
+proptest! {
+    #[test]
+    fn dead_universes_stay_dead(
+        (width, height) in (1..40u32, 1..40u32),
+        rule in rule(),
+        topology in topology(),
+    ) {
+        let mut universe = Universe::new_with_size(width, height);
+        universe.set_rule(rule).unwrap();
+        universe.set_topology(topology);
+        for _ in 0..GENERATIONS {
+            universe.tick();
+            prop_assert_eq!(universe.population(), 0);
+        }
+    }
+
+    #[test]
+    fn ticks_commute_with_turning(
+        text in square_board(),
+        rule in rule(),
+        topology in topology(),
+        quarter_turns in 1..4u8,
+    ) {
+        let mut ticked = universe(&text, rule, topology);
+        let turned = whole_board(&ticked).rotate(quarter_turns);
+        let mut turned = universe(&turned.to_plaintext(), rule, topology);
+        ticked.step(GENERATIONS);
+        turned.step(GENERATIONS);
+        prop_assert_eq!(whole_board(&ticked).rotate(quarter_turns), whole_board(&turned));
+    }
+
+    #[test]
+    fn ticks_commute_with_mirroring(
+        text in sized_board(),
+        rule in rule(),
+        topology in topology(),
+        axis in prop::sample::select(vec![Flip::Horizontal, Flip::Vertical]),
+    ) {
+        let mut ticked = universe(&text, rule, topology);
+        let mirrored = whole_board(&ticked).flip(axis);
+        let mut mirrored = universe(&mirrored.to_plaintext(), rule, topology);
+        ticked.step(GENERATIONS);
+        mirrored.step(GENERATIONS);
+        prop_assert_eq!(whole_board(&ticked).flip(axis), whole_board(&mirrored));
+    }
+
+    #[test]
+    fn ticks_commute_with_translation_on_a_torus(
+        text in sized_board(),
+        rule in rule(),
+        rows in -20..20i32,
+        columns in -20..20i32,
+    ) {
+        let mut ticked = universe(&text, rule, Topology::Torus);
+        let mut moved = ticked.clone();
+        moved.translate(rows, columns);
+        ticked.step(GENERATIONS);
+        ticked.translate(rows, columns);
+        moved.step(GENERATIONS);
+        prop_assert_eq!(ticked.to_ascii(), moved.to_ascii());
+    }
+
+    #[test]
+    fn rle_round_trips(text in sized_board(), rule in rule()) {
+        let universe = universe(&text, rule, Topology::Torus);
+        let pattern = universe.to_pattern();
+        prop_assert_eq!(Pattern::from_rle(&pattern.to_rle()).unwrap(), pattern);
+
+        let restored = Universe::deserialize(&universe.serialize()).unwrap();
+        prop_assert_eq!(restored.rule(), universe.rule());
+        prop_assert_eq!(restored.to_ascii(), universe.to_ascii());
+    }
+}
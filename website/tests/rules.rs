@@ -0,0 +1,282 @@
+//! Rulestrings, neighborhoods and rule tables, and the automata that aren't
+//! Life-like, run on the host.
+//!
+//! ```text
+//! cargo test -p wasm-game-of-life --test rules
+//! ```
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use wasm_game_of_life::elementary::Elementary;
+use wasm_game_of_life::engine::SimulationEngine;
+use wasm_game_of_life::lenia::Lenia;
+use wasm_game_of_life::rule::{Neighborhood, Rule};
+use wasm_game_of_life::sand::{Material, Sand};
+use wasm_game_of_life::table::{RuleTable, TableBoard, TableError};
+use wasm_game_of_life::turmite::{Turmite, TurmiteError, TurmiteSpec};
+use wasm_game_of_life::{Cell, Position, Universe};
+
+/// A dead universe of the given size with `cells` alive.
+fn universe(width: u32, height: u32, cells: &[(u32, u32)]) -> Universe {
+    let mut universe = Universe::new_with_size(width, height);
+    for &(row, column) in cells {
+        universe.set_cell(Position::new(row, column), Cell::Alive);
+    }
+    universe
+}
+
+/// The (`row`, `column`) of every live cell in row-major order.
+fn live_cells(universe: &Universe) -> Vec<(u32, u32)> {
+    let mut cells = Vec::new();
+    for row in 0..universe.height() {
+        for column in 0..universe.width() {
+            if universe.cell(Position::new(row, column)) == Some(Cell::Alive) {
+                cells.push((row, column));
+            }
+        }
+    }
+    cells
+}
+
+#[test]
+fn von_neumann_rules_count_orthogonal_neighbors() {
+    let mut universe = universe(5, 5, &[(2, 2)]);
+    universe.set_rule("B1/SV").unwrap();
+
+    universe.tick();
+    assert_eq!(live_cells(&universe), &[(1, 2), (2, 1), (2, 3), (3, 2)]);
+}
+
+#[test]
+fn rulestrings_name_their_neighborhood() {
+    let rule = Rule::parse("b6,7/s5,10,11r2").unwrap();
+    assert_eq!(rule.neighborhood(), Neighborhood::Moore2);
+    assert_eq!(rule.to_string(), "B67/S5,10,11R2");
+    assert_eq!(
+        rule.with_neighborhood(Neighborhood::VonNeumann).to_string(),
+        "B/SV"
+    );
+    assert!(Rule::parse("B5/S23V").is_err());
+}
+
+#[test]
+fn isotropic_rules_tell_neighbor_arrangements_apart() {
+    // Under Just Friends, two cells side by side are still: the cells
+    // beside both of them have two neighbors, but next to each other.
+    let mut universe = universe(7, 7, &[(3, 2), (3, 3)]);
+    universe.set_rule("B2-a/S12").unwrap();
+    universe.tick();
+    assert_eq!(live_cells(&universe), &[(3, 2), (3, 3)]);
+
+    assert_eq!(universe.rule().to_string(), "B2-a/S12");
+    assert_eq!(Rule::parse("b3/s2-i34q").unwrap().to_string(), "B3/S2-i34q");
+    // Every letter of a count adds up to the count itself.
+    assert_eq!(
+        Rule::parse("B1ce2cekain3cekainyqjr4cekainyqjrtwz5cekainyqjr6cekain7ce/S").unwrap(),
+        Rule::parse("B1234567/S").unwrap()
+    );
+    assert!(Rule::parse("B2x/S").is_err());
+    assert!(Rule::parse("B3a/S23V").is_err());
+}
+
+#[test]
+fn larger_than_life_counts_whole_ranges() {
+    // A 3x3 block under "born or survive with 9 live cells in range 1,
+    // counting yourself" keeps only its centre.
+    let mut universe = universe(7, 7, &[]);
+    universe.set_rule("R1,C0,M1,S9..9,B9..9,NM").unwrap();
+    for row in 2..5 {
+        for col in 2..5 {
+            universe.toggle_cell(Position::new(row, col));
+        }
+    }
+
+    universe.tick();
+    assert_eq!(live_cells(&universe), &[(3, 3)]);
+    assert_eq!(universe.rule().to_string(), "R1,C0,M1,S9..9,B9..9,NM");
+    assert!(Rule::parse("R11,C0,M1,S34..58,B34..45,NM").is_err());
+}
+
+#[test]
+fn multi_color_births_take_the_majority_color() {
+    // A vertical blinker turns horizontal, with the new cells taking the
+    // color two of their three parents share and the middle keeping its own.
+    let mut universe = universe(7, 7, &[]);
+    universe.set_rule("Immigration").unwrap();
+    let mut cells = vec![0; 49];
+    cells[7 + 3] = 2;
+    cells[2 * 7 + 3] = 2;
+    cells[3 * 7 + 3] = 1;
+    universe.set_cells(&cells);
+
+    universe.tick();
+    assert_eq!(live_cells(&universe), &[(2, 2), (2, 3), (2, 4)]);
+    assert_eq!(universe.state(Position::new(2, 2)), Some(2));
+    assert_eq!(universe.state(Position::new(2, 3)), Some(2));
+    assert_eq!(universe.state(Position::new(2, 4)), Some(2));
+
+    // Under QuadLife, parents of three different colors give the fourth.
+    let rule = Rule::parse("quadlife").unwrap();
+    assert_eq!(rule.birth_color([2, 3, 4]), 1);
+    // Four parents of every color, or three that share some, don't.
+    assert_eq!(rule.birth_color([1, 2, 3, 4]), 1);
+    assert_eq!(rule.birth_color([3, 3, 4]), 3);
+
+    // A lone parent passes on its own color, even with only two to pick
+    // from, as under a B1 rule.
+    let immigration = Rule::parse("B1/S/Immigration").unwrap();
+    assert_eq!(immigration.birth_color([2]), 2);
+    assert_eq!(immigration.birth_color([1]), 1);
+    assert_eq!(
+        Rule::parse("Immigration").unwrap().birth_color([2, 1, 2]),
+        2
+    );
+    assert_eq!(rule.to_string(), "QuadLife");
+    assert_eq!(
+        Rule::parse("B36/S23/Immigration").unwrap().to_string(),
+        "B36/S23/Immigration"
+    );
+    assert!(Rule::parse("B2/S/C3/QuadLife").is_err());
+}
+
+#[test]
+fn rule_tables_expand_symmetries_and_bind_variables() {
+    let table = RuleTable::parse(
+        "@RULE Spread
+@TABLE
+n_states:3
+neighborhood:vonNeumann
+symmetries:rotate4
+var a={1,2}
+# A dead cell next to one live cell takes its state, and lone cells age.
+0,a,0,0,0,a
+a,0,0,0,0,2
+@COLORS
+2 255 0 0",
+    )
+    .unwrap();
+    assert_eq!(table.name(), "Spread");
+    assert_eq!(table.color(2), Some("#FF0000"));
+
+    let mut board = TableBoard::new(5, 5, table);
+    board.set_state(2, 2, 1);
+    board.tick();
+    assert_eq!(board.state(2, 2), 2);
+    for (row, column) in [(1, 2), (2, 3), (3, 2), (2, 1)] {
+        assert_eq!(board.state(row, column), 1);
+    }
+    assert_eq!(board.state(1, 1), 0);
+
+    assert_eq!(
+        RuleTable::parse("@RULE Empty\n@TREE\nnum_states=2").unwrap_err(),
+        TableError::MissingTable
+    );
+    assert_eq!(
+        RuleTable::parse("n_states:2\nneighborhood:vonNeumann\nsymmetries:rotate8").unwrap_err(),
+        TableError::UnsupportedSymmetry("rotate8".to_string())
+    );
+}
+
+#[test]
+fn elementary_automata_fill_rows_then_scroll() {
+    let mut elementary = Elementary::new(7, 3);
+    elementary.set_rule(90);
+    elementary.set_cell(Position::new(0, 3), Cell::Alive);
+
+    // Rule 90 draws a Sierpinski triangle, one row per generation.
+    elementary.step(2);
+    let row = |elementary: &Elementary, row| -> String {
+        (0..7)
+            .map(|column| match elementary.cell(Position::new(row, column)) {
+                Some(Cell::Alive) => '#',
+                _ => '.',
+            })
+            .collect()
+    };
+    assert_eq!(row(&elementary, 1), "..#.#..");
+    assert_eq!(row(&elementary, 2), ".#...#.");
+
+    // Once the board is full, each generation pushes the oldest off the top.
+    elementary.tick();
+    assert_eq!(row(&elementary, 0), "..#.#..");
+    assert_eq!(row(&elementary, 2), "#.#.#.#");
+    assert_eq!(elementary.current_row(), Some(2));
+}
+
+#[test]
+fn sand_piles_up_and_sinks_through_water() {
+    let mut sand = Sand::new(5, 4);
+    sand.set_cell(Position::new(0, 2), Cell::Alive);
+    sand.step(3);
+    assert_eq!(sand.material(3, 2), Material::Sand);
+
+    // A second grain lands on the first and slides off to one side.
+    sand.set_cell(Position::new(0, 2), Cell::Alive);
+    sand.step(3);
+    assert_eq!(sand.material(3, 2), Material::Sand);
+    assert_eq!(sand.material(2, 2), Material::Empty);
+    assert!(sand.material(3, 1) == Material::Sand || sand.material(3, 3) == Material::Sand);
+
+    // Water spreads out along a wall, and sand sinks through it.
+    let mut sand = Sand::new(3, 3);
+    sand.set_material(Material::Wall);
+    for column in 0..3 {
+        sand.set_cell(Position::new(2, column), Cell::Alive);
+    }
+    sand.set_material(Material::Water);
+    sand.set_cell(Position::new(0, 1), Cell::Alive);
+    sand.set_cell(Position::new(1, 1), Cell::Alive);
+    let water = |sand: &Sand, row| {
+        (0..3)
+            .filter(|&column| sand.material(row, column) == Material::Water)
+            .count()
+    };
+    sand.step(2);
+    assert_eq!((water(&sand, 0), water(&sand, 1)), (0, 2));
+    sand.set_material(Material::Sand);
+    sand.set_cell(Position::new(0, 0), Cell::Alive);
+    sand.tick();
+    assert_eq!(sand.material(1, 0), Material::Sand);
+    assert_eq!(water(&sand, 0) + water(&sand, 1), 2);
+    assert_eq!(sand.material(2, 0), Material::Wall);
+}
+
+#[test]
+fn turmites_paint_the_tape_as_they_walk() {
+    let ant = TurmiteSpec::parse("{{{1, 2, 0}, {0, 8, 0}}}").unwrap();
+    assert_eq!(TurmiteSpec::parse("RL"), Ok(ant.clone()));
+    assert_eq!(ant.to_string(), "{{{1,2,0},{0,8,0}}}");
+    assert!(matches!(
+        TurmiteSpec::parse("{{{2,2,0},{0,8,0}}}"),
+        Err(TurmiteError::InvalidTransition(_))
+    ));
+
+    // Langton's ant turns right around a square, then left off the first
+    // cell it painted, wiping it.
+    let mut turmite = Turmite::new(11, 11, ant);
+    turmite.step(5);
+    assert_eq!(turmite.head(), Some((5, 4)));
+    assert_eq!(turmite.cell(Position::new(5, 5)), Some(Cell::Dead));
+    for (row, column) in [(5, 6), (6, 6), (6, 5)] {
+        assert_eq!(turmite.color(row, column), 1);
+    }
+
+    // With more colors, each is painted over with the next.
+    let mut turmite = Turmite::new(11, 11, TurmiteSpec::parse("RLR").unwrap());
+    turmite.step(5);
+    assert_eq!(turmite.color(5, 5), 2);
+}
+
+#[test]
+fn lenia_fades_lone_cells_gradually() {
+    let mut lenia = Lenia::new(40, 40);
+    lenia.set_cell(Position::new(20, 20), Cell::Alive);
+
+    // With nothing around it, a cell shrinks by the whole time step.
+    lenia.tick();
+    assert!((lenia.level(20, 20) - 0.9).abs() < 0.01);
+    assert_eq!(lenia.cell(Position::new(20, 20)), Some(Cell::Alive));
+
+    lenia.step(10);
+    assert_eq!(lenia.level(20, 20), 0.0);
+}
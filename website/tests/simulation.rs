@@ -0,0 +1,566 @@
+//! The universe and the chunked plane stepped, edited, wound back and
+//! measured on the host.
+//!
+//! ```text
+//! cargo test -p wasm-game-of-life --test simulation
+//! ```
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use wasm_game_of_life::chunks::ChunkedPlane;
+use wasm_game_of_life::engine::{Delta, Rewind, SimulationEngine};
+use wasm_game_of_life::hashlife::HashLife;
+use wasm_game_of_life::patterns::{Pattern, MAX_CELLS};
+use wasm_game_of_life::random::XorShift;
+use wasm_game_of_life::replay::{Edit, ReplayError};
+use wasm_game_of_life::rule::{Rule, RuleError};
+use wasm_game_of_life::scripting::NextGeneration;
+use wasm_game_of_life::{Bounds, Cell, Position, Universe};
+
+/// A dead universe of the given size with `cells` alive.
+fn universe(width: u32, height: u32, cells: &[(u32, u32)]) -> Universe {
+    let mut universe = Universe::new_with_size(width, height);
+    for &(row, column) in cells {
+        universe.set_cell(Position::new(row, column), Cell::Alive);
+    }
+    universe
+}
+
+/// The (`row`, `column`) of every live cell in row-major order.
+fn live_cells(universe: &Universe) -> Vec<(u32, u32)> {
+    let mut cells = Vec::new();
+    for row in 0..universe.height() {
+        for column in 0..universe.width() {
+            if universe.cell(Position::new(row, column)) == Some(Cell::Alive) {
+                cells.push((row, column));
+            }
+        }
+    }
+    cells
+}
+
+#[test]
+fn blinker_oscillates() {
+    let mut universe = universe(5, 5, &[(2, 1), (2, 2), (2, 3)]);
+
+    universe.tick();
+    assert_eq!(live_cells(&universe), vec![(1, 2), (2, 2), (3, 2)]);
+
+    universe.tick();
+    assert_eq!(live_cells(&universe), vec![(2, 1), (2, 2), (2, 3)]);
+}
+
+#[test]
+fn block_is_still() {
+    let block = [(1, 1), (1, 2), (2, 1), (2, 2)];
+    let mut universe = universe(4, 4, &block);
+
+    universe.step(10);
+    assert_eq!(live_cells(&universe), block.to_vec());
+}
+
+#[test]
+fn glider_moves_diagonally() {
+    let glider = [(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)];
+    let mut universe = universe(8, 8, &glider);
+
+    // A glider takes four generations to move one cell down and right.
+    universe.step(4);
+    let moved: Vec<_> = glider
+        .iter()
+        .map(|&(row, column)| (row + 1, column + 1))
+        .collect();
+    assert_eq!(live_cells(&universe), moved);
+}
+
+#[test]
+fn glider_wraps_around_the_torus() {
+    let glider = [(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)];
+    let mut universe = universe(6, 6, &glider);
+
+    // After 24 generations it has moved all the way around.
+    universe.step(24);
+    assert_eq!(live_cells(&universe), glider.to_vec());
+}
+
+#[test]
+fn run_until_stable_stops_at_a_cycle_or_the_cap() {
+    let mut blinker = universe(5, 5, &[(2, 1), (2, 2), (2, 3)]);
+    let settled = blinker.run_until_stable(100);
+    assert!(settled.is_stable());
+    assert_eq!((settled.generations, settled.period), (2, 2));
+    // Once settled it isn't ticked again.
+    assert_eq!(blinker.run_until_stable(100).generations, 0);
+
+    // A glider on a torus only repeats once it has been all the way round,
+    // and can be run there in parts.
+    let glider = [(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)];
+    let mut torus = universe(6, 6, &glider);
+    let partway = torus.run_until_stable(10);
+    assert!(!partway.is_stable());
+    assert_eq!((partway.generations, partway.period), (10, 0));
+    let rest = torus.run_until_stable(100);
+    assert_eq!((rest.generations, rest.period), (14, 24));
+    assert_eq!(live_cells(&torus), glider.to_vec());
+}
+
+#[test]
+fn glider_leaves_the_chunked_plane_behind() {
+    let mut plane = ChunkedPlane::new(6, 6);
+    plane.copy_from(&universe(6, 6, &[(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)]));
+
+    // 400 generations take it 100 cells away, through two more chunks, and
+    // nothing is left behind.
+    plane.step(400);
+    assert_eq!(plane.population(), 5);
+    assert_eq!(plane.chunk_count(), 1);
+    assert!(plane.get(101, 102));
+    assert!(!plane.get(1, 2));
+}
+
+#[test]
+fn planes_are_read_a_region_at_a_time() {
+    let mut plane = ChunkedPlane::new(6, 6);
+    plane.copy_from(&universe(6, 6, &[(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)]));
+    plane.step(400);
+    assert_eq!(plane.live_cells_in(96..108, 96..108).len(), 5);
+    assert_eq!(plane.live_cells_in(101..102, 100..103), vec![(101, 102)]);
+    assert!(plane.live_cells_in(0..64, 0..64).is_empty());
+
+    // Regions may reach past the visible region in any direction.
+    plane.set(-1, -70, true);
+    assert_eq!(plane.live_cells_in(-2..0, -80..-60), vec![(-1, -70)]);
+    assert!(plane.live_cells_in(5..5, -80..-60).is_empty());
+}
+
+#[test]
+fn planes_without_edges_refuse_b0_and_other_rules() {
+    let b0 = Rule::parse("B03/S23").unwrap();
+    assert!(b0.births_on_zero());
+    assert!(!Rule::conway().births_on_zero());
+
+    let mut plane = ChunkedPlane::new(6, 6);
+    let refused = Err(RuleError::BirthOnZero("B03/S23".to_string()));
+    assert_eq!(plane.set_rule(b0), refused);
+    assert_eq!(plane.rule(), Rule::conway());
+    let mut hashlife = HashLife::new(6, 6);
+    assert_eq!(hashlife.set_rule(b0), refused);
+    assert_eq!(hashlife.rule(), Rule::conway());
+    assert!(HashLife::from_macrocell("[M2] (golly 4.0)\n#R B03/S23\n").is_err());
+
+    // Only two-state rules counting the eight nearest neighbors are run.
+    for rule in [
+        "B2/S/C3",
+        "B2-a/S12",
+        "B1/S1V",
+        "B3/S23R2",
+        "R5,C0,M1,S34..58,B34..45,NM",
+        "Immigration",
+    ] {
+        let rule = Rule::parse(rule).unwrap();
+        assert!(!rule.is_life_like(), "{}", rule);
+        let refused = Err(RuleError::NotLifeLike(rule.to_string()));
+        assert_eq!(plane.set_rule(rule), refused);
+        assert_eq!(hashlife.set_rule(rule), refused);
+    }
+    assert_eq!(plane.rule(), Rule::conway());
+    assert_eq!(hashlife.rule(), Rule::conway());
+}
+
+#[test]
+fn ages_count_generations_alive() {
+    let mut universe = universe(5, 5, &[(2, 1), (2, 2), (2, 3)]);
+    assert_eq!(universe.age(Position::new(2, 2)), Some(1));
+
+    universe.step(3);
+    // The middle of a blinker never dies, while its ends keep being reborn.
+    assert_eq!(universe.age(Position::new(2, 2)), Some(4));
+    assert_eq!(universe.age(Position::new(1, 2)), Some(1));
+    assert_eq!(universe.age(Position::new(2, 1)), Some(0));
+}
+
+#[test]
+fn activity_counts_recent_changes() {
+    let mut universe = universe(5, 5, &[(2, 1), (2, 2), (2, 3)]);
+    universe.track_activity(4);
+
+    universe.step(10);
+    // The ends of a blinker change every generation, its middle never.
+    assert_eq!(universe.activity(Position::new(2, 1)), Some(4));
+    assert_eq!(universe.activity(Position::new(1, 2)), Some(4));
+    assert_eq!(universe.activity(Position::new(2, 2)), Some(0));
+
+    universe.stop_tracking_activity();
+    assert_eq!(universe.activity(Position::new(2, 1)), Some(0));
+}
+
+#[test]
+fn rewinding_replays_recorded_steps() {
+    let mut universe = universe(8, 8, &[(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)]);
+    let start = universe.cells();
+    universe.track_rewind(10);
+    universe.step(4);
+    let end = universe.cells();
+    assert_eq!(universe.rewind_steps(), 4);
+
+    universe.rewind_to(4);
+    assert_eq!(universe.cells(), start);
+    assert_eq!((universe.generation(), universe.population()), (0, 5));
+
+    universe.rewind_to(0);
+    assert_eq!(universe.cells(), end);
+    assert_eq!(universe.generation(), 4);
+
+    // Stepping from the past forgets the steps that came after it.
+    universe.rewind_to(2);
+    universe.tick();
+    assert_eq!((universe.rewind_steps(), universe.rewound()), (3, 0));
+
+    universe.toggle_cell(Position::new(7, 7));
+    assert_eq!(universe.rewind_steps(), 0);
+}
+
+#[test]
+fn rewinding_forgets_the_oldest_steps_to_bound_its_changes() {
+    let delta = |changes: u32| Delta {
+        generations: 1,
+        changes: (0..changes).map(|idx| (idx, 0, 1)).collect(),
+        rng: (XorShift::new(0), XorShift::new(0)),
+    };
+    let mut rewind = Rewind::new(10, 5);
+    rewind.record(delta(2));
+    rewind.record(delta(2));
+    assert_eq!((rewind.len(), rewind.changes()), (2, 4));
+    rewind.record(delta(3));
+    assert_eq!((rewind.len(), rewind.changes()), (2, 5));
+
+    // Undone steps that are forgotten free their changes too.
+    rewind.back();
+    rewind.record(delta(1));
+    assert_eq!((rewind.len(), rewind.changes()), (2, 3));
+
+    // A step too busy to keep at all leaves nothing to wind back to.
+    rewind.record(delta(6));
+    assert_eq!((rewind.len(), rewind.changes()), (0, 0));
+}
+
+#[test]
+fn checkpoints_go_back_without_replaying_everything() {
+    let glider = [(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)];
+    let mut expected = universe(16, 16, &glider);
+    expected.step(15);
+
+    let mut universe = universe(16, 16, &glider);
+    universe.set_checkpoint_interval(10);
+    let start = universe.checkpoint();
+    universe.step(35);
+    let generations: Vec<_> = universe
+        .checkpoint_ids()
+        .into_iter()
+        .map(|id| universe.checkpoint_generation(id).unwrap())
+        .collect();
+    assert_eq!(generations, vec![0, 10, 20, 30]);
+
+    assert!(universe.seek(15));
+    assert_eq!(universe.generation(), 15);
+    assert_eq!(universe.cells(), expected.cells());
+    assert!(!universe.seek(20));
+
+    // Edits drop the automatic checkpoints but not those taken by hand.
+    universe.toggle_cell(Position::new(8, 8));
+    assert_eq!(universe.checkpoint_ids(), vec![start]);
+    assert!(universe.restore(start));
+    assert_eq!((universe.generation(), universe.population()), (0, 5));
+}
+
+#[test]
+fn hooked_generations_can_be_wound_back_and_sought() {
+    let glider = [(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)];
+    let mut universe = universe(16, 16, &glider);
+    universe.track_rewind(10);
+    universe.set_checkpoint_interval(4);
+    universe.step(3);
+    let before = universe.cells();
+    // Generation 4 gets a block in the far corner as well.
+    universe.tick_with(|next| {
+        for (row, column) in [(14, 14), (14, 15), (15, 14), (15, 15)] {
+            next.set(row, column, Cell::Alive);
+        }
+    });
+    let hooked = universe.cells();
+    universe.step(3);
+    let end = universe.cells();
+    assert_eq!(universe.rewind_steps(), 7);
+
+    universe.rewind_to(4);
+    assert_eq!(universe.cells(), before);
+    assert_eq!(universe.generation(), 3);
+    universe.rewind_to(3);
+    assert_eq!(universe.cells(), hooked);
+    assert_eq!(universe.births(), 4 + 2);
+    universe.rewind_to(0);
+    assert_eq!(universe.cells(), end);
+
+    // The block is in every board sought after the hook, since they start
+    // from the checkpoint of the generation it made.
+    universe.step(2);
+    assert!(universe.seek(6));
+    assert_eq!(universe.population(), 5 + 4);
+    assert_eq!(universe.get(Position::new(15, 15)), Some(Cell::Alive));
+    assert!(universe.seek(4));
+    assert_eq!(universe.cells(), hooked);
+}
+
+#[test]
+fn diffs_are_cells_alive_on_one_board_only() {
+    // The middle cell has six neighbors, so HighLife brings it to life.
+    let mut life = universe(8, 8, &[(1, 1), (1, 2), (1, 3), (3, 1), (3, 2), (3, 3)]);
+    let mut highlife = life.clone();
+    highlife.set_rule("B36/S23").unwrap();
+    assert!(life.diff(&highlife).is_empty());
+
+    life.tick();
+    highlife.tick();
+    assert_eq!(life.diff(&highlife), vec![(2, 2)]);
+
+    let id = life.checkpoint();
+    life.toggle_cell(Position::new(6, 5));
+    assert_eq!(life.diff(&life.checkpoint_board(id).unwrap()), vec![(6, 5)]);
+}
+
+#[test]
+fn bounding_box_follows_edits_and_steps() {
+    let mut universe = universe(10, 10, &[(2, 3), (3, 4), (4, 2), (4, 3), (4, 4)]);
+    assert_eq!(universe.bounding_box(), Some((2..5, 2..5)));
+
+    universe.step(4);
+    assert_eq!(universe.bounding_box(), Some((3..6, 3..6)));
+
+    universe.set_cell(Position::new(8, 1), Cell::Alive);
+    assert_eq!(universe.bounding_box(), Some((3..9, 1..6)));
+    universe.set_cell(Position::new(8, 1), Cell::Dead);
+    assert_eq!(universe.bounding_box(), Some((3..6, 3..6)));
+
+    universe.clear();
+    assert_eq!(universe.bounding_box(), None);
+}
+
+#[test]
+fn centroid_is_the_mean_live_cell() {
+    let mut universe = universe(10, 10, &[(1, 1), (1, 3), (4, 2)]);
+    assert_eq!(universe.centroid(), Some((2.0, 2.0)));
+    universe.clear();
+    assert_eq!(universe.centroid(), None);
+}
+
+#[test]
+fn noise_is_reproducible_from_the_seed() {
+    let noisy = |seed| {
+        let mut universe = universe(32, 32, &[]);
+        universe.seed_rng(seed);
+        universe.set_noise(0.01);
+        universe.step(10);
+        universe
+    };
+    let (first, again) = (noisy(1), noisy(1));
+    assert!(first.population() > 0);
+    assert_eq!(first.cells(), again.cells());
+    assert_ne!(first.cells(), noisy(2).cells());
+
+    let mut quiet = universe(32, 32, &[]);
+    quiet.set_noise(0.0);
+    quiet.step(10);
+    assert_eq!(quiet.population(), 0);
+}
+
+#[test]
+fn noisy_boards_are_sought_and_wound_back_to_the_same_noise() {
+    let noisy = || {
+        let mut universe = universe(32, 32, &[]);
+        universe.seed_rng(3);
+        universe.set_noise(0.02);
+        universe
+    };
+    let mut expected = noisy();
+    expected.step(15);
+
+    let mut universe = noisy();
+    universe.track_rewind(50);
+    universe.set_checkpoint_interval(10);
+    universe.step(25);
+    let end = universe.cells();
+
+    // Stepping on from a wound back board flips the same cells again.
+    universe.rewind_to(5);
+    universe.step(5);
+    assert_eq!(universe.cells(), end);
+
+    assert!(universe.seek(15));
+    assert_eq!(universe.cells(), expected.cells());
+    universe.step(10);
+    assert_eq!(universe.cells(), end);
+
+    // Other noise makes for other boards from the checkpoints on.
+    universe.set_noise(0.05);
+    assert!(!universe.seek(15));
+}
+
+#[test]
+fn toggle_cell_flips_state() {
+    let mut universe = universe(3, 3, &[]);
+
+    universe.toggle_cell(Position::new(1, 2));
+    assert_eq!(universe.cell(Position::new(1, 2)), Some(Cell::Alive));
+    assert_eq!(universe.population(), 1);
+
+    assert_eq!(universe.toggle_cell(Position::new(1, 2)), Some(Cell::Dead));
+    assert_eq!(universe.get(Position::new(1, 2)), Some(Cell::Dead));
+    assert_eq!(universe.population(), 0);
+
+    // Cells off the board are left alone rather than wrapping or panicking.
+    for position in [Position::new(3, 0), Position::new(0, 3)] {
+        assert!(!universe.contains(position));
+        assert_eq!(universe.get(position), None);
+        assert_eq!(universe.toggle_cell(position), None);
+        assert_eq!(universe.set_cell(position, Cell::Alive), None);
+        assert_eq!(universe.state(position), None);
+        assert_eq!(universe.age(position), None);
+        assert!(!universe.insert_pattern(position, &Pattern::parse("OO\n").unwrap()));
+    }
+    assert_eq!(universe.population(), 0);
+    assert_eq!(
+        universe.set_cell(Position::new(2, 2), Cell::Alive),
+        Some(Cell::Dead)
+    );
+    assert_eq!(universe.live_cells(), vec![Position::new(2, 2)]);
+    universe.clear();
+    let edit = Edit::Toggle { row: 5, column: 1 };
+    assert_eq!(
+        edit.apply(&mut universe),
+        Err(ReplayError::OutOfBounds(Position::new(5, 1)))
+    );
+}
+
+#[test]
+fn live_cells_and_bounds_describe_the_board() {
+    let mut glider = universe(8, 8, &[(1, 2), (2, 3), (3, 1), (3, 2), (3, 3)]);
+    assert_eq!(
+        glider.live_cells(),
+        vec![
+            Position::new(1, 2),
+            Position::new(2, 3),
+            Position::new(3, 1),
+            Position::new(3, 2),
+            Position::new(3, 3),
+        ]
+    );
+    assert_eq!(
+        glider.bounds(),
+        Some(Bounds {
+            row: 1,
+            column: 1,
+            width: 3,
+            height: 3
+        })
+    );
+
+    glider.clear();
+    assert!(glider.live_cells().is_empty());
+    assert_eq!(glider.bounds(), None);
+}
+
+#[test]
+fn last_change_follows_edits_and_steps() {
+    let mut universe = universe(5, 5, &[]);
+    assert_eq!(universe.last_change(), None);
+
+    universe.toggle_cell(Position::new(2, 1));
+    universe.toggle_cell(Position::new(2, 3));
+    universe.toggle_cell(Position::new(2, 2));
+    assert_eq!(universe.last_change(), Some((2, 2)));
+
+    // The blinker's new bottom cell is the last to change in row-major order.
+    universe.tick();
+    assert_eq!(universe.last_change(), Some((3, 2)));
+
+    universe.clear();
+    assert_eq!(universe.last_change(), None);
+}
+
+#[test]
+fn resizing_clears_the_board() {
+    let mut universe = universe(4, 4, &[(0, 0), (3, 3)]);
+
+    universe.set_width(6);
+    assert_eq!(universe.width(), 6);
+    assert_eq!(universe.height(), 4);
+    assert!(live_cells(&universe).is_empty());
+
+    universe.set_height(2);
+    assert_eq!(universe.cells().len(), 12);
+    assert_eq!(universe.generation(), 0);
+}
+
+#[test]
+fn sizes_beyond_the_cell_limit_are_cut_down() {
+    // 2^16 squared would overflow a `u32` count of cells.
+    let mut universe = Universe::new_with_size(1 << 16, 1 << 16);
+    assert_eq!(universe.width(), 1 << 16);
+    assert_eq!(universe.height() as u64, MAX_CELLS >> 16);
+    let last = Position::new(universe.height() - 1, universe.width() - 1);
+    universe.set_cell(last, Cell::Alive);
+    assert_eq!(universe.get(last), Some(Cell::Alive));
+
+    let mut universe = Universe::new_with_size(64, 64);
+    universe.set_width(u32::MAX);
+    assert_eq!(universe.width() as u64, MAX_CELLS / 64);
+    universe.set_height(u32::MAX);
+    assert_eq!(universe.height(), 64);
+}
+
+#[test]
+fn rule_hooks_veto_and_override_the_next_generation() {
+    let mut blinker = Universe::from_ascii(".....\n.....\n.OOO.\n.....\n.....\n").unwrap();
+    blinker.tick_with(|next: &mut NextGeneration| {
+        assert_eq!(next.generation(), 1);
+        // The ends die and the cells above and below the middle are born.
+        assert_eq!(next.changed(), vec![7, 11, 13, 17]);
+        assert_eq!(next.live_neighbors(1, 2), 3);
+        assert_eq!(next.proposed(1, 2), Cell::Alive);
+        next.veto(1, 2);
+        next.set(0, 0, Cell::Alive);
+        assert_eq!(next.next(1, 2), Cell::Dead);
+    });
+    assert_eq!(blinker.to_ascii(), "O....\n.....\n..O..\n..O..\n.....\n");
+    assert_eq!((blinker.births(), blinker.deaths()), (2, 2));
+    assert_eq!(blinker.population(), 3);
+
+    // A hook that changes nothing leaves the rule's generation alone.
+    let mut glider = Universe::from_ascii(".O.\n..O\nOOO\n").unwrap();
+    let mut expected = glider.clone();
+    expected.tick();
+    glider.tick_with(|_| {});
+    assert_eq!(glider.to_ascii(), expected.to_ascii());
+
+    // Neighbors are counted as the rule counts them: two cells out, and
+    // the cell itself too.
+    let mut block =
+        Universe::from_ascii(".......\n.......\n..OOO..\n..OOO..\n..OOO..\n.......\n.......\n")
+            .unwrap();
+    block.set_rule("R2,C0,M1,S2..24,B3..24,NM").unwrap();
+    block.tick_with(|next| {
+        assert_eq!(next.live_neighbors(3, 3), 9);
+        assert_eq!(next.live_neighbors(1, 1), 4);
+        assert_eq!(next.live_neighbors(0, 0), 1);
+    });
+
+    // Under rules whose live cells come in colors, dying cells are dead
+    // rather than the second color.
+    let mut colors = Universe::from_ascii(".O.\n...\n...\n").unwrap();
+    colors.set_rule("Immigration").unwrap();
+    colors.tick_with(|next| {
+        next.set(1, 1, Cell::Dying);
+        assert_eq!(next.next(1, 1), Cell::Dead);
+    });
+    assert_eq!(colors.state(Position::new(1, 1)), Some(0));
+}
@@ -0,0 +1,359 @@
+//! The tools built on the engine, from brushes and censuses to searches,
+//! experiments and the statistics shown beside the board, run on the host.
+//!
+//! ```text
+//! cargo test -p wasm-game-of-life --test tools
+//! ```
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use wasm_game_of_life::audio::{Scale, Sonification, SOUND_BANDS};
+use wasm_game_of_life::brush::{self, Brush, BrushShape, Symmetry};
+use wasm_game_of_life::census::Census;
+use wasm_game_of_life::engine::SimulationEngine;
+use wasm_game_of_life::events::{CellChange, StabilizedEvent, TickEvent, Watcher};
+use wasm_game_of_life::experiment::{run_experiment, Experiment};
+use wasm_game_of_life::fps::{self, FrameStats, Rolling};
+use wasm_game_of_life::i18n::{Locale, Message};
+use wasm_game_of_life::lifespan::{Extent, Measurement};
+use wasm_game_of_life::patterns::Pattern;
+use wasm_game_of_life::replay::ReplayError;
+use wasm_game_of_life::search::SoupSearch;
+use wasm_game_of_life::viewport::Viewport;
+use wasm_game_of_life::{Cell, Position, Universe};
+
+/// A dead universe of the given size with `cells` alive.
+fn universe(width: u32, height: u32, cells: &[(u32, u32)]) -> Universe {
+    let mut universe = Universe::new_with_size(width, height);
+    for &(row, column) in cells {
+        universe.set_cell(Position::new(row, column), Cell::Alive);
+    }
+    universe
+}
+
+/// The (`row`, `column`) of every live cell in row-major order.
+fn live_cells(universe: &Universe) -> Vec<(u32, u32)> {
+    let mut cells = Vec::new();
+    for row in 0..universe.height() {
+        for column in 0..universe.width() {
+            if universe.cell(Position::new(row, column)) == Some(Cell::Alive) {
+                cells.push((row, column));
+            }
+        }
+    }
+    cells
+}
+
+#[test]
+fn lifespans_count_generations_until_the_pattern_settles() {
+    // Three cells of a block fill in the fourth and then stay put.
+    let mut measurement = Measurement::new(universe(8, 8, &[(3, 3), (3, 4), (4, 3)]), 100);
+    let lifespan = measurement.run(1);
+    assert!(!lifespan.finished);
+    let lifespan = measurement.run(100);
+    assert!(lifespan.finished && lifespan.is_settled());
+    assert_eq!((lifespan.lifespan(), lifespan.period), (1, 1));
+    assert_eq!(
+        (
+            lifespan.initial_population,
+            lifespan.peak_population,
+            lifespan.population
+        ),
+        (3, 4, 4)
+    );
+    assert_eq!(
+        lifespan.extent,
+        Extent {
+            width: 2,
+            height: 2
+        }
+    );
+    assert_eq!(measurement.run(100), lifespan);
+
+    // A blinker is in its cycle from the start, and grows no bigger than
+    // either of its phases.
+    let mut measurement = Measurement::new(universe(5, 5, &[(2, 1), (2, 2), (2, 3)]), 100);
+    let lifespan = measurement.run(100);
+    assert_eq!((lifespan.lifespan(), lifespan.period), (0, 2));
+    assert_eq!(
+        lifespan.initial_extent,
+        Extent {
+            width: 3,
+            height: 1
+        }
+    );
+    assert_eq!(lifespan.largest_extent, lifespan.initial_extent);
+}
+
+#[test]
+fn experiments_sweep_rules_and_densities_reproducibly() {
+    let config = r#"{ "width": 16, "height": 16, "rules": ["B3/S23", "B36/S23"],
+        "densities": [0, 0.4], "seeds": 3, "maxGenerations": 500 }"#;
+    let experiment = Experiment::from_json(config).unwrap();
+    let trials = experiment.run();
+    let swept: Vec<_> = trials
+        .iter()
+        .map(|trial| (trial.rule.to_string(), trial.density, trial.runs))
+        .collect();
+    assert_eq!(
+        swept,
+        vec![
+            ("B3/S23".to_string(), 0.0, 3),
+            ("B3/S23".to_string(), 0.4, 3),
+            ("B36/S23".to_string(), 0.0, 3),
+            ("B36/S23".to_string(), 0.4, 3),
+        ]
+    );
+    // Empty boards are settled from the start.
+    assert_eq!(trials[0].settled, 3);
+    assert_eq!(trials[0].mean_lifespan(), Some(0.0));
+    assert_eq!(trials[0].mean_final_population(), 0.0);
+    assert_eq!(experiment.run(), trials);
+
+    let results = run_experiment(config).unwrap();
+    assert!(results.starts_with(r#"[{"rule":"B3/S23","density":0.0,"runs":3,"settled":3,"#));
+    assert_eq!(run_experiment(config).unwrap(), results);
+
+    assert_eq!(
+        Experiment::from_json(r#"{ "densities": [1.5] }"#),
+        Err(ReplayError::Missing("densities"))
+    );
+    assert!(matches!(
+        run_experiment(r#"{ "rules": ["B3/S9"] }"#),
+        Err(ReplayError::Json(_))
+    ));
+}
+
+#[test]
+fn unbounded_viewports_pan_off_the_universe() {
+    // Cells 4 pixels wide with a grid line make a 51 pixel canvas.
+    let mut viewport = Viewport::new(4, 10, 10);
+    viewport.pan(-100.0, 0.0);
+    assert_eq!(viewport.visible_columns(), 0..10);
+    assert_eq!(viewport.visible_area(), (0..11, 0..11));
+
+    viewport.set_bounded(false);
+    viewport.pan(-100.0, 0.0);
+    assert_eq!(viewport.visible_columns(), 0..0);
+    assert_eq!(viewport.visible_area(), (0..11, -20..-9));
+    assert_eq!(viewport.plane_origin(0, -20), (1.0, 1.0));
+
+    // Bounding it again brings it back.
+    viewport.set_bounded(true);
+    assert_eq!(viewport.visible_columns(), 0..10);
+}
+
+#[test]
+fn brushes_paint_their_shape() {
+    let mut universe = Universe::new_with_size(10, 10);
+    assert!(universe.paint(5, 5, &Brush::new(BrushShape::Circle, 3), Cell::Alive));
+    assert_eq!(universe.population(), 21);
+    assert!(!universe.paint(5, 5, &Brush::new(BrushShape::Circle, 2), Cell::Alive));
+
+    // Square brushes wrap around the torus.
+    universe.clear();
+    universe.paint(0, 0, &Brush::new(BrushShape::Square, 2), Cell::Alive);
+    assert_eq!(universe.population(), 9);
+    assert_eq!(universe.cell(Position::new(9, 9)), Some(Cell::Alive));
+}
+
+#[test]
+fn lines_and_rectangles_rasterize_between_corners() {
+    assert_eq!(
+        brush::line((0, 0), (1, 3)),
+        &[(0, 0), (0, 1), (1, 2), (1, 3)]
+    );
+    assert_eq!(brush::line((2, 2), (0, 2)), &[(2, 2), (1, 2), (0, 2)]);
+    assert_eq!(brush::rectangle((2, 3), (0, 0), true).len(), 12);
+    assert_eq!(brush::rectangle((2, 3), (0, 0), false).len(), 10);
+}
+
+#[test]
+fn symmetry_mirrors_cells_about_the_middle() {
+    assert_eq!(Symmetry::Horizontal.images(1, 0, 4, 3), &[(1, 0), (1, 3)]);
+    assert_eq!(Symmetry::FourFold.images(1, 1, 3, 3), &[(1, 1)]);
+    let mut images = Symmetry::EightFold.images(0, 1, 5, 5);
+    images.sort_unstable();
+    assert_eq!(
+        images,
+        &[
+            (0, 1),
+            (0, 3),
+            (1, 0),
+            (1, 4),
+            (3, 0),
+            (3, 4),
+            (4, 1),
+            (4, 3)
+        ]
+    );
+    // Diagonal images of a universe with sides of different parity fall
+    // between cells.
+    assert_eq!(Symmetry::EightFold.images(0, 0, 4, 3).len(), 4);
+}
+
+#[test]
+fn census_recognizes_objects_in_any_phase_and_orientation() {
+    let mut universe = universe(32, 32, &[]);
+    let place = |universe: &mut Universe, row, column, rle: &str| {
+        universe.insert_pattern(Position::new(row, column), &Pattern::from_rle(rle).unwrap());
+    };
+    place(&mut universe, 1, 1, "x = 2, y = 2\n2o$2o!");
+    place(&mut universe, 1, 10, "x = 2, y = 2\n2o$2o!");
+    place(&mut universe, 10, 1, "x = 1, y = 3\no$o$o!");
+    // A glider flying the other way, and an LWSS.
+    place(&mut universe, 10, 10, "x = 3, y = 3\n3o$o2b$bo!");
+    place(&mut universe, 20, 20, "x = 5, y = 4\nbo2bo$o4b$o3bo$4o!");
+    place(&mut universe, 25, 1, "x = 4, y = 4\no3b$2o2b$o3b$4o!");
+
+    let census = Census::take(&universe);
+    assert_eq!(census.count("block"), 2);
+    assert_eq!(census.count("blinker"), 1);
+    assert_eq!(census.count("glider"), 1);
+    assert_eq!(census.count("lwss"), 1);
+    assert_eq!(census.other(), 1);
+    assert_eq!(census.to_string(), "block 2, blinker 1, glider 1, lwss 1");
+
+    universe.tick();
+    let census = Census::take(&universe);
+    assert_eq!(census.generation(), 1);
+    assert_eq!((census.count("glider"), census.count("lwss")), (1, 1));
+}
+
+#[test]
+fn soup_searches_are_reproducible_and_symmetric() {
+    let search = SoupSearch::new(7, Symmetry::Horizontal);
+    let soup = search.soup(3);
+    assert_eq!(soup.cells(), search.soup(3).cells());
+    assert_ne!(soup.cells(), search.soup(4).cells());
+    let cells = live_cells(&soup);
+    assert!(!cells.is_empty());
+    let (_, columns) = soup.bounding_box().unwrap();
+    for &(row, column) in &cells {
+        assert!(cells.contains(&(row, columns.start + columns.end - 1 - column)));
+    }
+
+    let mut search = SoupSearch::new(7, Symmetry::None);
+    search.search(2);
+    assert_eq!(search.soups(), 2);
+    let found: u64 = ["block", "beehive", "blinker", "loaf", "boat", "glider"]
+        .iter()
+        .map(|name| search.count(name))
+        .sum();
+    assert!(found + search.other() > 0);
+}
+
+#[test]
+fn frame_stats_roll_over_recent_frames() {
+    let mut stats = FrameStats::default();
+    assert_eq!(stats.frames_per_second(), None);
+    for frame in 0..=10 {
+        stats.frame(frame as f64 * 20.0, 2);
+        stats.simulated(frame as f64);
+        stats.rendered(4.0);
+    }
+    assert_eq!(stats.frames_per_second(), Some(50.0));
+    assert_eq!(stats.generations_per_second(), Some(100.0));
+    assert_eq!(stats.simulation().min(), Some(0.0));
+    assert_eq!(stats.simulation().mean(), Some(5.0));
+    assert_eq!(stats.simulation().max(), Some(10.0));
+    assert_eq!(stats.render().mean(), Some(4.0));
+
+    // A pause isn't a slow frame.
+    stats.frame(10_000.0, 2);
+    assert_eq!(stats.frames_per_second(), Some(50.0));
+
+    let mut rolling = Rolling::default();
+    for sample in 0..fps::WINDOW + 10 {
+        rolling.push(sample as f64);
+    }
+    assert_eq!(rolling.min(), Some(10.0));
+}
+
+#[test]
+fn sonification_maps_generations_to_notes() {
+    assert_eq!(Scale::Major.frequency(0), 220.0);
+    assert_eq!(Scale::Pentatonic.frequency(5), 440.0);
+
+    // A blinker turns two cells into two others.
+    let mut blinker = universe(5, 5, &[(2, 1), (2, 2), (2, 3)]);
+    blinker.tick();
+    assert_eq!(
+        Sonification::Changes.notes(&blinker),
+        &[(1, 1.0), (SOUND_BANDS + 1, 1.0)]
+    );
+    // All three are in the middle column, which falls in the fourth band.
+    assert_eq!(Sonification::Columns.notes(&blinker), &[(3, 1.0)]);
+}
+
+#[test]
+fn locales_follow_the_first_translated_language() {
+    assert_eq!(Locale::negotiate(&["de-DE", "fr-CA", "es"]), Locale::French);
+    assert_eq!(Locale::negotiate(&["ES"]), Locale::Spanish);
+    assert_eq!(Locale::negotiate(&["de", "ja"]), Locale::English);
+
+    assert_eq!(
+        Locale::English.format(Message::CouldNotLoad, &[&"glider", &"oops"]),
+        "Couldn't load glider: oops"
+    );
+    assert_eq!(
+        Locale::French.format(Message::Stats, &[&3, &12, &4, &1]),
+        "Génération 3, population 12 (+4 -1)"
+    );
+}
+
+#[test]
+fn watchers_report_ticks_cell_changes_and_settling() {
+    let mut blinker = universe(5, 5, &[(2, 1), (2, 2), (2, 3)]);
+    let mut watcher = Watcher::new(&blinker);
+    watcher.watch_cells(&blinker, true);
+
+    // Painting changes cells without ticking.
+    blinker.set_cell(Position::new(0, 0), Cell::Alive);
+    blinker.set_cell(Position::new(0, 0), Cell::Dead);
+    blinker.set_cell(Position::new(4, 4), Cell::Alive);
+    let changes = watcher.check(&mut blinker, true);
+    assert_eq!(changes.tick, None);
+    assert_eq!(
+        changes.cells,
+        vec![CellChange {
+            row: 4,
+            column: 4,
+            cell: Cell::Alive
+        }]
+    );
+    blinker.set_cell(Position::new(4, 4), Cell::Dead);
+    watcher.check(&mut blinker, true);
+
+    blinker.step(2);
+    let changes = watcher.check(&mut blinker, true);
+    assert_eq!(
+        changes.tick,
+        Some(TickEvent {
+            generation: 2,
+            generations: 2,
+            population: 3,
+            births: 2,
+            deaths: 2,
+        })
+    );
+    // Back where it was two generations ago.
+    assert!(changes.cells.is_empty());
+    assert_eq!(
+        changes.stabilized,
+        Some(StabilizedEvent {
+            generation: 2,
+            period: 2
+        })
+    );
+
+    // Settling is only reported once.
+    blinker.tick();
+    let changes = watcher.check(&mut blinker, true);
+    assert_eq!(changes.cells.len(), 4);
+    assert_eq!(changes.stabilized, None);
+
+    watcher.watch_cells(&blinker, false);
+    blinker.tick();
+    assert!(watcher.check(&mut blinker, false).cells.is_empty());
+}
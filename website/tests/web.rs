@@ -1,13 +1,139 @@
-//! Test suite for the Web and headless browsers.
+//! Test suite for the Web and headless browsers: what needs a page, a
+//! canvas or wasm memory. Everything else is tested on the host.
 
 #![cfg(target_arch = "wasm32")]
 
 extern crate wasm_bindgen_test;
 use wasm_bindgen_test::*;
 
+#[cfg(feature = "web")]
+use wasm_game_of_life::network::NetworkMessage;
+#[cfg(feature = "web")]
+use wasm_game_of_life::peer::Signal;
+#[cfg(feature = "web")]
+use wasm_game_of_life::widget;
+use wasm_game_of_life::{Cell, Position, Universe};
+
 wasm_bindgen_test_configure!(run_in_browser);
 
+/// A dead universe of the given size with `cells` alive.
+fn universe(width: u32, height: u32, cells: &[(u32, u32)]) -> Universe {
+    let mut universe = Universe::new_with_size(width, height);
+    for &(row, column) in cells {
+        universe.set_cell(Position::new(row, column), Cell::Alive);
+    }
+    universe
+}
+
 #[wasm_bindgen_test]
 fn pass() {
     assert_eq!(1 + 1, 2);
 }
+
+#[wasm_bindgen_test]
+#[cfg(feature = "web")]
+fn peer_signals_and_ticks_survive_copying() {
+    let offer = Signal {
+        offer: true,
+        sdp: "v=0\r\no=- 1 2 IN IP4 127.0.0.1\r\n".to_string(),
+    };
+    assert_eq!(
+        Signal::parse(&format!("  {}\n", offer.to_text())),
+        Some(offer)
+    );
+    assert_eq!(Signal::parse(r#"{"type":"rollback","sdp":""}"#), None);
+
+    let tick = NetworkMessage::Tick { generations: 3 };
+    assert_eq!(NetworkMessage::from_json(&tick.to_json()), Ok(tick));
+}
+
+#[wasm_bindgen_test]
+#[cfg(feature = "web")]
+fn widgets_run_side_by_side() {
+    let document = web_sys::window().unwrap().document().unwrap();
+    let body = document.body().unwrap();
+    let first = document.create_element("canvas").unwrap();
+    first.set_id("widget-first");
+    let second = document.create_element("canvas").unwrap();
+    body.append_child(&first).unwrap();
+    body.append_child(&second).unwrap();
+
+    let config = js_sys::JSON::parse(
+        r##"{"canvas":"#widget-first","pattern":"x = 3, y = 1\n3o!","width":5,"height":5}"##,
+    )
+    .unwrap();
+    let mut blinker = widget::init(config).unwrap();
+    assert_eq!(blinker.population(), 3);
+
+    let config = js_sys::Object::new();
+    js_sys::Reflect::set(&config, &"canvas".into(), &second).unwrap();
+    js_sys::Reflect::set(&config, &"rule".into(), &"B3/S012345678".into()).unwrap();
+    let mut striped = widget::init(config.into()).unwrap();
+    let population = striped.population();
+
+    blinker.step(3);
+    assert_eq!((blinker.generation(), striped.generation()), (3, 0));
+    assert_eq!(striped.population(), population);
+    striped.step(1);
+    assert!(striped.population() >= population);
+
+    blinker.play();
+    assert!(blinker.is_running());
+    blinker.pause();
+    assert!(!blinker.is_running());
+    blinker.dispose();
+    striped.dispose();
+
+    let missing = js_sys::JSON::parse(r##"{"canvas":"#no-such-canvas"}"##).unwrap();
+    assert!(widget::init(missing).is_err());
+    let invalid = js_sys::JSON::parse(r##"{"canvas":"#widget-first","width":-1}"##).unwrap();
+    assert!(widget::init(invalid).is_err());
+}
+
+#[wasm_bindgen_test]
+#[cfg(feature = "web")]
+fn canvases_drawn_on_with_webgl_are_refused() {
+    use wasm_bindgen::JsCast;
+
+    let document = web_sys::window().unwrap().document().unwrap();
+    let canvas = document
+        .create_element("canvas")
+        .unwrap()
+        .dyn_into::<web_sys::HtmlCanvasElement>()
+        .unwrap();
+    // Browsers without WebGL leave the canvas free for a 2d context.
+    if canvas.get_context("webgl").unwrap().is_none() {
+        return;
+    }
+    let config = js_sys::Object::new();
+    js_sys::Reflect::set(&config, &"canvas".into(), &canvas).unwrap();
+    assert!(widget::init(config.into()).is_err());
+}
+
+#[wasm_bindgen_test]
+fn cells_view_reads_packed_bits() {
+    let mut universe = universe(4, 3, &[(1, 0), (1, 1), (1, 2)]);
+    assert_eq!(universe.cells_view().to_vec(), &[0b0111_0000, 0b0000]);
+
+    // A tick replaces the buffer, so the view has to be taken again.
+    universe.tick();
+    assert_eq!(universe.cells_view().to_vec(), &[0b0010_0010, 0b0010]);
+}
+
+#[wasm_bindgen_test]
+fn rule_hooks_run_from_js() {
+    // A hook that kills everything the rule would have born.
+    let hook = js_sys::Function::new_with_args(
+        "next",
+        "for (const idx of next.changed()) {
+            const row = Math.floor(idx / next.width()), column = idx % next.width();
+            if (next.proposed(row, column) === 1) next.veto(row, column);
+        }",
+    );
+    let mut blinker = Universe::from_ascii(".....\n.....\n.OOO.\n.....\n.....\n").unwrap();
+    blinker.step_with(1, &hook).unwrap();
+    assert_eq!(blinker.to_ascii(), ".....\n.....\n..O..\n.....\n.....\n");
+    let throwing = js_sys::Function::new_with_args("next", "throw new Error('no')");
+    assert!(blinker.step_with(3, &throwing).is_err());
+    assert_eq!(blinker.generation(), 2);
+}
@@ -1,20 +1,82 @@
+mod serve;
+mod watch;
+
 fn build() {
-    xshell::cmd!("wasm-pack build  website --target web --out-dir ../out")
-        .read()
-        .unwrap();
-    for entry in std::fs::read_dir("website/site").unwrap() {
-        let entry = entry.unwrap();
+    build_site().unwrap();
+}
+
+/// Build the wasm module and copy it into `out/` along with the page.
+fn build_site() -> Result<(), Box<dyn std::error::Error>> {
+    xshell::cmd!("wasm-pack build  website --target web --out-dir ../out").read()?;
+    for entry in std::fs::read_dir("website/site")? {
+        let entry = entry?;
         let dest = "out/".to_string() + entry.file_name().to_str().unwrap();
-        std::fs::copy(entry.path(), dest).unwrap();
+        std::fs::copy(entry.path(), dest)?;
+    }
+    Ok(())
+}
+
+/// Build the module for Node, without the page, into `out-node/` along with
+/// the example script, ready for `npm publish`.
+fn build_node() -> Result<(), Box<dyn std::error::Error>> {
+    xshell::cmd!(
+        "wasm-pack build website --target nodejs --out-dir ../out-node -- --no-default-features"
+    )
+    .read()?;
+    std::fs::copy("website/node/example.js", "out-node/example.js")?;
+    // npm only publishes the files `package.json` lists.
+    let package = std::fs::read_to_string("out-node/package.json")?;
+    let package = package.replacen("\"files\": [", "\"files\": [\n    \"example.js\",", 1);
+    std::fs::write("out-node/package.json", package)?;
+    Ok(())
+}
+
+/// Run the native tests, then the browser tests with wasm-pack.
+///
+/// `--browser chrome|firefox|safari` picks the browser (Chrome by default)
+/// and `--headed` opens a window instead of running headless, which
+/// `wasm-pack` serves on a local port to visit.
+fn test(args: &[String]) {
+    let mut browser = "chrome".to_string();
+    let mut headless = true;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--browser" => match args.next().map(String::as_str) {
+                Some(name @ ("chrome" | "firefox" | "safari")) => browser = name.to_string(),
+                other => {
+                    eprintln!(
+                        "Unknown browser {}, expected chrome, firefox or safari",
+                        other.unwrap_or("(none)")
+                    );
+                    std::process::exit(1);
+                }
+            },
+            "--headed" => headless = false,
+            _ => {
+                eprintln!("Unknown test flag {}", arg);
+                std::process::exit(1);
+            }
+        }
     }
+
+    xshell::cmd!("cargo test -p wasm-game-of-life")
+        .run()
+        .unwrap();
+
+    let mut flags = vec![format!("--{}", browser)];
+    if headless {
+        flags.push("--headless".to_string());
+    }
+    xshell::cmd!("wasm-pack test {flags...} website")
+        .run()
+        .unwrap();
 }
 
 fn serve() {
     println!("Running site at:");
     println!("http://localhost:8000");
-    xshell::cmd!("python3 -m http.server --directory out/")
-        .read()
-        .unwrap();
+    serve::serve(8000);
 }
 
 fn github_setup() {
@@ -33,10 +95,14 @@ fn main() {
     let path = std::env::var("CARGO_MANIFEST_DIR").unwrap();
     let _env = xshell::pushd(path + "/..");
 
-    let task = std::env::args().nth(1);
-    match task.as_ref().map(|it| it.as_str()) {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let task = args.first();
+    match task.map(String::as_str) {
         Some("build") => build(),
+        Some("build-node") => build_node().unwrap(),
         Some("serve") => serve(),
+        Some("test") => test(&args[1..]),
+        Some("watch") => watch::watch(),
         Some("setup") => setup(),
         Some("github_panic") => setup(),
         _ => {
@@ -0,0 +1,144 @@
+//! A small static file server for `out/`, so `cargo xtask serve` doesn't
+//! need anything besides Rust.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Component, Path, PathBuf};
+use std::thread;
+
+const ROOT: &str = "out";
+
+pub fn serve(port: u16) {
+    let listener = TcpListener::bind(("127.0.0.1", port)).unwrap();
+    for stream in listener.incoming().flatten() {
+        thread::spawn(move || {
+            if let Err(err) = respond(stream) {
+                eprintln!("Couldn't answer a request: {}", err);
+            }
+        });
+    }
+}
+
+fn respond(stream: TcpStream) -> std::io::Result<()> {
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Skip the headers, none of which matter here.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = match (parts.next(), parts.next()) {
+        (Some(method), Some(target)) => (method, target),
+        _ => {
+            return send(
+                &stream,
+                "400 Bad Request",
+                "text/plain",
+                b"Bad request\n",
+                false,
+            )
+        }
+    };
+    let head = method == "HEAD";
+    if method != "GET" && !head {
+        return send(
+            &stream,
+            "405 Method Not Allowed",
+            "text/plain",
+            b"Method not allowed\n",
+            head,
+        );
+    }
+
+    match resolve(target).and_then(|path| std::fs::read(&path).ok().map(|body| (path, body))) {
+        Some((path, body)) => {
+            println!("{} {}", method, target);
+            send(&stream, "200 OK", mime_type(&path), &body, head)
+        }
+        None => {
+            println!("{} {} not found", method, target);
+            send(&stream, "404 Not Found", "text/plain", b"Not found\n", head)
+        }
+    }
+}
+
+/// The file under `ROOT` that a request for `target` is asking for, or
+/// `None` if it would escape `ROOT`.
+fn resolve(target: &str) -> Option<PathBuf> {
+    let path = target.split(['?', '#']).next().unwrap_or("");
+    let path = percent_decode(path)?;
+    let relative = Path::new(path.trim_start_matches('/'));
+    if relative
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_)))
+    {
+        return None;
+    }
+
+    let mut path = Path::new(ROOT).join(relative);
+    if path.is_dir() {
+        path.push("index.html");
+    }
+    Some(path)
+}
+
+/// Decode `%XX` escapes, as browsers send for spaces and the like.
+fn percent_decode(path: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut rest = path.bytes();
+    while let Some(byte) = rest.next() {
+        if byte == b'%' {
+            let hex = [rest.next()?, rest.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Browsers refuse to stream-compile wasm served with any other type than
+/// `application/wasm`, and refuse modules served as anything but JavaScript.
+fn mime_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("html") => "text/html; charset=utf-8",
+        Some("js") | Some("mjs") => "text/javascript; charset=utf-8",
+        Some("wasm") => "application/wasm",
+        Some("css") => "text/css; charset=utf-8",
+        Some("json") => "application/json",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("ico") => "image/x-icon",
+        Some("txt") | Some("ts") | Some("md") => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+fn send(
+    mut stream: &TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+    head: bool,
+) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\n\
+         Content-Type: {}\r\n\
+         Content-Length: {}\r\n\
+         Cache-Control: no-cache\r\n\
+         Cross-Origin-Opener-Policy: same-origin\r\n\
+         Cross-Origin-Embedder-Policy: require-corp\r\n\
+         Connection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    if !head {
+        stream.write_all(body)?;
+    }
+    stream.flush()
+}
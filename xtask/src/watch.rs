@@ -0,0 +1,115 @@
+//! `cargo xtask watch`: rebuild the site whenever its sources change and tell
+//! open pages to reload.
+//!
+//! Pages hear about rebuilds through a stream of server-sent events on
+//! `RELOAD_PORT`, which a script added to `out/index.html` listens to. Only
+//! `watch` adds the script, so `cargo xtask build` is unaffected.
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// Files and directories whose changes trigger a rebuild.
+const WATCHED: &[&str] = &["website/src", "website/site", "website/Cargo.toml"];
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+const RELOAD_PORT: u16 = 8001;
+
+/// The event streams of the pages that are open.
+type Clients = Arc<Mutex<Vec<TcpStream>>>;
+
+pub fn watch() {
+    let clients = Clients::default();
+    let listener = TcpListener::bind(("127.0.0.1", RELOAD_PORT)).unwrap();
+    {
+        let clients = clients.clone();
+        thread::spawn(move || accept(listener, clients));
+    }
+
+    let mut built = None;
+    let mut serving = false;
+    loop {
+        let modified = last_modified();
+        if built != Some(modified) {
+            built = Some(modified);
+            println!("Rebuilding...");
+            match super::build_site() {
+                Ok(()) => {
+                    add_reload_script().unwrap();
+                    notify(&clients);
+                    println!("Rebuilt");
+                }
+                Err(err) => eprintln!("Build failed: {}", err),
+            }
+            // The server can only start once there is something to serve.
+            if !serving {
+                serving = true;
+                thread::spawn(super::serve);
+            }
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// The most recent modification time of any watched file.
+fn last_modified() -> SystemTime {
+    fn visit(path: &Path, latest: &mut SystemTime) {
+        let metadata = match std::fs::metadata(path) {
+            Ok(metadata) => metadata,
+            Err(_) => return,
+        };
+        if let Ok(modified) = metadata.modified() {
+            *latest = (*latest).max(modified);
+        }
+        if metadata.is_dir() {
+            for entry in std::fs::read_dir(path).into_iter().flatten().flatten() {
+                visit(&entry.path(), latest);
+            }
+        }
+    }
+
+    let mut latest = SystemTime::UNIX_EPOCH;
+    for path in WATCHED {
+        visit(Path::new(path), &mut latest);
+    }
+    latest
+}
+
+fn add_reload_script() -> std::io::Result<()> {
+    let script = format!(
+        "<script>new EventSource(\"http://localhost:{}/\").onmessage = () => location.reload();</script>\n  </body>",
+        RELOAD_PORT
+    );
+    let index = std::fs::read_to_string("out/index.html")?;
+    std::fs::write("out/index.html", index.replacen("</body>", &script, 1))
+}
+
+/// Answer every connection with the headers of an event stream that stays
+/// open until the next rebuild.
+fn accept(listener: TcpListener, clients: Clients) {
+    for mut stream in listener.incoming().flatten() {
+        // The request itself doesn't matter, but it has to be read before
+        // answering.
+        let mut request = [0; 1024];
+        let _ = stream.read(&mut request);
+        let headers = "HTTP/1.1 200 OK\r\n\
+                       Content-Type: text/event-stream\r\n\
+                       Cache-Control: no-cache\r\n\
+                       Access-Control-Allow-Origin: *\r\n\r\n";
+        if stream.write_all(headers.as_bytes()).is_ok() {
+            clients.lock().unwrap().push(stream);
+        }
+    }
+}
+
+/// Tell every open page to reload, forgetting the ones that were closed.
+fn notify(clients: &Clients) {
+    clients
+        .lock()
+        .unwrap()
+        .retain_mut(|stream| stream.write_all(b"data: reload\n\n").is_ok());
+}